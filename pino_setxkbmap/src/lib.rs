@@ -0,0 +1,254 @@
+//! Query and switch the keyboard layouts of the X server through `setxkbmap`
+//!
+//! ```rust,no_run
+//! use pino_setxkbmap::XkbConfig;
+//!
+//! # #[cfg(feature = "process")]
+//! fn main() {
+//!     let config = XkbConfig::query().unwrap();
+//!     println!("{} ({})", config.layouts.join(","), config.options.join(","));
+//! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+pub mod xkbcomp;
+
+/// Errors of running setxkbmap
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// setxkbmap could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of setxkbmap could not be parsed, holding the offending line
+    Malformed(String),
+    /// There is no layout at the given index
+    NoSuchLayout(usize),
+    /// xkbcomp exited with a non-zero status, such as when the keymap does not compile
+    Compile { status: i32, stderr: String },
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse setxkbmap output: {}", line),
+            Error::NoSuchLayout(index) => write!(f, "there is no layout at index {}", index),
+            Error::Compile { status, stderr } => {
+                write!(
+                    f,
+                    "xkbcomp exited with status {}: {}",
+                    status,
+                    stderr.trim()
+                )
+            },
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Keyboard configuration, as printed by `setxkbmap -query`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct XkbConfig {
+    /// Rules that translate the rest into a keymap, usually `evdev`
+    pub rules: String,
+    /// Model of the keyboard, such as `pc105`
+    pub model: String,
+    /// Layout of each group, such as `us`, the first one being active
+    pub layouts: Vec<String>,
+    /// Variant of each layout, empty for the default variant
+    pub variants: Vec<String>,
+    /// Options such as `caps:escape` or `grp:alt_shift_toggle`
+    pub options: Vec<String>,
+}
+
+impl XkbConfig {
+    /// Query the keyboard configuration of the X server
+    #[cfg(feature = "process")]
+    pub fn query() -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// Query the keyboard configuration, running `setxkbmap -query` through the given runner
+    pub fn query_with_runner(runner: &dyn CommandRunner) -> Result<Self, Error> {
+        let output = run_checked(runner, &Cmd::new("setxkbmap").arg("-query"))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `setxkbmap -query`
+    ///
+    /// ```rust
+    /// use pino_setxkbmap::XkbConfig;
+    ///
+    /// let config = XkbConfig::parse(concat!(
+    ///     "rules:      evdev\n",
+    ///     "model:      pc105\n",
+    ///     "layout:     us,de\n",
+    ///     "variant:    ,nodeadkeys\n",
+    ///     "options:    grp:alt_shift_toggle,caps:escape\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(config.layouts, ["us", "de"]);
+    /// assert_eq!(config.variants, ["", "nodeadkeys"]);
+    /// assert_eq!(config.options, ["grp:alt_shift_toggle", "caps:escape"]);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut config = XkbConfig::default();
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| Error::Malformed(line.to_owned()))?;
+            let value = value.trim();
+            let list = || value.split(',').map(str::to_owned).collect();
+            match key {
+                "rules" => config.rules = value.to_owned(),
+                "model" => config.model = value.to_owned(),
+                "layout" => config.layouts = list(),
+                "variant" => config.variants = list(),
+                "options" => config.options = list(),
+                _ => {},
+            }
+        }
+        Ok(config)
+    }
+
+    /// Arguments of setxkbmap applying the configuration, replacing the current options
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+        for (flag, value) in [("-rules", &self.rules), ("-model", &self.model)] {
+            if !value.is_empty() {
+                args.extend([flag.to_owned(), value.clone()]);
+            }
+        }
+        args.extend([String::from("-layout"), self.layouts.join(",")]);
+        args.extend([String::from("-variant"), self.variants.join(",")]);
+        // an empty option clears the current ones, which would be added to otherwise
+        args.extend([String::from("-option"), String::new()]);
+        if !self.options.is_empty() {
+            args.extend([String::from("-option"), self.options.join(",")]);
+        }
+        args
+    }
+}
+
+/// Apply a keyboard configuration
+#[cfg(feature = "process")]
+pub fn set(config: &XkbConfig) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_with_runner(&display.runner(&runner::SystemRunner::default()), config)
+}
+
+/// Apply a keyboard configuration, running `setxkbmap` through the given runner
+pub fn set_with_runner(runner: &dyn CommandRunner, config: &XkbConfig) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("setxkbmap").args(config.args()))?;
+    Ok(())
+}
+
+/// Add an option such as `caps:escape` to the current ones
+#[cfg(feature = "process")]
+pub fn add_option(option: &str) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    add_option_with_runner(&display.runner(&runner::SystemRunner::default()), option)
+}
+
+/// Add an option to the current ones, running `setxkbmap` through the given runner
+pub fn add_option_with_runner(runner: &dyn CommandRunner, option: &str) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("setxkbmap").args(["-option", option]))?;
+    Ok(())
+}
+
+/// Make the layout at `index` of the configured ones active
+///
+/// setxkbmap can not lock a group, so the layout is moved to the front of the layouts along with
+/// its variant, the others keeping their order behind it.
+#[cfg(feature = "process")]
+pub fn switch_layout(index: usize) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    switch_layout_with_runner(&display.runner(&runner::SystemRunner::default()), index)
+}
+
+/// Make the layout at `index` active, running `setxkbmap` through the given runner
+pub fn switch_layout_with_runner(runner: &dyn CommandRunner, index: usize) -> Result<(), Error> {
+    let mut config = XkbConfig::query_with_runner(runner)?;
+    if index >= config.layouts.len() {
+        return Err(Error::NoSuchLayout(index));
+    }
+    let layout = config.layouts.remove(index);
+    config.layouts.insert(0, layout);
+    if !config.variants.is_empty() {
+        config.variants.resize(config.layouts.len(), String::new());
+        let variant = config.variants.remove(index);
+        config.variants.insert(0, variant);
+    }
+    set_with_runner(runner, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn switch_layout() {
+        let query = Output::from_stdout(concat!(
+            "rules:      evdev\n",
+            "model:      pc105\n",
+            "layout:     us,de,fr\n",
+            "variant:    ,nodeadkeys\n",
+        ));
+        let set = Cmd::new("setxkbmap").args([
+            "-rules",
+            "evdev",
+            "-model",
+            "pc105",
+            "-layout",
+            "de,us,fr",
+            "-variant",
+            "nodeadkeys,,",
+            "-option",
+            "",
+        ]);
+        let runner = MockRunner::new()
+            .respond(Cmd::new("setxkbmap").arg("-query"), query)
+            .respond(set, Output::default());
+        switch_layout_with_runner(&runner, 1).unwrap();
+        assert_eq!(runner.calls().len(), 2);
+
+        assert!(matches!(
+            switch_layout_with_runner(&runner, 3),
+            Err(Error::NoSuchLayout(3))
+        ));
+        assert!(XkbConfig::parse("rules evdev").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Dump the keymap of the X server and upload a compiled one through `xkbcomp`
+//!
+//! Unlike setxkbmap, which builds the keymap from the rules, this works on the xkb source of the
+//! keymap itself, so symbols can be edited directly:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_setxkbmap::xkbcomp;
+//!
+//! let keymap = xkbcomp::dump().unwrap();
+//! // turn caps lock into escape
+//! let keymap = keymap.replace("[       Caps_Lock ]", "[ Escape ]");
+//! xkbcomp::upload(&keymap, &[]).unwrap();
+//! # }
+//! ```
+
+use std::path::Path;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, Output as CmdOutput, RunError};
+
+use crate::Error;
+
+/// Dump the keymap of the X server as xkb source
+#[cfg(feature = "process")]
+pub fn dump() -> Result<String, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    dump_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        &display_name(&display),
+    )
+}
+
+/// Dump the keymap of `display`, such as `:0`, running `xkbcomp` through the given runner
+///
+/// ```rust
+/// use pino_setxkbmap::{runner::{Cmd, MockRunner, Output}, xkbcomp};
+///
+/// let cmd = Cmd::new("xkbcomp").args(["-w", "0", "-xkb", ":0", "-"]);
+/// let runner = MockRunner::new().respond(cmd, Output::from_stdout("xkb_keymap {\n};\n"));
+/// assert!(xkbcomp::dump_with_runner(&runner, ":0").unwrap().starts_with("xkb_keymap"));
+/// ```
+pub fn dump_with_runner(runner: &dyn CommandRunner, display: &str) -> Result<String, Error> {
+    let output = run(
+        runner,
+        Cmd::new("xkbcomp").args(["-w", "0", "-xkb", display, "-"]),
+    )?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Dump the keymap of the X server to a file as xkb source
+#[cfg(feature = "process")]
+pub fn dump_to_file(path: &Path) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    dump_to_file_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        &display_name(&display),
+        path,
+    )
+}
+
+/// Dump the keymap of `display` to a file, running `xkbcomp` through the given runner
+pub fn dump_to_file_with_runner(
+    runner: &dyn CommandRunner,
+    display: &str,
+    path: &Path,
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xkbcomp")
+        .args(["-w", "0", "-xkb", display])
+        .arg(path.to_string_lossy());
+    run(runner, cmd).map(|_| ())
+}
+
+/// Compile a keymap from xkb source and upload it to the X server
+///
+/// Files included by the keymap, such as custom symbols, are looked up in `include` before the
+/// system directories.
+#[cfg(feature = "process")]
+pub fn upload(keymap: &str, include: &[&Path]) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    upload_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        &display_name(&display),
+        keymap,
+        include,
+    )
+}
+
+/// Compile a keymap and upload it to `display`, running `xkbcomp` through the given runner
+pub fn upload_with_runner(
+    runner: &dyn CommandRunner,
+    display: &str,
+    keymap: &str,
+    include: &[&Path],
+) -> Result<(), Error> {
+    let cmd = compile(include).args(["-", display]).stdin(keymap);
+    run(runner, cmd).map(|_| ())
+}
+
+/// Compile a keymap from a file of xkb source and upload it to the X server
+#[cfg(feature = "process")]
+pub fn upload_file(path: &Path, include: &[&Path]) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    upload_file_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        &display_name(&display),
+        path,
+        include,
+    )
+}
+
+/// Compile a keymap file and upload it to `display`, running `xkbcomp` through the given runner
+pub fn upload_file_with_runner(
+    runner: &dyn CommandRunner,
+    display: &str,
+    path: &Path,
+    include: &[&Path],
+) -> Result<(), Error> {
+    let cmd = compile(include).arg(path.to_string_lossy()).arg(display);
+    run(runner, cmd).map(|_| ())
+}
+
+/// Command compiling a keymap, without its source and destination
+fn compile(include: &[&Path]) -> Cmd {
+    let include = include
+        .iter()
+        .map(|dir| format!("-I{}", dir.to_string_lossy()));
+    Cmd::new("xkbcomp").args(["-w", "0"]).args(include)
+}
+
+/// Display xkbcomp reads from or uploads to, which it does not take from the environment
+#[cfg(feature = "process")]
+fn display_name(display: &crate::DisplayHandle) -> String {
+    display
+        .name()
+        .or_else(|| std::env::var("DISPLAY").ok())
+        .unwrap_or_default()
+}
+
+/// Run xkbcomp, failing with the errors of the compiler if it exits with an error
+fn run(runner: &dyn CommandRunner, cmd: Cmd) -> Result<CmdOutput, Error> {
+    run_checked(runner, &cmd).map_err(|e| match e {
+        RunError::Exited { status, stderr, .. } => Error::Compile { status, stderr },
+        e => Error::Run(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn upload_keymap() {
+        let cmd = Cmd::new("xkbcomp").args(["-w", "0", "-I/home/pino/.config/xkb", "-", ":1"]);
+        let runner = MockRunner::new().respond(
+            cmd,
+            Output::from_failure(1, "Error:            Can't find file \"custom\"\n"),
+        );
+        let include = Path::new("/home/pino/.config/xkb");
+        let err = upload_with_runner(&runner, ":1", "xkb_keymap {};", &[include]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "xkbcomp exited with status 1: Error:            Can't find file \"custom\""
+        );
+        assert_eq!(
+            runner.calls()[0].stdin.as_deref(),
+            Some(&b"xkb_keymap {};"[..])
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Activate, close, move and change the state of managed windows
+//!
+//! Each action asks the window manager to act on the window through a client message, so how
+//! it is carried out, such as whether a tiling window manager honors a new geometry, is up to the
+//! window manager.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_wm::action::{self, State};
+//!
+//! let window = pino_wm::list().unwrap().into_iter().find(|w| w.class == "mpv").unwrap();
+//! action::send_to_desktop(window.id, 3).unwrap();
+//! action::set_state(window.id, State::Above, true).unwrap();
+//! # }
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{Error, WindowId};
+
+/// State of a window as defined by EWMH, in `_NET_WM_STATE`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum State {
+    /// Dialog blocking its parent
+    Modal,
+    /// Shown on every desktop
+    Sticky,
+    /// Spanning the height of the screen
+    MaximizedVert,
+    /// Spanning the width of the screen
+    MaximizedHorz,
+    /// Rolled up into its title bar
+    Shaded,
+    /// Left out of taskbars
+    SkipTaskbar,
+    /// Left out of pagers
+    SkipPager,
+    /// Minimized
+    Hidden,
+    /// Covering the whole monitor without decorations
+    Fullscreen,
+    /// Kept above other windows
+    Above,
+    /// Kept below other windows
+    Below,
+    /// Asking for the attention of the user, usually by flashing in the taskbar
+    DemandsAttention,
+}
+
+impl fmt::Display for State {
+    /// Name wmctrl takes, such as `maximized_vert`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            State::Modal => "modal",
+            State::Sticky => "sticky",
+            State::MaximizedVert => "maximized_vert",
+            State::MaximizedHorz => "maximized_horz",
+            State::Shaded => "shaded",
+            State::SkipTaskbar => "skip_taskbar",
+            State::SkipPager => "skip_pager",
+            State::Hidden => "hidden",
+            State::Fullscreen => "fullscreen",
+            State::Above => "above",
+            State::Below => "below",
+            State::DemandsAttention => "demands_attention",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Switch to the desktop of the window, raise it and give it the focus
+#[cfg(feature = "process")]
+pub fn activate(id: WindowId) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    activate_with_runner(&display.runner(&crate::runner::SystemRunner::default()), id)
+}
+
+/// Activate the window, running `wmctrl` through the given runner
+pub fn activate_with_runner(runner: &dyn CommandRunner, id: WindowId) -> Result<(), Error> {
+    run_checked(runner, &wmctrl(id, "-a"))?;
+    Ok(())
+}
+
+/// Ask the window to close, as its close button would, leaving the client a chance to save
+#[cfg(feature = "process")]
+pub fn close(id: WindowId) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    close_with_runner(&display.runner(&crate::runner::SystemRunner::default()), id)
+}
+
+/// Ask the window to close, running `wmctrl` through the given runner
+pub fn close_with_runner(runner: &dyn CommandRunner, id: WindowId) -> Result<(), Error> {
+    run_checked(runner, &wmctrl(id, "-c"))?;
+    Ok(())
+}
+
+/// Move the window to `position` and resize it to `size` in pixels, leaving the one that is
+/// `None` as it is
+///
+/// Maximized windows ignore the new geometry until their [State::MaximizedVert] and
+/// [State::MaximizedHorz] states are removed.
+#[cfg(feature = "process")]
+pub fn move_resize(
+    id: WindowId,
+    position: Option<(i32, i32)>,
+    size: Option<(u32, u32)>,
+) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    move_resize_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        position,
+        size,
+    )
+}
+
+/// Move and resize the window, running `wmctrl` through the given runner
+///
+/// ```rust
+/// use pino_wm::{action, runner::{Cmd, MockRunner, Output}, WindowId};
+///
+/// let cmd = Cmd::new("wmctrl").args(["-i", "-r", "0x2a00006", "-e", "0,-1,-1,800,600"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// action::move_resize_with_runner(&runner, WindowId(0x2a00006), None, Some((800, 600))).unwrap();
+/// ```
+pub fn move_resize_with_runner(
+    runner: &dyn CommandRunner,
+    id: WindowId,
+    position: Option<(i32, i32)>,
+    size: Option<(u32, u32)>,
+) -> Result<(), Error> {
+    // -1 leaves the value as it is, and gravity 0 keeps the one of the window
+    let (x, y) = position.unwrap_or((-1, -1));
+    let (width, height) = size.map_or((-1, -1), |(w, h)| (w as i64, h as i64));
+    let geometry = format!("0,{},{},{},{}", x, y, width, height);
+    run_checked(runner, &wmctrl(id, "-r").args(["-e", &geometry]))?;
+    Ok(())
+}
+
+/// Add `state` to the window if `on`, or remove it
+#[cfg(feature = "process")]
+pub fn set_state(id: WindowId, state: State, on: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_state_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        state,
+        on,
+    )
+}
+
+/// Add or remove a state of the window, running `wmctrl` through the given runner
+pub fn set_state_with_runner(
+    runner: &dyn CommandRunner,
+    id: WindowId,
+    state: State,
+    on: bool,
+) -> Result<(), Error> {
+    let change = if on { "add" } else { "remove" };
+    change_state(runner, id, change, state)
+}
+
+/// Add `state` to the window if it does not have it, or remove it
+#[cfg(feature = "process")]
+pub fn toggle_state(id: WindowId, state: State) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    toggle_state_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        state,
+    )
+}
+
+/// Toggle a state of the window, running `wmctrl` through the given runner
+pub fn toggle_state_with_runner(
+    runner: &dyn CommandRunner,
+    id: WindowId,
+    state: State,
+) -> Result<(), Error> {
+    change_state(runner, id, "toggle", state)
+}
+
+/// Move the window to the desktop at `desktop`, counting from `0`
+#[cfg(feature = "process")]
+pub fn send_to_desktop(id: WindowId, desktop: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    send_to_desktop_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        desktop,
+    )
+}
+
+/// Move the window to another desktop, running `wmctrl` through the given runner
+pub fn send_to_desktop_with_runner(
+    runner: &dyn CommandRunner,
+    id: WindowId,
+    desktop: u32,
+) -> Result<(), Error> {
+    let cmd = wmctrl(id, "-r").args(["-t", &desktop.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Add, remove or toggle a state of the window
+fn change_state(
+    runner: &dyn CommandRunner,
+    id: WindowId,
+    change: &str,
+    state: State,
+) -> Result<(), Error> {
+    let cmd = wmctrl(id, "-r").args(["-b", &format!("{},{}", change, state)]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Command running wmctrl with an action on the window with the given id
+fn wmctrl(id: WindowId, action: &str) -> Cmd {
+    Cmd::new("wmctrl").args(["-i", action, &id.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn window_actions() {
+        let id = WindowId(0x2a00006);
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("wmctrl").args(["-i", "-r", "0x2a00006", "-b", "toggle,fullscreen"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("wmctrl").args(["-i", "-r", "0x2a00006", "-e", "0,-10,20,-1,-1"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("wmctrl").args(["-i", "-a", "0x2a00006"]),
+                Output::from_failure(1, "Cannot find the window.\n"),
+            );
+
+        toggle_state_with_runner(&runner, id, State::Fullscreen).unwrap();
+        move_resize_with_runner(&runner, id, Some((-10, 20)), None).unwrap();
+        let err = activate_with_runner(&runner, id).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wmctrl exited with status 1: Cannot find the window."
+        );
+    }
+}
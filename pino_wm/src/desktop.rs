@@ -0,0 +1,283 @@
+//! Read, switch and rename the desktops of the window manager
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_wm::desktop;
+//!
+//! let desktops = desktop::desktops().unwrap();
+//! for desktop in &desktops.desktops {
+//!     let marker = if desktop.current { "*" } else { " " };
+//!     println!("{}{} {}", marker, desktop.index, desktop.name);
+//! }
+//! desktop::switch_to((desktops.current_index().unwrap_or(0) + 1) % desktops.count()).unwrap();
+//! # }
+//! ```
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{parse_value, Error};
+
+/// Desktops of the window manager, as listed by `wmctrl -d`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Desktops {
+    /// Every desktop, in the order of their index
+    pub desktops: Vec<Desktop>,
+}
+
+/// Desktop, also called workspace, of the window manager
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Desktop {
+    /// Index of the desktop, counting from `0`
+    pub index: u32,
+    /// Whether the desktop is shown
+    pub current: bool,
+    /// Name of the desktop, empty if the window manager did not name it
+    pub name: String,
+    /// Width of the desktop, which is the one of the screen unless it scrolls
+    pub width: u32,
+    /// Height of the desktop
+    pub height: u32,
+    /// Part of the desktop not covered by panels and bars, `None` if the window manager does not
+    /// set `_NET_WORKAREA`
+    pub work_area: Option<WorkArea>,
+}
+
+/// Part of a desktop left for windows, given by `_NET_WORKAREA`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WorkArea {
+    /// Horizontal position on the desktop
+    pub x: i32,
+    /// Vertical position on the desktop
+    pub y: i32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl Desktops {
+    /// Parse the output of `wmctrl -d`
+    ///
+    /// ```rust
+    /// use pino_wm::desktop::Desktops;
+    ///
+    /// let desktops = Desktops::parse(concat!(
+    ///     "0  - DG: 1920x1080  VP: 0,0  WA: 0,24 1920x1056  term\n",
+    ///     "1  * DG: 1920x1080  VP: 0,0  WA: 0,24 1920x1056  web\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(desktops.count(), 2);
+    /// assert_eq!(desktops.current_index(), Some(1));
+    /// assert_eq!(desktops.names(), ["term", "web"]);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let desktops = raw
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(Desktop::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Desktops { desktops })
+    }
+
+    /// Number of desktops
+    pub fn count(&self) -> u32 {
+        self.desktops.len() as u32
+    }
+
+    /// Desktop that is shown
+    pub fn current(&self) -> Option<&Desktop> {
+        self.desktops.iter().find(|desktop| desktop.current)
+    }
+
+    /// Index of the desktop that is shown
+    pub fn current_index(&self) -> Option<u32> {
+        self.current().map(|desktop| desktop.index)
+    }
+
+    /// Names of the desktops, in the order of their index
+    pub fn names(&self) -> Vec<&str> {
+        self.desktops
+            .iter()
+            .map(|desktop| desktop.name.as_str())
+            .collect()
+    }
+}
+
+impl Desktop {
+    /// Parse a line such as `0  * DG: 1920x1080  VP: 0,0  WA: 0,24 1920x1056  term`
+    fn parse(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let (index, rest) = line.trim_start().split_once(' ').ok_or_else(malformed)?;
+        let rest = rest.trim_start();
+        let current = rest.starts_with('*');
+        let size = |size: &str| -> Result<(u32, u32), Error> {
+            let (w, h) = size.split_once('x').ok_or_else(malformed)?;
+            Ok((parse_value(line, w)?, parse_value(line, h)?))
+        };
+
+        let (_, rest) = rest.split_once("DG: ").ok_or_else(malformed)?;
+        let (geometry, rest) = rest.split_once(' ').ok_or_else(malformed)?;
+        let (width, height) = size(geometry)?;
+        let (_, rest) = rest.split_once("WA: ").ok_or_else(malformed)?;
+        // the name follows two spaces, and may be missing altogether
+        let (work_area, name) = rest.split_once("  ").unwrap_or((rest, ""));
+        let work_area = match work_area.trim().split_once(' ') {
+            Some((position, size_)) => {
+                let (x, y) = position.split_once(',').ok_or_else(malformed)?;
+                let (width, height) = size(size_)?;
+                Some(WorkArea {
+                    x: parse_value(line, x)?,
+                    y: parse_value(line, y)?,
+                    width,
+                    height,
+                })
+            },
+            // N/A
+            None => None,
+        };
+        Ok(Desktop {
+            index: parse_value(line, index)?,
+            current,
+            name: name.to_owned(),
+            width,
+            height,
+            work_area,
+        })
+    }
+}
+
+/// Read the desktops of the window manager
+#[cfg(feature = "process")]
+pub fn desktops() -> Result<Desktops, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    desktops_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+}
+
+/// Read the desktops, running `wmctrl -d` through the given runner
+pub fn desktops_with_runner(runner: &dyn CommandRunner) -> Result<Desktops, Error> {
+    let output = run_checked(runner, &Cmd::new("wmctrl").arg("-d"))?;
+    Desktops::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Show the desktop at `index`, counting from `0`
+#[cfg(feature = "process")]
+pub fn switch_to(index: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    switch_to_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        index,
+    )
+}
+
+/// Show the desktop at `index`, running `wmctrl` through the given runner
+pub fn switch_to_with_runner(runner: &dyn CommandRunner, index: u32) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("wmctrl").args(["-s", &index.to_string()]))?;
+    Ok(())
+}
+
+/// Rename the desktop at `index`, counting from `0`
+///
+/// Neither wmctrl nor xprop can write the list of names in `_NET_DESKTOP_NAMES`, so this talks
+/// to the X server directly and needs the `native` feature. Desktops before `index` without a
+/// name are given an empty one.
+#[cfg(feature = "native")]
+pub fn rename(index: u32, name: &str) -> Result<(), Error> {
+    use x11rb::{
+        connection::Connection,
+        protocol::xproto::{AtomEnum, ConnectionExt, PropMode},
+        wrapper::ConnectionExt as _,
+    };
+
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    let err = |e: &dyn std::fmt::Display| Error::Connection(e.to_string());
+    let (conn, screen) = pino_xcommon::native::connect_display(&display).map_err(|e| err(&e))?;
+    let root = conn.setup().roots[screen].root;
+    let atom = |name: &str| -> Result<u32, Error> {
+        let reply = conn
+            .intern_atom(false, name.as_bytes())
+            .map_err(|e| err(&e))?
+            .reply()
+            .map_err(|e| err(&e))?;
+        Ok(reply.atom)
+    };
+    let (names_atom, utf8) = (atom("_NET_DESKTOP_NAMES")?, atom("UTF8_STRING")?);
+
+    let reply = conn
+        .get_property(false, root, names_atom, AtomEnum::ANY, 0, u32::MAX)
+        .map_err(|e| err(&e))?
+        .reply()
+        .map_err(|e| err(&e))?;
+    let value = rename_in(&reply.value, index as usize, name);
+    conn.change_property8(PropMode::REPLACE, root, names_atom, utf8, &value)
+        .map_err(|e| err(&e))?;
+    conn.flush().map_err(|e| err(&e))?;
+    Ok(())
+}
+
+/// Replace the name at `index` of a list of names each ending in a null byte, as stored in
+/// `_NET_DESKTOP_NAMES`
+#[cfg(any(feature = "native", test))]
+fn rename_in(names: &[u8], index: usize, name: &str) -> Vec<u8> {
+    let mut names = names
+        .strip_suffix(b"\0")
+        .unwrap_or(names)
+        .split(|&b| b == 0)
+        .filter(|_| !names.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+    if names.len() <= index {
+        names.resize(index + 1, vec![]);
+    }
+    names[index] = name.as_bytes().to_vec();
+    names
+        .into_iter()
+        .flat_map(|mut name| {
+            name.push(0);
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn read_and_switch() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("wmctrl").arg("-d"),
+                Output::from_stdout(concat!(
+                    "0  * DG: 3840x1080  VP: 0,0  WA: 0,24 3840x1056  1: term\n",
+                    "1  - DG: 3840x1080  VP: N/A  WA: N/A  \n",
+                    "2  - DG: 3840x1080  VP: N/A  WA: N/A  web\n",
+                )),
+            )
+            .respond(Cmd::new("wmctrl").args(["-s", "2"]), Output::default());
+
+        let desktops = desktops_with_runner(&runner).unwrap();
+        assert_eq!(desktops.current_index(), Some(0));
+        assert_eq!(desktops.names(), ["1: term", "", "web"]);
+        let work_area = desktops.desktops[0].work_area.unwrap();
+        assert_eq!((work_area.y, work_area.height), (24, 1056));
+        assert_eq!(desktops.desktops[2].work_area, None);
+        assert_eq!(desktops.desktops[2].width, 3840);
+        switch_to_with_runner(&runner, 2).unwrap();
+
+        assert_eq!(rename_in(b"a\0b\0", 1, "web"), b"a\0web\0");
+        assert_eq!(rename_in(b"", 1, "web"), b"\0web\0");
+        assert!(Desktops::parse("0  * DG: 1920\n").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! List the windows managed by an EWMH window manager through `wmctrl`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! for window in pino_wm::list().unwrap() {
+//!     println!("{} {} {}", window.id, window.class, window.title);
+//! }
+//! # }
+//! ```
+//!
+//! Renaming desktops talks to the X server directly and needs the `native` feature.
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    display::DisplayHandle, platform, runner, session::Session, window::WindowId,
+};
+
+pub mod action;
+pub mod desktop;
+
+/// Errors of running wmctrl
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// wmctrl could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of wmctrl could not be parsed, holding the offending line
+    Malformed(String),
+    /// Request to the X server failed, with the `native` feature
+    Connection(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse wmctrl output: {}", line),
+            Error::Connection(e) => write!(f, "request to the X server failed: {}", e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Window managed by the window manager, as listed by `wmctrl -l -p -G -x`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Window {
+    /// Id of the client window
+    pub id: WindowId,
+    /// Index of the desktop the window is on, `None` if it is shown on all of them
+    pub desktop: Option<u32>,
+    /// Process id of the client, `None` if it did not set `_NET_WM_PID`
+    pub pid: Option<u32>,
+    /// Horizontal position on the screen
+    pub x: i32,
+    /// Vertical position on the screen
+    pub y: i32,
+    /// Width of the client window
+    pub width: u32,
+    /// Height of the client window
+    pub height: u32,
+    /// Instance part of `WM_CLASS`, such as `Navigator`
+    pub instance: String,
+    /// Class part of `WM_CLASS`, such as `firefox`
+    pub class: String,
+    /// Host the client runs on, from `WM_CLIENT_MACHINE`
+    pub machine: String,
+    /// Title of the window
+    pub title: String,
+}
+
+impl Window {
+    /// Parse the output of `wmctrl -l -p -G -x`
+    ///
+    /// ```rust
+    /// use pino_wm::{Window, WindowId};
+    ///
+    /// let windows = Window::parse(concat!(
+    ///     "0x02a00006  0 4242   100 50   800  600  st-256color.st-256color  pino ~ - st\n",
+    ///     "0x01e00003 -1 0      0   0    1920 24   polybar.Polybar  pino polybar-main\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(windows[0].id, WindowId(0x2a00006));
+    /// assert_eq!(windows[0].title, "~ - st");
+    /// assert_eq!(windows[1].desktop, None);
+    /// assert_eq!(windows[1].class, "Polybar");
+    /// ```
+    pub fn parse(raw: &str) -> Result<Vec<Self>, Error> {
+        raw.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(Window::parse_line)
+            .collect()
+    }
+
+    fn parse_line(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let mut rest = line;
+        let mut field = || {
+            let (field, tail) = rest
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .ok_or_else(malformed)?;
+            rest = tail;
+            Ok::<_, Error>(field)
+        };
+        let id = parse_value(line, field()?)?;
+        let desktop = field()?;
+        let pid = parse_value(line, field()?)?;
+        let (x, y) = (parse_value(line, field()?)?, parse_value(line, field()?)?);
+        let (width, height) = (parse_value(line, field()?)?, parse_value(line, field()?)?);
+        let (instance, class) = split_class(field()?);
+        // the title is empty for windows without a name, leaving no whitespace after the host
+        let (machine, title) = rest
+            .trim_start()
+            .split_once(' ')
+            .unwrap_or((rest.trim(), ""));
+        Ok(Window {
+            id,
+            desktop: match desktop {
+                "-1" => None,
+                desktop => Some(parse_value(line, desktop)?),
+            },
+            pid: Some(pid).filter(|&pid| pid != 0),
+            x,
+            y,
+            width,
+            height,
+            instance: instance.to_owned(),
+            class: class.to_owned(),
+            machine: machine.to_owned(),
+            title: title.to_owned(),
+        })
+    }
+
+    /// Check if the window is shown on every desktop
+    pub fn sticky(&self) -> bool {
+        self.desktop.is_none()
+    }
+}
+
+/// Split `instance.class` as joined by wmctrl
+///
+/// Both parts may hold dots, as in `org.gnome.Nautilus.Org.gnome.Nautilus`, so halves that only
+/// differ in case are preferred over the first dot.
+fn split_class(class: &str) -> (&str, &str) {
+    let mid = class.len() / 2;
+    if class.is_char_boundary(mid) && class[mid..].starts_with('.') {
+        let (instance, rest) = (&class[..mid], &class[mid + 1..]);
+        if instance.eq_ignore_ascii_case(rest) {
+            return (instance, rest);
+        }
+    }
+    class.split_once('.').unwrap_or((class, ""))
+}
+
+/// List the windows managed by the window manager, from the bottom of the stack to the top
+#[cfg(feature = "process")]
+pub fn list() -> Result<Vec<Window>, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    list_with_runner(&display.runner(&runner::SystemRunner::default()))
+}
+
+/// List the managed windows, running `wmctrl -l -p -G -x` through the given runner
+pub fn list_with_runner(runner: &dyn CommandRunner) -> Result<Vec<Window>, Error> {
+    let output = run_checked(runner, &Cmd::new("wmctrl").args(["-l", "-p", "-G", "-x"]))?;
+    Window::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse a value, failing with the line it is part of
+fn parse_value<T: FromStr>(line: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::Malformed(line.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_windows() {
+        let windows = Window::parse(concat!(
+            "0x02a00006  2 4242   100  50   800  600  st-256color.st-256color  pino vim:  a  b\n",
+            "0x03400003  0 0      0    0    640  480  org.gnome.Nautilus.Org.gnome.Nautilus  N/A\n",
+            "0x01a00002  0 1337   -4   -20  1280 720  Navigator.firefox  pino Mozilla Firefox\n",
+        ))
+        .unwrap();
+
+        assert_eq!(windows[0].desktop, Some(2));
+        assert_eq!(windows[0].pid, Some(4242));
+        assert_eq!(windows[0].title, "vim:  a  b");
+        assert_eq!(windows[1].instance, "org.gnome.Nautilus");
+        assert_eq!(windows[1].class, "Org.gnome.Nautilus");
+        assert_eq!(windows[1].pid, None);
+        assert_eq!(windows[1].machine, "N/A");
+        assert_eq!(windows[1].title, "");
+        assert_eq!((windows[2].x, windows[2].y), (-4, -20));
+        assert_eq!(
+            (windows[2].instance.as_str(), windows[2].class.as_str()),
+            ("Navigator", "firefox")
+        );
+
+        assert!(Window::parse("0x02a00006 0 4242 100\n").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+//! List, extract, add and remove the cookies of an X authority file through `xauth`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xauth::{Authority, Cookie};
+//!
+//! // let a nested X server on :1 accept the same clients as :0
+//! let authority = Authority::new();
+//! let cookie = authority.list().unwrap().into_iter().find(|e| e.number() == Some(0)).unwrap();
+//! authority.add(":1", &cookie.cookie).unwrap();
+//! # }
+//! ```
+//!
+//! xauth edits files and never talks to the X server.
+
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{platform, runner};
+
+/// Name of the protocol of cookies that are checked by comparing them
+pub const MIT_MAGIC_COOKIE: &str = "MIT-MAGIC-COOKIE-1";
+
+/// Errors of running xauth
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xauth could not be run, failed or timed out
+    Run(RunError),
+    /// Output of xauth or a cookie could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::Malformed(line) => write!(f, "could not parse xauth output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Secret data a client presents to the X server, written in hexadecimal
+///
+/// Its [Debug](fmt::Debug) output only shows the length, so the cookie does not end up in logs.
+#[derive(Default, PartialEq, Eq, Hash, Clone)]
+pub struct Cookie(pub Vec<u8>);
+
+impl fmt::Debug for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cookie(<{} bytes>)", self.0.len())
+    }
+}
+
+impl Cookie {
+    /// New random cookie of 16 bytes, the size xauth and display managers use for
+    /// `MIT-MAGIC-COOKIE-1`, read from `/dev/urandom`
+    pub fn random() -> io::Result<Self> {
+        let mut cookie = vec![0; 16];
+        fs::File::open("/dev/urandom")?.read_exact(&mut cookie)?;
+        Ok(Cookie(cookie))
+    }
+}
+
+impl fmt::Display for Cookie {
+    /// Format as lowercase hexadecimal, as xauth prints and takes it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cookie {
+    type Err = Error;
+
+    /// From an even number of hexadecimal digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(s.to_owned());
+        let s = s.trim();
+        if !s.len().is_multiple_of(2) || !s.is_ascii() {
+            return Err(malformed());
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| malformed()))
+            .collect::<Result<_, _>>()
+            .map(Cookie)
+    }
+}
+
+#[cfg(feature = "serde")]
+pino_xcommon::serde_as_str!(Cookie, "cookie in hexadecimal");
+
+/// Entry of an authority file, as listed by `xauth list`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Entry {
+    /// Display the cookie is for, such as `pino/unix:0`, or `#ffff#...#:0` for any host
+    pub display: String,
+    /// Protocol the cookie is checked with, usually [MIT_MAGIC_COOKIE]
+    pub protocol: String,
+    /// Secret data of the entry
+    pub cookie: Cookie,
+}
+
+impl Entry {
+    /// Parse the output of `xauth list`
+    ///
+    /// ```rust
+    /// use pino_xauth::{Cookie, Entry};
+    ///
+    /// let entries = Entry::parse(concat!(
+    ///     "pino/unix:0  MIT-MAGIC-COOKIE-1  9d4f0a4e3c2b1a0f9e8d7c6b5a493827\n",
+    ///     "pino/unix:1  MIT-MAGIC-COOKIE-1  00ff\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(entries[0].number(), Some(0));
+    /// assert!(entries[0].is_mit_magic_cookie());
+    /// assert_eq!(entries[1].cookie, Cookie(vec![0x00, 0xff]));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Vec<Self>, Error> {
+        raw.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|line| {
+                let malformed = || Error::Malformed(line.to_owned());
+                let mut fields = line.split_whitespace();
+                let (Some(display), Some(protocol), Some(cookie), None) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(malformed());
+                };
+                Ok(Entry {
+                    display: display.to_owned(),
+                    protocol: protocol.to_owned(),
+                    cookie: cookie.parse().map_err(|_| malformed())?,
+                })
+            })
+            .collect()
+    }
+
+    /// Number of the display, `0` for `pino/unix:0`
+    pub fn number(&self) -> Option<u32> {
+        let (_, number) = self.display.rsplit_once(':')?;
+        number.split('.').next()?.parse().ok()
+    }
+
+    /// Check if the cookie is a `MIT-MAGIC-COOKIE-1`
+    pub fn is_mit_magic_cookie(&self) -> bool {
+        self.protocol == MIT_MAGIC_COOKIE
+    }
+}
+
+/// Authority file holding the cookies, edited through xauth
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Authority {
+    file: Option<PathBuf>,
+}
+
+impl Authority {
+    /// Authority file of the session, `$XAUTHORITY` or `~/.Xauthority` as xauth picks it
+    pub fn new() -> Self {
+        Authority::default()
+    }
+
+    /// Use the given authority file instead, such as one made for a nested X server
+    pub fn file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Authority file given with [Authority::file]
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Every entry of the authority file
+    #[cfg(feature = "process")]
+    pub fn list(&self) -> Result<Vec<Entry>, Error> {
+        self.list_with_runner(&runner::SystemRunner::default())
+    }
+
+    /// Every entry of the authority file, running `xauth list` through the given runner
+    pub fn list_with_runner(&self, runner: &dyn CommandRunner) -> Result<Vec<Entry>, Error> {
+        let output = run_checked(runner, &self.xauth().arg("list"))?;
+        Entry::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Entries for `display`, such as `:0`, in the binary format of authority files, to be
+    /// merged into another one
+    #[cfg(feature = "process")]
+    pub fn extract(&self, display: &str) -> Result<Vec<u8>, Error> {
+        self.extract_with_runner(&runner::SystemRunner::default(), display)
+    }
+
+    /// Entries for `display` in binary, running `xauth extract` through the given runner
+    pub fn extract_with_runner(
+        &self,
+        runner: &dyn CommandRunner,
+        display: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let output = run_checked(runner, &self.xauth().args(["extract", "-", display]))?;
+        Ok(output.stdout)
+    }
+
+    /// Add the entries of `data`, as returned by [Authority::extract], replacing those for the
+    /// same displays
+    #[cfg(feature = "process")]
+    pub fn merge(&self, data: &[u8]) -> Result<(), Error> {
+        self.merge_with_runner(&runner::SystemRunner::default(), data)
+    }
+
+    /// Add the entries of `data`, running `xauth merge` through the given runner
+    pub fn merge_with_runner(&self, runner: &dyn CommandRunner, data: &[u8]) -> Result<(), Error> {
+        run_checked(runner, &self.xauth().args(["merge", "-"]).stdin(data))?;
+        Ok(())
+    }
+
+    /// Add a `MIT-MAGIC-COOKIE-1` for `display`, such as `:1`, replacing any other for it
+    #[cfg(feature = "process")]
+    pub fn add(&self, display: &str, cookie: &Cookie) -> Result<(), Error> {
+        self.add_with_runner(&runner::SystemRunner::default(), display, cookie)
+    }
+
+    /// Add a cookie for `display`, running `xauth add` through the given runner
+    ///
+    /// The command is read from the standard input of xauth, since its command line can be read
+    /// by any user.
+    ///
+    /// ```rust
+    /// use pino_xauth::{runner::{Cmd, MockRunner, Output}, Authority, Cookie};
+    ///
+    /// let cmd = Cmd::new("xauth")
+    ///     .args(["-q", "-f", "/tmp/xephyr.auth", "source", "-"])
+    ///     .stdin("add :1 MIT-MAGIC-COOKIE-1 00ff\n");
+    /// let runner = MockRunner::new().respond(cmd, Output::default());
+    /// let authority = Authority::new().file("/tmp/xephyr.auth");
+    /// authority.add_with_runner(&runner, ":1", &Cookie(vec![0x00, 0xff])).unwrap();
+    /// ```
+    pub fn add_with_runner(
+        &self,
+        runner: &dyn CommandRunner,
+        display: &str,
+        cookie: &Cookie,
+    ) -> Result<(), Error> {
+        let add = format!("add {} {} {}\n", display, MIT_MAGIC_COOKIE, cookie);
+        let cmd = self.xauth().args(["source", "-"]).stdin(add);
+        run_checked(runner, &cmd)?;
+        Ok(())
+    }
+
+    /// Remove every entry for `display`, such as `:1`
+    #[cfg(feature = "process")]
+    pub fn remove(&self, display: &str) -> Result<(), Error> {
+        self.remove_with_runner(&runner::SystemRunner::default(), display)
+    }
+
+    /// Remove the entries for `display`, running `xauth remove` through the given runner
+    pub fn remove_with_runner(
+        &self,
+        runner: &dyn CommandRunner,
+        display: &str,
+    ) -> Result<(), Error> {
+        run_checked(runner, &self.xauth().args(["remove", display]))?;
+        Ok(())
+    }
+
+    /// xauth acting on the authority file, without complaining that it does not exist yet
+    fn xauth(&self) -> Cmd {
+        let cmd = Cmd::new("xauth").arg("-q");
+        match &self.file {
+            Some(file) => cmd.args(["-f".into(), file.to_string_lossy().into_owned()]),
+            None => cmd,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn copy_cookie() {
+        let extracted = b"\x01\x00\x00\x04pino\x00\x010".to_vec();
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xauth").args(["-q", "list"]),
+                Output::from_stdout(concat!(
+                    "pino/unix:0  MIT-MAGIC-COOKIE-1  9d4f0a4e3c2b1a0f9e8d7c6b5a493827\n",
+                    "#ffff#70696e6f#:2.0  XDM-AUTHORIZATION-1  00112233\n",
+                )),
+            )
+            .respond(
+                Cmd::new("xauth").args(["-q", "extract", "-", ":0"]),
+                Output::from_stdout(extracted.clone()),
+            )
+            .respond(
+                Cmd::new("xauth")
+                    .args(["-q", "-f", "/tmp/nested", "merge", "-"])
+                    .stdin(extracted),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("xauth").args(["-q", "-f", "/tmp/nested", "remove", ":0"]),
+                Output::from_failure(1, "xauth:  timeout in locking authority file /tmp/nested\n"),
+            );
+
+        let authority = Authority::new();
+        let entries = authority.list_with_runner(&runner).unwrap();
+        assert_eq!(
+            entries[0].cookie.to_string(),
+            "9d4f0a4e3c2b1a0f9e8d7c6b5a493827"
+        );
+        assert_eq!(entries[1].number(), Some(2));
+        assert!(!entries[1].is_mit_magic_cookie());
+
+        let nested = Authority::new().file("/tmp/nested");
+        let data = authority.extract_with_runner(&runner, ":0").unwrap();
+        nested.merge_with_runner(&runner, &data).unwrap();
+        assert!(nested.remove_with_runner(&runner, ":0").is_err());
+
+        assert!("0f0".parse::<Cookie>().is_err());
+        assert!("zz".parse::<Cookie>().is_err());
+    }
+
+    #[test]
+    fn report_failures() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xauth").args(["-q", "list"]),
+                Output::from_stdout("pino/unix:0  MIT-MAGIC-COOKIE-1\n"),
+            )
+            .respond(
+                Cmd::new("xauth").args(["-q", "-f", "/root/.Xauthority", "list"]),
+                Output::from_stdout("pino/unix:0  MIT-MAGIC-COOKIE-1  not-hex\n"),
+            )
+            .respond(
+                Cmd::new("xauth").args(["-q", "extract", "-", ":7"]),
+                Output::from_failure(1, "xauth:  unable to write authority file -\n"),
+            );
+
+        let err = Authority::new().list_with_runner(&runner).unwrap_err();
+        assert!(matches!(err, Error::Malformed(line) if line.ends_with("COOKIE-1")));
+        let root = Authority::new().file("/root/.Xauthority");
+        assert!(matches!(
+            root.list_with_runner(&runner),
+            Err(Error::Malformed(_))
+        ));
+
+        let err = Authority::new()
+            .extract_with_runner(&runner, ":7")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, ref stderr, .. }) if stderr.contains("unable to write")
+        ));
+
+        // not installed
+        let err = Authority::new()
+            .merge_with_runner(&MockRunner::new(), b"")
+            .unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Spawn { program, .. }) if program == "xauth"));
+    }
+
+    #[test]
+    fn add_cookie_secretly() {
+        let cookie = Cookie(vec![0x9d, 0x4f, 0x0a, 0x4e]);
+        let runner = MockRunner::new().respond(
+            Cmd::new("xauth")
+                .args(["-q", "source", "-"])
+                .stdin("add :1 MIT-MAGIC-COOKIE-1 9d4f0a4e\n"),
+            Output::default(),
+        );
+        Authority::new()
+            .add_with_runner(&runner, ":1", &cookie)
+            .unwrap();
+        assert!(runner.calls()[0]
+            .args
+            .iter()
+            .all(|arg| !arg.contains("9d4f")));
+        assert_eq!(format!("{:?}", cookie), "Cookie(<4 bytes>)");
+    }
+}
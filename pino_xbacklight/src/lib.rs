@@ -0,0 +1,255 @@
+//! Get and set the backlight brightness through `xbacklight`, or sysfs when it finds no backlight
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use std::time::Duration;
+//!
+//! use pino_xbacklight::Fade;
+//!
+//! println!("brightness at {}%", pino_xbacklight::get().unwrap());
+//! pino_xbacklight::increase(10.0, Fade::new(Duration::from_millis(150))).unwrap();
+//! # }
+//! ```
+//!
+//! xbacklight only sees backlights exposed through RandR, which the modesetting driver does not do,
+//! so [get], [set], [increase] and [decrease] fall back to [sysfs] when it fails.
+
+use std::{fmt, io, path::PathBuf, str::FromStr, time::Duration};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+pub mod sysfs;
+
+/// Errors of running xbacklight or accessing the backlight in sysfs
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xbacklight could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xbacklight or a sysfs file could not be parsed, holding the offending value
+    Malformed(String),
+    /// File of a backlight device could not be read or written, usually because writing needs
+    /// a udev rule or membership in the `video` group
+    Sysfs { path: PathBuf, source: io::Error },
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            Error::Sysfs { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(value) => write!(f, "could not parse backlight value: {}", value),
+            Error::Sysfs { path, source } => {
+                write!(f, "could not access {}: {}", path.display(), source)
+            },
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Gradual change of the brightness, split into `steps` equal changes over `duration`
+///
+/// The default changes the brightness at once.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Fade {
+    /// Time the whole change takes
+    pub duration: Duration,
+    /// Number of changes the fade is made of
+    pub steps: u32,
+}
+
+impl Fade {
+    /// Fade over `duration` in 20 steps, as xbacklight does by default
+    pub fn new(duration: Duration) -> Self {
+        Fade {
+            duration,
+            steps: 20,
+        }
+    }
+
+    /// Make the fade out of `steps` changes, at least one
+    pub fn steps(mut self, steps: u32) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+}
+
+impl Default for Fade {
+    fn default() -> Self {
+        Fade {
+            duration: Duration::ZERO,
+            steps: 1,
+        }
+    }
+}
+
+/// Brightness of the backlight, in percent
+#[cfg(feature = "process")]
+pub fn get() -> Result<f64, Error> {
+    with_fallback(get_with_runner, sysfs::Device::get)
+}
+
+/// Brightness of the backlight, running `xbacklight -get` through the given runner
+pub fn get_with_runner(runner: &dyn CommandRunner) -> Result<f64, Error> {
+    let output = run_checked(runner, &Cmd::new("xbacklight").arg("-get"))?;
+    parse_value(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Set the brightness of the backlight to `percent`
+#[cfg(feature = "process")]
+pub fn set(percent: f64, fade: Fade) -> Result<(), Error> {
+    with_fallback(
+        |runner| set_with_runner(runner, percent, fade),
+        |device| device.set(percent, fade),
+    )
+}
+
+/// Set the brightness, running `xbacklight -set` through the given runner
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use pino_xbacklight::{runner::{Cmd, MockRunner, Output}, Fade};
+///
+/// let cmd = Cmd::new("xbacklight").args(["-set", "40", "-time", "300", "-steps", "10"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// let fade = Fade::new(Duration::from_millis(300)).steps(10);
+/// pino_xbacklight::set_with_runner(&runner, 40.0, fade).unwrap();
+/// ```
+pub fn set_with_runner(runner: &dyn CommandRunner, percent: f64, fade: Fade) -> Result<(), Error> {
+    run_checked(runner, &xbacklight("-set", percent, fade))?;
+    Ok(())
+}
+
+/// Raise the brightness of the backlight by `percent`
+#[cfg(feature = "process")]
+pub fn increase(percent: f64, fade: Fade) -> Result<(), Error> {
+    with_fallback(
+        |runner| increase_with_runner(runner, percent, fade),
+        |device| device.set(device.get()? + percent, fade),
+    )
+}
+
+/// Raise the brightness, running `xbacklight -inc` through the given runner
+pub fn increase_with_runner(
+    runner: &dyn CommandRunner,
+    percent: f64,
+    fade: Fade,
+) -> Result<(), Error> {
+    run_checked(runner, &xbacklight("-inc", percent, fade))?;
+    Ok(())
+}
+
+/// Lower the brightness of the backlight by `percent`
+#[cfg(feature = "process")]
+pub fn decrease(percent: f64, fade: Fade) -> Result<(), Error> {
+    with_fallback(
+        |runner| decrease_with_runner(runner, percent, fade),
+        |device| device.set(device.get()? - percent, fade),
+    )
+}
+
+/// Lower the brightness, running `xbacklight -dec` through the given runner
+pub fn decrease_with_runner(
+    runner: &dyn CommandRunner,
+    percent: f64,
+    fade: Fade,
+) -> Result<(), Error> {
+    run_checked(runner, &xbacklight("-dec", percent, fade))?;
+    Ok(())
+}
+
+/// Run xbacklight, or act on the first backlight device in sysfs if xbacklight is missing,
+/// finds no backlight or has no X server to talk to
+#[cfg(feature = "process")]
+fn with_fallback<T>(
+    xbacklight: impl FnOnce(&dyn CommandRunner) -> Result<T, Error>,
+    sysfs: impl FnOnce(&sysfs::Device) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let display = DisplayHandle::default();
+    let err = match pino_xcommon::session::require_x(&display) {
+        Ok(()) => match xbacklight(&display.runner(&runner::SystemRunner::default())) {
+            Ok(value) => return Ok(value),
+            Err(e @ Error::Run(RunError::Spawn { .. } | RunError::Exited { .. })) => e,
+            Err(e) => return Err(e),
+        },
+        Err(session) => Error::NoXServer(session),
+    };
+    match sysfs::Device::first() {
+        Some(device) => sysfs(&device),
+        None => Err(err),
+    }
+}
+
+/// Command running xbacklight with an operation on `percent`, over the given fade
+fn xbacklight(operation: &str, percent: f64, fade: Fade) -> Cmd {
+    let time = fade.duration.as_millis().to_string();
+    let cmd = Cmd::new("xbacklight")
+        .args([operation, &percent.to_string()])
+        .args(["-time", &time, "-steps", &fade.steps.to_string()]);
+    // xbacklight sleeps through the fade before exiting
+    match fade.duration.is_zero() {
+        true => cmd,
+        false => cmd.timeout(runner::DEFAULT_TIMEOUT + fade.duration),
+    }
+}
+
+/// Parse a value, failing with the value itself
+fn parse_value<T: FromStr>(value: &str) -> Result<T, Error> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::Malformed(value.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn brightness_with_fade() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xbacklight").arg("-get"),
+                Output::from_stdout("62.500000\n"),
+            )
+            .respond(
+                Cmd::new("xbacklight").args(["-dec", "12.5", "-time", "0", "-steps", "1"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("xbacklight").args(["-inc", "5", "-time", "1500", "-steps", "20"]),
+                Output::from_failure(1, "No outputs have backlight property\n"),
+            );
+
+        assert_eq!(get_with_runner(&runner).unwrap(), 62.5);
+        decrease_with_runner(&runner, 12.5, Fade::default()).unwrap();
+        let fade = Fade::new(Duration::from_millis(1500));
+        let err = increase_with_runner(&runner, 5.0, fade).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "xbacklight exited with status 1: No outputs have backlight property"
+        );
+        assert_eq!(Fade::default().steps(0).steps, 1);
+    }
+}
@@ -0,0 +1,152 @@
+//! Backlight devices of the kernel in `/sys/class/backlight`
+//!
+//! They work without an X server and whatever the driver, but writing the brightness usually
+//! needs a udev rule or membership in the `video` group.
+//!
+//! ```rust,no_run
+//! use pino_xbacklight::{sysfs::Device, Fade};
+//!
+//! let device = Device::first().unwrap();
+//! println!("{} at {}%", device.name(), device.get().unwrap());
+//! device.set(100.0, Fade::default()).unwrap();
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::{parse_value, Error, Fade};
+
+/// Directory holding a directory for each backlight device
+pub const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// Backlight device, a directory such as `/sys/class/backlight/intel_backlight`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Device {
+    path: PathBuf,
+}
+
+impl Device {
+    /// Device in the given directory, which is not checked until it is read
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Device { path: path.into() }
+    }
+
+    /// Every backlight device, sorted by name
+    pub fn list() -> Vec<Device> {
+        let mut devices = fs::read_dir(BACKLIGHT_DIR)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| Device::open(entry.path()))
+            .collect::<Vec<_>>();
+        devices.sort_by(|a, b| a.path.cmp(&b.path));
+        devices
+    }
+
+    /// Device controlling the backlight of the screen, preferring the interfaces of the firmware
+    /// and platform over the raw one of the graphics card, as the kernel advises
+    pub fn first() -> Option<Device> {
+        Device::list()
+            .into_iter()
+            .min_by_key(|device| match device.kind().as_deref() {
+                Some("firmware") => 0,
+                Some("platform") => 1,
+                _ => 2,
+            })
+    }
+
+    /// Directory of the device
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Name of the device, such as `intel_backlight`
+    pub fn name(&self) -> &str {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    /// Brightness the device can be set to, from `0` to `max_brightness`
+    pub fn max(&self) -> Result<u32, Error> {
+        parse_value(&self.read("max_brightness")?)
+    }
+
+    /// Brightness the device is set to, from `0` to [Device::max]
+    pub fn brightness(&self) -> Result<u32, Error> {
+        parse_value(&self.read("brightness")?)
+    }
+
+    /// Brightness of the device, in percent
+    pub fn get(&self) -> Result<f64, Error> {
+        Ok(self.brightness()? as f64 * 100.0 / self.max()?.max(1) as f64)
+    }
+
+    /// Set the brightness of the device to `percent`, clamped between 0 and 100, blocking
+    /// through the fade
+    pub fn set(&self, percent: f64, fade: Fade) -> Result<(), Error> {
+        let max = self.max()?;
+        let target = (percent.clamp(0.0, 100.0) * max as f64 / 100.0).round() as u32;
+        let start = match fade.duration.is_zero() {
+            true => target,
+            false => self.brightness()?,
+        };
+        let steps = fade.steps.max(1);
+        for step in 1..=steps {
+            let value = start as f64 + (target as f64 - start as f64) * step as f64 / steps as f64;
+            self.write("brightness", &(value.round() as u32).to_string())?;
+            if step < steps {
+                thread::sleep(fade.duration / steps);
+            }
+        }
+        Ok(())
+    }
+
+    /// Type of interface, `firmware`, `platform` or `raw`
+    fn kind(&self) -> Option<String> {
+        self.read("type").ok().map(|kind| kind.trim().to_owned())
+    }
+
+    /// Read a file of the device
+    fn read(&self, file: &str) -> Result<String, Error> {
+        let path = self.path.join(file);
+        fs::read_to_string(&path).map_err(|source| Error::Sysfs { path, source })
+    }
+
+    /// Write a file of the device
+    fn write(&self, file: &str, value: &str) -> Result<(), Error> {
+        let path = self.path.join(file);
+        fs::write(&path, value).map_err(|source| Error::Sysfs { path, source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn read_and_write_device() {
+        let dir = env::temp_dir().join(format!("pino_xbacklight_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("max_brightness"), "1200\n").unwrap();
+        fs::write(dir.join("brightness"), "300\n").unwrap();
+        let device = Device::open(&dir);
+
+        assert_eq!(device.get().unwrap(), 25.0);
+        device
+            .set(50.0, Fade::new(Duration::from_millis(4)).steps(4))
+            .unwrap();
+        assert_eq!(device.brightness().unwrap(), 600);
+        device.set(120.0, Fade::default()).unwrap();
+        assert_eq!(device.brightness().unwrap(), 1200);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(device.get(), Err(Error::Sysfs { .. })));
+    }
+}
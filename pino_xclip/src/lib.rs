@@ -0,0 +1,325 @@
+//! Read and write the clipboard and primary selection through `xclip` or `xsel`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xclip::Selection;
+//!
+//! let text = pino_xclip::read(Selection::Primary).unwrap();
+//! pino_xclip::write(Selection::Clipboard, &text.to_uppercase()).unwrap();
+//! # }
+//! ```
+//!
+//! Whichever of xclip and xsel is installed is used, preferring xclip since xsel only handles text.
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+/// Errors of running xclip or xsel
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xclip could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Neither xclip nor xsel is installed
+    NoTool,
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::NoTool => write!(f, "neither xclip nor xsel is installed"),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Selection holding the data, as defined by ICCCM
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Selection {
+    /// Filled by copying explicitly, usually with Ctrl+C
+    #[default]
+    Clipboard,
+    /// Filled by selecting text, and pasted with the middle mouse button
+    Primary,
+}
+
+/// Program used to access the selections
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Tool {
+    /// `xclip`, which handles any target
+    Xclip,
+    /// `xsel`, which only handles text
+    Xsel,
+}
+
+impl Tool {
+    /// Installed tool, preferring xclip, detected once and remembered afterwards
+    #[cfg(feature = "process")]
+    pub fn detect() -> Option<Tool> {
+        static DETECTED: std::sync::OnceLock<Option<Tool>> = std::sync::OnceLock::new();
+        *DETECTED.get_or_init(|| Tool::detect_with_runner(&runner::SystemRunner::default()))
+    }
+
+    /// Installed tool, checking through the given runner which of them can be run
+    ///
+    /// ```rust
+    /// use pino_xclip::{runner::{Cmd, MockRunner, Output}, Tool};
+    ///
+    /// let runner = MockRunner::new().respond(Cmd::new("xsel").arg("--version"), Output::default());
+    /// assert_eq!(Tool::detect_with_runner(&runner), Some(Tool::Xsel));
+    /// assert_eq!(Tool::detect_with_runner(&MockRunner::new()), None);
+    /// ```
+    pub fn detect_with_runner(runner: &dyn CommandRunner) -> Option<Tool> {
+        [
+            (Tool::Xclip, Cmd::new("xclip").arg("-version")),
+            (Tool::Xsel, Cmd::new("xsel").arg("--version")),
+        ]
+        .into_iter()
+        .find(|(_, cmd)| runner.output(cmd).is_ok())
+        .map(|(tool, _)| tool)
+    }
+
+    /// Command reading the selection, converted to `target` if given
+    fn read(self, selection: Selection, target: Option<&str>) -> Result<Cmd, Error> {
+        let cmd = match self {
+            Tool::Xclip => xclip(selection, target).arg("-o"),
+            Tool::Xsel => xsel(selection, target)?.arg("--output"),
+        };
+        Ok(cmd)
+    }
+
+    /// Command taking over the selection, serving what it reads from its input
+    fn write(self, selection: Selection, target: Option<&str>) -> Result<Cmd, Error> {
+        let cmd = match self {
+            // xclip stays in the background to serve the selection without closing its output,
+            // which would be waited on forever
+            Tool::Xclip => xclip(selection, target).arg("-i").discard_output(),
+            Tool::Xsel => xsel(selection, target)?.arg("--input"),
+        };
+        Ok(cmd)
+    }
+}
+
+/// xclip acting on the selection, converting to `target` if given
+fn xclip(selection: Selection, target: Option<&str>) -> Cmd {
+    let name = match selection {
+        Selection::Clipboard => "clipboard",
+        Selection::Primary => "primary",
+    };
+    let cmd = Cmd::new("xclip").args(["-selection", name]);
+    match target {
+        Some(target) => cmd.args(["-t", target]),
+        None => cmd,
+    }
+}
+
+/// xsel acting on the selection, which it can only do as text
+fn xsel(selection: Selection, target: Option<&str>) -> Result<Cmd, Error> {
+    if let Some(target) = target {
+        return Err(Error::Run(RunError::Unsupported(format!(
+            "xsel can not convert the selection to {}, install xclip instead",
+            target
+        ))));
+    }
+    let flag = match selection {
+        Selection::Clipboard => "--clipboard",
+        Selection::Primary => "--primary",
+    };
+    Ok(Cmd::new("xsel").arg(flag))
+}
+
+/// Read the selection as text
+#[cfg(feature = "process")]
+pub fn read(selection: Selection) -> Result<String, Error> {
+    let (display, tool) = setup()?;
+    read_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        tool,
+        selection,
+    )
+}
+
+/// Read the selection as text, running the tool through the given runner
+pub fn read_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    selection: Selection,
+) -> Result<String, Error> {
+    let output = run_checked(runner, &tool.read(selection, None)?)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read the selection converted to a target such as `image/png`, which needs xclip
+#[cfg(feature = "process")]
+pub fn read_bytes(selection: Selection, target: &str) -> Result<Vec<u8>, Error> {
+    let (display, tool) = setup()?;
+    read_bytes_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        tool,
+        selection,
+        target,
+    )
+}
+
+/// Read the selection converted to a target, running the tool through the given runner
+pub fn read_bytes_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    selection: Selection,
+    target: &str,
+) -> Result<Vec<u8>, Error> {
+    Ok(run_checked(runner, &tool.read(selection, Some(target))?)?.stdout)
+}
+
+/// Targets the owner of the selection can convert it to, such as `UTF8_STRING` or `image/png`,
+/// which needs xclip
+#[cfg(feature = "process")]
+pub fn targets(selection: Selection) -> Result<Vec<String>, Error> {
+    let (display, tool) = setup()?;
+    targets_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        tool,
+        selection,
+    )
+}
+
+/// Targets of the selection, running the tool through the given runner
+pub fn targets_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    selection: Selection,
+) -> Result<Vec<String>, Error> {
+    let bytes = read_bytes_with_runner(runner, tool, selection, "TARGETS")?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Take over the selection with `text`
+///
+/// The tool keeps running in the background to hand the text to other clients, until another
+/// client takes over the selection.
+#[cfg(feature = "process")]
+pub fn write(selection: Selection, text: &str) -> Result<(), Error> {
+    let (display, tool) = setup()?;
+    write_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        tool,
+        selection,
+        text,
+    )
+}
+
+/// Take over the selection with `text`, running the tool through the given runner
+pub fn write_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    selection: Selection,
+    text: &str,
+) -> Result<(), Error> {
+    run_checked(runner, &tool.write(selection, None)?.stdin(text))?;
+    Ok(())
+}
+
+/// Take over the selection with data of a target such as `image/png`, which needs xclip
+#[cfg(feature = "process")]
+pub fn write_bytes(selection: Selection, target: &str, data: &[u8]) -> Result<(), Error> {
+    let (display, tool) = setup()?;
+    write_bytes_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        tool,
+        selection,
+        target,
+        data,
+    )
+}
+
+/// Take over the selection with data of a target, running the tool through the given runner
+pub fn write_bytes_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    selection: Selection,
+    target: &str,
+    data: &[u8],
+) -> Result<(), Error> {
+    run_checked(runner, &tool.write(selection, Some(target))?.stdin(data))?;
+    Ok(())
+}
+
+/// Display to run on and the tool to run
+#[cfg(feature = "process")]
+fn setup() -> Result<(DisplayHandle, Tool), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    Ok((display, Tool::detect().ok_or(Error::NoTool)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn read_and_write() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xclip").args(["-selection", "clipboard", "-t", "TARGETS", "-o"]),
+                Output::from_stdout("TARGETS\nimage/png\n"),
+            )
+            .respond(
+                Cmd::new("xclip").args(["-selection", "primary", "-t", "image/png", "-i"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("xsel").args(["--primary", "--output"]),
+                Output::from_stdout("selected"),
+            );
+
+        let targets = targets_with_runner(&runner, Tool::Xclip, Selection::Clipboard).unwrap();
+        assert_eq!(targets, ["TARGETS", "image/png"]);
+        let png = b"\x89PNG\r\n";
+        write_bytes_with_runner(&runner, Tool::Xclip, Selection::Primary, "image/png", png)
+            .unwrap();
+        assert_eq!(runner.calls()[1].stdin.as_deref(), Some(&png[..]));
+        assert!(runner.calls()[1].discard_output);
+        let text = read_with_runner(&runner, Tool::Xsel, Selection::Primary).unwrap();
+        assert_eq!(text, "selected");
+
+        let err = read_bytes_with_runner(&runner, Tool::Xsel, Selection::Primary, "image/png");
+        assert!(matches!(err, Err(Error::Run(RunError::Unsupported(_)))));
+        assert_eq!(runner.calls().len(), 3);
+
+        // a missing tool is reported by its own name
+        let err = write_with_runner(&runner, Tool::Xsel, Selection::Clipboard, "text");
+        assert!(matches!(
+            err,
+            Err(Error::Run(RunError::Spawn { program, .. })) if program == "xsel"
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+//! Choose between talking to the X server directly and running the client binaries
+//!
+//! When both the `native` and `process` features are enabled, operations try the native backend
+//! first and fall back to the binaries if it fails. The choice can be forced with [set] or the
+//! `PINO_BACKEND` environment variable (`auto`, `native` or `binary`), and the backend that
+//! served the last operation is reported by [last_used].
+//!
+//! ```rust
+//! use pino_xcommon::backend::{self, Backend};
+//!
+//! backend::set(Backend::Binary);
+//! assert_eq!(backend::configured(), Backend::Binary);
+//! ```
+
+use std::{
+    env, fmt,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Way of reaching the X server
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    /// Native if it is compiled in and works, the binaries otherwise
+    #[default]
+    Auto,
+    /// X protocol requests through x11rb, enabled by the `native` feature
+    Native,
+    /// The classic client binaries, enabled by the `process` feature
+    Binary,
+}
+
+impl Backend {
+    fn to_u8(self) -> u8 {
+        match self {
+            Backend::Auto => 1,
+            Backend::Native => 2,
+            Backend::Binary => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Backend::Auto),
+            2 => Some(Backend::Native),
+            3 => Some(Backend::Binary),
+            _ => None,
+        }
+    }
+
+    /// Check if the backend was compiled in
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Auto => Backend::Native.is_available() || Backend::Binary.is_available(),
+            Backend::Native => cfg!(feature = "native"),
+            Backend::Binary => cfg!(feature = "process"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Backend::Auto),
+            "native" => Ok(Backend::Native),
+            "binary" => Ok(Backend::Binary),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Auto => write!(f, "auto"),
+            Backend::Native => write!(f, "native"),
+            Backend::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serde_as_str!(Backend, "backend, one of auto, native or binary");
+
+/// Environment variable overriding the backend, unless one is [set]
+pub const ENV_VAR: &str = "PINO_BACKEND";
+
+static CONFIGURED: AtomicU8 = AtomicU8::new(0);
+static LAST_USED: AtomicU8 = AtomicU8::new(0);
+
+/// Force a backend for every following operation, taking precedence over [ENV_VAR]
+pub fn set(backend: Backend) {
+    CONFIGURED.store(backend.to_u8(), Ordering::Relaxed);
+}
+
+/// Backend given to [set], or in [ENV_VAR], [Backend::Auto] if neither is
+pub fn configured() -> Backend {
+    Backend::from_u8(CONFIGURED.load(Ordering::Relaxed))
+        .or_else(|| env::var(ENV_VAR).ok()?.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Backend that served the last successful operation, `None` before the first one
+pub fn last_used() -> Option<Backend> {
+    Backend::from_u8(LAST_USED.load(Ordering::Relaxed))
+}
+
+/// Backends to try in order, leaving out the ones that are not compiled in
+pub fn candidates() -> Vec<Backend> {
+    let order = match configured() {
+        Backend::Auto => vec![Backend::Native, Backend::Binary],
+        backend => vec![backend],
+    };
+    order.into_iter().filter(|b| b.is_available()).collect()
+}
+
+/// Run an operation with each of the [candidates] until one succeeds
+///
+/// `f` returns `None` for backends the calling crate does not implement. The error of the last
+/// backend tried is returned if all of them fail, and `None` if no backend was tried at all.
+pub fn dispatch<T, E>(mut f: impl FnMut(Backend) -> Option<Result<T, E>>) -> Option<Result<T, E>> {
+    let mut result = None;
+    for backend in candidates() {
+        let Some(outcome) = f(backend) else {
+            continue;
+        };
+        if outcome.is_ok() {
+            LAST_USED.store(backend.to_u8(), Ordering::Relaxed);
+            return Some(outcome);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%backend, "backend failed");
+        result = Some(outcome);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_backend() {
+        assert_eq!("Native".parse(), Ok(Backend::Native));
+        assert_eq!(Backend::Binary.to_string().parse(), Ok(Backend::Binary));
+        assert!("wayland".parse::<Backend>().is_err());
+    }
+
+    #[test]
+    fn dispatch_fallback() {
+        // the only test touching the global configuration, so it can not race with another
+        set(Backend::Auto);
+        let mut tried = vec![];
+        let result = dispatch(|backend| {
+            tried.push(backend);
+            Some(match backend {
+                Backend::Native => Err("no connection"),
+                _ => Ok(backend),
+            })
+        });
+        let expected = candidates();
+        assert_eq!(tried, expected);
+        if expected.last() == Some(&Backend::Binary) {
+            assert_eq!(result, Some(Ok(Backend::Binary)));
+            assert_eq!(last_used(), Some(Backend::Binary));
+        }
+
+        set(Backend::Native);
+        assert_eq!(candidates().len(), cfg!(feature = "native") as usize);
+        set(Backend::Auto);
+    }
+}
@@ -0,0 +1,159 @@
+//! Probe the version and supported flags of the installed client binaries
+//!
+//! ```rust
+//! use pino_xcommon::{
+//!     capability::{probe, Version},
+//!     runner::{Cmd, MockRunner, Output},
+//! };
+//!
+//! let runner = MockRunner::new()
+//!     .respond(Cmd::new("xrdb").arg("-version"), Output::from_stdout("xrdb 1.2.1\n"))
+//!     .respond(
+//!         Cmd::new("xrdb").arg("-help"),
+//!         Output::from_failure(1, "usage: xrdb [-options ...] [filename]\n -nocpp  do not use cpp\n"),
+//!     );
+//! let caps = probe(&runner, "xrdb", &["-nocpp", "-edit"]);
+//!
+//! assert_eq!(caps.version, Some(Version::new(1, 2, 1)));
+//! assert!(caps.supports("-nocpp"));
+//! assert!(!caps.supports("-edit"));
+//! ```
+
+use std::{fmt, io};
+
+use crate::runner::{Cmd, CommandRunner};
+
+/// Version of a program, missing components are zero
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Construct a version from its components
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// First dotted version number in `text`, such as `1.0.11` in `xmodmap 1.0.11`
+    ///
+    /// ```rust
+    /// # use pino_xcommon::capability::Version;
+    /// assert_eq!(Version::find("xmodmap 1.0.11"), Some(Version::new(1, 0, 11)));
+    /// assert_eq!(Version::find("version 2.1"), Some(Version::new(2, 1, 0)));
+    /// assert_eq!(Version::find("unknown option -version"), None);
+    /// ```
+    pub fn find(text: &str) -> Option<Self> {
+        text.split(|c: char| !c.is_ascii_digit() && c != '.')
+            .filter(|word| word.contains('.'))
+            .find_map(|word| {
+                let mut parts = word.split('.').map(|part| part.parse::<u32>().ok());
+                let major = parts.next()??;
+                let minor = parts.next()??;
+                let patch = parts.next().unwrap_or(Some(0))?;
+                Some(Version::new(major, minor, patch))
+            })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// What an installed program supports
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Capabilities {
+    pub program: String,
+    /// Whether the program could be run at all
+    pub installed: bool,
+    /// Version reported by `-version`, `None` if it does not report one
+    pub version: Option<Version>,
+    /// Flags that were probed for and are listed in the usage of the program
+    pub flags: Vec<String>,
+}
+
+impl Capabilities {
+    /// Check if `flag` was found in the usage of the program
+    pub fn supports(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Check if the program is at least `version`, `false` if its version is unknown
+    pub fn at_least(&self, version: Version) -> bool {
+        self.version.is_some_and(|v| v >= version)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.installed {
+            return write!(f, "{}: not installed", self.program);
+        }
+        match self.version {
+            Some(version) => write!(f, "{} {}", self.program, version)?,
+            None => write!(f, "{} (unknown version)", self.program)?,
+        }
+        if !self.flags.is_empty() {
+            write!(f, ", supports {}", self.flags.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `program -version` and `program -help`, checking which of `flags` its usage lists
+///
+/// Both commands may exit with an error, X client binaries print their usage on unknown options.
+pub fn probe(runner: &dyn CommandRunner, program: &str, flags: &[&str]) -> Capabilities {
+    let text = |arg: &str| -> io::Result<String> {
+        let output = runner.output(&Cmd::new(program).arg(arg))?;
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        Ok(text)
+    };
+
+    let mut caps = Capabilities {
+        program: program.to_owned(),
+        ..Default::default()
+    };
+    let Ok(version) = text("-version") else {
+        return caps;
+    };
+    caps.installed = true;
+    caps.version = Version::find(&version);
+
+    let usage = text("-help").unwrap_or_default();
+    let listed = usage
+        .split(|c: char| c.is_whitespace() || c == '[' || c == ']' || c == ',')
+        .collect::<Vec<_>>();
+    caps.flags = flags
+        .iter()
+        .filter(|flag| listed.contains(flag))
+        .map(|flag| flag.to_string())
+        .collect();
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::MockRunner;
+
+    #[test]
+    fn probe_missing() {
+        let caps = probe(&MockRunner::new(), "xmodmap", &["-pke"]);
+        assert!(!caps.installed);
+        assert_eq!(caps.to_string(), "xmodmap: not installed");
+    }
+}
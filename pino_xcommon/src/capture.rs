@@ -0,0 +1,250 @@
+//! Record the raw input and output of every command, to attach to bug reports
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use pino_xcommon::{
+//!     capture::{CaptureLog, CaptureRunner},
+//!     runner::{Cmd, CommandRunner, MockRunner, Output},
+//! };
+//!
+//! let log = Arc::new(CaptureLog::new());
+//! let mock = MockRunner::new().respond(
+//!     Cmd::new("xrdb").arg("-query"),
+//!     Output::from_stdout("*.color1: #ea6962\n"),
+//! );
+//! let runner = CaptureRunner::new(mock, log.clone());
+//! runner.output(&Cmd::new("xrdb").arg("-query")).unwrap();
+//!
+//! assert_eq!(log.entries()[0].cmd, Cmd::new("xrdb").arg("-query"));
+//! assert!(log.to_string().contains("*.color1: #ea6962"));
+//! ```
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read},
+    sync::{Arc, Mutex},
+};
+
+use crate::runner::{Cmd, CommandRunner, Output, Process};
+
+/// A command that was run along with its output, or the error it failed with
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Capture {
+    pub cmd: Cmd,
+    pub result: Result<Output, String>,
+}
+
+impl fmt::Display for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+        for (key, val) in self.cmd.env.iter() {
+            write!(f, " {}={}", key, val)?;
+        }
+        writeln!(f, " {}", self.cmd)?;
+        if let Some(stdin) = &self.cmd.stdin {
+            writeln!(f, "--- stdin\n{}", String::from_utf8_lossy(stdin))?;
+        }
+        match &self.result {
+            Ok(output) => {
+                writeln!(f, "--- stdout\n{}", String::from_utf8_lossy(&output.stdout))?;
+                writeln!(f, "--- stderr\n{}", String::from_utf8_lossy(&output.stderr))?;
+                writeln!(f, "--- status {}", output.status)
+            },
+            Err(e) => writeln!(f, "--- error {}", e),
+        }
+    }
+}
+
+/// Receives every command run through a [CaptureRunner]
+///
+/// Implemented for closures, so captures can be forwarded to a logger or file as they happen.
+pub trait CaptureSink: Send + Sync {
+    fn record(&self, capture: Capture);
+}
+
+impl<F: Fn(Capture) + Send + Sync> CaptureSink for F {
+    fn record(&self, capture: Capture) {
+        self(capture)
+    }
+}
+
+/// Keeps every capture in memory
+#[derive(Debug, Default)]
+pub struct CaptureLog {
+    entries: Mutex<Vec<Capture>>,
+}
+
+impl CaptureLog {
+    /// Construct an empty log
+    pub fn new() -> Self {
+        CaptureLog::default()
+    }
+
+    /// Every capture so far, in the order the commands finished
+    pub fn entries(&self) -> Vec<Capture> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+impl CaptureSink for CaptureLog {
+    fn record(&self, capture: Capture) {
+        self.entries.lock().unwrap().push(capture);
+    }
+}
+
+impl fmt::Display for CaptureLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for capture in self.entries.lock().unwrap().iter() {
+            writeln!(f, "{}", capture)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runner passing the command line, input and output of every command to a [CaptureSink]
+///
+/// Commands are recorded once they finish. The stdout of a spawned process includes whatever was
+/// read through [Process::stdout] before it was waited on.
+pub struct CaptureRunner<R> {
+    runner: R,
+    sink: Arc<dyn CaptureSink>,
+}
+
+impl<R: CommandRunner> CaptureRunner<R> {
+    /// Wrap `runner`, recording into `sink`
+    pub fn new(runner: R, sink: Arc<dyn CaptureSink>) -> Self {
+        CaptureRunner { runner, sink }
+    }
+}
+
+impl<R: CommandRunner> CommandRunner for CaptureRunner<R> {
+    fn spawn(&self, cmd: &Cmd) -> io::Result<Box<dyn Process>> {
+        match self.runner.spawn(cmd) {
+            Ok(process) => Ok(Box::new(CaptureProcess {
+                cmd: cmd.clone(),
+                process,
+                read: vec![],
+                sink: self.sink.clone(),
+            })),
+            Err(e) => {
+                self.sink.record(Capture {
+                    cmd: cmd.clone(),
+                    result: Err(e.to_string()),
+                });
+                Err(e)
+            },
+        }
+    }
+}
+
+struct CaptureProcess {
+    cmd: Cmd,
+    process: Box<dyn Process>,
+    // stdout read before the process was waited on
+    read: Vec<u8>,
+    sink: Arc<dyn CaptureSink>,
+}
+
+impl Read for CaptureProcess {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for CaptureProcess {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.process.stdout().fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let stdout = self.process.stdout();
+        if let Ok(buf) = stdout.fill_buf() {
+            self.read.extend_from_slice(&buf[..amt.min(buf.len())]);
+        }
+        stdout.consume(amt);
+    }
+}
+
+impl Process for CaptureProcess {
+    fn stdout(&mut self) -> &mut dyn BufRead {
+        self
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        self.process.kill()
+    }
+
+    fn killer(&self) -> Option<Box<dyn FnOnce() + Send>> {
+        self.process.killer()
+    }
+
+    fn wait(&mut self) -> io::Result<Output> {
+        let result = self.process.wait();
+        let mut stdout = std::mem::take(&mut self.read);
+        let captured = match &result {
+            Ok(output) => {
+                stdout.extend_from_slice(&output.stdout);
+                Ok(Output {
+                    stdout,
+                    ..output.clone()
+                })
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        self.sink.record(Capture {
+            cmd: self.cmd.clone(),
+            result: captured,
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::MockRunner;
+
+    #[test]
+    fn capture_partial_read() {
+        let log = Arc::new(CaptureLog::new());
+        let mock = MockRunner::new().respond(
+            Cmd::new("xmodmap").arg("-pke"),
+            Output::from_stdout("keycode  24 = q\nkeycode  25 = w\n"),
+        );
+        let runner = CaptureRunner::new(mock, log.clone());
+
+        let mut process = runner.spawn(&Cmd::new("xmodmap").arg("-pke")).unwrap();
+        let mut line = String::new();
+        process.stdout().read_line(&mut line).unwrap();
+        assert_eq!(line, "keycode  24 = q\n");
+        assert_eq!(process.wait().unwrap().stdout, b"keycode  25 = w\n");
+
+        let entries = log.entries();
+        assert_eq!(
+            entries[0].result,
+            Ok(Output::from_stdout("keycode  24 = q\nkeycode  25 = w\n"))
+        );
+    }
+
+    #[test]
+    fn capture_errors() {
+        let captures = Arc::new(Mutex::new(vec![]));
+        let sink = captures.clone();
+        let runner = CaptureRunner::new(
+            MockRunner::new(),
+            Arc::new(move |capture: Capture| sink.lock().unwrap().push(capture)),
+        );
+        assert!(runner.output(&Cmd::new("xrdb").stdin("*.a: b")).is_err());
+
+        let captures = captures.lock().unwrap();
+        assert!(captures[0].result.is_err());
+        assert!(captures[0]
+            .to_string()
+            .starts_with("$ xrdb\n--- stdin\n*.a: b\n"));
+    }
+}
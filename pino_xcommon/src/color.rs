@@ -0,0 +1,196 @@
+//! Colors as understood by the X server
+//!
+//! ```rust
+//! use pino_xcommon::color::Color;
+//!
+//! let color = "#ea6962".parse::<Color>().unwrap();
+//! assert_eq!(color, Color::rgb(0xea, 0x69, 0x62));
+//! assert_eq!("rgb:ea/69/62".parse::<Color>(), Ok(color));
+//! assert_eq!(color.to_string(), "#ea6962");
+//! ```
+
+use std::{fmt, str::FromStr};
+
+/// An opaque color with 8 bits per channel
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[cfg(feature = "serde")]
+crate::serde_as_str!(Color, "color in the form #rrggbb");
+
+/// Error returned when a string is not a color
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseColorError(String);
+
+impl std::error::Error for ParseColorError {}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color {}", self.0)
+    }
+}
+
+impl Color {
+    /// Construct a color from its channels
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Color packed as `0xRRGGBB`, the pixel format of a TrueColor visual
+    ///
+    /// ```rust
+    /// # use pino_xcommon::color::Color;
+    /// assert_eq!(Color::rgb(0xea, 0x69, 0x62).to_u32(), 0xea6962);
+    /// assert_eq!(Color::from_u32(0xea6962), Color::rgb(0xea, 0x69, 0x62));
+    /// ```
+    pub const fn to_u32(self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+
+    /// Color from a pixel packed as `0xRRGGBB`, ignoring the upper byte
+    pub const fn from_u32(pixel: u32) -> Self {
+        Color::rgb((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8)
+    }
+
+    /// Channels scaled to the 16 bits the X protocol uses
+    pub const fn to_rgb16(self) -> (u16, u16, u16) {
+        (
+            self.r as u16 * 0x101,
+            self.g as u16 * 0x101,
+            self.b as u16 * 0x101,
+        )
+    }
+
+    /// Color from channels with 16 bits each, rounding to the nearest 8 bit value
+    pub const fn from_rgb16(r: u16, g: u16, b: u16) -> Self {
+        const fn scale(c: u16) -> u8 {
+            ((c as u32 * 0xff + 0x7fff) / 0xffff) as u8
+        }
+        Color::rgb(scale(r), scale(g), scale(b))
+    }
+
+    /// Channels as fractions between `0.0` and `1.0`
+    pub fn to_f32(self) -> [f32; 3] {
+        [self.r, self.g, self.b].map(|c| c as f32 / 255.0)
+    }
+}
+
+/// Parse one to four hex digits into a 16 bit value and the largest value of that many digits
+fn hex_digits(digits: &str) -> Option<(u32, u32)> {
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    Some((value, (1 << (4 * digits.len())) - 1))
+}
+
+/// Channel of `#` specifications, whose digits are the most significant bits
+fn hash_channel(digits: &str) -> Option<u8> {
+    let (value, max) = hex_digits(digits)?;
+    Some((value << (16 - max.count_ones()) >> 8) as u8)
+}
+
+/// Channel of `rgb:` specifications, whose digits are scaled to the full range
+fn scaled_channel(digits: &str) -> Option<u8> {
+    let (value, max) = hex_digits(digits)?;
+    Some(((value * 0xff + max / 2) / max) as u8)
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parse `#rgb`, `#rrggbb`, `#rrrgggbbb`, `#rrrrggggbbbb` or `rgb:r/g/b` with one to four hex
+    /// digits per channel
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_owned());
+        let s = s.trim();
+        let channels = if let Some(hex) = s.strip_prefix('#') {
+            if hex.is_empty() || hex.len() % 3 != 0 || !hex.is_ascii() {
+                return Err(err());
+            }
+            let n = hex.len() / 3;
+            [&hex[..n], &hex[n..2 * n], &hex[2 * n..]].map(hash_channel)
+        } else if let Some(spec) = s.strip_prefix("rgb:") {
+            let mut parts = spec.split('/');
+            let channels = [parts.next(), parts.next(), parts.next()];
+            if parts.next().is_some() {
+                return Err(err());
+            }
+            channels.map(|c| c.and_then(scaled_channel))
+        } else {
+            return Err(err());
+        };
+
+        match channels {
+            [Some(r), Some(g), Some(b)] => Ok(Color::rgb(r, g, b)),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Color::rgb(r, g, b)
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Color::rgb(r, g, b)
+    }
+}
+
+impl From<Color> for [u8; 3] {
+    fn from(value: Color) -> Self {
+        [value.r, value.g, value.b]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formats() {
+        let color = Color::rgb(0xea, 0x69, 0x62);
+        assert_eq!("#ea6962".parse(), Ok(color));
+        assert_eq!("#EA6962".parse(), Ok(color));
+        assert_eq!("#eaea69696262".parse(), Ok(color));
+        assert_eq!("rgb:eaea/6969/6262".parse(), Ok(color));
+        assert_eq!("#f00".parse(), Ok(Color::rgb(0xf0, 0, 0)));
+        assert_eq!("rgb:f/0/0".parse(), Ok(Color::rgb(0xff, 0, 0)));
+
+        for invalid in [
+            "",
+            "ea6962",
+            "#ea696",
+            "#gg0000",
+            "rgb:ff/00",
+            "rgb:ff/0/0/0",
+        ] {
+            assert!(invalid.parse::<Color>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn convert_rgb16() {
+        let color = Color::rgb(0xea, 0x69, 0x62);
+        assert_eq!(color.to_rgb16(), (0xeaea, 0x6969, 0x6262));
+        let (r, g, b) = color.to_rgb16();
+        assert_eq!(Color::from_rgb16(r, g, b), color);
+        assert_eq!(
+            Color::from_rgb16(0xffff, 0, 0x8000),
+            Color::rgb(0xff, 0, 0x80)
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Installed cursor themes, as found by libXcursor
+//!
+//! ```rust,no_run
+//! use pino_xcommon::cursor;
+//!
+//! for theme in cursor::themes() {
+//!     println!("{} in sizes {:?}", theme.name, theme.sizes);
+//! }
+//! ```
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Size libXcursor picks when `Xcursor.size` is unset and the resolution is unknown
+pub const DEFAULT_SIZE: u32 = 24;
+
+/// Directories searched when `$XCURSOR_PATH` is unset, `~` being the home directory
+pub static DEFAULT_PATH: &[&str] = &[
+    "~/.local/share/icons",
+    "~/.icons",
+    "/usr/share/icons",
+    "/usr/share/pixmaps",
+];
+
+/// Cursors probed for the sizes of a theme, in order of preference
+static PROBED_CURSORS: &[&str] = &["left_ptr", "default"];
+
+/// Type of the table of contents entries holding an image
+const IMAGE_TYPE: u32 = 0xfffd_0002;
+
+/// Cursor theme installed on the system
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CursorTheme {
+    /// Name of the theme, the value of `Xcursor.theme`
+    pub name: String,
+    /// Directory of the theme, holding the `cursors` directory
+    pub path: PathBuf,
+    /// Nominal sizes the cursors are drawn in, in ascending order
+    pub sizes: Vec<u32>,
+    /// Themes cursors missing from this one are taken from, read from `index.theme`
+    pub inherits: Vec<String>,
+}
+
+impl CursorTheme {
+    /// Read the theme in a directory, `None` if it has no cursors
+    pub fn open(path: &Path) -> Option<Self> {
+        let cursors = path.join("cursors");
+        if !cursors.is_dir() {
+            return None;
+        }
+        let probed = PROBED_CURSORS
+            .iter()
+            .map(|name| cursors.join(name))
+            .find(|file| file.is_file())
+            .or_else(|| {
+                let mut files = fs::read_dir(&cursors).ok()?.flatten().map(|e| e.path());
+                files.find(|file| file.is_file())
+            });
+        let inherits = fs::read_to_string(path.join("index.theme"))
+            .ok()
+            .and_then(|index| {
+                index.lines().find_map(|line| {
+                    let (key, value) = line.split_once('=')?;
+                    (key.trim() == "Inherits").then(|| {
+                        value
+                            .split([',', ';'])
+                            .map(str::trim)
+                            .filter(|name| !name.is_empty())
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                })
+            })
+            .unwrap_or_default();
+        Some(CursorTheme {
+            name: path.file_name()?.to_string_lossy().into_owned(),
+            path: path.to_owned(),
+            sizes: probed
+                .and_then(|file| sizes(&file).ok())
+                .unwrap_or_default(),
+            inherits,
+        })
+    }
+
+    /// Available size closest to `size`, the one libXcursor would load for it
+    ///
+    /// ```rust
+    /// use pino_xcommon::cursor::CursorTheme;
+    ///
+    /// let theme = CursorTheme {
+    ///     name: String::from("Adwaita"),
+    ///     path: "/usr/share/icons/Adwaita".into(),
+    ///     sizes: vec![24, 32, 48, 64, 96],
+    ///     inherits: vec![],
+    /// };
+    /// assert_eq!(theme.nearest_size(36), Some(32));
+    /// assert_eq!(theme.nearest_size(128), Some(96));
+    /// ```
+    pub fn nearest_size(&self, size: u32) -> Option<u32> {
+        self.sizes.iter().copied().min_by_key(|s| s.abs_diff(size))
+    }
+}
+
+/// Directories searched for cursor themes, from `$XCURSOR_PATH` or else [DEFAULT_PATH]
+pub fn search_path() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let expand = |dir: &str| match (dir.strip_prefix("~/"), &home) {
+        (Some(rest), Some(home)) => Some(home.join(rest)),
+        (Some(_), None) => None,
+        (None, _) => Some(PathBuf::from(dir)),
+    };
+    match env::var("XCURSOR_PATH") {
+        Ok(path) if !path.is_empty() => path.split(':').filter_map(expand).collect(),
+        _ => DEFAULT_PATH.iter().copied().filter_map(expand).collect(),
+    }
+}
+
+/// Every cursor theme in the [search_path], sorted by name
+pub fn themes() -> Vec<CursorTheme> {
+    themes_in(&search_path())
+}
+
+/// Every cursor theme in the directories, sorted by name
+///
+/// A theme in an earlier directory hides those of the same name in later ones, like libXcursor
+/// does.
+pub fn themes_in(dirs: &[PathBuf]) -> Vec<CursorTheme> {
+    let mut themes = Vec::<CursorTheme>::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Some(theme) = CursorTheme::open(&entry.path()) else {
+                continue;
+            };
+            if !themes.iter().any(|t| t.name == theme.name) {
+                themes.push(theme);
+            }
+        }
+    }
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
+
+/// Installed cursor theme of the given name, such as the value of `Xcursor.theme`
+pub fn find(name: &str) -> Option<CursorTheme> {
+    themes().into_iter().find(|theme| theme.name == name)
+}
+
+/// Nominal sizes of the images in an Xcursor file, read from its table of contents
+pub fn sizes(file: &Path) -> io::Result<Vec<u32>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not an Xcursor file");
+    let mut file = fs::File::open(file)?;
+    let mut header = [0; 16];
+    file.read_exact(&mut header)?;
+    let word =
+        |bytes: &[u8], i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+    if &header[..4] != b"Xcur" {
+        return Err(invalid());
+    }
+    // the header length allows for fields added by later versions
+    let (len, toc) = (word(&header, 1) as usize, word(&header, 3) as usize);
+    if len < header.len() {
+        return Err(invalid());
+    }
+    io::copy(
+        &mut (&mut file).take((len - header.len()) as u64),
+        &mut io::sink(),
+    )?;
+    let mut entries = Vec::new();
+    file.take(toc as u64 * 12).read_to_end(&mut entries)?;
+
+    let mut sizes = entries
+        .chunks_exact(12)
+        .filter(|entry| word(entry, 0) == IMAGE_TYPE)
+        .map(|entry| word(entry, 1))
+        .collect::<Vec<_>>();
+    sizes.sort_unstable();
+    sizes.dedup();
+    Ok(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_themes() {
+        let dir = env::temp_dir().join(format!("pino_xcommon_cursor_{}", std::process::id()));
+        let xcursor = |sizes: &[u32]| {
+            let mut raw = b"Xcur".to_vec();
+            for word in [16, 0x1_0000, sizes.len() as u32] {
+                raw.extend(word.to_le_bytes());
+            }
+            for size in sizes {
+                for word in [IMAGE_TYPE, *size, 0] {
+                    raw.extend(word.to_le_bytes());
+                }
+            }
+            raw
+        };
+        let (user, system) = (dir.join("user"), dir.join("system"));
+        for theme in [
+            user.join("Bibata/cursors"),
+            system.join("Bibata/cursors"),
+            system.join("Adwaita/cursors"),
+            system.join("hicolor"),
+        ] {
+            fs::create_dir_all(theme).unwrap();
+        }
+        fs::write(
+            user.join("Bibata/cursors/left_ptr"),
+            xcursor(&[48, 24, 32, 24]),
+        )
+        .unwrap();
+        fs::write(
+            user.join("Bibata/index.theme"),
+            "[Icon Theme]\nInherits=Adwaita\n",
+        )
+        .unwrap();
+        fs::write(system.join("Adwaita/cursors/watch"), xcursor(&[64])).unwrap();
+
+        let themes = themes_in(&[user.clone(), system]);
+        let names = themes.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["Adwaita", "Bibata"]);
+        assert_eq!(themes[0].sizes, [64]);
+        assert_eq!(themes[1].path, user.join("Bibata"));
+        assert_eq!(themes[1].sizes, [24, 32, 48]);
+        assert_eq!(themes[1].inherits, ["Adwaita"]);
+        assert_eq!(themes[1].nearest_size(40), Some(32));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+//! Select the X display the `pino_*` crates talk to
+//!
+//! ```rust
+//! use pino_xcommon::display::DisplayHandle;
+//!
+//! let display = DisplayHandle::new()
+//!     .display(":1")
+//!     .screen(2)
+//!     .auth("/run/user/1000/gdm/Xauthority");
+//! assert_eq!(display.name().as_deref(), Some(":1.2"));
+//! ```
+
+use std::{env, path::PathBuf};
+
+use crate::runner::{Cmd, CommandRunner, Process};
+
+/// X display, screen and authority file to connect to
+///
+/// Anything left unset is taken from the environment (`$DISPLAY` and `$XAUTHORITY`), so the
+/// default handle targets the same display as the binaries would on their own.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DisplayHandle {
+    pub display: Option<String>,
+    pub screen: Option<usize>,
+    pub auth: Option<PathBuf>,
+}
+
+impl DisplayHandle {
+    /// Construct a handle targeting the display of the environment
+    pub fn new() -> Self {
+        DisplayHandle::default()
+    }
+
+    /// Display to connect to, such as `:1` or `host:0`
+    pub fn display(mut self, display: impl Into<String>) -> Self {
+        self.display = Some(display.into());
+        self
+    }
+
+    /// Screen of the display, overriding the one named by the display string
+    pub fn screen(mut self, screen: usize) -> Self {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// Authority file holding the credentials for the display
+    pub fn auth(mut self, auth: impl Into<PathBuf>) -> Self {
+        self.auth = Some(auth.into());
+        self
+    }
+
+    /// Display string including the screen, `None` if neither is overridden
+    pub fn name(&self) -> Option<String> {
+        if self.display.is_none() && self.screen.is_none() {
+            return None;
+        }
+        let display = self
+            .display
+            .clone()
+            .or_else(|| env::var("DISPLAY").ok())
+            .unwrap_or_default();
+        let Some(screen) = self.screen else {
+            return Some(display);
+        };
+
+        // the screen is the part after the dot following the display number
+        let host_len = display.rfind(':').map_or(0, |i| i + 1);
+        let base = match display[host_len..].find('.') {
+            Some(dot) => &display[..host_len + dot],
+            None => &display,
+        };
+        Some(format!("{}.{}", base, screen))
+    }
+
+    /// Point a command at this display by setting `DISPLAY` and `XAUTHORITY`
+    pub fn apply(&self, mut cmd: Cmd) -> Cmd {
+        if let Some(name) = self.name() {
+            cmd = cmd.env("DISPLAY", name);
+        }
+        if let Some(auth) = &self.auth {
+            cmd = cmd.env("XAUTHORITY", auth.to_string_lossy());
+        }
+        cmd
+    }
+
+    /// Wrap a runner so every command it runs targets this display
+    pub fn runner<'a>(&'a self, runner: &'a dyn CommandRunner) -> DisplayRunner<'a> {
+        DisplayRunner {
+            display: self,
+            runner,
+        }
+    }
+}
+
+/// Runner applying a [DisplayHandle] to every command, see [DisplayHandle::runner]
+pub struct DisplayRunner<'a> {
+    display: &'a DisplayHandle,
+    runner: &'a dyn CommandRunner,
+}
+
+impl CommandRunner for DisplayRunner<'_> {
+    fn spawn(&self, cmd: &Cmd) -> std::io::Result<Box<dyn Process>> {
+        self.runner.spawn(&self.display.apply(cmd.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{MockRunner, Output};
+
+    #[test]
+    fn display_name() {
+        assert_eq!(DisplayHandle::new().name(), None);
+        assert_eq!(
+            DisplayHandle::new().display("host:1.0").screen(1).name(),
+            Some(String::from("host:1.1"))
+        );
+        assert_eq!(
+            DisplayHandle::new().display(":0").name(),
+            Some(String::from(":0"))
+        );
+    }
+
+    #[test]
+    fn display_runner() {
+        let mock = MockRunner::new().respond(Cmd::new("xrdb"), Output::default());
+        let display = DisplayHandle::new().display(":1").auth("/tmp/auth");
+        display.runner(&mock).output(&Cmd::new("xrdb")).unwrap();
+        assert_eq!(
+            mock.calls()[0].env,
+            vec![
+                (String::from("DISPLAY"), String::from(":1")),
+                (String::from("XAUTHORITY"), String::from("/tmp/auth")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! Locate the files read in place of the X server when it can not be reached
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Where data was read from
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Source {
+    /// The running X server
+    Server,
+    /// A file in the home directory, read because the X server could not be reached
+    File(PathBuf),
+}
+
+/// Check if `$DISPLAY` is set, without which no X server can be reached
+pub fn has_display() -> bool {
+    env::var_os("DISPLAY").is_some_and(|display| !display.is_empty())
+}
+
+/// First of `names` that is a file in the home directory
+pub fn home_file(names: &[&str]) -> Option<PathBuf> {
+    let home = env::var_os("HOME")?;
+    names
+        .iter()
+        .map(|name| Path::new(&home).join(name))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,100 @@
+//! Gamma correction of the color channels, shared by RandR and the XFree86-VidMode extension
+//!
+//! ```rust
+//! use pino_xcommon::{color::Color, gamma::Gamma};
+//!
+//! let gamma = "1.0:0.9:0.8".parse::<Gamma>().unwrap();
+//! assert_eq!(gamma.to_string(), "1:0.9:0.8");
+//! assert_eq!(Gamma::tint(Color::rgb(0xff, 0xff, 0xff)), Gamma::IDENTITY);
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::color::Color;
+
+/// Gamma correction of each color channel, `1.0` leaves a channel unchanged
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Gamma {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+}
+
+impl Gamma {
+    /// Gamma that leaves every channel unchanged
+    pub const IDENTITY: Gamma = Gamma::uniform(1.0);
+
+    /// Same gamma for every channel
+    pub const fn uniform(gamma: f32) -> Self {
+        Gamma {
+            red: gamma,
+            green: gamma,
+            blue: gamma,
+        }
+    }
+
+    /// Gamma tinting the screen towards `color`, lowering each channel by how far it is from
+    /// full, so white leaves the screen unchanged and a warm color such as `#ffb46b` shifts it
+    /// to orange as night light tools do
+    ///
+    /// Channels are kept at `0.1` or above, the lowest gamma xgamma accepts.
+    pub fn tint(color: Color) -> Self {
+        let [red, green, blue] = color.to_f32().map(|c| c.max(0.1));
+        Gamma { red, green, blue }
+    }
+}
+
+impl Default for Gamma {
+    fn default() -> Self {
+        Gamma::IDENTITY
+    }
+}
+
+impl fmt::Display for Gamma {
+    /// Format as `RED:GREEN:BLUE`, as taken by `xrandr --gamma`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.red, self.green, self.blue)
+    }
+}
+
+impl FromStr for Gamma {
+    type Err = ();
+
+    /// From `RED:GREEN:BLUE`, or a single value for every channel
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let channels = s
+            .split(':')
+            .map(|c| c.trim().parse::<f32>().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+        match channels[..] {
+            [gamma] => Ok(Gamma::uniform(gamma)),
+            [red, green, blue] => Ok(Gamma { red, green, blue }),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gamma() {
+        assert_eq!("0.8".parse(), Ok(Gamma::uniform(0.8)));
+        assert_eq!(
+            " 1.0:0.9:0.8".parse::<Gamma>().unwrap().to_string(),
+            "1:0.9:0.8"
+        );
+        assert!("1.0:0.9".parse::<Gamma>().is_err());
+        assert_eq!(
+            Gamma::tint(Color::rgb(0xff, 0x66, 0)),
+            Gamma {
+                red: 1.0,
+                green: 0.4,
+                blue: 0.1
+            }
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Share one allocation between equal names
+//!
+//! Program names like `dwm`, resource names like `color4` and keysym names repeat across every
+//! table that is parsed. Interning them hands out clones of a single [Arc] instead of allocating a
+//! new string each time. The interner only holds weak references, so a name is freed once the last
+//! table using it is dropped, and parsing untrusted input does not grow it without bound.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use pino_xcommon::intern::intern;
+//!
+//! let a = intern("color4");
+//! let b = intern(&String::from("color4"));
+//! assert!(Arc::ptr_eq(&a, &b));
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+/// Set of interned names
+///
+/// Names whose every copy was dropped are pruned once the set has doubled in size since the last
+/// pruning.
+#[derive(Debug, Default)]
+pub struct Interner {
+    names: Mutex<Names>,
+}
+
+#[derive(Debug, Default)]
+struct Names {
+    names: HashMap<Box<str>, Weak<str>>,
+    /// Number of entries after which dead ones are pruned
+    prune_at: usize,
+}
+
+impl Interner {
+    /// Construct an empty interner
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Shared copy of `name`, allocated the first time it is seen
+    pub fn intern(&self, name: &str) -> Arc<str> {
+        let mut names = self.names.lock().unwrap();
+        if let Some(interned) = names.names.get(name).and_then(Weak::upgrade) {
+            return interned;
+        }
+        if names.names.len() >= names.prune_at {
+            names.names.retain(|_, weak| weak.strong_count() > 0);
+            names.prune_at = (names.names.len() * 2).max(64);
+        }
+        let interned = Arc::<str>::from(name);
+        names
+            .names
+            .insert(Box::from(name), Arc::downgrade(&interned));
+        interned
+    }
+
+    /// Number of distinct names interned that are still in use
+    pub fn len(&self) -> usize {
+        let names = self.names.lock().unwrap();
+        names
+            .names
+            .values()
+            .filter(|weak| weak.strong_count() > 0)
+            .count()
+    }
+
+    /// Check if no interned name is in use
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Interner shared by the parsers of every `pino_*` crate
+pub fn global() -> &'static Interner {
+    static GLOBAL: OnceLock<Interner> = OnceLock::new();
+    GLOBAL.get_or_init(Interner::new)
+}
+
+/// Intern `name` in the [global] interner
+pub fn intern(name: &str) -> Arc<str> {
+    global().intern(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_names() {
+        let interner = Interner::new();
+        let a = interner.intern("dwm");
+        let b = interner.intern("dwm");
+        let c = interner.intern("st");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&*c, "st");
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn free_unused_names() {
+        let interner = Interner::new();
+        let kept = interner.intern("color4");
+        for i in 0..1000 {
+            interner.intern(&format!("U{:04X}", i));
+        }
+        assert_eq!(interner.len(), 1);
+        assert!(interner.names.lock().unwrap().names.len() < 200);
+        assert!(Arc::ptr_eq(&kept, &interner.intern("color4")));
+    }
+}
@@ -0,0 +1,26 @@
+//! Shared plumbing for the `pino_*` crates
+//!
+//! Not meant to be depended on directly, the pieces that are useful downstream are re-exported by
+//! each crate.
+
+pub mod backend;
+pub mod capability;
+pub mod capture;
+pub mod color;
+pub mod cursor;
+pub mod display;
+pub mod fallback;
+pub mod gamma;
+pub mod intern;
+#[cfg(feature = "native")]
+pub mod native;
+#[cfg(feature = "tokio")]
+pub mod nonblocking;
+pub mod platform;
+pub mod resource;
+pub mod retry;
+pub mod runner;
+#[cfg(feature = "serde")]
+pub mod schema;
+pub mod session;
+pub mod window;
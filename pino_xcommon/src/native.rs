@@ -0,0 +1,19 @@
+//! Direct X protocol access, used in place of the external binaries when the `native` feature is
+//! enabled
+
+pub use x11rb;
+use x11rb::{errors::ConnectError, rust_connection::RustConnection};
+
+use crate::display::DisplayHandle;
+
+/// Connect to the display named by `$DISPLAY`, returning the connection and the default screen
+pub fn connect() -> Result<(RustConnection, usize), ConnectError> {
+    connect_display(&DisplayHandle::default())
+}
+
+/// Connect to the display of a handle, returning the connection and the screen to use
+///
+/// The authority file of the handle is not used, the credentials are read from `$XAUTHORITY`.
+pub fn connect_display(display: &DisplayHandle) -> Result<(RustConnection, usize), ConnectError> {
+    x11rb::connect(display.name().as_deref())
+}
@@ -0,0 +1,109 @@
+//! Async counterpart of [crate::runner], enabled by the `tokio` feature
+//!
+//! ```rust,no_run
+//! use pino_xcommon::{nonblocking, runner::Cmd};
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let output = nonblocking::output(&Cmd::new("xrdb").arg("-query")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{io, process::Stdio};
+
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{
+    platform::Facility,
+    runner::{
+        default_env, default_wrapper, is_shell_wrapper, program_path, wrap, Cmd, Output,
+        DEFAULT_TIMEOUT,
+    },
+};
+
+/// Run a command to completion without blocking the executor
+///
+/// The child is killed if the returned future is dropped before it completes, or if it runs for
+/// longer than the timeout of the command (or [DEFAULT_TIMEOUT] if it has none). The program is
+/// looked up through [program_path] and run through the [default_wrapper].
+pub async fn output(cmd: &Cmd) -> io::Result<Output> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("command", program = %cmd.program, args = ?cmd.args);
+    #[cfg(feature = "tracing")]
+    let start = std::time::Instant::now();
+
+    Facility::Process.check()?;
+    let wrapper = default_wrapper();
+    let mut command = if wrapper.is_empty() {
+        let mut command = Command::new(program_path(&cmd.program));
+        command.args(&cmd.args).envs(
+            default_env()
+                .iter()
+                .chain(cmd.env.iter())
+                .map(|(k, v)| (k, v)),
+        );
+        command
+    } else {
+        let env = [default_env(), cmd.env.clone()].concat();
+        let shell = is_shell_wrapper(&wrapper);
+        let line = wrap(&wrapper, shell, program_path(&cmd.program), cmd, &env);
+        let mut command = Command::new(&line[0]);
+        command.args(&line[1..]);
+        command
+    };
+    let mut child = command
+        .stdin(if cmd.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), cmd.stdin.as_ref()) {
+        stdin.write_all(input).await?;
+    }
+    let timeout = cmd.timeout.unwrap_or(DEFAULT_TIMEOUT);
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, format!("`{}` timed out", cmd)))??;
+    #[cfg(feature = "tracing")]
+    span.in_scope(|| {
+        tracing::debug!(
+            status = output.status.code(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "exited"
+        )
+    });
+
+    Ok(Output {
+        status: output.status.code().unwrap_or(-1),
+        stdout: output.stdout,
+        stderr: output.stderr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn output_async() {
+        let output = output(&Cmd::new("sh").args(["-c", "cat; exit 2"]).stdin("in"))
+            .await
+            .unwrap();
+        assert_eq!(output.status, 2);
+        assert_eq!(output.stdout, b"in");
+    }
+
+    #[tokio::test]
+    async fn output_timeout() {
+        let cmd = Cmd::new("sleep")
+            .arg("5")
+            .timeout(std::time::Duration::from_millis(50));
+        let err = output(&cmd).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
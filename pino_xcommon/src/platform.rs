@@ -0,0 +1,96 @@
+//! Check what the current platform supports
+//!
+//! Every crate compiles on any platform, operations the platform lacks fail at runtime with
+//! [Unsupported] instead. Applications can check for them at startup:
+//!
+//! ```rust
+//! use pino_xcommon::platform::Facility;
+//!
+//! if !Facility::EventSource.is_supported() {
+//!     println!("{}", Facility::EventSource.check().unwrap_err());
+//! }
+//! ```
+
+use std::{fmt, io};
+
+/// Name of the operating system, as in [std::env::consts::OS]
+pub const OS: &str = std::env::consts::OS;
+
+/// Part of the crates that only works on some platforms
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Facility {
+    /// Running the client binaries, with the `process` feature on platforms that can spawn
+    /// processes
+    Process,
+    /// Talking to the X server directly, with the `native` feature
+    Native,
+    /// Watching from an event loop through an eventfd, on Linux
+    EventSource,
+}
+
+impl Facility {
+    /// Check if the facility is available in this build on this platform
+    pub fn is_supported(self) -> bool {
+        match self {
+            Facility::Process => cfg!(all(feature = "process", not(target_family = "wasm"))),
+            Facility::Native => cfg!(feature = "native"),
+            Facility::EventSource => cfg!(target_os = "linux"),
+        }
+    }
+
+    /// Fail with [Unsupported] if the facility is not available
+    pub fn check(self) -> Result<(), Unsupported> {
+        if self.is_supported() {
+            Ok(())
+        } else {
+            Err(Unsupported(self))
+        }
+    }
+}
+
+impl fmt::Display for Facility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Facility::Process => write!(f, "running the client binaries"),
+            Facility::Native => write!(f, "connecting to the X server directly"),
+            Facility::EventSource => write!(f, "watching from an event loop"),
+        }
+    }
+}
+
+/// Operation that is not available on the current platform or in the current build
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Unsupported(pub Facility);
+
+impl std::error::Error for Unsupported {}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not supported on {}", self.0, OS)
+    }
+}
+
+impl From<Unsupported> for io::Error {
+    fn from(value: Unsupported) -> Self {
+        io::Error::new(io::ErrorKind::Unsupported, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_io_error() {
+        let err = io::Error::from(Unsupported(Facility::Native));
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "connecting to the X server directly is not supported on {}",
+                OS
+            )
+        );
+    }
+}
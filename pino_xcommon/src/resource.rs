@@ -0,0 +1,18 @@
+//! Convert resource values the way Xlib does
+//!
+//! ```rust
+//! use pino_xcommon::resource::parse_bool;
+//!
+//! assert_eq!(parse_bool("On"), Some(true));
+//! assert_eq!(parse_bool("0"), Some(false));
+//! assert_eq!(parse_bool("maybe"), None);
+//! ```
+
+/// Parse a boolean resource, any case of `true`, `on`, `yes` and `1` or of their opposites
+pub fn parse_bool(val: &str) -> Option<bool> {
+    match val.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,239 @@
+//! Retry commands that fail transiently
+//!
+//! The client binaries occasionally fail while a session is starting up, before the server
+//! accepts connections. Wrapping a runner with a [RetryPolicy] runs such commands again after a
+//! delay. Only commands that time out or could not connect to the server are retried, since other
+//! failures such as invalid arguments would fail the same way again and the command may already
+//! have changed something. [RetryRunner::retry_if] retries other failures as well.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use pino_xcommon::{
+//!     retry::RetryPolicy,
+//!     runner::{Cmd, CommandRunner, MockRunner, Output},
+//! };
+//!
+//! let refused = Output::from_failure(1, "xrdb: Can't open display ':0'\n");
+//! let mock = MockRunner::new().respond(Cmd::new("xrdb"), refused);
+//! let policy = RetryPolicy::new(3).backoff(Duration::ZERO);
+//! let output = policy.runner(&mock).output(&Cmd::new("xrdb")).unwrap();
+//!
+//! assert!(!output.success());
+//! assert_eq!(mock.calls().len(), 3);
+//! ```
+
+use std::{
+    io::{self, ErrorKind},
+    thread,
+    time::Duration,
+};
+
+use crate::runner::{Cmd, CommandRunner, Output, Process};
+
+/// How often and how long apart a failed command is run again
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Times a command is run at most, including the first attempt
+    pub attempts: u32,
+    /// Delay before the first retry
+    pub backoff: Duration,
+    /// Factor the delay is multiplied by after every retry
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    /// Run commands once, without retrying
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 1,
+            backoff: Duration::from_millis(100),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run commands up to `attempts` times, starting with a delay of 100ms that doubles every retry
+    pub fn new(attempts: u32) -> Self {
+        RetryPolicy {
+            attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the first retry
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Factor the delay is multiplied by after every retry, `1` for a constant delay
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Delay before the retry following attempt `attempt`, counting from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(self.multiplier.saturating_pow(attempt))
+    }
+
+    /// Wrap a runner so commands failing transiently are run again according to this policy
+    pub fn runner<'a>(&'a self, runner: &'a dyn CommandRunner) -> RetryRunner<'a> {
+        RetryRunner {
+            policy: self,
+            runner,
+            retry_if: Box::new(|_| false),
+        }
+    }
+
+    fn run<T>(
+        &self,
+        mut f: impl FnMut() -> io::Result<T>,
+        failed: impl Fn(&T) -> bool,
+    ) -> io::Result<T> {
+        let mut attempt = 0;
+        loop {
+            let result = f();
+            let transient = match &result {
+                Ok(value) => failed(value),
+                Err(e) => matches!(
+                    e.kind(),
+                    ErrorKind::TimedOut
+                        | ErrorKind::Interrupted
+                        | ErrorKind::ConnectionRefused
+                        | ErrorKind::ConnectionReset
+                ),
+            };
+            if !transient || attempt + 1 >= self.attempts {
+                return result;
+            }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(attempt, "retrying failed command");
+            thread::sleep(self.delay(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+/// Runner retrying failed commands, see [RetryPolicy::runner]
+///
+/// Commands that time out, are interrupted or exit because they could not connect to the server
+/// are retried. Once a process is spawned its output is streamed, so only [CommandRunner::output]
+/// retries commands that exit with an error.
+pub struct RetryRunner<'a> {
+    policy: &'a RetryPolicy,
+    runner: &'a dyn CommandRunner,
+    retry_if: Box<dyn Fn(&Output) -> bool + Send + Sync + 'a>,
+}
+
+impl<'a> RetryRunner<'a> {
+    /// Also retry commands whose output matches `retry_if`, such as every non-zero exit of a
+    /// command that is safe to run twice
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use pino_xcommon::{
+    ///     retry::RetryPolicy,
+    ///     runner::{Cmd, CommandRunner, MockRunner, Output},
+    /// };
+    ///
+    /// let mock = MockRunner::new().respond(Cmd::new("xrdb"), Output::from_failure(1, "busy"));
+    /// let policy = RetryPolicy::new(3).backoff(Duration::ZERO);
+    /// let runner = policy.runner(&mock).retry_if(|output| !output.success());
+    /// runner.output(&Cmd::new("xrdb")).unwrap();
+    /// assert_eq!(mock.calls().len(), 3);
+    /// ```
+    pub fn retry_if(mut self, retry_if: impl Fn(&Output) -> bool + Send + Sync + 'a) -> Self {
+        self.retry_if = Box::new(retry_if);
+        self
+    }
+}
+
+/// Check if the command exited because it could not connect to the server
+fn could_not_connect(output: &Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr).to_lowercase();
+    !output.success()
+        && [
+            "can't open display",
+            "cannot open display",
+            "unable to open display",
+            "connection refused",
+        ]
+        .iter()
+        .any(|message| stderr.contains(message))
+}
+
+impl CommandRunner for RetryRunner<'_> {
+    fn spawn(&self, cmd: &Cmd) -> io::Result<Box<dyn Process>> {
+        self.policy.run(|| self.runner.spawn(cmd), |_| false)
+    }
+
+    fn output(&self, cmd: &Cmd) -> io::Result<Output> {
+        self.policy.run(
+            || self.runner.output(cmd),
+            |output| could_not_connect(output) || (self.retry_if)(output),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::MockRunner;
+
+    #[test]
+    fn retry_delay() {
+        let policy = RetryPolicy::new(4).backoff(Duration::from_millis(10));
+        assert_eq!(policy.delay(0), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(40));
+        assert_eq!(policy.multiplier(1).delay(2), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn retry_runner() {
+        let policy = RetryPolicy::new(3).backoff(Duration::ZERO);
+
+        let mock = MockRunner::new().respond(Cmd::new("xrdb"), Output::from_stdout("ok"));
+        policy.runner(&mock).output(&Cmd::new("xrdb")).unwrap();
+        assert_eq!(mock.calls().len(), 1);
+
+        // timeouts are retried, missing programs are not
+        let mock = MockRunner::new().time_out(Cmd::new("xrdb"));
+        let err = policy.runner(&mock).output(&Cmd::new("xrdb")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(mock.calls().len(), 3);
+        assert!(policy.runner(&mock).output(&Cmd::new("xmodmap")).is_err());
+        assert_eq!(mock.calls().len(), 4);
+    }
+
+    #[test]
+    fn retry_only_transient() {
+        let policy = RetryPolicy::new(3).backoff(Duration::ZERO);
+        let cmd = Cmd::new("xprop").args(["-remove", "_BOGUS"]);
+        let mock = MockRunner::new()
+            .respond(
+                cmd.clone(),
+                Output::from_failure(1, "xprop: error: bad atom"),
+            )
+            .respond(
+                Cmd::new("xset"),
+                Output::from_failure(1, "xset:  unable to open display \":0\"\n"),
+            );
+
+        // permanent failures are returned at once
+        let output = policy.runner(&mock).output(&cmd).unwrap();
+        assert!(!output.success());
+        assert_eq!(mock.calls().len(), 1);
+
+        policy.runner(&mock).output(&Cmd::new("xset")).unwrap();
+        assert_eq!(mock.calls().len(), 4);
+
+        let runner = policy.runner(&mock).retry_if(|output| output.status == 1);
+        runner.output(&cmd).unwrap();
+        assert_eq!(mock.calls().len(), 7);
+    }
+}
@@ -0,0 +1,573 @@
+//! Run the external programs the `pino_*` crates wrap
+//!
+//! Every subprocess call goes through a [CommandRunner]. `SystemRunner` runs the real binaries
+//! and is enabled by the `process` feature, while [MockRunner] returns canned output so code
+//! using these crates can be tested without an X server or the binaries installed.
+//!
+//! ```rust
+//! use pino_xcommon::runner::{Cmd, CommandRunner, MockRunner, Output};
+//!
+//! let runner = MockRunner::new().respond(
+//!     Cmd::new("xrdb").arg("-query"),
+//!     Output::from_stdout("*.color1: #ea6962\n"),
+//! );
+//! let output = runner.output(&Cmd::new("xrdb").arg("-query")).unwrap();
+//! assert_eq!(output.stdout, b"*.color1: #ea6962\n");
+//! assert!(runner.output(&Cmd::new("xmodmap")).is_err());
+//! ```
+
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    io::{self, BufRead, Cursor, Read},
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+#[cfg(feature = "process")]
+mod system;
+#[cfg(feature = "process")]
+pub use system::SystemRunner;
+
+/// Program to run along with its arguments and input
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Cmd {
+    pub program: String,
+    pub args: Vec<String>,
+    pub stdin: Option<Vec<u8>>,
+    /// Environment variables set on top of the environment of the current process
+    pub env: Vec<(String, String)>,
+    /// Overrides the timeout of the runner
+    pub timeout: Option<Duration>,
+    /// Whether the output of the command is thrown away instead of read
+    pub discard_output: bool,
+}
+
+impl Cmd {
+    /// Construct a command running `program` without arguments
+    pub fn new(program: &str) -> Self {
+        Cmd {
+            program: program.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Append an argument
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Append several arguments
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set an environment variable for the command
+    pub fn env(mut self, key: impl Into<String>, val: impl Into<String>) -> Self {
+        self.env.push((key.into(), val.into()));
+        self
+    }
+
+    /// Time the command may run for before it is killed
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Bytes written to the standard input of the command
+    pub fn stdin(mut self, stdin: impl Into<Vec<u8>>) -> Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+
+    /// Throw the standard output and error away, for commands that leave a process behind which
+    /// would keep them open and be waited on forever
+    pub fn discard_output(mut self) -> Self {
+        self.discard_output = true;
+        self
+    }
+}
+
+impl fmt::Display for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a command that ran to completion
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Output {
+    /// Exit code, `-1` if the command was terminated by a signal
+    pub status: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Output {
+    /// Successful output printing `stdout`
+    pub fn from_stdout(stdout: impl Into<Vec<u8>>) -> Self {
+        Output {
+            stdout: stdout.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Failed output exiting with `status` and printing `stderr`
+    pub fn from_failure(status: i32, stderr: impl Into<Vec<u8>>) -> Self {
+        Output {
+            status,
+            stderr: stderr.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Check if the command exited with code zero
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+/// A spawned command, whose output can be read while it is running
+pub trait Process {
+    /// Standard output of the command
+    fn stdout(&mut self) -> &mut dyn BufRead;
+
+    /// Terminate the command
+    fn kill(&mut self) -> io::Result<()>;
+
+    /// Closure terminating the command from another thread, such as while this one is blocked
+    /// reading its output, `None` if the process can not be terminated that way
+    fn killer(&self) -> Option<Box<dyn FnOnce() + Send>> {
+        None
+    }
+
+    /// Wait for the command to exit
+    ///
+    /// The returned stdout holds whatever was not already read through [Process::stdout].
+    fn wait(&mut self) -> io::Result<Output>;
+}
+
+/// Runs commands on behalf of the `pino_*` crates
+pub trait CommandRunner: Send + Sync {
+    /// Start a command
+    fn spawn(&self, cmd: &Cmd) -> io::Result<Box<dyn Process>>;
+
+    /// Run a command to completion
+    fn output(&self, cmd: &Cmd) -> io::Result<Output> {
+        self.spawn(cmd)?.wait()
+    }
+}
+
+/// Failure of running a client binary, wrapped by the error type of every `pino_*` crate
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RunError {
+    /// Program could not be run, it is likely not installed
+    Spawn { program: String, source: io::Error },
+    /// Program exited with a non-zero status
+    Exited {
+        program: String,
+        status: i32,
+        stderr: String,
+    },
+    /// Program did not finish within its timeout and was killed
+    TimedOut { program: String },
+    /// The platform can not run the program, or the program can not perform the operation
+    Unsupported(String),
+}
+
+impl RunError {
+    /// Classify an error of spawning or waiting for `program`
+    pub fn from_io(program: &str, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::TimedOut => RunError::TimedOut {
+                program: program.to_owned(),
+            },
+            io::ErrorKind::Unsupported => RunError::Unsupported(source.to_string()),
+            _ => RunError::Spawn {
+                program: program.to_owned(),
+                source,
+            },
+        }
+    }
+}
+
+impl std::error::Error for RunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunError::Spawn { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::Spawn { program, source } => write!(
+                f,
+                "could not run {}, do you have it installed? ({})",
+                program, source
+            ),
+            RunError::Exited {
+                program,
+                status,
+                stderr,
+            } => write!(
+                f,
+                "{} exited with status {}: {}",
+                program,
+                status,
+                stderr.trim()
+            ),
+            RunError::TimedOut { program } => write!(f, "{} timed out", program),
+            RunError::Unsupported(e) => write!(f, "unsupported: {}", e),
+        }
+    }
+}
+
+/// Run a command to completion, failing unless it exits with code zero
+///
+/// ```rust
+/// use pino_xcommon::runner::{run_checked, Cmd, MockRunner, Output, RunError};
+///
+/// let runner = MockRunner::new().respond(Cmd::new("xhost"), Output::from_failure(1, "denied"));
+/// assert!(matches!(
+///     run_checked(&runner, &Cmd::new("xhost")),
+///     Err(RunError::Exited { status: 1, .. })
+/// ));
+/// ```
+pub fn run_checked(runner: &dyn CommandRunner, cmd: &Cmd) -> Result<Output, RunError> {
+    let output = runner
+        .output(cmd)
+        .map_err(|e| RunError::from_io(&cmd.program, e))?;
+    check_output(&cmd.program, output)
+}
+
+/// Fail unless `program` exited with code zero, for commands whose output was read while they ran
+pub fn check_output(program: &str, output: Output) -> Result<Output, RunError> {
+    if !output.success() {
+        return Err(RunError::Exited {
+            program: program.to_owned(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    Ok(output)
+}
+
+/// Time a command may run for before it is killed, unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable overriding the path of `program`
+///
+/// The name is `PINO_` followed by the program name in upper case, with every character that is
+/// not alphanumeric replaced by `_`, such as `PINO_XRDB` for `xrdb`.
+///
+/// ```rust
+/// assert_eq!(pino_xcommon::runner::env_var("xrdb"), "PINO_XRDB");
+/// assert_eq!(pino_xcommon::runner::env_var("xdg-open"), "PINO_XDG_OPEN");
+/// ```
+pub fn env_var(program: &str) -> String {
+    let name = program
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("PINO_{}", name)
+}
+
+static DEFAULT_ENV: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Set environment variables on every command spawned through a default `SystemRunner` or the
+/// async variants, such as `DISPLAY`, `PATH` or `LC_ALL=C`
+///
+/// Useful when running from a systemd user unit or a greeter, which lack the environment of the
+/// interactive session. Variables set on a runner or on the [Cmd] itself take precedence.
+///
+/// ```rust
+/// use pino_xcommon::runner;
+///
+/// runner::set_default_env([("LC_ALL", "C"), ("DISPLAY", ":0")]);
+/// assert_eq!(runner::default_env()[0], (String::from("LC_ALL"), String::from("C")));
+/// ```
+pub fn set_default_env<I, K, V>(env: I)
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    *DEFAULT_ENV.lock().unwrap() = env.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+}
+
+/// Environment variables given to [set_default_env]
+pub fn default_env() -> Vec<(String, String)> {
+    DEFAULT_ENV.lock().unwrap().clone()
+}
+
+/// Environment variable holding a command every program is run through, such as
+/// `flatpak-spawn --host`, unless one is set with [set_default_wrapper]
+pub const WRAPPER_VAR: &str = "PINO_WRAPPER";
+
+static DEFAULT_WRAPPER: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+/// Run every command spawned through a default `SystemRunner` or the async variants through a
+/// wrapper, taking precedence over [WRAPPER_VAR]
+///
+/// Useful from inside a Flatpak sandbox (`flatpak-spawn --host`) or to manage the X session of
+/// another machine (`ssh host`). An empty wrapper runs commands directly. The words after an `ssh`
+/// wrapper are quoted, see [is_shell_wrapper].
+///
+/// ```rust
+/// use pino_xcommon::runner;
+///
+/// runner::set_default_wrapper(["flatpak-spawn", "--host"]);
+/// assert_eq!(runner::default_wrapper(), ["flatpak-spawn", "--host"]);
+/// ```
+pub fn set_default_wrapper<I, S>(wrapper: I)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    *DEFAULT_WRAPPER.lock().unwrap() = Some(wrapper.into_iter().map(Into::into).collect());
+}
+
+/// Wrapper given to [set_default_wrapper], or the words of [WRAPPER_VAR], empty if neither is set
+pub fn default_wrapper() -> Vec<String> {
+    DEFAULT_WRAPPER.lock().unwrap().clone().unwrap_or_else(|| {
+        env::var(WRAPPER_VAR)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect()
+    })
+}
+
+/// Check if `wrapper` joins the words after it into a line a shell parses again, as `ssh` does
+///
+/// ```rust
+/// use pino_xcommon::runner::is_shell_wrapper;
+///
+/// assert!(is_shell_wrapper(&[String::from("/usr/bin/ssh"), String::from("host")]));
+/// assert!(!is_shell_wrapper(&[String::from("flatpak-spawn"), String::from("--host")]));
+/// ```
+pub fn is_shell_wrapper(wrapper: &[String]) -> bool {
+    wrapper
+        .first()
+        .is_some_and(|program| Path::new(program).file_name() == Some("ssh".as_ref()))
+}
+
+/// Quote `word` for a POSIX shell, leaving it as is when it has no special characters
+///
+/// ```rust
+/// use pino_xcommon::runner::shell_quote;
+///
+/// assert_eq!(shell_quote("-query"), "-query");
+/// assert_eq!(shell_quote("keycode 66 = Escape"), "'keycode 66 = Escape'");
+/// assert_eq!(shell_quote("it's $(id)"), r"'it'\''s $(id)'");
+/// ```
+pub fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+=:,./@%".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        return word.to_owned();
+    }
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Command line running `program` with the arguments of `cmd` through `wrapper`
+///
+/// Variables set on the wrapper would not reach a program on the host or on another machine, so
+/// `env` is passed through `env(1)` instead. With `shell` set, for wrappers such as `ssh` that have
+/// a shell parse the command line again, every word after the wrapper is quoted with
+/// [shell_quote].
+///
+/// ```rust
+/// use pino_xcommon::runner::{wrap, Cmd};
+///
+/// let wrapper = [String::from("ssh"), String::from("host")];
+/// let env = [(String::from("DISPLAY"), String::from(":0"))];
+/// let cmd = Cmd::new("xmodmap").args(["-e", "keycode 66 = Escape"]);
+/// let line = wrap(&wrapper, true, "xmodmap".into(), &cmd, &env);
+/// assert_eq!(
+///     line,
+///     ["ssh", "host", "env", "DISPLAY=:0", "xmodmap", "-e", "'keycode 66 = Escape'"]
+/// );
+/// ```
+pub fn wrap(
+    wrapper: &[String],
+    shell: bool,
+    program: OsString,
+    cmd: &Cmd,
+    env: &[(String, String)],
+) -> Vec<OsString> {
+    let mut words = vec![];
+    if !env.is_empty() {
+        words.push("env".into());
+        words.extend(
+            env.iter()
+                .map(|(k, v)| OsString::from(format!("{}={}", k, v))),
+        );
+    }
+    words.push(program);
+    words.extend(cmd.args.iter().map(OsString::from));
+    if shell {
+        words = words
+            .iter()
+            .map(|word| shell_quote(&word.to_string_lossy()).into())
+            .collect();
+    }
+    wrapper.iter().map(OsString::from).chain(words).collect()
+}
+
+/// Path `program` is run from, taken from its [env_var] if set and looked up in `PATH` otherwise
+pub fn program_path(program: &str) -> OsString {
+    env::var_os(env_var(program))
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| program.into())
+}
+
+/// Returns canned output instead of running anything
+///
+/// Commands are matched on their program and arguments, commands without a response fail as if
+/// the program was not installed. Every command run is recorded and can be inspected with
+/// [MockRunner::calls].
+#[derive(Debug, Default)]
+pub struct MockRunner {
+    // `None` stands for a command that times out
+    responses: Vec<(Cmd, Option<Output>)>,
+    calls: Mutex<Vec<Cmd>>,
+}
+
+struct MockProcess {
+    stdout: Cursor<Vec<u8>>,
+    output: Option<Output>,
+}
+
+impl MockRunner {
+    /// Construct a runner without any responses
+    pub fn new() -> Self {
+        MockRunner::default()
+    }
+
+    /// Respond to `cmd` with `output`
+    ///
+    /// The standard input of `cmd` is ignored when matching.
+    pub fn respond(mut self, cmd: Cmd, output: Output) -> Self {
+        self.responses.push((cmd, Some(output)));
+        self
+    }
+
+    /// Make `cmd` fail with [io::ErrorKind::TimedOut] once waited on
+    pub fn time_out(mut self, cmd: Cmd) -> Self {
+        self.responses.push((cmd, None));
+        self
+    }
+
+    /// Every command run so far, in order
+    pub fn calls(&self) -> Vec<Cmd> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for MockRunner {
+    fn spawn(&self, cmd: &Cmd) -> io::Result<Box<dyn Process>> {
+        self.calls.lock().unwrap().push(cmd.clone());
+
+        let (_, output) = self
+            .responses
+            .iter()
+            .find(|(expected, _)| expected.program == cmd.program && expected.args == cmd.args)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no response for `{}`", cmd),
+                )
+            })?;
+        let output = output.clone().map(|output| match cmd.discard_output {
+            true => Output {
+                status: output.status,
+                ..Default::default()
+            },
+            false => output,
+        });
+
+        Ok(Box::new(MockProcess {
+            stdout: Cursor::new(
+                output
+                    .as_ref()
+                    .map(|output| output.stdout.clone())
+                    .unwrap_or_default(),
+            ),
+            output,
+        }))
+    }
+}
+
+impl Process for MockProcess {
+    fn stdout(&mut self) -> &mut dyn BufRead {
+        &mut self.stdout
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> io::Result<Output> {
+        let output = self
+            .output
+            .clone()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "timed out"))?;
+        let mut stdout = vec![];
+        self.stdout.read_to_end(&mut stdout)?;
+        Ok(Output { stdout, ..output })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_partial_read() {
+        let runner = MockRunner::new().respond(
+            Cmd::new("xmodmap").arg("-pke"),
+            Output::from_stdout("keycode  24 = q\nkeycode  25 = w\n"),
+        );
+        let mut process = runner.spawn(&Cmd::new("xmodmap").arg("-pke")).unwrap();
+        let mut line = String::new();
+        process.stdout().read_line(&mut line).unwrap();
+        assert_eq!(line, "keycode  24 = q\n");
+
+        let output = process.wait().unwrap();
+        assert_eq!(output.stdout, b"keycode  25 = w\n");
+        assert!(output.success());
+        assert_eq!(runner.calls(), vec![Cmd::new("xmodmap").arg("-pke")]);
+    }
+
+    #[test]
+    fn mock_missing_response() {
+        let runner = MockRunner::new();
+        let err = runner
+            .output(&Cmd::new("xrdb").arg("-query"))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
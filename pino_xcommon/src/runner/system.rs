@@ -0,0 +1,462 @@
+//! Runner spawning child processes, enabled by the `process` feature
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use super::{
+    default_env, default_wrapper, is_shell_wrapper, program_path, wrap, Cmd, CommandRunner, Output,
+    Process, DEFAULT_TIMEOUT,
+};
+use crate::platform::Facility;
+
+/// Runs commands as child processes of the current process
+///
+/// Commands that run for longer than the timeout are killed and reaped, and fail with
+/// [io::ErrorKind::TimedOut]. On Unix every command gets its own process group, which is killed as
+/// a whole, so children such as the preprocessor of xrdb can not keep the output open.
+///
+/// Programs are looked up through [program_path], unless their path is set with
+/// [SystemRunner::program]. Commands inherit the environment of the current process, with the
+/// [default_env] and the variables set with [SystemRunner::env] on top. They are run through the
+/// [default_wrapper] unless another one is set with [SystemRunner::wrapper].
+#[derive(Debug, Clone)]
+pub struct SystemRunner {
+    timeout: Option<Duration>,
+    programs: HashMap<String, OsString>,
+    env: Vec<(String, String)>,
+    wrapper: Vec<String>,
+    // whether the words after the wrapper are quoted for a shell
+    shell: bool,
+}
+
+impl Default for SystemRunner {
+    fn default() -> Self {
+        SystemRunner {
+            timeout: Some(DEFAULT_TIMEOUT),
+            programs: HashMap::new(),
+            env: default_env(),
+            shell: is_shell_wrapper(&default_wrapper()),
+            wrapper: default_wrapper(),
+        }
+    }
+}
+
+impl SystemRunner {
+    /// Construct a runner using [DEFAULT_TIMEOUT]
+    pub fn new() -> Self {
+        SystemRunner::default()
+    }
+
+    /// Time commands may run for, `None` to wait for them indefinitely
+    ///
+    /// A timeout set on the [Cmd] itself takes precedence.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run `program` from `path`, taking precedence over its environment variable
+    ///
+    /// ```rust
+    /// use pino_xcommon::runner::SystemRunner;
+    ///
+    /// let runner = SystemRunner::new().program("xrdb", "/opt/x11/bin/xrdb");
+    /// assert_eq!(runner.resolve("xrdb"), "/opt/x11/bin/xrdb");
+    /// ```
+    pub fn program(mut self, program: &str, path: impl Into<OsString>) -> Self {
+        self.programs.insert(program.to_owned(), path.into());
+        self
+    }
+
+    /// Set an environment variable for every command, the ones set on the [Cmd] take precedence
+    ///
+    /// ```rust
+    /// use pino_xcommon::runner::SystemRunner;
+    ///
+    /// let runner = SystemRunner::new()
+    ///     .env("LC_ALL", "C")
+    ///     .env("PATH", "/usr/bin:/bin");
+    /// ```
+    pub fn env(mut self, key: impl Into<String>, val: impl Into<String>) -> Self {
+        self.env.push((key.into(), val.into()));
+        self
+    }
+
+    /// Run every command through `wrapper`, such as `flatpak-spawn --host` or `ssh host`
+    ///
+    /// The environment of the command is passed on through `env(1)`, and the path of the program
+    /// is resolved as usual, so it has to exist where the wrapper runs it. An empty wrapper runs
+    /// commands directly. The words after an `ssh` wrapper are quoted, as with
+    /// [SystemRunner::shell_wrapper].
+    ///
+    /// ```rust
+    /// use pino_xcommon::runner::SystemRunner;
+    ///
+    /// let runner = SystemRunner::new().wrapper(["flatpak-spawn", "--host"]);
+    /// ```
+    pub fn wrapper<I, S>(mut self, wrapper: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.wrapper = wrapper.into_iter().map(Into::into).collect();
+        self.shell = is_shell_wrapper(&self.wrapper);
+        self
+    }
+
+    /// Run every command through a `wrapper` that has a shell parse the command line again, such
+    /// as `ssh host` or `su -c`, quoting every word after it
+    ///
+    /// ```rust
+    /// use pino_xcommon::runner::SystemRunner;
+    ///
+    /// let runner = SystemRunner::new().shell_wrapper(["su", "-", "user", "-c"]);
+    /// ```
+    pub fn shell_wrapper<I, S>(mut self, wrapper: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.wrapper = wrapper.into_iter().map(Into::into).collect();
+        self.shell = true;
+        self
+    }
+
+    /// Path `program` is run from
+    pub fn resolve(&self, program: &str) -> OsString {
+        self.programs
+            .get(program)
+            .cloned()
+            .unwrap_or_else(|| program_path(program))
+    }
+}
+
+struct SystemProcess {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: std::time::Instant,
+    cmd: String,
+    child: Arc<Mutex<Child>>,
+    stdout: BufReader<Box<dyn Read + Send>>,
+    // stderr is drained in the background so a chatty command can not block on a full pipe
+    stderr: Option<JoinHandle<Vec<u8>>>,
+    // dropped once the command exits, which stops the watchdog
+    watchdog: Option<Sender<()>>,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl CommandRunner for SystemRunner {
+    fn spawn(&self, cmd: &Cmd) -> io::Result<Box<dyn Process>> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("command", program = %cmd.program, args = ?cmd.args);
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        Facility::Process.check()?;
+        let mut command = if self.wrapper.is_empty() {
+            let mut command = Command::new(self.resolve(&cmd.program));
+            command
+                .args(&cmd.args)
+                .envs(self.env.iter().chain(cmd.env.iter()).map(|(k, v)| (k, v)));
+            command
+        } else {
+            let env = [self.env.as_slice(), cmd.env.as_slice()].concat();
+            let line = wrap(
+                &self.wrapper,
+                self.shell,
+                self.resolve(&cmd.program),
+                cmd,
+                &env,
+            );
+            let mut command = Command::new(&line[0]);
+            command.args(&line[1..]);
+            command
+        };
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let spawned = command
+            .stdin(if cmd.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(output_stdio(cmd))
+            .stderr(output_stdio(cmd))
+            .spawn();
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &spawned {
+            tracing::warn!(error = %e, "failed to spawn");
+        }
+        let mut child = spawned?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pid = child.id(), "spawned");
+
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), cmd.stdin.clone()) {
+            thread::spawn(move || stdin.write_all(&input));
+        }
+        let stdout: Box<dyn Read + Send> = match child.stdout.take() {
+            Some(stdout) => Box::new(stdout),
+            None if cmd.discard_output => Box::new(io::empty()),
+            None => return Err(io::ErrorKind::BrokenPipe.into()),
+        };
+        let stderr = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut buf = vec![];
+                let _ = stderr.read_to_end(&mut buf);
+                buf
+            })
+        });
+
+        let child = Arc::new(Mutex::new(child));
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let watchdog = cmd.timeout.or(self.timeout).map(|timeout| {
+            let (tx, rx) = mpsc::channel::<()>();
+            let child = child.clone();
+            let timed_out = timed_out.clone();
+            thread::spawn(move || {
+                if let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(timeout) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    let _ = kill(&mut child.lock().unwrap());
+                }
+            });
+            tx
+        });
+
+        #[cfg(feature = "tracing")]
+        drop(_entered);
+        Ok(Box::new(SystemProcess {
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(feature = "tracing")]
+            start: std::time::Instant::now(),
+            cmd: cmd.to_string(),
+            child,
+            stdout: BufReader::new(stdout),
+            stderr,
+            watchdog,
+            timed_out,
+        }))
+    }
+}
+
+impl Process for SystemProcess {
+    fn stdout(&mut self) -> &mut dyn BufRead {
+        &mut self.stdout
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        kill(&mut self.child.lock().unwrap())
+    }
+
+    fn killer(&self) -> Option<Box<dyn FnOnce() + Send>> {
+        let child = self.child.clone();
+        Some(Box::new(move || {
+            let mut child = child.lock().unwrap();
+            // once reaped, the process group could belong to another command
+            if let Ok(None) = child.try_wait() {
+                let _ = kill(&mut child);
+            }
+        }))
+    }
+
+    fn wait(&mut self) -> io::Result<Output> {
+        // the watchdog kills the process group of the command, closing stdout for its children too
+        let mut stdout = vec![];
+        let read = self.stdout.read_to_end(&mut stdout);
+
+        // poll instead of blocking on wait, the watchdog needs the lock to kill the command
+        let status = loop {
+            if let Some(status) = self.child.lock().unwrap().try_wait()? {
+                break status;
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+        self.watchdog.take();
+        let stderr = self
+            .stderr
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
+        #[cfg(feature = "tracing")]
+        self.span.in_scope(|| {
+            tracing::debug!(
+                status = status.code(),
+                duration_ms = self.start.elapsed().as_millis() as u64,
+                timed_out = self.timed_out.load(Ordering::SeqCst),
+                "exited"
+            )
+        });
+
+        if self.timed_out.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("`{}` timed out", self.cmd),
+            ));
+        }
+        read?;
+
+        Ok(Output {
+            status: status.code().unwrap_or(-1),
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl Drop for SystemProcess {
+    fn drop(&mut self) {
+        // a process that was never waited on is killed rather than left behind as a zombie
+        let mut child = self.child.lock().unwrap();
+        if let Ok(None) = child.try_wait() {
+            let _ = kill(&mut child);
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Pipe for the output of the command, or nothing if it is thrown away
+fn output_stdio(cmd: &Cmd) -> Stdio {
+    match cmd.discard_output {
+        true => Stdio::null(),
+        false => Stdio::piped(),
+    }
+}
+
+/// Kill the command along with every process left in its process group
+fn kill(child: &mut Child) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        // the command leads its own group, whose id is its pid
+        let pgid = child.id() as libc::pid_t;
+        // SAFETY: kill only sends a signal, the group can not be reused while the child is unreaped
+        if unsafe { libc::kill(-pgid, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_runner_timeout() {
+        let start = std::time::Instant::now();
+        let err = SystemRunner::new()
+            .output(
+                &Cmd::new("sleep")
+                    .arg("5")
+                    .timeout(Duration::from_millis(50)),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn system_runner_discard_output() {
+        // the sleep left behind would keep piped output open for 3 seconds
+        let start = std::time::Instant::now();
+        let cmd = Cmd::new("sh")
+            .args(["-c", "sleep 3 & echo hi; echo oops >&2"])
+            .discard_output();
+        let output = SystemRunner::new().output(&cmd).unwrap();
+        assert!(output.success());
+        assert!(output.stdout.is_empty() && output.stderr.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn system_runner_timeout_children() {
+        // the sleep inherits stdout, and would keep it open after sh is killed
+        let start = std::time::Instant::now();
+        let err = SystemRunner::new()
+            .output(
+                &Cmd::new("sh")
+                    .args(["-c", "sleep 3; true"])
+                    .timeout(Duration::from_millis(100)),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn system_runner() {
+        let output = SystemRunner::new()
+            .output(
+                &Cmd::new("sh")
+                    .args(["-c", "cat; echo err >&2; exit 3"])
+                    .stdin("in"),
+            )
+            .unwrap();
+        assert_eq!(output.status, 3);
+        assert_eq!(output.stdout, b"in");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn system_runner_program() {
+        let runner = SystemRunner::new().program("pino-test-echo", "echo");
+        let output = runner
+            .output(&Cmd::new("pino-test-echo").arg("hi"))
+            .unwrap();
+        assert_eq!(output.stdout, b"hi\n");
+        assert_eq!(runner.resolve("xrdb"), program_path("xrdb"));
+    }
+
+    #[test]
+    fn system_runner_env() {
+        let runner = SystemRunner::new()
+            .env("PINO_TEST_A", "runner")
+            .env("PINO_TEST_B", "runner");
+        let cmd = Cmd::new("sh")
+            .arg("-c")
+            .arg("echo $PINO_TEST_A $PINO_TEST_B")
+            .env("PINO_TEST_B", "cmd");
+        assert_eq!(runner.output(&cmd).unwrap().stdout, b"runner cmd\n");
+    }
+
+    #[test]
+    fn system_runner_wrapper() {
+        let runner = SystemRunner::new()
+            .wrapper(["sh", "-c", "echo wrapped; exec \"$@\"", "sh"])
+            .env("PINO_TEST_A", "runner");
+        let cmd = Cmd::new("sh").args(["-c", "echo $PINO_TEST_A"]);
+        assert_eq!(runner.output(&cmd).unwrap().stdout, b"wrapped\nrunner\n");
+    }
+
+    #[test]
+    fn system_runner_shell_wrapper() {
+        // joins its arguments and parses them again, as ssh does on the other end
+        let runner = SystemRunner::new()
+            .shell_wrapper(["sh", "-c", "sh -c \"$*\"", "sh"])
+            .env("PINO_TEST_A", "two words");
+        let cmd = Cmd::new("sh").args([
+            "-c",
+            "printf '%s|' \"$PINO_TEST_A\" \"$@\"",
+            "sh",
+            "keycode 66 = Escape",
+            "$(echo injected); `id` 'quoted'",
+        ]);
+        assert_eq!(
+            runner.output(&cmd).unwrap().stdout,
+            b"two words|keycode 66 = Escape|$(echo injected); `id` 'quoted'|"
+        );
+    }
+}
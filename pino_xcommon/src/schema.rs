@@ -0,0 +1,65 @@
+//! Serialization of the data types of every `pino_*` crate, enabled by the `serde` feature
+//!
+//! With the `schemars` feature every serializable type also implements `schemars::JsonSchema`,
+//! so tools consuming the output can validate it against a generated schema. Types with a textual
+//! form understood by X, such as [Color](crate::color::Color), are serialized as that text.
+//!
+//! ```rust
+//! use pino_xcommon::color::Color;
+//!
+//! let json = serde_json::to_string(&Color::rgb(0xea, 0x69, 0x62)).unwrap();
+//! assert_eq!(json, r##""#ea6962""##);
+//! assert_eq!(serde_json::from_str::<Color>(&json).unwrap(), Color::rgb(0xea, 0x69, 0x62));
+//! ```
+
+#[doc(hidden)]
+#[cfg(feature = "schemars")]
+pub use schemars;
+#[doc(hidden)]
+pub use serde;
+
+/// Serialize a type as its [Display](std::fmt::Display) output and deserialize it with
+/// [FromStr](std::str::FromStr), described as a string in its JSON Schema
+///
+/// The schema is only implemented if the `schemars` feature of the calling crate is enabled.
+#[macro_export]
+macro_rules! serde_as_str {
+    ($ty:ident, $description:literal) => {
+        impl $crate::schema::serde::Serialize for $ty {
+            fn serialize<S: $crate::schema::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> $crate::schema::serde::Deserialize<'de> for $ty {
+            fn deserialize<D: $crate::schema::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                use $crate::schema::serde::de::Error;
+
+                let s = String::deserialize(deserializer)?;
+                s.parse()
+                    .map_err(|_| D::Error::custom(format!("expected {}, got {}", $description, s)))
+            }
+        }
+
+        #[cfg(feature = "schemars")]
+        impl $crate::schema::schemars::JsonSchema for $ty {
+            fn schema_name() -> std::borrow::Cow<'static, str> {
+                stringify!($ty).into()
+            }
+
+            fn json_schema(
+                _: &mut $crate::schema::schemars::SchemaGenerator,
+            ) -> $crate::schema::schemars::Schema {
+                $crate::schema::schemars::json_schema!({
+                    "type": "string",
+                    "description": $description,
+                })
+            }
+        }
+    };
+}
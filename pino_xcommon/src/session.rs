@@ -0,0 +1,112 @@
+//! Detect what kind of graphical session the current process runs in
+//!
+//! Under XWayland the X client binaries keep working, but the keymap belongs to the Wayland
+//! compositor. Without any X server they can not work at all, which is reported up front instead
+//! of through the error of a failed connection.
+//!
+//! ```rust
+//! use pino_xcommon::session::Session;
+//!
+//! let session = Session::from_vars(|name| match name {
+//!     "DISPLAY" => Some(String::from(":0")),
+//!     "WAYLAND_DISPLAY" => Some(String::from("wayland-1")),
+//!     _ => None,
+//! });
+//! assert_eq!(session, Session::XWayland);
+//! assert!(session.has_x());
+//! ```
+
+use std::{env, fmt};
+
+use crate::display::DisplayHandle;
+
+/// Kind of graphical session
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Session {
+    /// X server without a Wayland compositor
+    X11,
+    /// Wayland compositor running XWayland for X clients
+    XWayland,
+    /// Wayland compositor without XWayland, X clients can not connect
+    Wayland,
+    /// Neither, such as in a TTY, over SSH without forwarding or in CI
+    Headless,
+}
+
+impl Session {
+    /// Session of the current process, according to its environment
+    pub fn detect() -> Self {
+        Session::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Session described by the given environment variables
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let set = |name| var(name).is_some_and(|val| !val.is_empty());
+        let wayland = set("WAYLAND_DISPLAY")
+            || var("XDG_SESSION_TYPE").is_some_and(|t| t.eq_ignore_ascii_case("wayland"));
+        match (set("DISPLAY"), wayland) {
+            (true, false) => Session::X11,
+            (true, true) => Session::XWayland,
+            (false, true) => Session::Wayland,
+            (false, false) => Session::Headless,
+        }
+    }
+
+    /// Check if X clients can connect
+    pub fn has_x(self) -> bool {
+        matches!(self, Session::X11 | Session::XWayland)
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Session::X11 => write!(f, "X11"),
+            Session::XWayland => write!(f, "XWayland"),
+            Session::Wayland => write!(f, "Wayland"),
+            Session::Headless => write!(f, "headless"),
+        }
+    }
+}
+
+/// Fail with the detected session if `display` targets the display of the environment and this
+/// is a Wayland session without XWayland
+///
+/// Headless sessions are let through, since an X server may still be reachable by other means.
+pub fn require_x(display: &DisplayHandle) -> Result<(), Session> {
+    if display.display.is_some() {
+        return Ok(());
+    }
+    match Session::detect() {
+        Session::Wayland => Err(Session::Wayland),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_session() {
+        let session = |vars: &[(&str, &str)]| {
+            Session::from_vars(|name| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            })
+        };
+        assert_eq!(session(&[("DISPLAY", ":0")]), Session::X11);
+        assert_eq!(
+            session(&[("XDG_SESSION_TYPE", "wayland")]),
+            Session::Wayland
+        );
+        assert_eq!(
+            session(&[("DISPLAY", ""), ("XDG_SESSION_TYPE", "tty")]),
+            Session::Headless
+        );
+        assert!(!Session::Wayland.has_x());
+    }
+}
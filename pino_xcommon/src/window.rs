@@ -0,0 +1,94 @@
+//! Identify the window a client binary acts on
+//!
+//! ```rust
+//! use pino_xcommon::window::{Target, WindowId};
+//!
+//! let id = "0x2a00006".parse::<WindowId>().unwrap();
+//! assert_eq!(id, WindowId(0x2a00006));
+//! assert_eq!(Target::Id(id).args(), ["-id", "0x2a00006"]);
+//! ```
+
+use std::{fmt, num::ParseIntError, str::FromStr, time::Duration};
+
+/// Time the user is given to click a window when it is picked interactively
+pub const SELECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Resource id of a window, written in hexadecimal as X clients print it
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct WindowId(pub u32);
+
+impl fmt::Display for WindowId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl FromStr for WindowId {
+    type Err = ParseIntError;
+
+    /// From a hexadecimal id prefixed with `0x`, or a decimal one
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).map(WindowId),
+            None => s.parse().map(WindowId),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serde_as_str!(WindowId, "window id such as 0x2a00006");
+
+/// Window a command acts on
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Target {
+    /// Window with the given id
+    Id(WindowId),
+    /// Root window of the screen
+    Root,
+    /// Window with the given name, as matched by the client binary
+    Name(String),
+    /// Window the user clicks on, waiting up to [SELECT_TIMEOUT]
+    Select,
+}
+
+impl Target {
+    /// Arguments selecting the window, empty for [Target::Select]
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Target::Id(id) => vec![String::from("-id"), id.to_string()],
+            Target::Root => vec![String::from("-root")],
+            Target::Name(name) => vec![String::from("-name"), name.clone()],
+            Target::Select => vec![],
+        }
+    }
+
+    /// Time a command selecting the window may run for, `None` for the timeout of the runner
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            Target::Select => Some(SELECT_TIMEOUT),
+            _ => None,
+        }
+    }
+}
+
+impl From<WindowId> for Target {
+    fn from(id: WindowId) -> Self {
+        Target::Id(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_window_id() {
+        assert_eq!("42".parse(), Ok(WindowId(42)));
+        assert_eq!("0X1f".parse(), Ok(WindowId(0x1f)));
+        assert!("0xzz".parse::<WindowId>().is_err());
+        assert_eq!(WindowId(0x1f).to_string(), "0x1f");
+    }
+}
@@ -0,0 +1,226 @@
+//! Synthetic key, pointer and button input through XTEST or `xdotool`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xdo::{Action, KeySym};
+//!
+//! pino_xdo::perform(&[
+//!     Action::MoveTo(640, 400),
+//!     Action::Click(1),
+//!     Action::Type(String::from("hello")),
+//!     Action::Key(KeySym::KEY_RETURN),
+//! ])
+//! .unwrap();
+//! # }
+//! ```
+//!
+//! With the `native` feature the events are faked through the XTEST extension, with keysyms
+//! resolved to keycodes through the keytable of `pino_xmodmap`. Otherwise, or when that fails,
+//! every action runs xdotool once. Everything that runs xdotool is behind the default `process`
+//! feature. Without it only the `*_with_runner` functions are available.
+
+#[cfg(feature = "native")]
+mod native;
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{backend, display::DisplayHandle, platform, runner, session::Session};
+pub use pino_xmodmap::KeySym;
+
+/// Errors of faking input
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xdotool could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Request to the X server failed, with the `native` feature
+    Connection(String),
+    /// Keytable could not be read or has no key for a keysym, with the `native` feature
+    Keymap(pino_xmodmap::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            Error::Keymap(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Connection(e) => write!(f, "request to the X server failed: {}", e),
+            Error::Keymap(e) => write!(f, "could not resolve key: {}", e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// A single piece of synthetic input
+///
+/// Buttons are numbered like in X, `1` to `3` being the left, middle and right buttons and `4`
+/// and `5` scrolling up and down.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Action {
+    /// Press a key and keep it down
+    KeyDown(KeySym),
+    /// Release a key pressed with [Action::KeyDown]
+    KeyUp(KeySym),
+    /// Press and release a key
+    Key(KeySym),
+    /// Press and release the keys producing each character of the text
+    Type(String),
+    /// Move the pointer to a position on the root window
+    MoveTo(i32, i32),
+    /// Press a button and keep it down
+    ButtonDown(u8),
+    /// Release a button pressed with [Action::ButtonDown]
+    ButtonUp(u8),
+    /// Press and release a button
+    Click(u8),
+}
+
+impl Action {
+    /// Arguments of xdotool performing the action
+    fn xdotool_args(&self) -> Vec<String> {
+        match self {
+            Action::KeyDown(keysym) => vec![String::from("keydown"), keysym.to_string()],
+            Action::KeyUp(keysym) => vec![String::from("keyup"), keysym.to_string()],
+            Action::Key(keysym) => vec![String::from("key"), keysym.to_string()],
+            Action::Type(text) => vec![String::from("type"), String::from("--"), text.clone()],
+            Action::MoveTo(x, y) => vec![String::from("mousemove"), x.to_string(), y.to_string()],
+            Action::ButtonDown(button) => vec![String::from("mousedown"), button.to_string()],
+            Action::ButtonUp(button) => vec![String::from("mouseup"), button.to_string()],
+            Action::Click(button) => vec![String::from("click"), button.to_string()],
+        }
+    }
+}
+
+/// Perform the actions in order on the default display
+///
+/// The native backend is tried first when it is compiled in, see [backend].
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn perform(actions: &[Action]) -> Result<(), Error> {
+    perform_on(&DisplayHandle::default(), actions)
+}
+
+/// Perform the actions in order on the given display
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn perform_on(display: &DisplayHandle, actions: &[Action]) -> Result<(), Error> {
+    use pino_xcommon::backend::{dispatch, Backend};
+
+    pino_xcommon::session::require_x(display).map_err(Error::NoXServer)?;
+    dispatch(|backend| match backend {
+        #[cfg(feature = "native")]
+        Backend::Native => Some(native::perform(display, actions)),
+        #[cfg(feature = "process")]
+        Backend::Binary => Some(perform_with_runner(
+            &display.runner(&runner::SystemRunner::default()),
+            actions,
+        )),
+        _ => None,
+    })
+    .unwrap_or_else(|| Err(Error::Connection(String::from("no backend available"))))
+}
+
+/// Perform the actions in order, running `xdotool` once for each through the given runner
+///
+/// ```rust
+/// use pino_xdo::{runner::{Cmd, MockRunner, Output}, Action, KeySym};
+///
+/// let runner = MockRunner::new()
+///     .respond(Cmd::new("xdotool").args(["mousemove", "10", "20"]), Output::default())
+///     .respond(Cmd::new("xdotool").args(["key", "Return"]), Output::default());
+/// let actions = [Action::MoveTo(10, 20), Action::Key(KeySym::KEY_RETURN)];
+/// pino_xdo::perform_with_runner(&runner, &actions).unwrap();
+/// ```
+pub fn perform_with_runner(runner: &dyn CommandRunner, actions: &[Action]) -> Result<(), Error> {
+    for action in actions {
+        run_checked(runner, &Cmd::new("xdotool").args(action.xdotool_args()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn drag_and_type() {
+        let xdotool = |args: &[&str]| Cmd::new("xdotool").args(args.iter().copied());
+        let runner = MockRunner::new()
+            .respond(xdotool(&["mousedown", "1"]), Output::default())
+            .respond(xdotool(&["mousemove", "300", "40"]), Output::default())
+            .respond(xdotool(&["mouseup", "1"]), Output::default())
+            .respond(xdotool(&["keydown", "Shift_L"]), Output::default())
+            .respond(xdotool(&["type", "--", "-n hi"]), Output::default())
+            .respond(xdotool(&["keyup", "Shift_L"]), Output::default())
+            .respond(
+                xdotool(&["click", "4"]),
+                Output {
+                    status: 1,
+                    ..Output::default()
+                },
+            );
+
+        let actions = [
+            Action::ButtonDown(1),
+            Action::MoveTo(300, 40),
+            Action::ButtonUp(1),
+            Action::KeyDown(KeySym::KEY_SHIFT_L),
+            Action::Type(String::from("-n hi")),
+            Action::KeyUp(KeySym::KEY_SHIFT_L),
+        ];
+        perform_with_runner(&runner, &actions).unwrap();
+        let err = perform_with_runner(&runner, &[Action::Click(4)]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn report_failures() {
+        let xdotool = |args: &[&str]| Cmd::new("xdotool").args(args.iter().copied());
+        let runner = MockRunner::new()
+            .respond(
+                xdotool(&["mousemove", "10", "20"]),
+                Output::from_failure(1, "Error: Can't open display: :5\n"),
+            )
+            .respond(xdotool(&["click", "1"]), Output::default())
+            .time_out(xdotool(&["key", "Return"]));
+
+        // the first failing action stops the rest
+        let actions = [Action::MoveTo(10, 20), Action::Click(1)];
+        let err = perform_with_runner(&runner, &actions).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, ref stderr, .. }) if stderr.contains("Can't open display")
+        ));
+        assert_eq!(runner.calls(), vec![xdotool(&["mousemove", "10", "20"])]);
+        let err = perform_with_runner(&runner, &[Action::Key(KeySym::KEY_RETURN)]).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::TimedOut { .. })));
+
+        let err = perform_with_runner(&MockRunner::new(), &[Action::Click(1)]).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Spawn { program, .. }) if program == "xdotool"));
+        let err = Error::NoXServer(Session::Wayland);
+        assert_eq!(err.to_string(), "no X server in a Wayland session");
+    }
+}
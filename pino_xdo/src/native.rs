@@ -0,0 +1,134 @@
+//! Input faked through the XTEST extension, enabled by the `native` feature
+
+use pino_xcommon::{display::DisplayHandle, native::connect_display, runner::RunError};
+use pino_xmodmap::{KeyCode, KeySym, KeyTable, Modifier, DEFAULT_COLUMNS};
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        xproto::{
+            Window, BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+            MOTION_NOTIFY_EVENT,
+        },
+        xtest::{self, ConnectionExt},
+    },
+    rust_connection::RustConnection,
+};
+
+use crate::{Action, Error};
+
+fn err(e: impl std::fmt::Display) -> Error {
+    Error::Connection(e.to_string())
+}
+
+struct Xtest {
+    conn: RustConnection,
+    root: Window,
+    keys: KeyTable,
+    shift: KeyCode,
+}
+
+impl Xtest {
+    fn connect(display: &DisplayHandle) -> Result<Self, Error> {
+        let (conn, screen) = connect_display(display).map_err(err)?;
+        if conn
+            .extension_information(xtest::X11_EXTENSION_NAME)
+            .map_err(err)?
+            .is_none()
+        {
+            return Err(Error::Run(RunError::Unsupported(String::from(
+                "the X server has no XTEST extension",
+            ))));
+        }
+        let root = conn.setup().roots[screen].root;
+        let keys = KeyTable::with_display(display, DEFAULT_COLUMNS).map_err(Error::Keymap)?;
+        let (_, shift) = keys.get_key(KeySym::KEY_SHIFT_L).map_err(Error::Keymap)?;
+        Ok(Xtest {
+            conn,
+            root,
+            keys,
+            shift,
+        })
+    }
+
+    fn fake(&self, kind: u8, detail: u8, x: i16, y: i16) -> Result<(), Error> {
+        self.conn
+            .xtest_fake_input(kind, detail, 0, self.root, x, y, 0)
+            .map_err(err)?
+            .check()
+            .map_err(err)
+    }
+
+    /// Keycode of the keysym, and if shift has to be held to produce it
+    fn keycode(&self, keysym: KeySym) -> Result<(KeyCode, bool), Error> {
+        match self.keys.get_key(keysym.clone()).map_err(Error::Keymap)? {
+            (Modifier::Key, code) => Ok((code, false)),
+            (Modifier::ShiftKey, code) => Ok((code, true)),
+            _ => Err(Error::Run(RunError::Unsupported(format!(
+                "{} needs a modifier other than shift",
+                keysym
+            )))),
+        }
+    }
+
+    fn key_down(&self, keysym: KeySym) -> Result<(), Error> {
+        let (code, shifted) = self.keycode(keysym)?;
+        if shifted {
+            self.fake(KEY_PRESS_EVENT, self.shift, 0, 0)?;
+        }
+        self.fake(KEY_PRESS_EVENT, code, 0, 0)
+    }
+
+    fn key_up(&self, keysym: KeySym) -> Result<(), Error> {
+        let (code, shifted) = self.keycode(keysym)?;
+        self.fake(KEY_RELEASE_EVENT, code, 0, 0)?;
+        if shifted {
+            self.fake(KEY_RELEASE_EVENT, self.shift, 0, 0)?;
+        }
+        Ok(())
+    }
+
+    fn perform(&self, action: &Action) -> Result<(), Error> {
+        match action {
+            Action::KeyDown(keysym) => self.key_down(keysym.clone()),
+            Action::KeyUp(keysym) => self.key_up(keysym.clone()),
+            Action::Key(keysym) => {
+                self.key_down(keysym.clone())?;
+                self.key_up(keysym.clone())
+            },
+            Action::Type(text) => {
+                for c in text.chars() {
+                    let keysym = match c {
+                        '\n' => KeySym::KEY_RETURN,
+                        '\t' => KeySym::KEY_TAB,
+                        c => KeySym::try_from(c).map_err(|_| {
+                            Error::Run(RunError::Unsupported(format!("no keysym for {:?}", c)))
+                        })?,
+                    };
+                    self.key_down(keysym.clone())?;
+                    self.key_up(keysym)?;
+                }
+                Ok(())
+            },
+            Action::MoveTo(x, y) => {
+                let (x, y) = (*x as i16, *y as i16);
+                // detail 0 moves to an absolute position
+                self.fake(MOTION_NOTIFY_EVENT, 0, x, y)
+            },
+            Action::ButtonDown(button) => self.fake(BUTTON_PRESS_EVENT, *button, 0, 0),
+            Action::ButtonUp(button) => self.fake(BUTTON_RELEASE_EVENT, *button, 0, 0),
+            Action::Click(button) => {
+                self.fake(BUTTON_PRESS_EVENT, *button, 0, 0)?;
+                self.fake(BUTTON_RELEASE_EVENT, *button, 0, 0)
+            },
+        }
+    }
+}
+
+/// Fake the actions in order, equivalent to running xdotool for each
+pub(crate) fn perform(display: &DisplayHandle, actions: &[Action]) -> Result<(), Error> {
+    let xtest = Xtest::connect(display)?;
+    for action in actions {
+        xtest.perform(action)?;
+    }
+    xtest.conn.flush().map_err(err)
+}
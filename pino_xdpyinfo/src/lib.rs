@@ -0,0 +1,300 @@
+//! Query the dimensions, resolution and extensions of the X server through `xdpyinfo`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xdpyinfo::DisplayInfo;
+//!
+//! let info = DisplayInfo::query().unwrap();
+//! let screen = info.screen().unwrap();
+//! println!("{}x{} at {:?} dpi", screen.width, screen.height, info.dpi());
+//! assert!(info.has_extension("RANDR"));
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+/// Errors of running xdpyinfo
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xdpyinfo could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xdpyinfo could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xdpyinfo output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Information about the X server, as printed by `xdpyinfo`
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DisplayInfo {
+    /// Name of the display, such as `:0`
+    pub name: String,
+    /// Vendor of the X server, such as `The X.Org Foundation`
+    pub vendor: String,
+    /// Release number of the X server, as defined by its vendor
+    pub vendor_release: u32,
+    /// Names of the extensions the X server supports, such as `RANDR`
+    pub extensions: Vec<String>,
+    /// Number of the screen clients use unless told otherwise
+    pub default_screen: usize,
+    /// Screens of the display
+    pub screens: Vec<ScreenInfo>,
+}
+
+/// Screen of the display, as listed by `xdpyinfo`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ScreenInfo {
+    /// Number of the screen, `0` on all but multi-screen setups
+    pub number: usize,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Width in millimeters as the X server was told, which is often derived from a fixed DPI
+    pub width_mm: u32,
+    /// Height in millimeters
+    pub height_mm: u32,
+    /// Depths the screen supports in bits per pixel
+    pub depths: Vec<u8>,
+    /// Depth of the root window
+    pub root_depth: u8,
+}
+
+impl DisplayInfo {
+    /// Query the information of the X server
+    #[cfg(feature = "process")]
+    pub fn query() -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// Query the information of the X server, running `xdpyinfo` through the given runner
+    pub fn query_with_runner(runner: &dyn CommandRunner) -> Result<Self, Error> {
+        let output = run_checked(runner, &Cmd::new("xdpyinfo"))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `xdpyinfo`
+    ///
+    /// ```rust
+    /// use pino_xdpyinfo::DisplayInfo;
+    ///
+    /// let info = DisplayInfo::parse(concat!(
+    ///     "name of display:    :0\n",
+    ///     "number of extensions:    2\n",
+    ///     "    Composite\n",
+    ///     "    RANDR\n",
+    ///     "default screen number:    0\n",
+    ///     "\n",
+    ///     "screen #0:\n",
+    ///     "  dimensions:    2560x1440 pixels (677x381 millimeters)\n",
+    ///     "  depths (2):    24, 32\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert!(info.has_extension("RANDR"));
+    /// assert_eq!(info.screens[0].width, 2560);
+    /// assert_eq!(info.dpi().map(f64::round), Some(96.0));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut info = DisplayInfo::default();
+        let mut in_extensions = false;
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            if line.starts_with(' ') {
+                if in_extensions {
+                    info.extensions.push(line.trim().to_owned());
+                } else if let Some(screen) = info.screens.last_mut() {
+                    screen.parse_line(line)?;
+                }
+                continue;
+            }
+            in_extensions = false;
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| Error::Malformed(line.to_owned()))?;
+            let value = value.trim();
+            match key {
+                "name of display" => info.name = value.to_owned(),
+                "vendor string" => info.vendor = value.to_owned(),
+                "vendor release number" => info.vendor_release = parse_value(line, value)?,
+                "number of extensions" => in_extensions = true,
+                "default screen number" => info.default_screen = parse_value(line, value)?,
+                _ => {
+                    if let Some(number) = key.strip_prefix("screen #") {
+                        info.screens.push(ScreenInfo {
+                            number: parse_value(line, number)?,
+                            ..Default::default()
+                        });
+                    }
+                },
+            }
+        }
+        Ok(info)
+    }
+
+    /// Default screen, which clients use unless told otherwise
+    pub fn screen(&self) -> Option<&ScreenInfo> {
+        self.screens
+            .iter()
+            .find(|s| s.number == self.default_screen)
+    }
+
+    /// Horizontal resolution of the default screen in dots per inch, see [ScreenInfo::resolution]
+    pub fn dpi(&self) -> Option<f64> {
+        self.screen().and_then(ScreenInfo::dpi)
+    }
+
+    /// Check if the X server supports an extension, such as `RANDR`
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == name)
+    }
+}
+
+impl ScreenInfo {
+    /// Horizontal and vertical resolution in dots per inch, computed from the dimensions
+    ///
+    /// `None` if the X server does not know the physical size of the screen.
+    pub fn resolution(&self) -> Option<(f64, f64)> {
+        if self.width_mm == 0 || self.height_mm == 0 {
+            return None;
+        }
+        let dpi = |px: u32, mm: u32| px as f64 * 25.4 / mm as f64;
+        Some((
+            dpi(self.width, self.width_mm),
+            dpi(self.height, self.height_mm),
+        ))
+    }
+
+    /// Horizontal resolution in dots per inch, see [ScreenInfo::resolution]
+    pub fn dpi(&self) -> Option<f64> {
+        self.resolution().map(|(x, _)| x)
+    }
+
+    /// Parse an indented line of the section of the screen
+    fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            return Ok(());
+        };
+        let mut words = value.split_whitespace();
+        match key {
+            "dimensions" => {
+                // 1920x1080 pixels (508x285 millimeters)
+                let size = |word: Option<&str>| {
+                    let (w, h) = word?.trim_start_matches('(').split_once('x')?;
+                    Some((w.parse().ok()?, h.parse().ok()?))
+                };
+                (self.width, self.height) =
+                    size(words.next()).ok_or_else(|| Error::Malformed(line.to_owned()))?;
+                (self.width_mm, self.height_mm) =
+                    size(words.nth(1)).ok_or_else(|| Error::Malformed(line.to_owned()))?;
+            },
+            "depth of root window" => {
+                self.root_depth = parse_value(line, words.next().unwrap_or_default())?
+            },
+            _ if key.starts_with("depths") => {
+                self.depths = value
+                    .split(',')
+                    .map(|depth| parse_value(line, depth.trim()))
+                    .collect::<Result<_, _>>()?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+/// Parse a value, failing with the line it is part of
+fn parse_value<T: FromStr>(line: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::Malformed(line.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_xdpyinfo() {
+        let info = DisplayInfo::parse(concat!(
+            "name of display:    :0\n",
+            "version number:    11.0\n",
+            "vendor string:    The X.Org Foundation\n",
+            "vendor release number:    12101008\n",
+            "X.Org version: 21.1.8\n",
+            "supported pixmap formats:\n",
+            "    depth 1, bits_per_pixel 1, scanline_pad 32\n",
+            "    depth 24, bits_per_pixel 32, scanline_pad 32\n",
+            "keycode range:    minimum 8, maximum 255\n",
+            "focus:  window 0x2600006, revert to Parent\n",
+            "number of extensions:    3\n",
+            "    BIG-REQUESTS\n",
+            "    RANDR\n",
+            "    XInputExtension\n",
+            "default screen number:    1\n",
+            "number of screens:    2\n",
+            "\n",
+            "screen #0:\n",
+            "  dimensions:    1920x1080 pixels (0x0 millimeters)\n",
+            "  resolution:    0x0 dots per inch\n",
+            "  depths (2):    24, 1\n",
+            "  root window id:    0x3b2\n",
+            "  depth of root window:    24 planes\n",
+            "\n",
+            "screen #1:\n",
+            "  dimensions:    3840x2160 pixels (508x285 millimeters)\n",
+            "  resolution:    192x192 dots per inch\n",
+            "  depths (7):    24, 1, 4, 8, 15, 16, 32\n",
+            "  depth of root window:    24 planes\n",
+            "  number of colormaps:    minimum 1, maximum 1\n",
+        ))
+        .unwrap();
+
+        assert_eq!(info.vendor, "The X.Org Foundation");
+        assert_eq!(info.vendor_release, 12101008);
+        assert_eq!(
+            info.extensions,
+            ["BIG-REQUESTS", "RANDR", "XInputExtension"]
+        );
+        assert!(!info.has_extension("depth 1, bits_per_pixel 1, scanline_pad 32"));
+        assert_eq!(info.screens.len(), 2);
+        assert_eq!(info.screens[0].resolution(), None);
+        assert_eq!(info.screens[1].depths, [24, 1, 4, 8, 15, 16, 32]);
+        assert_eq!(info.screens[1].root_depth, 24);
+        assert_eq!(info.dpi().map(f64::round), Some(192.0));
+
+        assert!(DisplayInfo::parse("screen #0:\n  dimensions:    1920 pixels\n").is_err());
+    }
+}
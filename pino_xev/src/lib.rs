@@ -0,0 +1,526 @@
+//! Follow the key, button, focus and property events of a window through `xev`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xev::{Event, EventMask, Events, Target};
+//!
+//! let events = Events::new(&Target::Root, &[EventMask::Keyboard, EventMask::Property]).unwrap();
+//! for event in events {
+//!     match event.unwrap() {
+//!         Event::KeyPress(key) => println!("pressed {}", key.keysym),
+//!         Event::Property(prop) => println!("{} changed", prop.atom),
+//!         _ => {},
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{check_output, Cmd, CommandRunner, Process, RunError};
+pub use pino_xcommon::{
+    display::DisplayHandle,
+    platform, runner,
+    session::Session,
+    window::{Target, WindowId},
+};
+
+/// Errors of running xev
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xev could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xev could not be parsed, holding the offending event
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(event) => write!(f, "could not parse xev output: {}", event),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Kind of events to subscribe to, passed to `xev -event`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum EventMask {
+    /// Key presses and releases
+    Keyboard,
+    /// Mouse button presses and releases, which only one client may select on a window
+    Button,
+    /// Keyboard focus entering and leaving the window
+    Focus,
+    /// Properties of the window being changed or deleted
+    Property,
+}
+
+impl fmt::Display for EventMask {
+    /// Name xev takes, such as `keyboard`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EventMask::Keyboard => "keyboard",
+            EventMask::Button => "button",
+            EventMask::Focus => "focus",
+            EventMask::Property => "property",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Event received by the window
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Event {
+    /// Key was pressed
+    KeyPress(KeyEvent),
+    /// Key was released, also sent between the presses of a key held down
+    KeyRelease(KeyEvent),
+    /// Mouse button was pressed, or the wheel scrolled
+    ButtonPress(ButtonEvent),
+    /// Mouse button was released
+    ButtonRelease(ButtonEvent),
+    /// Window gained the keyboard focus
+    FocusIn(FocusEvent),
+    /// Window lost the keyboard focus
+    FocusOut(FocusEvent),
+    /// Property of the window was changed or deleted
+    Property(PropertyEvent),
+    /// Any other event, such as `KeymapNotify`, holding its name
+    Other(String),
+}
+
+/// Key pressed or released
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct KeyEvent {
+    /// Window the event was reported on
+    pub window: WindowId,
+    /// Server time of the event, in milliseconds
+    pub time: u32,
+    /// Horizontal position of the pointer relative to the window
+    pub x: i32,
+    /// Vertical position of the pointer relative to the window
+    pub y: i32,
+    /// Horizontal position of the pointer on the screen
+    pub root_x: i32,
+    /// Vertical position of the pointer on the screen
+    pub root_y: i32,
+    /// Modifiers and buttons held before the event, as a mask such as `0x4` for Control
+    pub state: u16,
+    /// Keycode of the key
+    pub keycode: u8,
+    /// Name of the keysym the key produces with the held modifiers, such as `a` or
+    /// `XF86AudioPlay`, `NoSymbol` if it has none
+    pub keysym: String,
+}
+
+/// Mouse button pressed or released
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ButtonEvent {
+    /// Window the event was reported on
+    pub window: WindowId,
+    /// Server time of the event, in milliseconds
+    pub time: u32,
+    /// Horizontal position of the pointer relative to the window
+    pub x: i32,
+    /// Vertical position of the pointer relative to the window
+    pub y: i32,
+    /// Horizontal position of the pointer on the screen
+    pub root_x: i32,
+    /// Vertical position of the pointer on the screen
+    pub root_y: i32,
+    /// Modifiers and buttons held before the event
+    pub state: u16,
+    /// Number of the button, `4` and `5` being the scroll wheel
+    pub button: u8,
+}
+
+/// Keyboard focus entering or leaving a window
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FocusEvent {
+    /// Window the event was reported on
+    pub window: WindowId,
+    /// Why the focus changed, such as `NotifyNormal` or `NotifyGrab`
+    pub mode: String,
+    /// How the window relates to the one losing or gaining the focus, such as `NotifyNonlinear`
+    pub detail: String,
+}
+
+/// Property of a window changed or deleted
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PropertyEvent {
+    /// Window the property belongs to
+    pub window: WindowId,
+    /// Server time of the event, in milliseconds
+    pub time: u32,
+    /// Name of the property, such as `_NET_WM_NAME`
+    pub atom: String,
+    /// Whether the property was deleted rather than given a new value
+    pub deleted: bool,
+}
+
+impl Event {
+    /// Parse an event as xev prints it, a line naming the event followed by indented lines
+    ///
+    /// ```rust
+    /// use pino_xev::{Event, WindowId};
+    ///
+    /// let event = Event::parse(concat!(
+    ///     "ButtonPress event, serial 34, synthetic NO, window 0x2a00001,\n",
+    ///     "    root 0x1e2, subw 0x0, time 2209377, (47,76), root:(1012,559),\n",
+    ///     "    state 0x0, button 3, same_screen YES\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// let Event::ButtonPress(button) = event else { panic!() };
+    /// assert_eq!(button.window, WindowId(0x2a00001));
+    /// assert_eq!(button.button, 3);
+    /// assert_eq!((button.root_x, button.root_y), (1012, 559));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(raw.trim().to_owned());
+        let (name, _) = raw
+            .trim_start()
+            .split_once(" event")
+            .ok_or_else(malformed)?;
+        let window = parse_value(raw, field(raw, "window").ok_or_else(malformed)?)?;
+        let value = |key: &str| field(raw, key).ok_or_else(malformed);
+        let pointer = || -> Result<_, Error> {
+            let (x, y) = point(raw, ", (").ok_or_else(malformed)?;
+            let (root_x, root_y) = point(raw, "root:(").ok_or_else(malformed)?;
+            let state = value("state")?;
+            let state = state.strip_prefix("0x").ok_or_else(malformed)?;
+            let state = u16::from_str_radix(state, 16).map_err(|_| malformed())?;
+            let time = parse_value(raw, value("time")?)?;
+            Ok((time, x, y, root_x, root_y, state))
+        };
+
+        let event = match name {
+            "KeyPress" | "KeyRelease" => {
+                let (time, x, y, root_x, root_y, state) = pointer()?;
+                // keycode 38 (keysym 0x61, a)
+                let keycode = value("keycode")?.split(' ').next().unwrap_or_default();
+                let (_, keysym) = raw.split_once("(keysym ").ok_or_else(malformed)?;
+                let (_, keysym) = keysym.split_once(", ").ok_or_else(malformed)?;
+                let (keysym, _) = keysym.split_once(')').ok_or_else(malformed)?;
+                let key = KeyEvent {
+                    window,
+                    time,
+                    x,
+                    y,
+                    root_x,
+                    root_y,
+                    state,
+                    keycode: parse_value(raw, keycode)?,
+                    keysym: keysym.to_owned(),
+                };
+                match name {
+                    "KeyPress" => Event::KeyPress(key),
+                    _ => Event::KeyRelease(key),
+                }
+            },
+            "ButtonPress" | "ButtonRelease" => {
+                let (time, x, y, root_x, root_y, state) = pointer()?;
+                let button = ButtonEvent {
+                    window,
+                    time,
+                    x,
+                    y,
+                    root_x,
+                    root_y,
+                    state,
+                    button: parse_value(raw, value("button")?)?,
+                };
+                match name {
+                    "ButtonPress" => Event::ButtonPress(button),
+                    _ => Event::ButtonRelease(button),
+                }
+            },
+            "FocusIn" | "FocusOut" => {
+                let focus = FocusEvent {
+                    window,
+                    mode: value("mode")?.to_owned(),
+                    detail: value("detail")?.to_owned(),
+                };
+                match name {
+                    "FocusIn" => Event::FocusIn(focus),
+                    _ => Event::FocusOut(focus),
+                }
+            },
+            "PropertyNotify" => {
+                // atom 0x27 (WM_NAME)
+                let (_, atom) = value("atom")?.split_once('(').ok_or_else(malformed)?;
+                Event::Property(PropertyEvent {
+                    window,
+                    time: parse_value(raw, value("time")?)?,
+                    atom: atom.trim_end_matches(')').to_owned(),
+                    deleted: value("state")? == "PropertyDelete",
+                })
+            },
+            name => Event::Other(name.to_owned()),
+        };
+        Ok(event)
+    }
+
+    /// Window the event was reported on, `None` for [Event::Other]
+    pub fn window(&self) -> Option<WindowId> {
+        match self {
+            Event::KeyPress(key) | Event::KeyRelease(key) => Some(key.window),
+            Event::ButtonPress(button) | Event::ButtonRelease(button) => Some(button.window),
+            Event::FocusIn(focus) | Event::FocusOut(focus) => Some(focus.window),
+            Event::Property(prop) => Some(prop.window),
+            Event::Other(_) => None,
+        }
+    }
+}
+
+/// Stream of the events received by a window
+///
+/// The stream ends when the window is destroyed, and xev is killed when it is dropped.
+pub struct Events {
+    process: Box<dyn Process>,
+    event: String,
+    line: String,
+    done: bool,
+}
+
+impl Events {
+    /// Subscribe to the given kinds of events on the target window, or all of them if `masks`
+    /// is empty
+    ///
+    /// xev can only follow a window by its id or the root window. Only one client may select
+    /// button events on a window, which the client owning it usually does, so subscribing to
+    /// [EventMask::Button] on windows of other clients fails.
+    #[cfg(feature = "process")]
+    pub fn new(target: &Target, masks: &[EventMask]) -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        let runner = runner::SystemRunner::new().timeout(None);
+        Self::with_runner(&display.runner(&runner), target, masks)
+    }
+
+    /// Subscribe to events on the target window, running `xev` through the given runner
+    ///
+    /// xev runs until the window is destroyed, so the runner should not time it out.
+    ///
+    /// ```rust
+    /// use pino_xev::{runner::{Cmd, MockRunner, Output}, Event, EventMask, Events, Target};
+    ///
+    /// let runner = MockRunner::new().respond(
+    ///     Cmd::new("xev").args(["-root", "-event", "focus"]),
+    ///     Output::from_stdout(concat!(
+    ///         "FocusOut event, serial 18, synthetic NO, window 0x1e2,\n",
+    ///         "    mode NotifyGrab, detail NotifyPointerRoot\n",
+    ///         "\n",
+    ///     )),
+    /// );
+    /// let mut events = Events::with_runner(&runner, &Target::Root, &[EventMask::Focus]).unwrap();
+    ///
+    /// assert!(matches!(events.next(), Some(Ok(Event::FocusOut(_)))));
+    /// assert!(events.next().is_none());
+    /// ```
+    pub fn with_runner(
+        runner: &dyn CommandRunner,
+        target: &Target,
+        masks: &[EventMask],
+    ) -> Result<Self, Error> {
+        if matches!(target, Target::Name(_) | Target::Select) {
+            return Err(Error::Run(RunError::Unsupported(String::from(
+                "xev can only follow a window by its id or the root window",
+            ))));
+        }
+        let mut cmd = Cmd::new("xev").args(target.args());
+        for mask in masks {
+            cmd = cmd.args(["-event", &mask.to_string()]);
+        }
+        Ok(Events {
+            process: runner
+                .spawn(&cmd)
+                .map_err(|e| RunError::from_io("xev", e))?,
+            event: String::new(),
+            line: String::new(),
+            done: false,
+        })
+    }
+
+    /// Error xev exited with, once its output is exhausted
+    fn finish(&mut self) -> Option<Result<Event, Error>> {
+        self.done = true;
+        match self.process.wait() {
+            Ok(output) => check_output("xev", output).err().map(|e| Err(e.into())),
+            Err(e) => Some(Err(RunError::from_io("xev", e).into())),
+        }
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.event.clear();
+        loop {
+            self.line.clear();
+            let end = match self.process.stdout().read_line(&mut self.line) {
+                Ok(0) => true,
+                Ok(_) => self.line.trim().is_empty(),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(RunError::from_io("xev", e).into()));
+                },
+            };
+            if end {
+                if !self.event.is_empty() {
+                    return Some(Event::parse(&self.event));
+                }
+                if self.line.is_empty() {
+                    return self.finish();
+                }
+                continue;
+            }
+            // every event starts with an unindented line, anything else before it is skipped
+            if !self.event.is_empty() || self.line.contains(" event, serial ") {
+                self.event.push_str(&self.line);
+            }
+        }
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.process.kill();
+            let _ = self.process.wait();
+        }
+    }
+}
+
+/// Value following `key` and a space in an event, up to the next comma or line
+fn field<'a>(raw: &'a str, key: &str) -> Option<&'a str> {
+    let needle = format!(" {} ", key);
+    let (_, rest) = raw.split_once(&needle)?;
+    let end = rest.find([',', '\n']).unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+/// Position such as `(47,76)` following `prefix` in an event
+fn point(raw: &str, prefix: &str) -> Option<(i32, i32)> {
+    let (_, rest) = raw.split_once(prefix)?;
+    let (point, _) = rest.split_once(')')?;
+    let (x, y) = point.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Parse a value, failing with the event it is part of
+fn parse_value<T: FromStr>(raw: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::Malformed(raw.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn follow_events() {
+        let stdout = concat!(
+            "KeyPress event, serial 34, synthetic NO, window 0x2a00001,\n",
+            "    root 0x1e2, subw 0x0, time 2209377, (47,76), root:(1012,559),\n",
+            "    state 0x4, keycode 38 (keysym 0x61, a), same_screen YES,\n",
+            "    XLookupString gives 1 bytes: (01) \"\u{1}\"\n",
+            "    XmbLookupString gives 1 bytes: (01) \"\u{1}\"\n",
+            "    XFilterEvent returns: False\n",
+            "\n",
+            "KeymapNotify event, serial 34, synthetic NO, window 0x0,\n",
+            "    keys:  4294967236 0   0   0   16  0   0   0   0   0   0   0   0   0   0   0   \n",
+            "           0   0   0   0   0   0   0   0   0   0   0   0   0   0   0   0   \n",
+            "\n",
+            "PropertyNotify event, serial 35, synthetic NO, window 0x2a00001,\n",
+            "    atom 0x15c (_NET_WM_STATE), time 2209500, state PropertyDelete\n",
+            "\n",
+            "FocusIn event, serial 36, synthetic NO, window 0x2a00001,\n",
+            "    mode NotifyUngrab, detail NotifyNonlinear\n",
+        );
+        let runner = MockRunner::new().respond(
+            Cmd::new("xev").args(["-id", "0x2a00001", "-event", "keyboard", "-event", "focus"]),
+            Output::from_stdout(stdout),
+        );
+        let target = Target::Id(WindowId(0x2a00001));
+        let masks = [EventMask::Keyboard, EventMask::Focus];
+        let events = Events::with_runner(&runner, &target, &masks)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            events[0],
+            Event::KeyPress(KeyEvent {
+                window: WindowId(0x2a00001),
+                time: 2209377,
+                x: 47,
+                y: 76,
+                root_x: 1012,
+                root_y: 559,
+                state: 0x4,
+                keycode: 38,
+                keysym: String::from("a"),
+            })
+        );
+        assert_eq!(events[1], Event::Other(String::from("KeymapNotify")));
+        assert_eq!(
+            events[2],
+            Event::Property(PropertyEvent {
+                window: WindowId(0x2a00001),
+                time: 2209500,
+                atom: String::from("_NET_WM_STATE"),
+                deleted: true,
+            })
+        );
+        let Event::FocusIn(focus) = &events[3] else {
+            panic!("expected focus, got {:?}", events[3]);
+        };
+        assert_eq!(focus.mode, "NotifyUngrab");
+        assert_eq!(events.len(), 4);
+
+        let err = Events::with_runner(&runner, &Target::Select, &[])
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Run(RunError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,335 @@
+//! List the fonts of fontconfig through `fc-list` and the core X fonts through `xlsfonts`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! for family in pino_xfonts::families().unwrap() {
+//!     println!("{}", family);
+//! }
+//!
+//! // check the font of a resource before merging it
+//! if !pino_xfonts::exists("JetBrains Mono:size=11").unwrap() {
+//!     println!("JetBrains Mono is not installed");
+//! }
+//! # }
+//! ```
+
+use std::{fmt, path::PathBuf};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+/// Families fontconfig always resolves to an installed font
+pub static GENERIC_FAMILIES: &[&str] = &[
+    "monospace",
+    "mono",
+    "sans-serif",
+    "sans",
+    "serif",
+    "cursive",
+    "fantasy",
+    "emoji",
+    "math",
+    "system-ui",
+];
+
+/// Format fc-list prints every font in, one per line
+const FC_LIST_FORMAT: &str = "%{file}\\t%{family}\\t%{style}\\n";
+
+/// Errors of running fc-list or xlsfonts
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// fc-list or xlsfonts could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server for xlsfonts, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse font: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Font file known to fontconfig
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Font {
+    /// Names of the family, the first being the main one, such as `DejaVu Sans`
+    pub families: Vec<String>,
+    /// Names of the style, such as `Bold Oblique`
+    pub styles: Vec<String>,
+    pub file: PathBuf,
+}
+
+impl Font {
+    /// Check if one of the names of the family is `family`, ignoring case
+    pub fn has_family(&self, family: &str) -> bool {
+        self.families.iter().any(|f| f.eq_ignore_ascii_case(family))
+    }
+}
+
+/// Font named by a resource, in either of the forms programs accept
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FontSpec {
+    /// Fontconfig pattern such as `JetBrains Mono:size=11`, holding the families it lists
+    Fontconfig(Vec<String>),
+    /// Core font name such as `-*-terminus-medium-r-*-*-12-*-*-*-*-*-*-*`, possibly with
+    /// wildcards
+    Xlfd(String),
+}
+
+impl FontSpec {
+    /// Parse the value of a font resource
+    ///
+    /// The `xft:` prefix some programs take is stripped, and a fontconfig pattern without a
+    /// family matches any font.
+    ///
+    /// ```rust
+    /// use pino_xfonts::FontSpec;
+    ///
+    /// assert_eq!(
+    ///     FontSpec::parse("xft:Iosevka,Noto Color Emoji:size=11:antialias=true"),
+    ///     FontSpec::Fontconfig(vec![String::from("Iosevka"), String::from("Noto Color Emoji")])
+    /// );
+    /// assert_eq!(
+    ///     FontSpec::parse("-*-fixed-medium-r-*-*-13-*"),
+    ///     FontSpec::Xlfd(String::from("-*-fixed-medium-r-*-*-13-*"))
+    /// );
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with('-') {
+            return FontSpec::Xlfd(value.to_owned());
+        }
+        let value = value.strip_prefix("xft:").unwrap_or(value);
+        let families = value.split(':').next().unwrap_or_default();
+        FontSpec::Fontconfig(
+            families
+                .split(',')
+                .map(|family| family.trim().replace("\\-", "-"))
+                .filter(|family| !family.is_empty())
+                .collect(),
+        )
+    }
+}
+
+/// Parse the output of fc-list run with [FC_LIST_FORMAT], one font per line
+///
+/// ```rust
+/// let fonts = pino_xfonts::parse_fc_list(
+///     "/usr/share/fonts/DejaVuSans.ttf\tDejaVu Sans,DejaVu Sans Light\tExtraLight\n",
+/// )
+/// .unwrap();
+/// assert!(fonts[0].has_family("dejavu sans light"));
+/// assert_eq!(fonts[0].styles, ["ExtraLight"]);
+/// ```
+pub fn parse_fc_list(raw: &str) -> Result<Vec<Font>, Error> {
+    let list = |field: &str| -> Vec<String> {
+        field
+            .split(',')
+            .map(|name| name.trim().replace("\\-", "-"))
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split('\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(file), Some(families), Some(styles)) => Ok(Font {
+                    families: list(families),
+                    styles: list(styles),
+                    file: PathBuf::from(file),
+                }),
+                _ => Err(Error::Malformed(line.to_owned())),
+            }
+        })
+        .collect()
+}
+
+/// Every font known to fontconfig
+#[cfg(feature = "process")]
+pub fn list() -> Result<Vec<Font>, Error> {
+    list_with_runner(&runner::SystemRunner::default())
+}
+
+/// Every font known to fontconfig, running `fc-list` through the given runner
+pub fn list_with_runner(runner: &dyn CommandRunner) -> Result<Vec<Font>, Error> {
+    let cmd = Cmd::new("fc-list").args(["--format", FC_LIST_FORMAT]);
+    let output = run_checked(runner, &cmd)?;
+    parse_fc_list(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Main name of every family known to fontconfig, sorted and without duplicates
+#[cfg(feature = "process")]
+pub fn families() -> Result<Vec<String>, Error> {
+    let mut families = list()?
+        .into_iter()
+        .filter_map(|font| font.families.into_iter().next())
+        .collect::<Vec<_>>();
+    families.sort();
+    families.dedup();
+    Ok(families)
+}
+
+/// Names of the core fonts of the X server matching an XLFD pattern, `*` for all of them
+#[cfg(feature = "process")]
+pub fn core_fonts(pattern: &str) -> Result<Vec<String>, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    core_fonts_with_runner(&display.runner(&runner::SystemRunner::default()), pattern)
+}
+
+/// Names of the core fonts matching a pattern, running `xlsfonts` through the given runner
+///
+/// ```rust
+/// use pino_xfonts::runner::{Cmd, MockRunner, Output};
+///
+/// let runner = MockRunner::new().respond(
+///     Cmd::new("xlsfonts").args(["-fn", "*fixed*"]),
+///     Output::from_stdout("-misc-fixed-medium-r-normal--13-120-75-75-c-70-iso10646-1\nfixed\n"),
+/// );
+/// let fonts = pino_xfonts::core_fonts_with_runner(&runner, "*fixed*").unwrap();
+/// assert_eq!(fonts.len(), 2);
+/// ```
+pub fn core_fonts_with_runner(
+    runner: &dyn CommandRunner,
+    pattern: &str,
+) -> Result<Vec<String>, Error> {
+    let cmd = Cmd::new("xlsfonts").args(["-fn", pattern]);
+    let output = match run_checked(runner, &cmd) {
+        // xlsfonts complains on stderr when nothing matches
+        Err(RunError::Exited { stderr, .. }) if stderr.contains("unmatched") => return Ok(vec![]),
+        output => output?,
+    };
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Check if a core font name matches an XLFD pattern, where `*` matches any run of characters
+/// and `?` any single one, ignoring case like the X server
+///
+/// ```rust
+/// let name = "-misc-fixed-medium-r-normal--13-120-75-75-c-70-iso10646-1";
+/// assert!(pino_xfonts::matches("-*-FIXED-medium-r-*-*-13-*", name));
+/// assert!(!pino_xfonts::matches("-*-terminus-*", name));
+/// ```
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase().into_bytes();
+    let name = name.to_ascii_lowercase().into_bytes();
+    // the last `*` and the position in the name it was tried at, to backtrack to
+    let (mut p, mut n, mut star) = (0, 0, None);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            },
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            },
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Check if the font of a resource is installed
+///
+/// Every family of a fontconfig pattern has to be known to fontconfig, or be one of the
+/// [GENERIC_FAMILIES], while a core font name has to match a font of the X server.
+#[cfg(feature = "process")]
+pub fn exists(value: &str) -> Result<bool, Error> {
+    match FontSpec::parse(value) {
+        FontSpec::Fontconfig(families) => Ok(families_exist(&list()?, &families)),
+        FontSpec::Xlfd(pattern) => Ok(!core_fonts(&pattern)?.is_empty()),
+    }
+}
+
+/// Check if every family is in the fonts or one of the [GENERIC_FAMILIES]
+pub fn families_exist(fonts: &[Font], families: &[String]) -> bool {
+    families.iter().all(|family| {
+        GENERIC_FAMILIES
+            .iter()
+            .any(|generic| generic.eq_ignore_ascii_case(family))
+            || fonts.iter().any(|font| font.has_family(family))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn validate_fonts() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("fc-list").args(["--format", FC_LIST_FORMAT]),
+                Output::from_stdout(concat!(
+                    "/usr/share/fonts/iosevka.ttc\tIosevka,Iosevka Light\tLight,Regular\n",
+                    "/usr/share/fonts/NotoColorEmoji.ttf\tNoto Color Emoji\tRegular\n",
+                )),
+            )
+            .respond(
+                Cmd::new("xlsfonts").args(["-fn", "*terminus*"]),
+                Output::from_failure(1, "xlsfonts: pattern \"*terminus*\" unmatched\n"),
+            );
+
+        let fonts = list_with_runner(&runner).unwrap();
+        assert_eq!(fonts[0].styles, ["Light", "Regular"]);
+        let FontSpec::Fontconfig(families) = FontSpec::parse("Iosevka Light,monospace:size=10")
+        else {
+            panic!("not a fontconfig pattern");
+        };
+        assert!(families_exist(&fonts, &families));
+        assert!(!families_exist(&fonts, &[String::from("Hack")]));
+
+        assert!(core_fonts_with_runner(&runner, "*terminus*")
+            .unwrap()
+            .is_empty());
+        assert!(parse_fc_list("no tabs here\n").is_err());
+        assert!(matches(
+            "*-iso10646-?",
+            "-misc-fixed-medium-r-normal--13-120-75-75-c-70-iso10646-1"
+        ));
+    }
+}
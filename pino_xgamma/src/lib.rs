@@ -0,0 +1,168 @@
+//! Query and set the gamma correction of the whole screen through `xgamma`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xgamma::{Color, Gamma};
+//!
+//! println!("gamma is {}", pino_xgamma::get().unwrap());
+//! pino_xgamma::set(Gamma::tint(Color::rgb(0xff, 0xc8, 0x96))).unwrap();
+//! # }
+//! ```
+//!
+//! xgamma goes through the XFree86-VidMode extension, which the X server applies to the gamma ramps
+//! RandR keeps for every output of the screen. Outputs are adjusted one at a time with
+//! `pino_xrandr::gamma` instead.
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    color::Color, display::DisplayHandle, gamma::Gamma, platform, runner, session::Session,
+};
+
+/// Errors of running xgamma
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xgamma could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xgamma could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xgamma output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Parse the gamma xgamma reports, such as `-> Red  1.000, Green  0.900, Blue  0.800`
+///
+/// When the gamma was changed the new one, on the last line, is returned.
+///
+/// ```rust
+/// use pino_xgamma::Gamma;
+///
+/// let gamma = pino_xgamma::parse(concat!(
+///     "-> Red  1.000, Green  1.000, Blue  1.000\n",
+///     "<- Red  1.000, Green  0.900, Blue  0.800\n",
+/// ))
+/// .unwrap();
+/// assert_eq!(gamma, Gamma { red: 1.0, green: 0.9, blue: 0.8 });
+/// ```
+pub fn parse(raw: &str) -> Result<Gamma, Error> {
+    let line = raw
+        .lines()
+        .rfind(|l| l.contains("Red"))
+        .ok_or_else(|| Error::Malformed(raw.trim().to_owned()))?;
+    let malformed = || Error::Malformed(line.trim().to_owned());
+    let channel = |name: &str| -> Result<f32, Error> {
+        let (_, rest) = line.split_once(name).ok_or_else(malformed)?;
+        let value = rest.trim_start().split(',').next().unwrap_or_default();
+        value.trim().parse().map_err(|_| malformed())
+    };
+    Ok(Gamma {
+        red: channel("Red")?,
+        green: channel("Green")?,
+        blue: channel("Blue")?,
+    })
+}
+
+/// Gamma correction of the screen
+#[cfg(feature = "process")]
+pub fn get() -> Result<Gamma, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    get_with_runner(&display.runner(&runner::SystemRunner::default()))
+}
+
+/// Gamma correction of the screen, running `xgamma` through the given runner
+pub fn get_with_runner(runner: &dyn CommandRunner) -> Result<Gamma, Error> {
+    // xgamma reports on stderr
+    let output = run_checked(runner, &Cmd::new("xgamma"))?;
+    parse(&String::from_utf8_lossy(&output.stderr))
+}
+
+/// Set the gamma correction of the screen, each channel between `0.1` and `10.0`
+#[cfg(feature = "process")]
+pub fn set(gamma: Gamma) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_with_runner(&display.runner(&runner::SystemRunner::default()), gamma)
+}
+
+/// Set the gamma correction of the screen, running `xgamma` through the given runner
+///
+/// ```rust
+/// use pino_xgamma::{runner::{Cmd, MockRunner, Output}, Gamma};
+///
+/// let cmd = Cmd::new("xgamma").args(["-quiet", "-rgamma", "1", "-ggamma", "0.9", "-bgamma", "0.8"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// let gamma = Gamma { red: 1.0, green: 0.9, blue: 0.8 };
+/// pino_xgamma::set_with_runner(&runner, gamma).unwrap();
+/// ```
+pub fn set_with_runner(runner: &dyn CommandRunner, gamma: Gamma) -> Result<(), Error> {
+    let cmd = Cmd::new("xgamma")
+        .arg("-quiet")
+        .args(["-rgamma", &gamma.red.to_string()])
+        .args(["-ggamma", &gamma.green.to_string()])
+        .args(["-bgamma", &gamma.blue.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Undo any gamma correction of the screen, the same as setting [Gamma::IDENTITY]
+#[cfg(feature = "process")]
+pub fn reset() -> Result<(), Error> {
+    set(Gamma::IDENTITY)
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn query_and_tint() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xgamma"),
+                Output {
+                    stderr: b"-> Red  1.000, Green  0.700, Blue  0.420\n".to_vec(),
+                    ..Output::default()
+                },
+            )
+            .respond(
+                Cmd::new("xgamma")
+                    .args(["-quiet", "-rgamma", "1", "-ggamma", "0.4", "-bgamma", "0.1"]),
+                Output::default(),
+            );
+
+        let gamma = get_with_runner(&runner).unwrap();
+        assert_eq!((gamma.green, gamma.blue), (0.7, 0.42));
+        set_with_runner(&runner, Gamma::tint(Color::rgb(0xff, 0x66, 0))).unwrap();
+        assert!(parse("xgamma: Unable to query video extension version\n").is_err());
+    }
+}
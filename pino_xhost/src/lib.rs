@@ -0,0 +1,335 @@
+//! Query and change the access control list of the X server through `xhost`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xhost::Entry;
+//!
+//! // let root run graphical programs on this display
+//! let root = Entry::LocalUser(String::from("root"));
+//! if !pino_xhost::query().unwrap().allows(&root) {
+//!     pino_xhost::add(&root).unwrap();
+//! }
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+/// Errors of running xhost
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xhost could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xhost could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xhost output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Entry of the access control list
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Entry {
+    /// Host reached over IPv4, by name or address
+    Inet(String),
+    /// Host reached over IPv6, by name or address
+    Inet6(String),
+    /// Every client connecting through a local socket
+    Local,
+    /// Clients run by the given user, such as `si:localuser:root`
+    LocalUser(String),
+    /// Clients run by a member of the given group, such as `si:localgroup:video`
+    LocalGroup(String),
+    /// Any other server interpreted entry, such as `si:hostname:pino`
+    ServerInterpreted { kind: String, value: String },
+    /// Entry of a family xhost does not take apart, such as `DNET:pino`
+    Other(String),
+}
+
+impl fmt::Display for Entry {
+    /// Format as xhost takes it, such as `si:localuser:root`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Inet(host) => write!(f, "inet:{}", host),
+            Entry::Inet6(host) => write!(f, "inet6:{}", host),
+            Entry::Local => write!(f, "local:"),
+            Entry::LocalUser(user) => write!(f, "si:localuser:{}", user),
+            Entry::LocalGroup(group) => write!(f, "si:localgroup:{}", group),
+            Entry::ServerInterpreted { kind, value } => write!(f, "si:{}:{}", kind, value),
+            Entry::Other(entry) => write!(f, "{}", entry),
+        }
+    }
+}
+
+impl FromStr for Entry {
+    type Err = Error;
+
+    /// From an entry as xhost lists or takes it, ignoring the case of the family
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some((family, rest)) = s.split_once(':') else {
+            // a host without a family is looked up by xhost
+            return match s.is_empty() {
+                true => Err(Error::Malformed(s.to_owned())),
+                false => Ok(Entry::Inet(s.to_owned())),
+            };
+        };
+        let entry = match family.to_ascii_lowercase().as_str() {
+            "inet" => Entry::Inet(rest.to_owned()),
+            "inet6" => Entry::Inet6(rest.to_owned()),
+            "local" => Entry::Local,
+            "si" => {
+                let (kind, value) = rest
+                    .split_once(':')
+                    .ok_or_else(|| Error::Malformed(s.to_owned()))?;
+                match kind.to_ascii_lowercase().as_str() {
+                    "localuser" => Entry::LocalUser(value.to_owned()),
+                    "localgroup" => Entry::LocalGroup(value.to_owned()),
+                    _ => Entry::ServerInterpreted {
+                        kind: kind.to_owned(),
+                        value: value.to_owned(),
+                    },
+                }
+            },
+            _ => Entry::Other(s.to_owned()),
+        };
+        Ok(entry)
+    }
+}
+
+/// Access control of the X server, as printed by `xhost`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AccessControl {
+    /// Whether only the clients in `entries`, or holding a cookie, may connect
+    pub enabled: bool,
+    /// Clients allowed to connect without a cookie
+    pub entries: Vec<Entry>,
+}
+
+impl AccessControl {
+    /// Parse the output of `xhost`
+    ///
+    /// ```rust
+    /// use pino_xhost::{AccessControl, Entry};
+    ///
+    /// let acl = AccessControl::parse(concat!(
+    ///     "access control enabled, only authorized clients can connect\n",
+    ///     "SI:localuser:pino\n",
+    ///     "INET:192.168.1.5\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert!(acl.enabled);
+    /// assert_eq!(acl.entries[0], Entry::LocalUser(String::from("pino")));
+    /// assert!(acl.allows(&Entry::Inet(String::from("192.168.1.5"))));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut lines = raw.lines().filter(|l| !l.trim().is_empty());
+        let status = lines.next().unwrap_or_default();
+        let enabled = match status {
+            s if s.starts_with("access control enabled") => true,
+            s if s.starts_with("access control disabled") => false,
+            s => return Err(Error::Malformed(s.to_owned())),
+        };
+        Ok(AccessControl {
+            enabled,
+            entries: lines.map(str::parse).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Check if the clients of `entry` may connect without a cookie, which is any client
+    /// while access control is disabled
+    pub fn allows(&self, entry: &Entry) -> bool {
+        !self.enabled || self.entries.contains(entry)
+    }
+}
+
+/// Access control list of the X server
+#[cfg(feature = "process")]
+pub fn query() -> Result<AccessControl, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    query_with_runner(&display.runner(&runner::SystemRunner::default()))
+}
+
+/// Access control list of the X server, running `xhost` through the given runner
+pub fn query_with_runner(runner: &dyn CommandRunner) -> Result<AccessControl, Error> {
+    let output = run_checked(runner, &Cmd::new("xhost"))?;
+    AccessControl::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Allow the clients of `entry` to connect without a cookie
+#[cfg(feature = "process")]
+pub fn add(entry: &Entry) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    add_with_runner(&display.runner(&runner::SystemRunner::default()), entry)
+}
+
+/// Add an entry to the list, running `xhost +ENTRY` through the given runner
+///
+/// ```rust
+/// use pino_xhost::{runner::{Cmd, MockRunner, Output}, Entry};
+///
+/// let cmd = Cmd::new("xhost").arg("+si:localuser:root");
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// pino_xhost::add_with_runner(&runner, &Entry::LocalUser(String::from("root"))).unwrap();
+/// ```
+pub fn add_with_runner(runner: &dyn CommandRunner, entry: &Entry) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xhost").arg(format!("+{}", entry)))?;
+    Ok(())
+}
+
+/// Stop allowing the clients of `entry` to connect without a cookie
+#[cfg(feature = "process")]
+pub fn remove(entry: &Entry) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    remove_with_runner(&display.runner(&runner::SystemRunner::default()), entry)
+}
+
+/// Remove an entry from the list, running `xhost -ENTRY` through the given runner
+pub fn remove_with_runner(runner: &dyn CommandRunner, entry: &Entry) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xhost").arg(format!("-{}", entry)))?;
+    Ok(())
+}
+
+/// Enable access control, or disable it to let any client connect, which should only be done
+/// on trusted networks
+#[cfg(feature = "process")]
+pub fn set_enabled(enabled: bool) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_enabled_with_runner(&display.runner(&runner::SystemRunner::default()), enabled)
+}
+
+/// Enable or disable access control, running `xhost -` or `xhost +` through the given runner
+pub fn set_enabled_with_runner(runner: &dyn CommandRunner, enabled: bool) -> Result<(), Error> {
+    let arg = if enabled { "-" } else { "+" };
+    run_checked(runner, &Cmd::new("xhost").arg(arg))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn query_and_change() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xhost"),
+                Output::from_stdout(concat!(
+                    "access control enabled, only authorized clients can connect\n",
+                    "LOCAL:\n",
+                    "SI:localgroup:video\n",
+                    "SI:hostname:pino\n",
+                    "INET6:::1\n",
+                    "DNET:pino\n",
+                )),
+            )
+            .respond(
+                Cmd::new("xhost").arg("-si:hostname:pino"),
+                Output::from_stdout("si:hostname:pino being removed from access control list\n"),
+            )
+            .respond(
+                Cmd::new("xhost").arg("+"),
+                Output::from_failure(
+                    1,
+                    "xhost:  must be on local machine to enable or disable access control.\n",
+                ),
+            );
+
+        let acl = query_with_runner(&runner).unwrap();
+        assert_eq!(
+            acl.entries,
+            [
+                Entry::Local,
+                Entry::LocalGroup(String::from("video")),
+                Entry::ServerInterpreted {
+                    kind: String::from("hostname"),
+                    value: String::from("pino")
+                },
+                Entry::Inet6(String::from("::1")),
+                Entry::Other(String::from("DNET:pino")),
+            ]
+        );
+        assert!(!acl.allows(&Entry::LocalUser(String::from("root"))));
+        remove_with_runner(&runner, &acl.entries[2]).unwrap();
+        assert!(set_enabled_with_runner(&runner, false).is_err());
+
+        let acl =
+            AccessControl::parse("access control disabled, clients can connect from any host\n");
+        assert!(acl.unwrap().allows(&Entry::Local));
+        assert_eq!("pino".parse::<Entry>().unwrap().to_string(), "inet:pino");
+    }
+
+    #[test]
+    fn report_failures() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xhost"),
+                Output::from_failure(1, "xhost:  unable to open display \":5\"\n"),
+            )
+            .respond(
+                Cmd::new("xhost").arg("+si:localuser:root"),
+                Output::from_stdout("non-network local connections being added\n"),
+            );
+        let err = query_with_runner(&runner).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, ref stderr, .. }) if stderr.contains("unable to open display")
+        ));
+        // output is not read when changing the list
+        add_with_runner(&runner, &Entry::LocalUser(String::from("root"))).unwrap();
+
+        assert!(matches!(
+            AccessControl::parse("xhost: bad hostname\n"),
+            Err(Error::Malformed(line)) if line == "xhost: bad hostname"
+        ));
+        assert!(AccessControl::parse("").is_err());
+        let raw = "access control enabled, only authorized clients can connect\nSI:localuser\n";
+        assert!(matches!(
+            AccessControl::parse(raw),
+            Err(Error::Malformed(line)) if line == "SI:localuser"
+        ));
+
+        let err = remove_with_runner(&MockRunner::new(), &Entry::Local).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Spawn { program, .. }) if program == "xhost"));
+        let err = Error::NoXServer(Session::Wayland);
+        assert_eq!(err.to_string(), "no X server in a Wayland session");
+    }
+}
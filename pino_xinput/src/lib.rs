@@ -0,0 +1,406 @@
+//! List and configure the input devices of the X server through `xinput`
+//!
+//! ```rust,no_run
+//! use pino_xinput::Device;
+//!
+//! # #[cfg(feature = "process")]
+//! fn main() {
+//!     for device in Device::list().unwrap() {
+//!         println!("{}: {} ({:?})", device.id, device.name, device.role);
+//!     }
+//! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
+//! ```
+//!
+//! Devices are enabled, disabled, floated and reattached by id, such as to ignore the touchpad
+//! while an external mouse is plugged in:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xinput::Device;
+//!
+//! let devices = Device::list().unwrap();
+//! if let Some(touchpad) = devices.iter().find(|d| d.name.contains("TouchPad")) {
+//!     pino_xinput::disable(touchpad.id).unwrap();
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+pub mod props;
+pub mod watch;
+
+/// Errors of running xinput
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xinput could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xinput could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xinput output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Whether a device moves a pointer or types on a keyboard
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DeviceType {
+    Pointer,
+    Keyboard,
+}
+
+/// Place of a device in the hierarchy of the X server
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Role {
+    /// Virtual device the clients see, such as the `Virtual core pointer`
+    Master,
+    /// Physical device, its events are sent through the master it is attached to
+    Slave,
+    /// Physical device that is not attached to any master, so it does not move the cursor or type
+    Floating,
+}
+
+/// Input device of the X server
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Device {
+    pub id: u32,
+    /// Name of the device, which is not unique, such as a mouse and its keyboard interface
+    pub name: String,
+    pub role: Role,
+    /// Type of the device, `None` if it is floating
+    pub device_type: Option<DeviceType>,
+    /// Master a slave is attached to, or the other half of a master pointer and keyboard
+    pub attachment: Option<u32>,
+    pub enabled: bool,
+}
+
+impl Device {
+    /// List the input devices of the X server
+    #[cfg(feature = "process")]
+    pub fn list() -> Result<Vec<Self>, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::list_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// List the input devices, running `xinput list --long` through the given runner
+    pub fn list_with_runner(runner: &dyn CommandRunner) -> Result<Vec<Self>, Error> {
+        let output = run_checked(runner, &Cmd::new("xinput").args(["list", "--long"]))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `xinput list`
+    ///
+    /// With `--long` the disabled devices are marked as such, otherwise every device is assumed
+    /// to be enabled. The classes listed by `--long` are skipped.
+    ///
+    /// ```rust
+    /// use pino_xinput::{Device, DeviceType, Role};
+    ///
+    /// let devices = Device::parse(concat!(
+    ///     "⎡ Virtual core pointer                    \tid=2\t[master pointer  (3)]\n",
+    ///     "⎜   ↳ Virtual core XTEST pointer              \tid=4\t[slave  pointer  (2)]\n",
+    ///     "⎜   ↳ SynPS/2 Synaptics TouchPad              \tid=12\t[slave  pointer  (2)]\n",
+    ///     "⎣ Virtual core keyboard                   \tid=3\t[master keyboard (2)]\n",
+    ///     "    ↳ AT Translated Set 2 keyboard            \tid=11\t[slave  keyboard (3)]\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// let touchpad = &devices[2];
+    /// assert_eq!(touchpad.id, 12);
+    /// assert_eq!(touchpad.name, "SynPS/2 Synaptics TouchPad");
+    /// assert_eq!(touchpad.role, Role::Slave);
+    /// assert_eq!(touchpad.device_type, Some(DeviceType::Pointer));
+    /// assert_eq!(touchpad.attachment, Some(2));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Vec<Self>, Error> {
+        let mut devices = Vec::<Device>::new();
+        for line in raw.lines() {
+            if line.trim() == "This device is disabled" {
+                if let Some(device) = devices.last_mut() {
+                    device.enabled = false;
+                }
+            } else if line.contains("\tid=") {
+                devices.push(Device::parse_line(line)?);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Parse the line of a device, `⎜   ↳ NAME\tid=ID\t[slave  pointer  (MASTER)]`
+    fn parse_line(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let mut fields = line.split('\t');
+        let name = fields
+            .next()
+            .ok_or_else(malformed)?
+            .trim_start_matches(['⎡', '⎜', '⎣', '↳', '∼', ' '])
+            .trim_end();
+        let id = fields.next().and_then(|id| id.trim().strip_prefix("id="));
+        let id = id.and_then(|id| id.parse().ok()).ok_or_else(malformed)?;
+        let place = fields.next().ok_or_else(malformed)?;
+        let place = place.trim().trim_start_matches('[').trim_end_matches(']');
+
+        let mut words = place.split_whitespace();
+        let role = match words.next() {
+            Some("master") => Role::Master,
+            Some("slave") => Role::Slave,
+            Some("floating") => Role::Floating,
+            _ => return Err(malformed()),
+        };
+        let device_type = match words.next() {
+            Some("pointer") => Some(DeviceType::Pointer),
+            Some("keyboard") => Some(DeviceType::Keyboard),
+            _ => None,
+        };
+        let attachment = words
+            .next()
+            .and_then(|id| id.trim_matches(['(', ')']).parse().ok());
+        Ok(Device {
+            id,
+            name: name.to_owned(),
+            role,
+            device_type,
+            attachment,
+            enabled: true,
+        })
+    }
+
+    /// Check if the device is a master device
+    pub fn is_master(&self) -> bool {
+        self.role == Role::Master
+    }
+
+    /// Check if the device is a physical device, attached to a master or not
+    pub fn is_slave(&self) -> bool {
+        self.role != Role::Master
+    }
+}
+
+/// Enable a device, so its events are delivered again
+#[cfg(feature = "process")]
+pub fn enable(id: u32) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    enable_with_runner(&display.runner(&runner::SystemRunner::default()), id)
+}
+
+/// Enable a device, running `xinput` through the given runner
+pub fn enable_with_runner(runner: &dyn CommandRunner, id: u32) -> Result<(), Error> {
+    run_checked(
+        runner,
+        &Cmd::new("xinput").args(["enable", &id.to_string()]),
+    )?;
+    Ok(())
+}
+
+/// Disable a device, dropping its events until it is enabled again
+#[cfg(feature = "process")]
+pub fn disable(id: u32) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    disable_with_runner(&display.runner(&runner::SystemRunner::default()), id)
+}
+
+/// Disable a device, running `xinput` through the given runner
+pub fn disable_with_runner(runner: &dyn CommandRunner, id: u32) -> Result<(), Error> {
+    run_checked(
+        runner,
+        &Cmd::new("xinput").args(["disable", &id.to_string()]),
+    )?;
+    Ok(())
+}
+
+/// Detach a slave device from its master
+///
+/// A floating device still sends events to the clients that select them on the device itself,
+/// but no longer moves the cursor or types.
+#[cfg(feature = "process")]
+pub fn float(id: u32) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    float_with_runner(&display.runner(&runner::SystemRunner::default()), id)
+}
+
+/// Detach a slave device from its master, running `xinput` through the given runner
+pub fn float_with_runner(runner: &dyn CommandRunner, id: u32) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xinput").args(["float", &id.to_string()]))?;
+    Ok(())
+}
+
+/// Attach a slave device to a master, of the same type as the device
+#[cfg(feature = "process")]
+pub fn reattach(id: u32, master: u32) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    reattach_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        id,
+        master,
+    )
+}
+
+/// Attach a slave device to a master, running `xinput` through the given runner
+///
+/// ```rust
+/// use pino_xinput::runner::{Cmd, MockRunner, Output};
+///
+/// let cmd = Cmd::new("xinput").args(["reattach", "12", "2"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// pino_xinput::reattach_with_runner(&runner, 12, 2).unwrap();
+/// ```
+pub fn reattach_with_runner(runner: &dyn CommandRunner, id: u32, master: u32) -> Result<(), Error> {
+    let cmd = Cmd::new("xinput").args(["reattach", &id.to_string(), &master.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Logical button of each physical button of a device, button `n` being at index `n - 1`
+#[cfg(feature = "process")]
+pub fn get_button_map(id: u32) -> Result<Vec<u8>, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    get_button_map_with_runner(&display.runner(&runner::SystemRunner::default()), id)
+}
+
+/// Read the button mapping of a device, running `xinput` through the given runner
+pub fn get_button_map_with_runner(runner: &dyn CommandRunner, id: u32) -> Result<Vec<u8>, Error> {
+    let output = run_checked(
+        runner,
+        &Cmd::new("xinput").args(["get-button-map", &id.to_string()]),
+    )?;
+    let map = String::from_utf8_lossy(&output.stdout);
+    map.split_whitespace()
+        .map(|button| {
+            button
+                .parse()
+                .map_err(|_| Error::Malformed(map.trim().to_owned()))
+        })
+        .collect()
+}
+
+/// Remap the buttons of a single device, `map[n - 1]` being the logical button of button `n`
+///
+/// Unlike the pointer mapping of xmodmap this only affects the given device, so an external
+/// mouse can be made left handed while the touchpad is left alone. `0` disables a button, and
+/// the buttons past the end of `map` are left as they are.
+#[cfg(feature = "process")]
+pub fn set_button_map(id: u32, map: &[u8]) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_button_map_with_runner(&display.runner(&runner::SystemRunner::default()), id, map)
+}
+
+/// Remap the buttons of a device, running `xinput` through the given runner
+///
+/// ```rust
+/// use pino_xinput::runner::{Cmd, MockRunner, Output};
+///
+/// // left handed
+/// let cmd = Cmd::new("xinput").args(["set-button-map", "9", "3", "2", "1"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// pino_xinput::set_button_map_with_runner(&runner, 9, &[3, 2, 1]).unwrap();
+/// ```
+pub fn set_button_map_with_runner(
+    runner: &dyn CommandRunner,
+    id: u32,
+    map: &[u8],
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xinput")
+        .args(["set-button-map", &id.to_string()])
+        .args(map.iter().map(u8::to_string));
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn parse_long() {
+        let devices = Device::parse(concat!(
+            "⎡ Virtual core pointer                    \tid=2\t[master pointer  (3)]\n",
+            "\tReporting 7 classes:\n",
+            "\t\tClass originated from: 2. Type: XIButtonClass\n",
+            "⎜   ↳ Logitech USB Receiver                   \tid=9\t[slave  pointer  (2)]\n",
+            "\tThis device is disabled\n",
+            "\tReporting 7 classes:\n",
+            "⎣ Virtual core keyboard                   \tid=3\t[master keyboard (2)]\n",
+            "∼ ELAN Touchscreen                            \tid=15\t[floating slave]\n",
+        ))
+        .unwrap();
+        assert_eq!(devices.len(), 4);
+        assert!(!devices[1].enabled);
+        assert!(devices[0].enabled && devices[0].is_master());
+        assert_eq!(devices[2].device_type, Some(DeviceType::Keyboard));
+
+        let floating = &devices[3];
+        assert_eq!(floating.name, "ELAN Touchscreen");
+        assert_eq!(floating.role, Role::Floating);
+        assert_eq!(floating.device_type, None);
+        assert_eq!(floating.attachment, None);
+        assert!(floating.is_slave());
+
+        assert!(Device::parse("⎡ Virtual core pointer\tid=two\t[master pointer  (3)]").is_err());
+    }
+
+    #[test]
+    fn button_map() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xinput").args(["get-button-map", "9"]),
+                Output::from_stdout("3 2 1 4 5 6 7 \n"),
+            )
+            .respond(
+                Cmd::new("xinput").args(["get-button-map", "2"]),
+                Output::from_stdout("device has no buttons\n"),
+            );
+        assert_eq!(
+            get_button_map_with_runner(&runner, 9).unwrap(),
+            [3, 2, 1, 4, 5, 6, 7]
+        );
+        assert!(matches!(
+            get_button_map_with_runner(&runner, 2),
+            Err(Error::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+//! Read and change the properties of an input device, such as the libinput settings of a touchpad
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xinput::props::{self, Setting};
+//!
+//! props::set(12, Setting::Tapping(true)).unwrap();
+//! props::set(12, Setting::AccelSpeed(0.3)).unwrap();
+//!
+//! for setting in props::list_props(12).unwrap().settings() {
+//!     println!("{:?}", setting);
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::Error;
+
+/// Single value of a property
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// Name of an atom
+    Atom(String),
+}
+
+impl Value {
+    /// Value as a number, integers included
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// Format as taken by `xinput set-prop`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(float) => write!(f, "{}", float),
+            Value::Str(s) | Value::Atom(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Property of an input device
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Property {
+    /// Name of the property, such as `libinput Tapping Enabled`
+    pub name: String,
+    /// Atom of the name
+    pub atom: u32,
+    pub values: Vec<Value>,
+}
+
+impl Property {
+    /// Value of a property holding a single flag
+    pub fn bool(&self) -> Option<bool> {
+        match self.values[..] {
+            [Value::Int(i)] => Some(i != 0),
+            _ => None,
+        }
+    }
+
+    /// Value of a property holding a single number
+    pub fn float(&self) -> Option<f64> {
+        match &self.values[..] {
+            [value] => value.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// Parse the line of a property, `NAME (ATOM):\tVALUE, VALUE`
+    fn parse(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let (head, values) = line.split_once("):").ok_or_else(malformed)?;
+        let (name, atom) = head.trim().rsplit_once(" (").ok_or_else(malformed)?;
+        Ok(Property {
+            name: name.to_owned(),
+            atom: atom.parse().map_err(|_| malformed())?,
+            values: parse_values(values).ok_or_else(malformed)?,
+        })
+    }
+}
+
+/// Parse the values of a property, quoted strings, atoms followed by their id, or numbers
+fn parse_values(raw: &str) -> Option<Vec<Value>> {
+    let mut values = vec![];
+    let mut rest = raw.trim();
+    if rest == "<no items>" {
+        return Some(values);
+    }
+    while !rest.is_empty() {
+        let (value, tail) = if let Some(quoted) = rest.strip_prefix('"') {
+            let (s, tail) = quoted.split_once('"')?;
+            match tail.trim_start().strip_prefix('(') {
+                Some(id) => (Value::Atom(s.to_owned()), id.split_once(')')?.1),
+                None => (Value::Str(s.to_owned()), tail),
+            }
+        } else {
+            let (item, tail) = rest.split_once(',').unwrap_or((rest, ""));
+            let item = item.trim();
+            let value = match (item.parse(), item.parse()) {
+                (Ok(i), _) => Value::Int(i),
+                (_, Ok(f)) => Value::Float(f),
+                _ => Value::Atom(item.to_owned()),
+            };
+            (value, tail)
+        };
+        values.push(value);
+        rest = tail.trim_start().trim_start_matches(',').trim_start();
+    }
+    Some(values)
+}
+
+/// Common libinput setting, with its typed value
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Setting {
+    /// Scroll the content rather than the view, like on a touchscreen
+    NaturalScrolling(bool),
+    /// Tap the touchpad to click
+    Tapping(bool),
+    /// Pointer acceleration from `-1.0` (slowest) to `1.0` (fastest)
+    AccelSpeed(f64),
+    /// Press the left and right buttons together for a middle click
+    MiddleEmulation(bool),
+    /// Swap the left and right buttons
+    LeftHanded(bool),
+    /// Ignore the touchpad while typing
+    DisableWhileTyping(bool),
+}
+
+impl Setting {
+    /// Name of the property holding the setting
+    pub fn property(&self) -> &'static str {
+        match self {
+            Setting::NaturalScrolling(_) => "libinput Natural Scrolling Enabled",
+            Setting::Tapping(_) => "libinput Tapping Enabled",
+            Setting::AccelSpeed(_) => "libinput Accel Speed",
+            Setting::MiddleEmulation(_) => "libinput Middle Emulation Enabled",
+            Setting::LeftHanded(_) => "libinput Left Handed Enabled",
+            Setting::DisableWhileTyping(_) => "libinput Disable While Typing Enabled",
+        }
+    }
+
+    /// Values of the property holding the setting
+    pub fn values(&self) -> Vec<Value> {
+        match *self {
+            Setting::AccelSpeed(speed) => vec![Value::Float(speed)],
+            Setting::NaturalScrolling(on)
+            | Setting::Tapping(on)
+            | Setting::MiddleEmulation(on)
+            | Setting::LeftHanded(on)
+            | Setting::DisableWhileTyping(on) => vec![Value::Int(on.into())],
+        }
+    }
+
+    /// Read the setting from its property, `kind` only selecting the setting
+    fn read(kind: Setting, prop: &Property) -> Option<Self> {
+        Some(match kind {
+            Setting::NaturalScrolling(_) => Setting::NaturalScrolling(prop.bool()?),
+            Setting::Tapping(_) => Setting::Tapping(prop.bool()?),
+            Setting::AccelSpeed(_) => Setting::AccelSpeed(prop.float()?),
+            Setting::MiddleEmulation(_) => Setting::MiddleEmulation(prop.bool()?),
+            Setting::LeftHanded(_) => Setting::LeftHanded(prop.bool()?),
+            Setting::DisableWhileTyping(_) => Setting::DisableWhileTyping(prop.bool()?),
+        })
+    }
+}
+
+/// Every setting, with placeholder values
+const SETTINGS: [Setting; 6] = [
+    Setting::NaturalScrolling(false),
+    Setting::Tapping(false),
+    Setting::AccelSpeed(0.0),
+    Setting::MiddleEmulation(false),
+    Setting::LeftHanded(false),
+    Setting::DisableWhileTyping(false),
+];
+
+/// Properties of an input device, as listed by `xinput list-props`
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Properties {
+    pub props: Vec<Property>,
+}
+
+impl Properties {
+    /// Parse the output of `xinput list-props`
+    ///
+    /// ```rust
+    /// use pino_xinput::props::{Properties, Setting, Value};
+    ///
+    /// let props = Properties::parse(concat!(
+    ///     "Device 'SynPS/2 Synaptics TouchPad':\n",
+    ///     "\tDevice Enabled (168):\t1\n",
+    ///     "\tlibinput Tapping Enabled (325):\t1\n",
+    ///     "\tlibinput Accel Speed (310):\t-0.250000\n",
+    ///     "\tDevice Node (283):\t\"/dev/input/event6\"\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(props.get("Device Node").unwrap().values, [Value::Str("/dev/input/event6".into())]);
+    /// assert_eq!(props.settings(), [Setting::Tapping(true), Setting::AccelSpeed(-0.25)]);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let props = raw
+            .lines()
+            .filter(|line| line.starts_with(char::is_whitespace) && !line.trim().is_empty())
+            .map(Property::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Properties { props })
+    }
+
+    /// Property with the given name
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.props.iter().find(|prop| prop.name == name)
+    }
+
+    /// Iterate over the properties
+    pub fn iter(&self) -> impl Iterator<Item = &Property> {
+        self.props.iter()
+    }
+
+    /// Common libinput settings the device supports, with their current values
+    pub fn settings(&self) -> Vec<Setting> {
+        SETTINGS
+            .into_iter()
+            .filter_map(|kind| Setting::read(kind, self.get(kind.property())?))
+            .collect()
+    }
+}
+
+/// List the properties of the device with the given id
+#[cfg(feature = "process")]
+pub fn list_props(id: u32) -> Result<Properties, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    list_props_with_runner(&display.runner(&crate::runner::SystemRunner::default()), id)
+}
+
+/// List the properties of a device, running `xinput` through the given runner
+pub fn list_props_with_runner(runner: &dyn CommandRunner, id: u32) -> Result<Properties, Error> {
+    let output = run_checked(
+        runner,
+        &Cmd::new("xinput").args(["list-props", &id.to_string()]),
+    )?;
+    Properties::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Read a single property of a device, `None` if the device does not have it
+#[cfg(feature = "process")]
+pub fn get_prop(id: u32, name: &str) -> Result<Option<Property>, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    get_prop_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        name,
+    )
+}
+
+/// Read a single property of a device, running `xinput` through the given runner
+pub fn get_prop_with_runner(
+    runner: &dyn CommandRunner,
+    id: u32,
+    name: &str,
+) -> Result<Option<Property>, Error> {
+    let props = list_props_with_runner(runner, id)?;
+    Ok(props.props.into_iter().find(|prop| prop.name == name))
+}
+
+/// Change a property of a device, the values are converted to the type it already has
+#[cfg(feature = "process")]
+pub fn set_prop(id: u32, name: &str, values: &[Value]) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_prop_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        name,
+        values,
+    )
+}
+
+/// Change a property of a device, running `xinput` through the given runner
+///
+/// ```rust
+/// use pino_xinput::{props::{self, Value}, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xinput").args(["set-prop", "12", "libinput Accel Speed", "0.5"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// props::set_prop_with_runner(&runner, 12, "libinput Accel Speed", &[Value::Float(0.5)]).unwrap();
+/// ```
+pub fn set_prop_with_runner(
+    runner: &dyn CommandRunner,
+    id: u32,
+    name: &str,
+    values: &[Value],
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xinput")
+        .args(["set-prop", &id.to_string(), name])
+        .args(values.iter().map(Value::to_string));
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Change a common libinput setting of a device
+#[cfg(feature = "process")]
+pub fn set(id: u32, setting: Setting) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        id,
+        setting,
+    )
+}
+
+/// Change a common libinput setting of a device, running `xinput` through the given runner
+pub fn set_with_runner(runner: &dyn CommandRunner, id: u32, setting: Setting) -> Result<(), Error> {
+    set_prop_with_runner(runner, id, setting.property(), &setting.values())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn parse_props() {
+        let runner = MockRunner::new().respond(
+            Cmd::new("xinput").args(["list-props", "12"]),
+            Output::from_stdout(concat!(
+                "Device 'SynPS/2 Synaptics TouchPad':\n",
+                "\tCoordinate Transformation Matrix (170):\t1.000000, 0.000000, 0.000000\n",
+                "\tlibinput Natural Scrolling Enabled (301):\t0\n",
+                "\tlibinput Scroll Methods Available (304):\t1, 1, 0\n",
+                "\tlibinput Click Method Enabled (317):\t\"clickfinger\" (318)\n",
+                "\tlibinput Send Events Modes Available (285):\t<no items>\n",
+            )),
+        );
+        let props = list_props_with_runner(&runner, 12).unwrap();
+        assert_eq!(props.props.len(), 5);
+        assert_eq!(props.props[0].values[1], Value::Float(0.0));
+        assert_eq!(props.props[2].values, [1, 1, 0].map(Value::Int));
+        assert_eq!(props.props[2].bool(), None);
+        assert_eq!(props.props[3].values, [Value::Atom("clickfinger".into())]);
+        assert_eq!(props.props[3].atom, 317);
+        assert!(props.props[4].values.is_empty());
+        assert_eq!(props.settings(), [Setting::NaturalScrolling(false)]);
+
+        let prop = get_prop_with_runner(&runner, 12, "Device Enabled").unwrap();
+        assert_eq!(prop, None);
+
+        let set = Cmd::new("xinput").args(["set-prop", "12", "libinput Tapping Enabled", "1"]);
+        let runner = MockRunner::new().respond(set, Output::default());
+        set_with_runner(&runner, 12, Setting::Tapping(true)).unwrap();
+    }
+}
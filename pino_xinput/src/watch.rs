@@ -0,0 +1,249 @@
+//! Notify about input devices being plugged in, unplugged or reconfigured
+//!
+//! A [Watcher] polls the devices from a background thread and sends an [Event] for every change,
+//! so per-device settings such as the acceleration of a mouse can be applied again whenever it
+//! is plugged in.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xinput::{
+//!     props::{self, Setting},
+//!     watch::{Event, Watcher},
+//! };
+//!
+//! let handle = Watcher::new().spawn();
+//! for event in handle.events() {
+//!     if let Event::DeviceAdded(device) = event {
+//!         if device.name.contains("Logitech") {
+//!             props::set(device.id, Setting::AccelSpeed(-0.5)).unwrap();
+//!         }
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{Device, DisplayHandle, Error};
+
+/// Time between two polls, unless configured otherwise
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Change to the input devices observed by a [Watcher]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event {
+    /// Device was plugged in
+    DeviceAdded(Device),
+    /// Device was unplugged
+    DeviceRemoved(Device),
+    /// Device was enabled, disabled, floated or attached to another master
+    DeviceChanged(Device),
+    /// Devices could not be read, reported once until they can be read again
+    Error(Error),
+}
+
+/// Changes between two readings of the devices
+///
+/// Devices are told apart by id, which the X server does not reuse until the device is gone.
+///
+/// ```rust
+/// use pino_xinput::{watch::{changes, Event}, Device};
+///
+/// let old = Device::parse("⎜   ↳ Logitech USB Receiver\tid=9\t[slave  pointer  (2)]").unwrap();
+/// let new = Device::parse("∼ Logitech USB Receiver\tid=9\t[floating slave]").unwrap();
+///
+/// assert!(matches!(&changes(&old, &new)[..], [Event::DeviceChanged(device)] if device.id == 9));
+/// assert!(matches!(&changes(&old, &[])[..], [Event::DeviceRemoved(device)] if device.id == 9));
+/// ```
+pub fn changes(old: &[Device], new: &[Device]) -> Vec<Event> {
+    let mut events = vec![];
+    for device in new {
+        match old.iter().find(|prev| prev.id == device.id) {
+            None => events.push(Event::DeviceAdded(device.clone())),
+            Some(prev) if prev != device => events.push(Event::DeviceChanged(device.clone())),
+            Some(_) => {},
+        }
+    }
+    for prev in old {
+        if !new.iter().any(|device| device.id == prev.id) {
+            events.push(Event::DeviceRemoved(prev.clone()));
+        }
+    }
+    events
+}
+
+/// Polls the input devices for changes, see the [module](self) documentation
+pub struct Watcher {
+    runner: Box<dyn CommandRunner>,
+    display: DisplayHandle,
+    interval: Duration,
+    last: Option<Vec<Device>>,
+    failing: bool,
+}
+
+impl Watcher {
+    /// Construct a watcher running xinput on the system
+    #[cfg(feature = "process")]
+    pub fn new() -> Self {
+        Watcher::with_runner(crate::runner::SystemRunner::default())
+    }
+
+    /// Construct a watcher running xinput through the given runner
+    pub fn with_runner(runner: impl CommandRunner + 'static) -> Self {
+        Watcher {
+            runner: Box::new(runner),
+            display: DisplayHandle::default(),
+            interval: DEFAULT_INTERVAL,
+            last: None,
+            failing: false,
+        }
+    }
+
+    /// Watch the given display instead of the one of the environment
+    pub fn display(mut self, display: DisplayHandle) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Time between two polls
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Read the devices once, returning what changed since the previous poll
+    ///
+    /// The first poll only records the current state, so it reports nothing but errors.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let runner = self.display.runner(self.runner.as_ref());
+        let devices = run_checked(&runner, &Cmd::new("xinput").args(["list", "--long"]))
+            .map_err(Error::from)
+            .and_then(|output| Device::parse(&String::from_utf8_lossy(&output.stdout)));
+        let devices = match devices {
+            Ok(devices) => devices,
+            Err(e) => {
+                if self.failing {
+                    return vec![];
+                }
+                self.failing = true;
+                return vec![Event::Error(e)];
+            },
+        };
+        self.failing = false;
+        match self.last.replace(devices) {
+            Some(old) => changes(&old, self.last.as_ref().unwrap()),
+            None => vec![],
+        }
+    }
+
+    /// Poll from a background thread, sending every event to the channel of the returned handle
+    ///
+    /// The thread runs until the handle is stopped or dropped.
+    pub fn spawn(mut self) -> WatchHandle {
+        let (tx, rx) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || loop {
+            for event in self.poll() {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+            match stopped.recv_timeout(self.interval) {
+                Err(RecvTimeoutError::Timeout) => {},
+                _ => return,
+            }
+        });
+        WatchHandle {
+            events: rx,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for Watcher {
+    fn default() -> Self {
+        Watcher::new()
+    }
+}
+
+/// Running [Watcher], stopped when dropped
+pub struct WatchHandle {
+    events: Receiver<Event>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// Events sent by the watcher, the channel disconnects once it stops
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+
+    /// Stop the background thread and wait for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // dropping the sender wakes the thread up from its wait
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn poll_devices() {
+        let list = Cmd::new("xinput").args(["list", "--long"]);
+        let runner = MockRunner::new().respond(
+            list.clone(),
+            Output::from_stdout(concat!(
+                "⎡ Virtual core pointer\tid=2\t[master pointer  (3)]\n",
+                "⎜   ↳ SynPS/2 Synaptics TouchPad\tid=12\t[slave  pointer  (2)]\n",
+            )),
+        );
+        let mut watcher = Watcher::with_runner(runner);
+        assert!(watcher.poll().is_empty());
+
+        let runner = MockRunner::new().respond(
+            list,
+            Output::from_stdout(concat!(
+                "⎡ Virtual core pointer\tid=2\t[master pointer  (3)]\n",
+                "⎜   ↳ SynPS/2 Synaptics TouchPad\tid=12\t[slave  pointer  (2)]\n",
+                "\tThis device is disabled\n",
+                "⎜   ↳ Logitech USB Receiver\tid=9\t[slave  pointer  (2)]\n",
+            )),
+        );
+        watcher.runner = Box::new(runner);
+        let events = watcher.poll();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Event::DeviceChanged(d) if d.id == 12 && !d.enabled));
+        assert!(matches!(&events[1], Event::DeviceAdded(d) if d.id == 9));
+
+        watcher.runner = Box::new(MockRunner::new());
+        assert!(matches!(&watcher.poll()[..], [Event::Error(_)]));
+        assert!(watcher.poll().is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! Disconnect the X client owning a window through `xkill`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xkill::Target;
+//!
+//! // let the user click the frozen window
+//! let id = pino_xkill::kill(&Target::Select).unwrap();
+//! println!("killed the client of {}", id);
+//! # }
+//! ```
+//!
+//! The X server closes the connection of the client, which makes most of them exit, but the process
+//! is not signalled and may keep running. [kill_client] talks to the X server directly and needs
+//! the `native` feature.
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    display::DisplayHandle,
+    platform, runner,
+    session::Session,
+    window::{Target, WindowId},
+};
+
+/// Errors of running xkill
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xkill could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xkill could not be parsed, holding it
+    Malformed(String),
+    /// Request to the X server failed, with the `native` feature
+    Connection(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(output) => write!(f, "could not parse xkill output: {}", output),
+            Error::Connection(e) => write!(f, "request to the X server failed: {}", e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Parse the resource whose client xkill reports killing, such as in
+/// `xkill:  killing creator of resource 0x2a00006`
+///
+/// ```rust
+/// use pino_xkill::WindowId;
+///
+/// let raw = concat!(
+///     "Select the window whose client you wish to kill with button 1....\n",
+///     "xkill:  killing creator of resource 0x2a00006\n",
+/// );
+/// assert_eq!(pino_xkill::parse(raw).unwrap(), WindowId(0x2a00006));
+/// ```
+pub fn parse(raw: &str) -> Result<WindowId, Error> {
+    let malformed = || Error::Malformed(raw.trim().to_owned());
+    let (_, id) = raw
+        .split_once("killing creator of resource ")
+        .ok_or_else(malformed)?;
+    let id = id.split_whitespace().next().unwrap_or_default();
+    id.parse().map_err(|_| malformed())
+}
+
+/// Disconnect the client owning the target window, returning the id of the window
+///
+/// Only a window id and [Target::Select], which waits for the user to click a window, are
+/// supported.
+#[cfg(feature = "process")]
+pub fn kill(target: &Target) -> Result<WindowId, Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    kill_with_runner(&display.runner(&runner::SystemRunner::default()), target)
+}
+
+/// Disconnect the client owning the target window, running `xkill` through the given runner
+///
+/// ```rust
+/// use pino_xkill::{runner::{Cmd, MockRunner, Output}, Target, WindowId};
+///
+/// let runner = MockRunner::new().respond(
+///     Cmd::new("xkill").args(["-id", "0x2a00006"]),
+///     Output::from_stdout("xkill:  killing creator of resource 0x2a00006\n"),
+/// );
+/// let id = pino_xkill::kill_with_runner(&runner, &Target::Id(WindowId(0x2a00006))).unwrap();
+/// assert_eq!(id, WindowId(0x2a00006));
+/// ```
+pub fn kill_with_runner(runner: &dyn CommandRunner, target: &Target) -> Result<WindowId, Error> {
+    let cmd = match target {
+        Target::Id(id) => Cmd::new("xkill").args(["-id", &id.to_string()]),
+        // xkill waits for button 1, and aborts on any other
+        Target::Select => Cmd::new("xkill"),
+        _ => {
+            return Err(Error::Run(RunError::Unsupported(String::from(
+                "xkill can only kill the client of a window by its id or a picked one",
+            ))))
+        },
+    };
+    let cmd = match target.timeout() {
+        Some(timeout) => cmd.timeout(timeout),
+        None => cmd,
+    };
+    let output = run_checked(runner, &cmd)?;
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Disconnect the client owning `id` through XKillClient, without running xkill
+#[cfg(feature = "native")]
+pub fn kill_client(id: WindowId) -> Result<(), Error> {
+    use x11rb::{connection::Connection, protocol::xproto::ConnectionExt};
+
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    let err = |e: &dyn std::fmt::Display| Error::Connection(e.to_string());
+    let (conn, _) = pino_xcommon::native::connect_display(&display).map_err(|e| err(&e))?;
+    conn.kill_client(id.0)
+        .map_err(|e| err(&e))?
+        .check()
+        .map_err(|e| err(&e))?;
+    conn.flush().map_err(|e| err(&e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn kill_picked_window() {
+        let runner = MockRunner::new().respond(
+            Cmd::new("xkill"),
+            Output::from_stdout(concat!(
+                "Select the window whose client you wish to kill with button 1....\n",
+                "xkill:  killing creator of resource 0x3c00007\n",
+            )),
+        );
+
+        let id = kill_with_runner(&runner, &Target::Select).unwrap();
+        assert_eq!(id, WindowId(0x3c00007));
+        let err = kill_with_runner(&runner, &Target::Root).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Unsupported(_))));
+        assert!(parse("xkill:  aborting\n").is_err());
+    }
+
+    #[test]
+    fn report_failures() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xkill").args(["-id", "0x1"]),
+                Output::from_failure(1, "X Error of failed request:  BadValue\n"),
+            )
+            .respond(
+                Cmd::new("xkill").args(["-id", "0x2"]),
+                Output::from_stdout("xkill:  killing creator of resource 0xzz\n"),
+            )
+            .time_out(Cmd::new("xkill"));
+
+        let err = kill_with_runner(&runner, &Target::Id(WindowId(0x1))).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, ref stderr, .. }) if stderr.contains("BadValue")
+        ));
+        let err = kill_with_runner(&runner, &Target::Id(WindowId(0x2))).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Malformed(ref output) if output == "xkill:  killing creator of resource 0xzz"
+        ));
+        let err = kill_with_runner(&runner, &Target::Select).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::TimedOut { .. })));
+
+        let err = kill_with_runner(&MockRunner::new(), &Target::Select).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Spawn { program, .. }) if program == "xkill"));
+        let err = Error::NoXServer(Session::Wayland);
+        assert_eq!(err.to_string(), "no X server in a Wayland session");
+    }
+}
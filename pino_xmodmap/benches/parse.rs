@@ -0,0 +1,73 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pino_xmodmap::{KeySym, KeyTable, DEFAULT_COLUMNS};
+
+/// Counts the bytes currently allocated, to report the memory held by a parsed table
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: Counting = Counting;
+
+/// Bytes still allocated after running `f` and keeping its result
+fn retained<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = LIVE.load(Ordering::Relaxed);
+    let value = f();
+    (value, LIVE.load(Ordering::Relaxed) - before)
+}
+
+/// Output of `xmodmap -pke` binding every keycode, with names this library does not know
+fn large_table() -> String {
+    let names = [
+        "XF86AudioPlay",
+        "XF86AudioNext",
+        "XF86Launch1",
+        "dead_acute",
+        "a",
+        "A",
+    ];
+    (8..=255)
+        .map(|keycode| {
+            let syms = (0..8)
+                .map(|i| names[(keycode + i) % names.len()])
+                .collect::<Vec<_>>();
+            format!("keycode {:3} = {}\n", keycode, syms.join(" "))
+        })
+        .collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let raw = large_table();
+    c.bench_function("parse full keytable", |b| {
+        b.iter(|| KeyTable::parse(black_box(&raw), DEFAULT_COLUMNS).unwrap())
+    });
+
+    let play = "XF86AudioPlay".parse::<KeySym>().unwrap();
+    // warm up the interner, so only the memory held by the table itself is counted
+    KeyTable::parse(&raw, DEFAULT_COLUMNS).unwrap();
+    let (table, bytes) = retained(|| KeyTable::parse(&raw, DEFAULT_COLUMNS).unwrap());
+    println!("full keytable holds {} KiB", bytes / 1024);
+    c.bench_function("lookup unknown keysym", |b| {
+        b.iter(|| table.get_keycodes(black_box(&play)))
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
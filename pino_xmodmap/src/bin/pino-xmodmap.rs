@@ -0,0 +1,193 @@
+//! Command line interface to `pino_xmodmap`, enabled by the `cli` feature
+
+use std::{process::ExitCode, str::FromStr};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use pino_xmodmap::{
+    expr::{self, Expression},
+    modmap::ModifierMap,
+    Error, KeyCode, KeySym, KeyTable,
+};
+
+/// Inspect and remap the keyboard mapping of the system
+#[derive(Parser)]
+#[command(name = "pino-xmodmap", version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the keycodes and levels producing a character or keysym
+    Lookup { key: String },
+    /// Print the keysyms bound to a keycode
+    Keycode { keycode: KeyCode },
+    /// Report common keymap misconfigurations
+    Lint,
+    /// Bind the keycodes of one keysym to another, such as `caps=escape`
+    Remap { remap: String },
+    /// Print the whole keytable
+    Dump {
+        #[arg(long, value_enum, default_value_t = Format::Xmodmap)]
+        format: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Xmodmap,
+    Json,
+}
+
+/// Short names accepted by `lookup` and `remap` on top of keysym names
+static SHORT_NAMES: &[(&str, KeySym)] = &[
+    ("caps", KeySym::KEY_CAPS_LOCK),
+    ("esc", KeySym::KEY_ESCAPE),
+    ("escape", KeySym::KEY_ESCAPE),
+    ("ctrl", KeySym::KEY_CONTROL_L),
+    ("lctrl", KeySym::KEY_CONTROL_L),
+    ("rctrl", KeySym::KEY_CONTROL_R),
+    ("shift", KeySym::KEY_SHIFT_L),
+    ("lshift", KeySym::KEY_SHIFT_L),
+    ("rshift", KeySym::KEY_SHIFT_R),
+    ("alt", KeySym::KEY_ALT_L),
+    ("lalt", KeySym::KEY_ALT_L),
+    ("ralt", KeySym::KEY_ALT_R),
+    ("super", KeySym::KEY_SUPER_L),
+    ("lsuper", KeySym::KEY_SUPER_L),
+    ("rsuper", KeySym::KEY_SUPER_R),
+    ("tab", KeySym::KEY_TAB),
+    ("enter", KeySym::KEY_RETURN),
+    ("backspace", KeySym::KEY_BACKSPACE),
+    ("space", KeySym::KEY_SPACE),
+];
+
+/// Parse a short name, keysym name or single character
+fn parse_key(key: &str) -> Result<KeySym, Error> {
+    if let Some((_, keysym)) = SHORT_NAMES.iter().find(|(name, _)| *name == key) {
+        return Ok(keysym.clone());
+    }
+    if let Ok(keysym) = KeySym::from_str(key) {
+        return Ok(keysym);
+    }
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => KeySym::try_from(c).map_err(|_| Error::NonExistentKeySym(key.into())),
+        _ => Err(Error::NonExistentKeySym(key.into())),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    match args.command {
+        Command::Lookup { key } => {
+            let keysym = parse_key(&key)?;
+            let table = KeyTable::new()?;
+            let keycodes = table.get_keycodes(&keysym);
+            if keycodes.is_empty() {
+                return Err(Error::NonExistentKeySym(keysym.to_string()));
+            }
+            for keycode in keycodes {
+                let level = table
+                    .get_raw(keycode)?
+                    .iter()
+                    .position(|sym| *sym == keysym)
+                    .unwrap_or_default();
+                println!("{}\tkeycode {}\tlevel {}", keysym, keycode, level + 1);
+            }
+        },
+        Command::Keycode { keycode } => {
+            let syms = KeyTable::new()?
+                .get_raw(keycode)?
+                .iter()
+                .map(KeySym::to_string)
+                .collect::<Vec<_>>();
+            println!("keycode {} = {}", keycode, syms.join(" "));
+        },
+        Command::Lint => {
+            let lints = KeyTable::new()?.lint(&ModifierMap::new()?);
+            for lint in lints.iter() {
+                println!("{}", lint);
+            }
+        },
+        Command::Remap { remap } => {
+            let (from, to) = remap.split_once('=').ok_or_else(|| Error::InvalidFormat {
+                line: None,
+                content: remap.clone(),
+            })?;
+            let (from, to) = (parse_key(from.trim())?, parse_key(to.trim())?);
+            let table = KeyTable::new()?;
+
+            // a modifier key keeps its modifier unless it is removed from the map first
+            let mut exprs = vec![];
+            if from.is_modifier() {
+                let modmap = ModifierMap::new()?;
+                for keycode in table.get_keycodes(&from) {
+                    if let Some(mask) = modmap.get_modifier(keycode) {
+                        for index in mask.indices() {
+                            exprs.push(Expression::Remove(index, vec![from.clone()]));
+                        }
+                    }
+                }
+            }
+            exprs.push(Expression::Keysym(from, vec![to]));
+
+            let applied = table.apply(&exprs)?;
+            for expr in applied.iter() {
+                println!("{}", expr);
+            }
+            for mismatch in expr::verify(&applied)? {
+                eprintln!("keycode {} was not remapped", mismatch.keycode);
+            }
+        },
+        Command::Dump { format } => {
+            let table = KeyTable::new()?;
+            match format {
+                Format::Xmodmap => {
+                    for (keycode, syms) in table.iter_keys() {
+                        let syms = syms.iter().map(KeySym::to_string).collect::<Vec<_>>();
+                        println!("keycode {:>3} = {}", keycode, syms.join(" "));
+                    }
+                },
+                Format::Json => {
+                    let keys = table
+                        .iter_keys()
+                        .map(|(keycode, syms)| {
+                            let syms = syms
+                                .iter()
+                                .map(|sym| json_string(&sym.to_string()))
+                                .collect::<Vec<_>>();
+                            format!("  \"{}\": [{}]", keycode, syms.join(", "))
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{{\n{}\n}}", keys.join(",\n"));
+                },
+            }
+        },
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("pino-xmodmap: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -0,0 +1,233 @@
+//! Export a keytable to the configuration format of low-level remappers
+//!
+//! Generates `defsrc`/`deflayer` blocks for [kmonad](https://github.com/kmonad/kmonad) and
+//! [kanata](https://github.com/jtroo/kanata), where the source layer is the physical (US) layout
+//! and the layer holds what each key produces under the current X mapping.
+//!
+//! ```rust
+//! use pino_xmodmap::{export::Remapper, KeyTable, DEFAULT_COLUMNS};
+//!
+//! // swap escape and grave
+//! let table = KeyTable::parse("keycode   9 = grave\nkeycode  49 = Escape", DEFAULT_COLUMNS).unwrap();
+//! let config = table.export(Remapper::Kanata, "base");
+//! assert!(config.contains("(deflayer base\n  grv"));
+//! ```
+
+use crate::{KeyCode, KeySym, KeyTable, Modifier};
+
+/// Low-level remapper to export to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Remapper {
+    Kmonad,
+    Kanata,
+}
+
+/// Physical key name and the keysym it produces on a US layout, for each keycode
+static KEY_NAMES: &[(KeyCode, &str, KeySym)] = &[
+    (9, "esc", KeySym::KEY_ESCAPE),
+    (67, "f1", KeySym::KEY_F1),
+    (68, "f2", KeySym::KEY_F2),
+    (69, "f3", KeySym::KEY_F3),
+    (70, "f4", KeySym::KEY_F4),
+    (71, "f5", KeySym::KEY_F5),
+    (72, "f6", KeySym::KEY_F6),
+    (73, "f7", KeySym::KEY_F7),
+    (74, "f8", KeySym::KEY_F8),
+    (75, "f9", KeySym::KEY_F9),
+    (76, "f10", KeySym::KEY_F10),
+    (95, "f11", KeySym::KEY_F11),
+    (96, "f12", KeySym::KEY_F12),
+    (49, "grv", KeySym::KEY_GRAVE),
+    (10, "1", KeySym::KEY_1),
+    (11, "2", KeySym::KEY_2),
+    (12, "3", KeySym::KEY_3),
+    (13, "4", KeySym::KEY_4),
+    (14, "5", KeySym::KEY_5),
+    (15, "6", KeySym::KEY_6),
+    (16, "7", KeySym::KEY_7),
+    (17, "8", KeySym::KEY_8),
+    (18, "9", KeySym::KEY_9),
+    (19, "0", KeySym::KEY_0),
+    (20, "min", KeySym::KEY_MINUS),
+    (21, "eql", KeySym::KEY_EQUAL),
+    (22, "bspc", KeySym::KEY_BACKSPACE),
+    (23, "tab", KeySym::KEY_TAB),
+    (24, "q", KeySym::KEY_q),
+    (25, "w", KeySym::KEY_w),
+    (26, "e", KeySym::KEY_e),
+    (27, "r", KeySym::KEY_r),
+    (28, "t", KeySym::KEY_t),
+    (29, "y", KeySym::KEY_y),
+    (30, "u", KeySym::KEY_u),
+    (31, "i", KeySym::KEY_i),
+    (32, "o", KeySym::KEY_o),
+    (33, "p", KeySym::KEY_p),
+    (34, "lbrc", KeySym::KEY_BRACKETLEFT),
+    (35, "rbrc", KeySym::KEY_BRACKETRIGHT),
+    (51, "bksl", KeySym::KEY_BACKSLASH),
+    (66, "caps", KeySym::KEY_CAPS_LOCK),
+    (38, "a", KeySym::KEY_a),
+    (39, "s", KeySym::KEY_s),
+    (40, "d", KeySym::KEY_d),
+    (41, "f", KeySym::KEY_f),
+    (42, "g", KeySym::KEY_g),
+    (43, "h", KeySym::KEY_h),
+    (44, "j", KeySym::KEY_j),
+    (45, "k", KeySym::KEY_k),
+    (46, "l", KeySym::KEY_l),
+    (47, "scln", KeySym::KEY_SEMICOLON),
+    (48, "quot", KeySym::KEY_APOSTROPHE),
+    (36, "ret", KeySym::KEY_RETURN),
+    (50, "lsft", KeySym::KEY_SHIFT_L),
+    (52, "z", KeySym::KEY_z),
+    (53, "x", KeySym::KEY_x),
+    (54, "c", KeySym::KEY_c),
+    (55, "v", KeySym::KEY_v),
+    (56, "b", KeySym::KEY_b),
+    (57, "n", KeySym::KEY_n),
+    (58, "m", KeySym::KEY_m),
+    (59, "comm", KeySym::KEY_COMMA),
+    (60, ".", KeySym::KEY_PERIOD),
+    (61, "/", KeySym::KEY_SLASH),
+    (62, "rsft", KeySym::KEY_SHIFT_R),
+    (37, "lctl", KeySym::KEY_CONTROL_L),
+    (133, "lmet", KeySym::KEY_SUPER_L),
+    (64, "lalt", KeySym::KEY_ALT_L),
+    (65, "spc", KeySym::KEY_SPACE),
+    (108, "ralt", KeySym::KEY_ALT_R),
+    (134, "rmet", KeySym::KEY_SUPER_R),
+    (105, "rctl", KeySym::KEY_CONTROL_R),
+    (118, "ins", KeySym::KEY_INSERT),
+    (110, "home", KeySym::KEY_HOME),
+    (112, "pgup", KeySym::KEY_PRIOR),
+    (119, "del", KeySym::KEY_DELETE),
+    (115, "end", KeySym::KEY_END),
+    (117, "pgdn", KeySym::KEY_NEXT),
+    (111, "up", KeySym::KEY_UP),
+    (113, "left", KeySym::KEY_LEFT),
+    (116, "down", KeySym::KEY_DOWN),
+    (114, "right", KeySym::KEY_RIGHT),
+];
+
+/// Keysyms that need shift on a US layout, and the physical key producing them
+static SHIFTED_NAMES: &[(KeySym, &str)] = &[
+    (KeySym::KEY_TILDE, "grv"),
+    (KeySym::KEY_EXCLAMATION, "1"),
+    (KeySym::KEY_AT, "2"),
+    (KeySym::KEY_NUMBERSIGN, "3"),
+    (KeySym::KEY_DOLLAR, "4"),
+    (KeySym::KEY_PERCENT, "5"),
+    (KeySym::KEY_CIRCUM, "6"),
+    (KeySym::KEY_AMPERSAND, "7"),
+    (KeySym::KEY_ASTERISK, "8"),
+    (KeySym::KEY_PARENLEFT, "9"),
+    (KeySym::KEY_PARENRIGHT, "0"),
+    (KeySym::KEY_UNDERSCORE, "min"),
+    (KeySym::KEY_PLUS, "eql"),
+    (KeySym::KEY_BRACELEFT, "lbrc"),
+    (KeySym::KEY_BRACERIGHT, "rbrc"),
+    (KeySym::KEY_BAR, "bksl"),
+    (KeySym::KEY_COLON, "scln"),
+    (KeySym::KEY_DOUBLEQUOTE, "quot"),
+    (KeySym::KEY_LESS, "comm"),
+    (KeySym::KEY_GREATER, "."),
+    (KeySym::KEY_QUESTION, "/"),
+];
+
+/// Number of keys on each row of the exported blocks
+static ROW_LENGTHS: &[usize] = &[13, 14, 14, 13, 12, 7, 10];
+
+/// Remapper action producing a keysym, `_` if it can not be expressed
+fn action(keysym: &KeySym) -> String {
+    if *keysym == KeySym::KEY_NONE {
+        return String::from("_");
+    }
+    if let Some((_, name, _)) = KEY_NAMES.iter().find(|(_, _, sym)| sym == keysym) {
+        return name.to_string();
+    }
+    if let Some((_, name)) = SHIFTED_NAMES.iter().find(|(sym, _)| sym == keysym) {
+        return format!("S-{}", name);
+    }
+    if let Some(i) = crate::ALL_UPPER_CASE.iter().position(|sym| sym == keysym) {
+        return format!("S-{}", crate::ALL_LOWER_CASE[i]);
+    }
+    String::from("_")
+}
+
+fn block(header: &str, keys: &[String]) -> String {
+    let mut out = format!("({}\n", header);
+    let mut keys = keys.iter();
+    for len in ROW_LENGTHS {
+        let row = keys.by_ref().take(*len).cloned().collect::<Vec<_>>();
+        out.push_str(&format!("  {}\n", row.join(" ")));
+    }
+    out.push_str(")\n");
+    out
+}
+
+impl KeyTable {
+    /// Export the keytable as a `defsrc` block and a `deflayer` block with the given name
+    ///
+    /// Keys that are unmapped are exported as `XX`, keysyms that the remapper can not produce are
+    /// left transparent.
+    pub fn export(&self, remapper: Remapper, layer: &str) -> String {
+        let src = KEY_NAMES
+            .iter()
+            .map(|(_, name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        let mapped = KEY_NAMES
+            .iter()
+            .map(|(keycode, _, _)| {
+                if !self.is_mapped(*keycode) {
+                    return String::from("XX");
+                }
+                self.get_keysym(Modifier::Key, *keycode)
+                    .map(|sym| action(&sym))
+                    .unwrap_or_else(|_| String::from("_"))
+            })
+            .collect::<Vec<_>>();
+
+        let header = match remapper {
+            Remapper::Kmonad => ";; add a defcfg block with your input and output devices\n",
+            Remapper::Kanata => "(defcfg)\n",
+        };
+        format!(
+            "{}\n{}\n{}",
+            header,
+            block("defsrc", &src),
+            block(&format!("deflayer {}", layer), &mapped)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_COLUMNS;
+
+    #[test]
+    fn export_layer() {
+        let table = KeyTable::parse(
+            "keycode   9 = grave\nkeycode  10 = exclam\nkeycode  38 = A a\nkeycode  49 = Escape",
+            DEFAULT_COLUMNS,
+        )
+        .unwrap();
+        let config = table.export(Remapper::Kmonad, "base");
+
+        let layer = config.split("(deflayer base\n").nth(1).unwrap();
+        let keys = layer.split_ascii_whitespace().collect::<Vec<_>>();
+        assert_eq!(keys[0], "grv");
+        assert_eq!(keys[1], "XX");
+        assert_eq!(keys[13], "esc");
+        assert_eq!(keys[14], "S-1");
+        assert_eq!(keys[42], "S-a");
+        assert!(config.starts_with(";;"));
+    }
+
+    #[test]
+    fn rows_cover_every_key() {
+        assert_eq!(ROW_LENGTHS.iter().sum::<usize>(), KEY_NAMES.len());
+    }
+}
@@ -0,0 +1,434 @@
+//! Parse, generate and apply .Xmodmap expressions
+//!
+//! Supports both `keycode` and `keysym` remap statements. Since xmodmap itself only operates on
+//! keycodes, `keysym` statements can be resolved against a [KeyTable] to the `keycode` statements
+//! they stand for.
+//!
+//! ```rust
+//! use pino_xmodmap::{expr::Expression, KeySym, KeyTable, DEFAULT_COLUMNS};
+//!
+//! let table = KeyTable::parse("keycode  66 = Escape", DEFAULT_COLUMNS).unwrap();
+//! let expr = "keysym Escape = Tab".parse::<Expression>().unwrap();
+//!
+//! let resolved = table.resolve_expression(&expr).unwrap();
+//! assert_eq!(resolved, vec![Expression::Keycode(66, vec![KeySym::KEY_TAB])]);
+//! assert_eq!(resolved[0].to_string(), "keycode 66 = Tab");
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    runner::{Cmd, CommandRunner},
+    Error, KeyCode, KeySym, KeyTable, ModIndex, Modifier, DEFAULT_COLUMNS,
+};
+
+/// A single .Xmodmap statement
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Expression {
+    /// `keycode NUMBER = KEYSYMNAME ...`
+    Keycode(KeyCode, Vec<KeySym>),
+    /// `keysym KEYSYMNAME = KEYSYMNAME ...`
+    Keysym(KeySym, Vec<KeySym>),
+    /// `clear MODIFIERNAME`
+    Clear(ModIndex),
+    /// `add MODIFIERNAME = KEYSYMNAME ...`
+    Add(ModIndex, Vec<KeySym>),
+    /// `remove MODIFIERNAME = KEYSYMNAME ...`
+    Remove(ModIndex, Vec<KeySym>),
+}
+
+/// A remap that did not take effect, as found by [verify]
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Mismatch {
+    pub keycode: KeyCode,
+    /// Keysyms that were requested
+    pub expected: Vec<KeySym>,
+    /// Keysyms currently bound to the keycode
+    pub found: Vec<KeySym>,
+}
+
+/// Parse every expression in the contents of an .Xmodmap file
+///
+/// Blank lines and comments (starting with `!`) are skipped, as are `pointer` statements since
+/// they remap buttons rather than keys.
+pub fn parse_file(contents: &str) -> Result<Vec<Expression>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_expressions").entered();
+
+    contents
+        .lines()
+        .map(|l| l.trim())
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('!'))
+        .filter(|(_, l)| l.split_ascii_whitespace().next() != Some("pointer"))
+        .map(|(i, l)| Expression::from_str(l).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+fn parse_keysyms(input: &str) -> Result<Vec<KeySym>, Error> {
+    input
+        .split_ascii_whitespace()
+        .map(|s| KeySym::from_str(s).map_err(|_| Error::NonExistentKeySym(s.into())))
+        .collect()
+}
+
+/// Parse a keycode the way xmodmap does, in hex after `0x`, in octal after `0` and in decimal
+/// otherwise
+fn parse_keycode(input: &str) -> Option<KeyCode> {
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => KeyCode::from_str_radix(hex, 16).ok(),
+        None if input.len() > 1 && input.starts_with('0') => {
+            KeyCode::from_str_radix(&input[1..], 8).ok()
+        },
+        None => input.parse().ok(),
+    }
+}
+
+fn parse_modifier(input: &str) -> Result<ModIndex, Error> {
+    ModIndex::from_str(input.trim()).map_err(|_| Error::invalid(input.trim()))
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Expression, Self::Err> {
+        let invalid = || Error::invalid(input.trim());
+        let (command, rest) = input
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid)?;
+
+        if command == "clear" {
+            return Ok(Expression::Clear(parse_modifier(rest)?));
+        }
+
+        let (lhs, rhs) = rest.split_once('=').ok_or_else(invalid)?;
+        let lhs = lhs.trim();
+        match command {
+            "keycode" => {
+                let keycode = parse_keycode(lhs).ok_or_else(invalid)?;
+                Ok(Expression::Keycode(keycode, parse_keysyms(rhs)?))
+            },
+            "keysym" => {
+                let keysym =
+                    KeySym::from_str(lhs).map_err(|_| Error::NonExistentKeySym(lhs.into()))?;
+                Ok(Expression::Keysym(keysym, parse_keysyms(rhs)?))
+            },
+            "add" => Ok(Expression::Add(parse_modifier(lhs)?, parse_keysyms(rhs)?)),
+            "remove" => Ok(Expression::Remove(
+                parse_modifier(lhs)?,
+                parse_keysyms(rhs)?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn fmt_keysyms(syms: &[KeySym]) -> String {
+    syms.iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Keycode(keycode, syms) => {
+                write!(f, "keycode {} = {}", keycode, fmt_keysyms(syms))
+            },
+            Expression::Keysym(keysym, syms) => {
+                write!(f, "keysym {} = {}", keysym, fmt_keysyms(syms))
+            },
+            Expression::Clear(modifier) => write!(f, "clear {}", modifier),
+            Expression::Add(modifier, syms) => {
+                write!(f, "add {} = {}", modifier, fmt_keysyms(syms))
+            },
+            Expression::Remove(modifier, syms) => {
+                write!(f, "remove {} = {}", modifier, fmt_keysyms(syms))
+            },
+        }
+    }
+}
+
+impl KeyTable {
+    /// Resolve an expression against the current keytable
+    ///
+    /// `keysym` expressions are turned into a `keycode` expression for every keycode the keysym
+    /// is currently bound to, all other expressions are returned unchanged.
+    pub fn resolve_expression(&self, expr: &Expression) -> Result<Vec<Expression>, Error> {
+        match expr {
+            Expression::Keysym(keysym, syms) => {
+                let keycodes = self.get_keycodes(keysym);
+                if keycodes.is_empty() {
+                    return Err(Error::NonExistentKeySym(keysym.to_string()));
+                }
+                Ok(keycodes
+                    .into_iter()
+                    .map(|keycode| Expression::Keycode(keycode, syms.clone()))
+                    .collect())
+            },
+            _ => Ok(vec![expr.clone()]),
+        }
+    }
+
+    /// Build a keytable holding only the keycodes bound by expressions, such as those of an
+    /// `~/.Xmodmap` file
+    ///
+    /// `keysym` expressions are resolved against the keycodes bound so far and skipped if the
+    /// keysym is not bound yet, modifier expressions are ignored.
+    ///
+    /// ```rust
+    /// # use pino_xmodmap::{expr, KeySym, KeyTable, Modifier, DEFAULT_COLUMNS};
+    /// let exprs = expr::parse_file("keycode 66 = Caps_Lock\nkeysym Caps_Lock = Escape").unwrap();
+    /// let table = KeyTable::from_expressions(&exprs, DEFAULT_COLUMNS);
+    ///
+    /// assert_eq!(table.get_keysym(Modifier::Key, 66).unwrap(), KeySym::KEY_ESCAPE);
+    /// ```
+    pub fn from_expressions(exprs: &[Expression], columns: &[Option<Modifier>]) -> Self {
+        let mut table = KeyTable::empty(columns);
+        for expr in exprs {
+            for resolved in table.resolve_expression(expr).unwrap_or_default() {
+                if let Expression::Keycode(keycode, syms) = resolved {
+                    table.insert_syms(keycode, syms);
+                }
+            }
+        }
+        table
+    }
+}
+
+/// Re-read the mapping from xmodmap and check that applied expressions took effect
+///
+/// Other clients can race with xmodmap and overwrite changes, so this should be called with the
+/// expressions returned by [KeyTable::apply]. Only `keycode` expressions are checked.
+///
+/// ```rust,no_run
+/// use pino_xmodmap::{expr::{self, Expression}, KeyTable};
+///
+/// let table = KeyTable::new().unwrap();
+/// let applied = table.apply(&["keysym Escape = Tab".parse::<Expression>().unwrap()]).unwrap();
+/// for mismatch in expr::verify(&applied).unwrap() {
+///     println!("keycode {} was not remapped", mismatch.keycode);
+/// }
+/// ```
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn verify(applied: &[Expression]) -> Result<Vec<Mismatch>, Error> {
+    Ok(KeyTable::new()?.mismatches(applied))
+}
+
+/// Async variant of [verify], enabled by the `tokio` feature
+#[cfg(feature = "tokio")]
+pub async fn verify_async(applied: &[Expression]) -> Result<Vec<Mismatch>, Error> {
+    Ok(KeyTable::new_async().await?.mismatches(applied))
+}
+
+/// Like [verify], re-reading the mapping through the given runner
+pub fn verify_with_runner(
+    runner: &dyn CommandRunner,
+    applied: &[Expression],
+) -> Result<Vec<Mismatch>, Error> {
+    Ok(KeyTable::with_runner(runner, DEFAULT_COLUMNS)?.mismatches(applied))
+}
+
+impl KeyTable {
+    /// Apply expressions to the X server using xmodmap
+    ///
+    /// `keysym` expressions are resolved against this table first. Returns the expressions that
+    /// were actually applied, which can be passed to [verify]. With the `native` feature the
+    /// expressions are sent to the X server directly instead.
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn apply(&self, exprs: &[Expression]) -> Result<Vec<Expression>, Error> {
+        use pino_xcommon::backend::{dispatch, Backend};
+
+        pino_xcommon::session::require_x(&crate::DisplayHandle::default())
+            .map_err(Error::NoXServer)?;
+        dispatch(|backend| match backend {
+            #[cfg(feature = "native")]
+            Backend::Native => Some((|| {
+                let mut applied = vec![];
+                for expr in exprs {
+                    applied.append(&mut self.resolve_expression(expr)?);
+                }
+                crate::native::apply(self, &applied).map(|_| applied)
+            })()),
+            #[cfg(feature = "process")]
+            Backend::Binary => {
+                Some(self.apply_with_runner(&crate::runner::SystemRunner::default(), exprs))
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| Err(Error::no_backend()))
+    }
+
+    /// Like [KeyTable::apply], running xmodmap through the given runner
+    pub fn apply_with_runner(
+        &self,
+        runner: &dyn CommandRunner,
+        exprs: &[Expression],
+    ) -> Result<Vec<Expression>, Error> {
+        let mut applied = vec![];
+        for expr in exprs {
+            applied.append(&mut self.resolve_expression(expr)?);
+        }
+
+        let mut cmd = Cmd::new("xmodmap");
+        for expr in applied.iter() {
+            cmd = cmd.arg("-e").arg(expr.to_string());
+        }
+        let output = runner.output(&cmd).map_err(Error::run("xmodmap"))?;
+        if !output.success() {
+            return Err(Error::exited("xmodmap", &output));
+        }
+
+        Ok(applied)
+    }
+
+    /// Compare applied expressions against this table
+    pub fn mismatches(&self, applied: &[Expression]) -> Vec<Mismatch> {
+        let mut mismatches = vec![];
+        for expr in applied {
+            let Expression::Keycode(keycode, expected) = expr else {
+                continue;
+            };
+            let found = self.get_raw(*keycode).unwrap_or(&[]).to_vec();
+            let matches = expected
+                .iter()
+                .enumerate()
+                .all(|(i, sym)| *sym == found.get(i).cloned().unwrap_or(KeySym::KEY_NONE));
+            if !matches {
+                mismatches.push(Mismatch {
+                    keycode: *keycode,
+                    expected: expected.clone(),
+                    found,
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_COLUMNS;
+
+    #[test]
+    fn table_from_expressions() {
+        let exprs =
+            parse_file("keycode 66 = Caps_Lock\nkeycode 66 = Escape\nkeysym a = b").unwrap();
+        let table = KeyTable::from_expressions(&exprs, DEFAULT_COLUMNS);
+        assert_eq!(table.get_keycodes(&KeySym::KEY_ESCAPE), vec![66]);
+        assert!(table.get_key(KeySym::KEY_CAPS_LOCK).is_err());
+        assert!(!table.is_mapped(38));
+    }
+
+    #[test]
+    fn parse_statements() {
+        let exprs =
+            parse_file("! comment\n\nkeysym Escape = Tab\nclear Lock\nadd mod4 = a A").unwrap();
+        assert_eq!(
+            exprs,
+            vec![
+                Expression::Keysym(KeySym::KEY_ESCAPE, vec![KeySym::KEY_TAB]),
+                Expression::Clear(ModIndex::Lock),
+                Expression::Add(ModIndex::Mod4, vec![KeySym::KEY_a, KeySym::KEY_A]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_error_line() {
+        let err = parse_file("! comment\nclear Lock\nbogus").unwrap_err();
+        assert!(matches!(err, Error::InvalidFormat { line: Some(3), .. }));
+        assert!(matches!(
+            parse_file("keycode x = a").unwrap_err(),
+            Error::InvalidFormat { line: Some(1), content } if content == "keycode x = a"
+        ));
+    }
+
+    #[test]
+    fn parse_keycode_bases() {
+        let exprs = parse_file("keycode 0x42 = Escape\nkeycode 0102 = Escape\nkeycode 66 = Escape");
+        let expected = Expression::Keycode(66, vec![KeySym::KEY_ESCAPE]);
+        assert_eq!(exprs.unwrap(), vec![expected; 3]);
+        assert!(parse_file("keycode 0x = a").is_err());
+        assert!(parse_file("keycode 09 = a").is_err());
+        assert!(parse_file("keycode 0x100 = a").is_err());
+    }
+
+    #[test]
+    fn skip_pointer() {
+        let exprs = parse_file("pointer = 3 2 1\n  pointer = default\nclear Lock").unwrap();
+        assert_eq!(exprs, vec![Expression::Clear(ModIndex::Lock)]);
+        assert!(Expression::from_str("pointer = 3 2 1").is_err());
+    }
+
+    #[test]
+    fn generate_statements() {
+        let expr = Expression::Keysym(KeySym::KEY_ESCAPE, vec![KeySym::KEY_a, KeySym::KEY_A]);
+        assert_eq!(expr.to_string(), "keysym Escape = a A");
+        assert_eq!(Expression::Clear(ModIndex::Lock).to_string(), "clear Lock");
+    }
+
+    #[test]
+    fn modifier_statements_round_trip() {
+        for index in ModIndex::ALL {
+            for expr in [
+                Expression::Clear(index),
+                Expression::Add(index, vec![KeySym::KEY_a]),
+                Expression::Remove(index, vec![KeySym::KEY_a]),
+            ] {
+                assert_eq!(expr.to_string().parse::<Expression>().unwrap(), expr);
+            }
+        }
+        assert!("clear Shift+Lock".parse::<Expression>().is_err());
+        assert!("clear ".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn find_mismatches() {
+        let table = KeyTable::parse(
+            "keycode   9 = Escape\nkeycode  66 = Tab NoSymbol Tab",
+            DEFAULT_COLUMNS,
+        )
+        .unwrap();
+        let applied = vec![
+            Expression::Keycode(9, vec![KeySym::KEY_TAB]),
+            Expression::Keycode(66, vec![KeySym::KEY_TAB]),
+            Expression::Clear(ModIndex::Lock),
+        ];
+        assert_eq!(
+            table.mismatches(&applied),
+            vec![Mismatch {
+                keycode: 9,
+                expected: vec![KeySym::KEY_TAB],
+                found: vec![KeySym::KEY_ESCAPE],
+            }]
+        );
+    }
+
+    #[test]
+    fn resolve_keysym() {
+        let table = KeyTable::parse(
+            "keycode   9 = Escape\nkeycode  66 = Escape",
+            DEFAULT_COLUMNS,
+        )
+        .unwrap();
+        let expr = Expression::Keysym(KeySym::KEY_ESCAPE, vec![KeySym::KEY_TAB]);
+        assert_eq!(
+            table.resolve_expression(&expr).unwrap(),
+            vec![
+                Expression::Keycode(9, vec![KeySym::KEY_TAB]),
+                Expression::Keycode(66, vec![KeySym::KEY_TAB]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,691 @@
+//! Physical keyboard geometry from the XKB geometry description
+//!
+//! Reads the keymap through `xkbcomp` and lays out each key of the `xkb_geometry` section, so the
+//! physical position of a keycode can be looked up. All dimensions are in millimeters. Section
+//! rotation is not taken into account.
+//!
+//! ```rust
+//! use pino_xmodmap::geometry::Geometry;
+//!
+//! let xkb = r#"
+//! xkb_keycodes "evdev" {
+//!     <ESC> = 9;
+//!     <FK01> = 67;
+//! };
+//! xkb_geometry "pc(pc104)" {
+//!     width= 470;
+//!     height= 180;
+//!     shape "NORM" { { [ 18, 18 ] }, { [ 2, 1 ], [ 16, 16 ] } };
+//!     section "Function" {
+//!         top= 22;
+//!         left= 19;
+//!         row {
+//!             top= 1;
+//!             left= 1;
+//!             keys { { <ESC>, "NORM", 1 }, { <FK01>, "NORM", 20 } };
+//!         };
+//!     };
+//! };
+//! "#;
+//!
+//! let geometry = Geometry::parse(xkb).unwrap();
+//! let f1 = geometry.key(67).unwrap();
+//! assert_eq!((f1.x, f1.y, f1.width), (59.0, 23.0, 18.0));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    runner::{Cmd, CommandRunner},
+    DisplayHandle, Error, KeyCode,
+};
+
+/// Keyboard geometry
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Geometry {
+    pub name: String,
+    pub width: f32,
+    pub height: f32,
+    pub shapes: BTreeMap<String, Shape>,
+    pub sections: Vec<Section>,
+}
+
+/// Bounding box of a key shape
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Shape {
+    pub name: String,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Group of rows of keys, positioned relative to the keyboard
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Section {
+    pub name: String,
+    pub top: f32,
+    pub left: f32,
+    pub rows: Vec<Row>,
+}
+
+/// Single row of keys, positioned relative to its section
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Row {
+    pub top: f32,
+    pub left: f32,
+    pub vertical: bool,
+    pub keys: Vec<GeometryKey>,
+}
+
+/// Physical key, positioned relative to the keyboard
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GeometryKey {
+    /// XKB key name, such as `AE01`
+    pub name: String,
+    /// Keycode the key name is bound to, if any
+    pub keycode: Option<KeyCode>,
+    pub shape: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    KeyName(String),
+    Number(f32),
+    Punct(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {},
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '"' => {
+                let s = chars.by_ref().take_while(|c| *c != '"').collect();
+                tokens.push(Token::Str(s));
+            },
+            '<' => {
+                let s = chars.by_ref().take_while(|c| *c != '>').collect();
+                tokens.push(Token::KeyName(s));
+            },
+            c if c.is_ascii_digit()
+                || (c == '-' || c == '.')
+                    && chars
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_digit() || *c == '.') =>
+            {
+                let mut s = String::from(c);
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    s.push(*c);
+                    chars.next();
+                }
+                let number = s.parse().map_err(|_| Error::invalid(s))?;
+                tokens.push(Token::Number(number));
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                let mut s = String::from(c);
+                while let Some(c) = chars
+                    .peek()
+                    .filter(|c| c.is_alphanumeric() || **c == '_' || **c == '.')
+                {
+                    s.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(s));
+            },
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Index one past the brace matching the opening brace at `start`
+fn skip_block(tokens: &[Token], start: usize) -> Result<usize, Error> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Punct('{') => depth += 1,
+            Token::Punct('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i + 1);
+                }
+            },
+            _ => {},
+        }
+    }
+    Err(Error::invalid("unbalanced braces"))
+}
+
+/// Split the contents of a block into its statements, as ranges of token indices
+fn statements(tokens: &[Token], start: usize, end: usize) -> Result<Vec<(usize, usize)>, Error> {
+    let mut statements = vec![];
+    let mut i = start;
+    let mut begin = start;
+    while i < end {
+        match tokens[i] {
+            Token::Punct('{') => i = skip_block(tokens, i)?,
+            Token::Punct(';') => {
+                if begin < i {
+                    statements.push((begin, i));
+                }
+                i += 1;
+                begin = i;
+            },
+            _ => i += 1,
+        }
+    }
+    if begin < end {
+        statements.push((begin, end));
+    }
+    Ok(statements)
+}
+
+/// Find the opening brace of a block statement, and the index one past its closing brace
+fn block_bounds(tokens: &[Token], start: usize, end: usize) -> Result<(usize, usize), Error> {
+    let open = (start..end)
+        .find(|i| tokens[*i] == Token::Punct('{'))
+        .ok_or_else(|| Error::invalid("missing block"))?;
+    Ok((open, skip_block(tokens, open)?))
+}
+
+/// Value of an assignment statement `name = value`
+fn assignment(tokens: &[Token], start: usize) -> Option<(&str, &Token)> {
+    match (
+        tokens.get(start),
+        tokens.get(start + 1),
+        tokens.get(start + 2),
+    ) {
+        (Some(Token::Ident(name)), Some(Token::Punct('=')), Some(value)) => Some((name, value)),
+        _ => None,
+    }
+}
+
+fn number(token: &Token) -> f32 {
+    match token {
+        Token::Number(n) => *n,
+        _ => 0.0,
+    }
+}
+
+fn parse_keycodes(
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+) -> Result<HashMap<String, KeyCode>, Error> {
+    let mut keycodes = HashMap::new();
+    let mut aliases = vec![];
+    for (begin, _) in statements(tokens, start, end)? {
+        match &tokens[begin..] {
+            [Token::KeyName(name), Token::Punct('='), Token::Number(code), ..] => {
+                keycodes.insert(name.clone(), *code as KeyCode);
+            },
+            [Token::Ident(alias), Token::KeyName(name), Token::Punct('='), Token::KeyName(real), ..]
+                if alias == "alias" =>
+            {
+                aliases.push((name.clone(), real.clone()));
+            },
+            _ => {},
+        }
+    }
+    for (name, real) in aliases {
+        if let Some(code) = keycodes.get(&real).copied() {
+            keycodes.insert(name, code);
+        }
+    }
+    Ok(keycodes)
+}
+
+fn parse_shape(tokens: &[Token], name: String, start: usize, end: usize) -> Shape {
+    // bounding box of every point in every outline
+    let mut width: f32 = 0.0;
+    let mut height: f32 = 0.0;
+    for window in tokens[start..end].windows(5) {
+        if let [Token::Punct('['), Token::Number(x), Token::Punct(','), Token::Number(y), Token::Punct(']')] =
+            window
+        {
+            width = width.max(*x);
+            height = height.max(*y);
+        }
+    }
+    Shape {
+        name,
+        width,
+        height,
+    }
+}
+
+struct KeyDefaults<'a> {
+    shape: String,
+    gap: f32,
+    shapes: &'a BTreeMap<String, Shape>,
+    keycodes: &'a HashMap<String, KeyCode>,
+}
+
+fn parse_row(
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+    section: (f32, f32),
+    defaults: &KeyDefaults,
+) -> Result<Row, Error> {
+    let mut row = Row {
+        top: 0.0,
+        left: 0.0,
+        vertical: false,
+        keys: vec![],
+    };
+    let mut keys_block = None;
+
+    for (begin, stmt_end) in statements(tokens, start, end)? {
+        match assignment(tokens, begin) {
+            Some(("top", value)) => row.top = number(value),
+            Some(("left", value)) => row.left = number(value),
+            Some(("vertical", Token::Ident(value))) => row.vertical = value == "True",
+            _ if tokens[begin] == Token::Ident(String::from("keys")) => {
+                keys_block = Some(block_bounds(tokens, begin, stmt_end)?);
+            },
+            _ => {},
+        }
+    }
+
+    let Some((open, close)) = keys_block else {
+        return Ok(row);
+    };
+
+    let mut cursor = 0.0;
+    let mut i = open + 1;
+    while i < close - 1 {
+        // each key is either `<NAME>` or `{ <NAME>, "SHAPE", gap, ... }`
+        let (fields, next) = match tokens[i] {
+            Token::Punct('{') => {
+                let key_end = skip_block(tokens, i)?;
+                (&tokens[i + 1..key_end - 1], key_end)
+            },
+            Token::KeyName(_) => (&tokens[i..i + 1], i + 1),
+            _ => {
+                i += 1;
+                continue;
+            },
+        };
+        i = next;
+
+        let Some(Token::KeyName(name)) = fields.first() else {
+            continue;
+        };
+        let mut shape = defaults.shape.clone();
+        let mut gap = defaults.gap;
+        for field in fields[1..].split(|t| *t == Token::Punct(',')) {
+            match field {
+                [Token::Str(s)] => shape = s.clone(),
+                [Token::Number(n)] => gap = *n,
+                [Token::Ident(k), Token::Punct('='), Token::Str(s)] if k == "shape" => {
+                    shape = s.clone()
+                },
+                [Token::Ident(k), Token::Punct('='), Token::Number(n)] if k == "gap" => gap = *n,
+                _ => {},
+            }
+        }
+
+        let (width, height) = defaults
+            .shapes
+            .get(&shape)
+            .map(|s| (s.width, s.height))
+            .unwrap_or((0.0, 0.0));
+        cursor += gap;
+        let (x, y) = if row.vertical {
+            (row.left, row.top + cursor)
+        } else {
+            (row.left + cursor, row.top)
+        };
+        cursor += if row.vertical { height } else { width };
+
+        row.keys.push(GeometryKey {
+            name: name.clone(),
+            keycode: defaults.keycodes.get(name).copied(),
+            shape,
+            x: section.1 + x,
+            y: section.0 + y,
+            width,
+            height,
+        });
+    }
+
+    Ok(row)
+}
+
+fn parse_section(
+    tokens: &[Token],
+    name: String,
+    start: usize,
+    end: usize,
+    defaults: &KeyDefaults,
+) -> Result<Section, Error> {
+    let mut section = Section {
+        name,
+        top: 0.0,
+        left: 0.0,
+        rows: vec![],
+    };
+    let mut defaults = KeyDefaults {
+        shape: defaults.shape.clone(),
+        gap: defaults.gap,
+        shapes: defaults.shapes,
+        keycodes: defaults.keycodes,
+    };
+
+    for (begin, stmt_end) in statements(tokens, start, end)? {
+        match assignment(tokens, begin) {
+            Some(("top", value)) => section.top = number(value),
+            Some(("left", value)) => section.left = number(value),
+            Some(("key.shape", Token::Str(shape))) => defaults.shape = shape.clone(),
+            Some(("key.gap", value)) => defaults.gap = number(value),
+            _ if tokens[begin] == Token::Ident(String::from("row")) => {
+                let (open, close) = block_bounds(tokens, begin, stmt_end)?;
+                let row = parse_row(
+                    tokens,
+                    open + 1,
+                    close - 1,
+                    (section.top, section.left),
+                    &defaults,
+                )?;
+                section.rows.push(row);
+            },
+            _ => {},
+        }
+    }
+
+    Ok(section)
+}
+
+fn parse_geometry(
+    tokens: &[Token],
+    name: String,
+    start: usize,
+    end: usize,
+    keycodes: &HashMap<String, KeyCode>,
+) -> Result<Geometry, Error> {
+    let mut geometry = Geometry {
+        name,
+        width: 0.0,
+        height: 0.0,
+        shapes: BTreeMap::new(),
+        sections: vec![],
+    };
+    let mut default_shape = String::from("NORM");
+    let mut default_gap = 0.0;
+
+    // shapes have to be known before keys can be laid out
+    let stmts = statements(tokens, start, end)?;
+    for (begin, stmt_end) in stmts.iter().copied() {
+        if let [Token::Ident(kind), Token::Str(name), ..] = &tokens[begin..stmt_end] {
+            if kind == "shape" {
+                let (open, close) = block_bounds(tokens, begin, stmt_end)?;
+                let shape = parse_shape(tokens, name.clone(), open, close);
+                geometry.shapes.insert(name.clone(), shape);
+            }
+        }
+    }
+
+    for (begin, stmt_end) in stmts {
+        match assignment(tokens, begin) {
+            Some(("width", value)) => geometry.width = number(value),
+            Some(("height", value)) => geometry.height = number(value),
+            Some(("key.shape", Token::Str(shape))) => default_shape = shape.clone(),
+            Some(("key.gap", value)) => default_gap = number(value),
+            _ => {
+                if let [Token::Ident(kind), Token::Str(name), ..] = &tokens[begin..stmt_end] {
+                    if kind == "section" {
+                        let defaults = KeyDefaults {
+                            shape: default_shape.clone(),
+                            gap: default_gap,
+                            shapes: &geometry.shapes,
+                            keycodes,
+                        };
+                        let (open, close) = block_bounds(tokens, begin, stmt_end)?;
+                        let section =
+                            parse_section(tokens, name.clone(), open + 1, close - 1, &defaults)?;
+                        geometry.sections.push(section);
+                    }
+                }
+            },
+        }
+    }
+
+    Ok(geometry)
+}
+
+impl Geometry {
+    /// Read the geometry of the keyboard attached to the X server
+    ///
+    /// Requires `xkbcomp` to be installed.
+    #[cfg(feature = "process")]
+    pub fn read() -> Result<Self, Error> {
+        Self::read_display(&DisplayHandle::default())
+    }
+
+    /// Read the geometry of the keyboard attached to the given display
+    #[cfg(feature = "process")]
+    pub fn read_display(display: &DisplayHandle) -> Result<Self, Error> {
+        Self::read_from(&crate::runner::SystemRunner::default(), display)
+    }
+
+    /// Read the geometry of the keyboard, running `xkbcomp` through the given runner
+    pub fn read_with_runner(runner: &dyn CommandRunner) -> Result<Self, Error> {
+        Self::read_from(runner, &DisplayHandle::default())
+    }
+
+    fn read_from(runner: &dyn CommandRunner, display: &DisplayHandle) -> Result<Self, Error> {
+        let source = Self::source(display)?;
+        let output = display
+            .runner(runner)
+            .output(&Cmd::new("xkbcomp").arg("-xkb").arg(source).arg("-"))
+            .map_err(Error::run("xkbcomp"))?;
+        if !output.success() {
+            return Err(Error::exited("xkbcomp", &output));
+        }
+        let raw = String::from_utf8(output.stdout)
+            .map_err(|_| Error::invalid("xkbcomp output is not UTF-8"))?;
+
+        Self::parse(&raw)
+    }
+
+    /// Display xkbcomp reads the keymap from, which it takes as its source argument
+    pub(crate) fn source(display: &DisplayHandle) -> Result<String, Error> {
+        pino_xcommon::session::require_x(display).map_err(Error::NoXServer)?;
+        display
+            .name()
+            .or_else(|| std::env::var("DISPLAY").ok())
+            .ok_or_else(|| Error::NoXServer(pino_xcommon::session::Session::detect()))
+    }
+
+    /// Parse the geometry out of a keymap in xkb format, as output by `xkbcomp -xkb`
+    ///
+    /// Key names are resolved to keycodes using the `xkb_keycodes` section, if present.
+    pub fn parse(xkb: &str) -> Result<Self, Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse_geometry").entered();
+
+        let tokens = tokenize(xkb)?;
+
+        let mut keycodes = HashMap::new();
+        let mut geometry = None;
+        let mut i = 0;
+        while i < tokens.len() {
+            match (&tokens[i], tokens.get(i + 1)) {
+                (Token::Ident(kind), Some(Token::Str(name)))
+                    if kind == "xkb_keycodes" || kind == "xkb_geometry" =>
+                {
+                    let (open, close) = block_bounds(&tokens, i, tokens.len())?;
+                    if kind == "xkb_keycodes" {
+                        keycodes = parse_keycodes(&tokens, open + 1, close - 1)?;
+                    } else {
+                        geometry = Some((name.clone(), open + 1, close - 1));
+                    }
+                    i = close;
+                },
+                _ => i += 1,
+            }
+        }
+
+        let (name, start, end) =
+            geometry.ok_or_else(|| Error::invalid("missing xkb_geometry section"))?;
+        parse_geometry(&tokens, name, start, end, &keycodes)
+    }
+
+    /// Every key of the keyboard
+    pub fn keys(&self) -> impl Iterator<Item = &GeometryKey> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.rows.iter())
+            .flat_map(|row| row.keys.iter())
+    }
+
+    /// Physical key bound to a keycode
+    pub fn key(&self, keycode: KeyCode) -> Option<&GeometryKey> {
+        self.keys().find(|key| key.keycode == Some(keycode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XKB: &str = r#"
+xkb_keymap {
+xkb_keycodes "evdev+aliases(qwerty)" {
+    minimum = 8;
+    maximum = 255;
+    <TLDE> = 49;
+    <AE01> = 10;
+    <LFSH> = 50;
+    <AC01> = 38;
+    alias <LatA> = <AC01>;
+};
+xkb_geometry "pc(pc104)" {
+    width=       470;
+    height=      180;
+    baseColor=   "white";
+    shape.cornerRadius= 1;
+    shape "NORM" {
+        { [  18,  18 ] },
+        { [   2,   1 ], [  16,  16 ] }
+    };
+    shape "LFSH" {
+        { [  42,  18 ] },
+        { [   2,   1 ], [  40,  16 ] }
+    };
+    solid "LedPanel" {
+        shape= "LEDS";
+        top=  52;
+        left= 377;
+        color= "grey10";
+    };
+    section "Alpha" {
+        key.color= "grey20";
+        priority=  7;
+        top=       61;
+        left=      19;
+        width=     287;
+        height=    95;
+        row {
+            top= 1;
+            left= 1;
+            keys {
+                <TLDE>, <AE01>
+            };
+        };
+        row {
+            top= 39;
+            left= 1;
+            keys {
+                { <LFSH>, "LFSH" }, { <LatA>, 1, color="red" }
+            };
+        };
+    };
+    section "Pad" {
+        top= 61;
+        left= 400;
+        row {
+            top= 1;
+            vertical= True;
+            keys { <KPAD>, <KPEN> };
+        };
+    };
+};
+};
+"#;
+
+    #[test]
+    fn parse_geometry() {
+        let geometry = Geometry::parse(XKB).unwrap();
+        assert_eq!(geometry.width, 470.0);
+        assert_eq!(geometry.shapes["LFSH"].width, 42.0);
+        assert_eq!(geometry.sections.len(), 2);
+
+        let one = geometry.key(10).unwrap();
+        assert_eq!((one.x, one.y), (38.0, 62.0));
+
+        let a = geometry.key(38).unwrap();
+        assert_eq!(a.name, "LatA");
+        assert_eq!((a.x, a.y), (63.0, 100.0));
+
+        let pad = geometry.keys().find(|k| k.name == "KPEN").unwrap();
+        assert_eq!((pad.x, pad.y), (400.0, 80.0));
+        assert_eq!(pad.keycode, None);
+    }
+
+    #[test]
+    fn tokenize_punctuation() {
+        assert_eq!(
+            tokenize("x= -1.5 - .5 .").unwrap(),
+            vec![
+                Token::Ident(String::from("x")),
+                Token::Punct('='),
+                Token::Number(-1.5),
+                Token::Punct('-'),
+                Token::Number(0.5),
+                Token::Punct('.'),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_from_display() {
+        let runner = crate::runner::MockRunner::new().respond(
+            Cmd::new("xkbcomp")
+                .arg("-xkb")
+                .arg(":1")
+                .arg("-")
+                .env("DISPLAY", ":1"),
+            crate::runner::Output::from_stdout(XKB),
+        );
+        let display = DisplayHandle::new().display(":1");
+        let geometry = Geometry::read_from(&runner, &display).unwrap();
+        assert_eq!(geometry.sections.len(), 2);
+    }
+}
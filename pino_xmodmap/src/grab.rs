@@ -0,0 +1,136 @@
+//! Compute the key grabs needed for a hotkey binding
+//!
+//! A binding such as `super+c` must be grabbed on every keycode that produces the keysym (in any
+//! layout group), and once more for each combination of lock modifiers that may be active, or the
+//! hotkey silently stops working when Caps Lock or Num Lock is on.
+//!
+//! ```rust
+//! use pino_xmodmap::{grab::Binding, KeyTable, ModMask, DEFAULT_COLUMNS};
+//!
+//! let table = KeyTable::parse("keycode  54 = c C", DEFAULT_COLUMNS).unwrap();
+//! let binding = "super+c".parse::<Binding>().unwrap();
+//!
+//! let specs = table.grab_specs(&binding);
+//! assert!(specs.contains(&(ModMask::MOD4, 54)));
+//! assert!(specs.contains(&(ModMask::MOD4 | ModMask::LOCK, 54)));
+//! ```
+
+use std::str::FromStr;
+
+use crate::{Error, KeyCode, KeySym, KeyTable, ModMask};
+
+/// Lock modifier combinations that are grabbed in addition to the binding's modifiers
+///
+/// Assumes Num Lock is on `Mod2`, which is the case for nearly every setup.
+pub static DEFAULT_LOCK_MASKS: &[ModMask] = &[
+    ModMask::NONE,
+    ModMask::LOCK,
+    ModMask::MOD2,
+    ModMask(ModMask::LOCK.0 | ModMask::MOD2.0),
+];
+
+/// Modifiers needed to reach each column of `xmodmap -pke` output
+///
+/// Columns 0-3 are the two levels of the first two groups, 4-5 are the third and fourth level
+/// of the first group which are reached through `ISO_Level3_Shift` (usually `Mod5`), 6-9 are the
+/// fifth to eighth level reached through `ISO_Level5_Shift` (usually `Mod3`).
+static COLUMN_MASKS: &[ModMask] = &[
+    ModMask::NONE,
+    ModMask::SHIFT,
+    ModMask::NONE,
+    ModMask::SHIFT,
+    ModMask::MOD5,
+    ModMask(ModMask::MOD5.0 | ModMask::SHIFT.0),
+    ModMask::MOD3,
+    ModMask(ModMask::MOD3.0 | ModMask::SHIFT.0),
+    ModMask(ModMask::MOD3.0 | ModMask::MOD5.0),
+    ModMask(ModMask::MOD3.0 | ModMask::MOD5.0 | ModMask::SHIFT.0),
+];
+
+/// A hotkey binding, a set of modifiers and a keysym
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Binding {
+    pub mods: ModMask,
+    pub keysym: KeySym,
+}
+
+impl FromStr for Binding {
+    type Err = Error;
+
+    /// Parse a binding of the form `super+shift+c`
+    fn from_str(input: &str) -> Result<Binding, Self::Err> {
+        let mut parts = input.split('+').map(|p| p.trim()).collect::<Vec<_>>();
+        let keysym = parts.pop().ok_or_else(|| Error::invalid(input))?;
+        let keysym =
+            KeySym::from_str(keysym).map_err(|_| Error::NonExistentKeySym(keysym.into()))?;
+
+        let mut mods = ModMask::NONE;
+        for part in parts {
+            mods |= ModMask::from_str(part).map_err(|_| Error::invalid(input))?;
+        }
+
+        Ok(Binding { mods, keysym })
+    }
+}
+
+impl KeyTable {
+    /// Every (modifier mask, keycode) pair that should be grabbed for a binding
+    ///
+    /// Uses [DEFAULT_LOCK_MASKS] for the lock modifier combinations.
+    pub fn grab_specs(&self, binding: &Binding) -> Vec<(ModMask, KeyCode)> {
+        self.grab_specs_with_locks(binding, DEFAULT_LOCK_MASKS)
+    }
+
+    /// Every (modifier mask, keycode) pair that should be grabbed for a binding, using the given
+    /// lock modifier combinations
+    pub fn grab_specs_with_locks(
+        &self,
+        binding: &Binding,
+        lock_masks: &[ModMask],
+    ) -> Vec<(ModMask, KeyCode)> {
+        let mut specs = vec![];
+
+        for (keycode, syms) in self.iter_keys() {
+            for (sym, column_mask) in syms.iter().zip(COLUMN_MASKS) {
+                if *sym != binding.keysym {
+                    continue;
+                }
+                for lock_mask in lock_masks {
+                    specs.push((binding.mods | *column_mask | *lock_mask, keycode));
+                }
+            }
+        }
+
+        specs.sort();
+        specs.dedup();
+        specs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_COLUMNS;
+
+    #[test]
+    fn parse_binding() {
+        let binding = Binding::from_str("super+shift+c").unwrap();
+        assert_eq!(binding.mods, ModMask::MOD4 | ModMask::SHIFT);
+        assert_eq!(binding.keysym, KeySym::KEY_c);
+        assert!(Binding::from_str("hyper+c").is_err());
+    }
+
+    #[test]
+    fn grab_every_group() {
+        // `c` is on a different key in the second group
+        let table =
+            KeyTable::parse("keycode  54 = c C\nkeycode  55 = v V c C", DEFAULT_COLUMNS).unwrap();
+        let binding = Binding::from_str("super+c").unwrap();
+
+        let specs = table.grab_specs_with_locks(&binding, &[ModMask::NONE]);
+        assert_eq!(specs, vec![(ModMask::MOD4, 54), (ModMask::MOD4, 55)]);
+        assert_eq!(table.grab_specs(&binding).len(), 8);
+    }
+}
@@ -0,0 +1,184 @@
+//! Conversions between X keycodes, USB HID usage ids and Windows scancodes
+//!
+//! X keycodes on evdev based servers are the linux input event codes offset by 8, which lets us
+//! map them onto the identifiers used by other platforms. Useful for cross-platform remapping
+//! tools that want to share key identifiers between backends.
+//!
+//! ```rust
+//! use pino_xmodmap::hid;
+//!
+//! assert_eq!(hid::keycode_to_hid(38), Some(0x04));
+//! assert_eq!(hid::hid_to_keycode(0x04), Some(38));
+//! assert_eq!(hid::keycode_to_scancode(38), Some(0x1e));
+//! ```
+
+use crate::KeyCode;
+
+/// USB HID usage id, from the keyboard/keypad usage page (0x07)
+pub type HidUsage = u16;
+
+/// Windows (set 1) scancode, extended keys have the 0xe0 prefix in the high byte
+pub type ScanCode = u16;
+
+/// Table of (X keycode, HID usage, scancode) for each key with a known conversion
+pub static KEY_CONVERSIONS: &[(KeyCode, HidUsage, ScanCode)] = &[
+    (9, 0x29, 0x01),     // Escape
+    (10, 0x1e, 0x02),    // 1
+    (11, 0x1f, 0x03),    // 2
+    (12, 0x20, 0x04),    // 3
+    (13, 0x21, 0x05),    // 4
+    (14, 0x22, 0x06),    // 5
+    (15, 0x23, 0x07),    // 6
+    (16, 0x24, 0x08),    // 7
+    (17, 0x25, 0x09),    // 8
+    (18, 0x26, 0x0a),    // 9
+    (19, 0x27, 0x0b),    // 0
+    (20, 0x2d, 0x0c),    // minus
+    (21, 0x2e, 0x0d),    // equal
+    (22, 0x2a, 0x0e),    // BackSpace
+    (23, 0x2b, 0x0f),    // Tab
+    (24, 0x14, 0x10),    // q
+    (25, 0x1a, 0x11),    // w
+    (26, 0x08, 0x12),    // e
+    (27, 0x15, 0x13),    // r
+    (28, 0x17, 0x14),    // t
+    (29, 0x1c, 0x15),    // y
+    (30, 0x18, 0x16),    // u
+    (31, 0x0c, 0x17),    // i
+    (32, 0x12, 0x18),    // o
+    (33, 0x13, 0x19),    // p
+    (34, 0x2f, 0x1a),    // bracketleft
+    (35, 0x30, 0x1b),    // bracketright
+    (36, 0x28, 0x1c),    // Return
+    (37, 0xe0, 0x1d),    // Control_L
+    (38, 0x04, 0x1e),    // a
+    (39, 0x16, 0x1f),    // s
+    (40, 0x07, 0x20),    // d
+    (41, 0x09, 0x21),    // f
+    (42, 0x0a, 0x22),    // g
+    (43, 0x0b, 0x23),    // h
+    (44, 0x0d, 0x24),    // j
+    (45, 0x0e, 0x25),    // k
+    (46, 0x0f, 0x26),    // l
+    (47, 0x33, 0x27),    // semicolon
+    (48, 0x34, 0x28),    // apostrophe
+    (49, 0x35, 0x29),    // grave
+    (50, 0xe1, 0x2a),    // Shift_L
+    (51, 0x31, 0x2b),    // backslash
+    (52, 0x1d, 0x2c),    // z
+    (53, 0x1b, 0x2d),    // x
+    (54, 0x06, 0x2e),    // c
+    (55, 0x19, 0x2f),    // v
+    (56, 0x05, 0x30),    // b
+    (57, 0x11, 0x31),    // n
+    (58, 0x10, 0x32),    // m
+    (59, 0x36, 0x33),    // comma
+    (60, 0x37, 0x34),    // period
+    (61, 0x38, 0x35),    // slash
+    (62, 0xe5, 0x36),    // Shift_R
+    (63, 0x55, 0x37),    // KP_Multiply
+    (64, 0xe2, 0x38),    // Alt_L
+    (65, 0x2c, 0x39),    // space
+    (66, 0x39, 0x3a),    // Caps_Lock
+    (67, 0x3a, 0x3b),    // F1
+    (68, 0x3b, 0x3c),    // F2
+    (69, 0x3c, 0x3d),    // F3
+    (70, 0x3d, 0x3e),    // F4
+    (71, 0x3e, 0x3f),    // F5
+    (72, 0x3f, 0x40),    // F6
+    (73, 0x40, 0x41),    // F7
+    (74, 0x41, 0x42),    // F8
+    (75, 0x42, 0x43),    // F9
+    (76, 0x43, 0x44),    // F10
+    (77, 0x53, 0x45),    // Num_Lock
+    (78, 0x47, 0x46),    // Scroll_Lock
+    (79, 0x5f, 0x47),    // KP_7
+    (80, 0x60, 0x48),    // KP_8
+    (81, 0x61, 0x49),    // KP_9
+    (82, 0x56, 0x4a),    // KP_Subtract
+    (83, 0x5c, 0x4b),    // KP_4
+    (84, 0x5d, 0x4c),    // KP_5
+    (85, 0x5e, 0x4d),    // KP_6
+    (86, 0x57, 0x4e),    // KP_Add
+    (87, 0x59, 0x4f),    // KP_1
+    (88, 0x5a, 0x50),    // KP_2
+    (89, 0x5b, 0x51),    // KP_3
+    (90, 0x62, 0x52),    // KP_0
+    (91, 0x63, 0x53),    // KP_Decimal
+    (94, 0x64, 0x56),    // less (ISO 102nd key)
+    (95, 0x44, 0x57),    // F11
+    (96, 0x45, 0x58),    // F12
+    (104, 0x58, 0xe01c), // KP_Enter
+    (105, 0xe4, 0xe01d), // Control_R
+    (106, 0x54, 0xe035), // KP_Divide
+    (107, 0x46, 0xe037), // Print
+    (108, 0xe6, 0xe038), // Alt_R
+    (110, 0x4a, 0xe047), // Home
+    (111, 0x52, 0xe048), // Up
+    (112, 0x4b, 0xe049), // Prior
+    (113, 0x50, 0xe04b), // Left
+    (114, 0x4f, 0xe04d), // Right
+    (115, 0x4d, 0xe04f), // End
+    (116, 0x51, 0xe050), // Down
+    (117, 0x4e, 0xe051), // Next
+    (118, 0x49, 0xe052), // Insert
+    (119, 0x4c, 0xe053), // Delete
+    (121, 0x7f, 0xe020), // XF86AudioMute
+    (122, 0x81, 0xe02e), // XF86AudioLowerVolume
+    (123, 0x80, 0xe030), // XF86AudioRaiseVolume
+    (125, 0x67, 0x59),   // KP_Equal
+    (133, 0xe3, 0xe05b), // Super_L
+    (134, 0xe7, 0xe05c), // Super_R
+    (135, 0x65, 0xe05d), // Menu
+];
+
+/// Convert an X keycode to a USB HID usage id
+pub fn keycode_to_hid(keycode: KeyCode) -> Option<HidUsage> {
+    KEY_CONVERSIONS
+        .iter()
+        .find(|(k, _, _)| *k == keycode)
+        .map(|(_, hid, _)| *hid)
+}
+
+/// Convert a USB HID usage id to an X keycode
+pub fn hid_to_keycode(hid: HidUsage) -> Option<KeyCode> {
+    KEY_CONVERSIONS
+        .iter()
+        .find(|(_, h, _)| *h == hid)
+        .map(|(keycode, _, _)| *keycode)
+}
+
+/// Convert an X keycode to a Windows scancode
+pub fn keycode_to_scancode(keycode: KeyCode) -> Option<ScanCode> {
+    KEY_CONVERSIONS
+        .iter()
+        .find(|(k, _, _)| *k == keycode)
+        .map(|(_, _, scancode)| *scancode)
+}
+
+/// Convert a Windows scancode to an X keycode
+pub fn scancode_to_keycode(scancode: ScanCode) -> Option<KeyCode> {
+    KEY_CONVERSIONS
+        .iter()
+        .find(|(_, _, s)| *s == scancode)
+        .map(|(keycode, _, _)| *keycode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (keycode, hid, scancode) in KEY_CONVERSIONS {
+            assert_eq!(hid_to_keycode(*hid), Some(*keycode));
+            assert_eq!(scancode_to_keycode(*scancode), Some(*keycode));
+        }
+    }
+
+    #[test]
+    fn extended_scancode() {
+        assert_eq!(keycode_to_scancode(111), Some(0xe048));
+        assert_eq!(keycode_to_hid(111), Some(0x52));
+    }
+}
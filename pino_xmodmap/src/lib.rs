@@ -1,21 +1,67 @@
 //! Tiny xmodmap parsing library
 //!
-//! ```rust
+//! ```rust,no_run
 //! use pino_xmodmap::{KeyTable, Modifier, KeySym};
 //!
+//! # #[cfg(feature = "process")]
 //! fn main() {
 //!     let xmodmap = KeyTable::new().unwrap();
 //!     let a_key = xmodmap.get_key(KeySym::KEY_a).unwrap();
 //! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
 //! ```
+//!
+//! Everything that runs xmodmap is behind the default `process` feature. Without it only the
+//! parsers, keysym tables and `*_with_runner` functions are built, so the crate can be used where
+//! `std::process` is unavailable, such as on wasm32.
 
 pub use std::str::FromStr;
-use std::{collections::HashMap, fmt, process::Command};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead},
+    sync::{Arc, OnceLock},
+};
+
+pub use pino_xcommon::{
+    backend,
+    capability::{Capabilities, Version},
+    capture,
+    display::DisplayHandle,
+    fallback::Source,
+    platform,
+    retry::RetryPolicy,
+    runner,
+    session::Session,
+};
+use pino_xcommon::{
+    intern::intern,
+    platform::Facility,
+    runner::{Cmd, CommandRunner},
+};
+
+pub mod export;
+pub mod expr;
+#[cfg(feature = "geometry")]
+pub mod geometry;
+pub mod grab;
+pub mod hid;
+pub mod lint;
+pub mod media;
+pub mod modmap;
+#[cfg(feature = "native")]
+mod native;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+pub mod term;
 
 /// Each possible modifier key combination
 ///
 /// These corresponds to each column in the .Xmodmap file
-#[derive(std::cmp::PartialEq, std::cmp::Eq, std::hash::Hash, Clone)]
+#[derive(Debug, std::cmp::PartialEq, std::cmp::Eq, std::hash::Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Modifier {
     Key,
     ShiftKey,
@@ -23,44 +69,414 @@ pub enum Modifier {
     ModeSwitchShiftKey,
     ISOLevel3ShiftKey,
     ISOLevel3ShiftShiftKey,
+    ISOLevel5ShiftKey,
+    ISOLevel5ShiftShiftKey,
+    ISOLevel3ISOLevel5ShiftKey,
+    ISOLevel3ISOLevel5ShiftShiftKey,
 }
 
+impl Modifier {
+    /// Number of modifier key combinations
+    const COUNT: usize = 10;
+
+    /// Position of the combination in the declaration order
+    fn index(&self) -> usize {
+        self.clone() as usize
+    }
+}
+
+/// Files in the home directory read by [KeyTable::new_or_fallback], in order of preference
+pub static FALLBACK_FILES: &[&str] = &[".Xmodmap", ".xmodmap"];
+
+/// Flags looked for in the usage of xmodmap by [KeyTable::capabilities]
+pub static CAPABILITY_FLAGS: &[&str] = &["-pke", "-pk", "-pm", "-e", "-n"];
+
+/// Default interpretation of the columns output by `xmodmap -pke`
+///
+/// Levels five and up (used by layouts such as Neo) follow the first four columns, this matches
+/// keys that only define a single group.
+///
+/// Columns that are `None` are not assigned a modifier, but are still available through
+/// [KeyTable::get_raw].
+pub static DEFAULT_COLUMNS: &[Option<Modifier>] = &[
+    Some(Modifier::Key),
+    Some(Modifier::ShiftKey),
+    Some(Modifier::ModeSwitchKey),
+    Some(Modifier::ModeSwitchShiftKey),
+    Some(Modifier::ISOLevel3ShiftKey),
+    Some(Modifier::ISOLevel3ShiftShiftKey),
+    Some(Modifier::ISOLevel5ShiftKey),
+    Some(Modifier::ISOLevel5ShiftShiftKey),
+    Some(Modifier::ISOLevel3ISOLevel5ShiftKey),
+    Some(Modifier::ISOLevel3ISOLevel5ShiftShiftKey),
+];
+
 /// Key code as referenced by xmodmap
 pub type KeyCode = u8;
 
 /// Combination of a modifier and a keycode
 pub type Key = (Modifier, KeyCode);
 
+/// Raw X modifier state mask, as used by key events and key grabs
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ModMask(pub u16);
+
+impl ModMask {
+    pub const NONE: ModMask = ModMask(0);
+    pub const SHIFT: ModMask = ModMask(1 << 0);
+    pub const LOCK: ModMask = ModMask(1 << 1);
+    pub const CONTROL: ModMask = ModMask(1 << 2);
+    pub const MOD1: ModMask = ModMask(1 << 3);
+    pub const MOD2: ModMask = ModMask(1 << 4);
+    pub const MOD3: ModMask = ModMask(1 << 5);
+    pub const MOD4: ModMask = ModMask(1 << 6);
+    pub const MOD5: ModMask = ModMask(1 << 7);
+
+    /// Check if every modifier in `other` is also set in this mask
+    pub fn contains(self, other: ModMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Each of the modifiers set in this mask
+    ///
+    /// ```rust
+    /// use pino_xmodmap::{ModIndex, ModMask};
+    ///
+    /// let mask = ModMask::SHIFT | ModMask::MOD4;
+    /// assert_eq!(mask.indices().collect::<Vec<_>>(), [ModIndex::Shift, ModIndex::Mod4]);
+    /// ```
+    pub fn indices(self) -> impl Iterator<Item = ModIndex> {
+        ModIndex::ALL
+            .into_iter()
+            .filter(move |index| self.contains(index.mask()))
+    }
+}
+
+/// One of the eight modifiers of the modifier map, as named in `clear`, `add` and `remove`
+/// statements
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ModIndex {
+    Shift,
+    Lock,
+    Control,
+    Mod1,
+    Mod2,
+    Mod3,
+    Mod4,
+    Mod5,
+}
+
+impl ModIndex {
+    /// Every modifier, in the order of the modifier map
+    pub const ALL: [ModIndex; 8] = [
+        ModIndex::Shift,
+        ModIndex::Lock,
+        ModIndex::Control,
+        ModIndex::Mod1,
+        ModIndex::Mod2,
+        ModIndex::Mod3,
+        ModIndex::Mod4,
+        ModIndex::Mod5,
+    ];
+
+    /// Mask with only this modifier set
+    pub fn mask(self) -> ModMask {
+        ModMask(1 << self as u16)
+    }
+}
+
+impl fmt::Display for ModIndex {
+    /// Name used by xmodmap
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ModIndex::Shift => "Shift",
+            ModIndex::Lock => "Lock",
+            ModIndex::Control => "Control",
+            ModIndex::Mod1 => "Mod1",
+            ModIndex::Mod2 => "Mod2",
+            ModIndex::Mod3 => "Mod3",
+            ModIndex::Mod4 => "Mod4",
+            ModIndex::Mod5 => "Mod5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ModIndex {
+    type Err = ();
+
+    /// From a modifier name, with the same aliases as [ModMask]
+    fn from_str(input: &str) -> Result<ModIndex, Self::Err> {
+        let mask = ModMask::from_str(input)?;
+        ModIndex::ALL
+            .into_iter()
+            .find(|index| index.mask() == mask)
+            .ok_or(())
+    }
+}
+
+impl std::ops::BitOr for ModMask {
+    type Output = ModMask;
+    fn bitor(self, rhs: ModMask) -> ModMask {
+        ModMask(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for ModMask {
+    fn bitor_assign(&mut self, rhs: ModMask) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl fmt::Display for ModMask {
+    /// From ModMask to the modifier names used by xmodmap, joined with `+`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = self
+            .indices()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", set.join("+"))
+    }
+}
+
+impl FromStr for ModMask {
+    type Err = ();
+
+    /// From modifier name (as used in hotkey configs) to ModMask
+    fn from_str(input: &str) -> Result<ModMask, Self::Err> {
+        match input.to_ascii_lowercase().as_str() {
+            "shift" => Ok(ModMask::SHIFT),
+            "lock" => Ok(ModMask::LOCK),
+            "control" | "ctrl" => Ok(ModMask::CONTROL),
+            "mod1" | "alt" => Ok(ModMask::MOD1),
+            "mod2" => Ok(ModMask::MOD2),
+            "mod3" => Ok(ModMask::MOD3),
+            "mod4" | "super" => Ok(ModMask::MOD4),
+            "mod5" => Ok(ModMask::MOD5),
+            _ => Err(()),
+        }
+    }
+}
+
 /// Master table of conversions between key and key sym
+///
+/// With the `serde` feature it is serialized as its columns and the keysyms of each keycode.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use pino_xmodmap::{KeySym, KeyTable, Modifier, DEFAULT_COLUMNS};
+///
+/// let table = KeyTable::parse("keycode  38 = a A", &DEFAULT_COLUMNS[..2]).unwrap();
+/// let json = serde_json::to_string(&table).unwrap();
+/// assert_eq!(json, r#"{"columns":["Key","ShiftKey"],"keys":{"38":["a","A"]}}"#);
+///
+/// let table = serde_json::from_str::<KeyTable>(&json).unwrap();
+/// assert_eq!(table.get_key(KeySym::KEY_A).unwrap(), (Modifier::ShiftKey, 38));
+/// # }
+/// ```
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "KeyTableDump", from = "KeyTableDump")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct KeyTable {
-    key_to_keysym: HashMap<Key, KeySym>,
-    keysym_to_key: HashMap<KeySym, Key>,
+    /// Keysyms of every keycode, `width` levels per keycode padded with `NoSymbol`
+    levels: Vec<KeySym>,
+    /// Number of levels stored for each keycode
+    width: usize,
+    /// Number of keysyms bound to each keycode, `None` if it is not in the table
+    lens: Vec<Option<usize>>,
+    /// Reverse index from keysym to its first (column, keycode)
+    keysym_to_key: HashMap<KeySym, (u8, KeyCode)>,
+    /// Modifier each column is interpreted as
+    columns: Vec<Option<Modifier>>,
+    /// Column of each modifier, indexed by [Modifier::index]
+    column_of: [Option<u8>; Modifier::COUNT],
+}
+
+/// Serialized form of [KeyTable], without the reverse index
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct KeyTableDump {
+    columns: Vec<Option<Modifier>>,
+    keys: std::collections::BTreeMap<KeyCode, Vec<KeySym>>,
+}
+
+#[cfg(feature = "serde")]
+impl From<KeyTable> for KeyTableDump {
+    fn from(table: KeyTable) -> Self {
+        KeyTableDump {
+            keys: table
+                .iter_keys()
+                .map(|(code, syms)| (code, syms.to_vec()))
+                .collect(),
+            columns: table.columns,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<KeyTableDump> for KeyTable {
+    fn from(dump: KeyTableDump) -> Self {
+        KeyTable::from_keys(dump.keys.into_iter().collect(), &dump.columns)
+    }
 }
 
 /// Xmodmap related errors
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// Missing xmodmap executable
-    XmodmapRunError,
-    /// Xmodmap file was malformed
-    InvalidFormat,
+    /// Program (`xmodmap` or `xkbcomp`) could not be run
+    Spawn {
+        program: &'static str,
+        source: io::Error,
+    },
+    /// Program exited with a non-zero status
+    Exited {
+        program: &'static str,
+        status: i32,
+        stderr: String,
+    },
+    /// Program did not finish within its timeout and was killed
+    TimedOut { program: &'static str },
+    /// The installed program or the platform does not support an argument or operation
+    Unsupported {
+        program: &'static str,
+        feature: String,
+    },
+    /// Output could not be read
+    Read(io::Error),
+    /// Request to the X server failed, with the `native` feature
+    Connection(String),
+    /// Input was malformed, `line` is the 1-based line number when parsing multiple lines
+    InvalidFormat {
+        line: Option<usize>,
+        content: String,
+    },
     /// Key code does not exist
-    NonExistentKeyCode,
-    /// Key sym does not exist
-    NonExistentKeySym,
+    NonExistentKeyCode(KeyCode),
+    /// Key sym does not exist, holding the name that was looked up
+    NonExistentKeySym(String),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Spawn { source, .. } => Some(source),
+            Error::Read(source) => Some(source),
+            _ => None,
+        }
+    }
 }
 
-impl std::error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::XmodmapRunError => write!(
+            Error::Spawn { program, source } => write!(
                 f,
-                "could not run xmodmap command, do you have it installed?"
+                "could not run {} command, do you have it installed? ({})",
+                program, source
             ),
-            Error::InvalidFormat => write!(f, "invalid xmodmap format"),
-            Error::NonExistentKeyCode => write!(f, "non-existent keycode"),
-            Error::NonExistentKeySym => write!(f, "non-existent keysym"),
+            Error::Exited {
+                program,
+                status,
+                stderr,
+            } => write!(
+                f,
+                "{} exited with status {}: {}",
+                program,
+                status,
+                stderr.trim()
+            ),
+            Error::TimedOut { program } => write!(f, "{} timed out", program),
+            Error::Unsupported { program, feature } => {
+                write!(f, "{}: {} is not supported", program, feature)
+            },
+            Error::Read(e) => write!(f, "could not read output: {}", e),
+            Error::Connection(e) => write!(f, "X server request failed: {}", e),
+            Error::InvalidFormat {
+                line: Some(line),
+                content,
+            } => write!(f, "invalid xmodmap format on line {}: {}", line, content),
+            Error::InvalidFormat {
+                line: None,
+                content,
+            } => write!(f, "invalid xmodmap format: {}", content),
+            Error::NonExistentKeyCode(code) => write!(f, "non-existent keycode {}", code),
+            Error::NonExistentKeySym(name) => write!(f, "non-existent keysym {}", name),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+        }
+    }
+}
+
+impl Error {
+    /// Map a failure to run `program` to [Error::TimedOut] if it timed out, or to [Error::Spawn]
+    pub(crate) fn run(program: &'static str) -> impl Fn(io::Error) -> Error {
+        move |source| match source.kind() {
+            io::ErrorKind::TimedOut => Error::TimedOut { program },
+            io::ErrorKind::Unsupported => Error::Unsupported {
+                program,
+                feature: format!("{} on {}", Facility::Process, platform::OS),
+            },
+            _ => Error::Spawn { program, source },
+        }
+    }
+
+    /// Error for a run of `program` that exited with a non-zero status
+    ///
+    /// Older versions reject arguments they do not know with a usage message, which is reported
+    /// as [Error::Unsupported].
+    pub(crate) fn exited(program: &'static str, output: &runner::Output) -> Error {
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let unrecognized = stderr.lines().find_map(|l| {
+            let (_, arg) = l.split_once("unrecognized argument")?;
+            Some(arg.trim().to_owned())
+        });
+        match unrecognized {
+            Some(feature) => Error::Unsupported { program, feature },
+            None => Error::Exited {
+                program,
+                status: output.status,
+                stderr,
+            },
+        }
+    }
+
+    /// Neither the native backend nor the binaries are compiled in, or both were disabled
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub(crate) fn no_backend() -> Error {
+        Error::Connection(String::from("no backend available"))
+    }
+
+    /// Malformed input, without a line number
+    pub(crate) fn invalid(content: impl Into<String>) -> Error {
+        Error::InvalidFormat {
+            line: None,
+            content: content.into(),
+        }
+    }
+
+    /// Attach the 1-based line number to an [Error::InvalidFormat] that lacks one
+    pub(crate) fn at_line(self, number: usize) -> Error {
+        match self {
+            Error::InvalidFormat {
+                line: None,
+                content,
+            } => Error::InvalidFormat {
+                line: Some(number),
+                content,
+            },
+            e => e,
         }
     }
 }
@@ -68,59 +484,442 @@ impl fmt::Display for Error {
 impl KeyTable {
     // requires that user has xmodmap program installed
     /// Reads from xmodmap file and populates keytable
+    #[cfg(any(feature = "process", feature = "native"))]
     pub fn new() -> Result<Self, Error> {
-        let mut key_to_keysym: HashMap<Key, KeySym> = HashMap::new();
-        let mut keysym_to_key: HashMap<KeySym, Key> = HashMap::new();
-
-        let output = Command::new("xmodmap")
-            .arg("-pke")
-            .output()
-            .or(Err(Error::XmodmapRunError))?;
-        let raw_xmodmap = String::from_utf8(output.stdout).or(Err(Error::XmodmapRunError))?;
-
-        for l in raw_xmodmap.lines() {
-            let mut split = l.split_ascii_whitespace();
-
-            assert_eq!(Some("keycode"), split.next());
-            let keycode = split
-                .next()
-                .ok_or(Error::InvalidFormat)?
-                .parse::<u8>()
-                .or(Err(Error::InvalidFormat))?;
-            assert_eq!(Some("="), split.next());
-
-            // TODO handle case where next() fails in a better way
-            let a = KeySym::from_str(split.next().unwrap_or("")).unwrap_or(KeySym::KEY_NONE);
-            let b = KeySym::from_str(split.next().unwrap_or("")).unwrap_or(KeySym::KEY_NONE);
-            key_to_keysym.insert((Modifier::Key, keycode), a.clone());
-            keysym_to_key.insert(a, (Modifier::Key, keycode));
-            key_to_keysym.insert((Modifier::ShiftKey, keycode), b.clone());
-            keysym_to_key.insert(b, (Modifier::Key, keycode));
-        }
-
-        Ok(KeyTable {
-            key_to_keysym,
-            keysym_to_key,
+        Self::with_columns(DEFAULT_COLUMNS)
+    }
+
+    /// Reads from xmodmap, interpreting each column as the given modifier
+    ///
+    /// Useful when the default column interpretation does not match the XKB key types in use. With
+    /// the `native` feature the mapping is requested from the X server directly instead, falling
+    /// back to xmodmap if that fails and the `process` feature is enabled as well, see
+    /// [pino_xcommon::backend].
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn with_columns(columns: &[Option<Modifier>]) -> Result<Self, Error> {
+        Self::with_display(&DisplayHandle::default(), columns)
+    }
+
+    /// Reads the keytable of the given display, interpreting each column as the given modifier
+    ///
+    /// ```rust,no_run
+    /// # use pino_xmodmap::{DisplayHandle, KeyTable, DEFAULT_COLUMNS};
+    /// let display = DisplayHandle::new().display(":1");
+    /// let table = KeyTable::with_display(&display, DEFAULT_COLUMNS).unwrap();
+    /// ```
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn with_display(
+        display: &DisplayHandle,
+        columns: &[Option<Modifier>],
+    ) -> Result<Self, Error> {
+        use pino_xcommon::backend::{dispatch, Backend};
+
+        pino_xcommon::session::require_x(display).map_err(Error::NoXServer)?;
+        dispatch(|backend| match backend {
+            #[cfg(feature = "native")]
+            Backend::Native => Some(
+                native::keyboard_mapping(display).map(|keys| KeyTable::from_keys(keys, columns)),
+            ),
+            #[cfg(feature = "process")]
+            Backend::Binary => Some(Self::with_runner(
+                &display.runner(&runner::SystemRunner::default()),
+                columns,
+            )),
+            _ => None,
         })
+        .unwrap_or_else(|| Err(Error::no_backend()))
+    }
+
+    /// Read from xmodmap, or from `~/.Xmodmap` if that fails
+    ///
+    /// The first of [FALLBACK_FILES] that exists is read with [KeyTable::from_expressions] when
+    /// `$DISPLAY` is unset or xmodmap fails, so tools keep working in a TTY, over SSH or in CI.
+    /// Such a table only holds the keycodes the file binds. The error of reading xmodmap is
+    /// returned if none of the files exist.
+    ///
+    /// ```rust,no_run
+    /// # use pino_xmodmap::{KeyTable, Source};
+    /// let (table, source) = KeyTable::new_or_fallback().unwrap();
+    /// if source != Source::Server {
+    ///     println!("X server unavailable, only remapped keys are known");
+    /// }
+    /// ```
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn new_or_fallback() -> Result<(Self, Source), Error> {
+        let err = if pino_xcommon::fallback::has_display() {
+            match Self::new() {
+                Ok(table) => return Ok((table, Source::Server)),
+                Err(e) => e,
+            }
+        } else {
+            Error::Connection(String::from("DISPLAY is not set"))
+        };
+
+        let path = pino_xcommon::fallback::home_file(FALLBACK_FILES).ok_or(err)?;
+        let raw = std::fs::read_to_string(&path).map_err(Error::Read)?;
+        let exprs = expr::parse_file(&raw)?;
+        Ok((
+            Self::from_expressions(&exprs, DEFAULT_COLUMNS),
+            Source::File(path),
+        ))
+    }
+
+    /// Query xmodmap through the given runner, naming each column of its output
+    pub fn with_runner(
+        runner: &dyn CommandRunner,
+        columns: &[Option<Modifier>],
+    ) -> Result<Self, Error> {
+        let mut process = runner
+            .spawn(&Cmd::new("xmodmap").arg("-pke"))
+            .map_err(Error::run("xmodmap"))?;
+
+        // parse as the output is produced, bailing out on the first bad line
+        let table = match Self::from_reader(process.stdout(), columns) {
+            Ok(table) => table,
+            Err(e) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(e);
+            },
+        };
+
+        let output = process.wait().map_err(Error::run("xmodmap"))?;
+        if !output.success() {
+            return Err(Error::exited("xmodmap", &output));
+        }
+
+        Ok(table)
+    }
+
+    /// Version and supported flags of the installed xmodmap
+    ///
+    /// ```rust,no_run
+    /// # use pino_xmodmap::KeyTable;
+    /// let caps = KeyTable::capabilities();
+    /// println!("{}", caps);
+    /// ```
+    #[cfg(feature = "process")]
+    pub fn capabilities() -> Capabilities {
+        Self::capabilities_with_runner(&runner::SystemRunner::default())
+    }
+
+    /// Version and supported flags of xmodmap, running it through the given runner
+    pub fn capabilities_with_runner(runner: &dyn CommandRunner) -> Capabilities {
+        pino_xcommon::capability::probe(runner, "xmodmap", CAPABILITY_FLAGS)
+    }
+
+    /// Populate keytable from the output of `xmodmap -pke`
+    ///
+    /// ```rust
+    /// # use pino_xmodmap::{KeyTable, KeySym, Modifier, DEFAULT_COLUMNS};
+    /// let table = KeyTable::parse("keycode  38 = a A a A", DEFAULT_COLUMNS).unwrap();
+    ///
+    /// assert_eq!(table.get_keysym(Modifier::ShiftKey, 38).unwrap(), KeySym::KEY_A);
+    /// assert_eq!(table.get_key(KeySym::KEY_A).unwrap(), (Modifier::ShiftKey, 38));
+    /// ```
+    pub fn parse(raw_xmodmap: &str, columns: &[Option<Modifier>]) -> Result<Self, Error> {
+        Self::from_reader(raw_xmodmap.as_bytes(), columns)
+    }
+
+    /// Populate keytable from a reader producing the output of `xmodmap -pke`
+    ///
+    /// Lines are parsed one at a time, so the output never has to be buffered in full and
+    /// errors are reported as soon as a malformed line is read.
+    pub fn from_reader<R: BufRead>(
+        mut reader: R,
+        columns: &[Option<Modifier>],
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse_keytable").entered();
+        let mut table = KeyTable::empty(columns);
+
+        let mut line = String::new();
+        for number in 1.. {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(Error::Read)?;
+            if read == 0 {
+                break;
+            }
+            table.insert_line(&line).map_err(|e| e.at_line(number))?;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(keycodes = table.iter_keys().count(), "parsed");
+
+        Ok(table)
+    }
+
+    /// Table without any keycode, reading its levels as the given columns
+    fn empty(columns: &[Option<Modifier>]) -> Self {
+        let keycodes = KeyCode::MAX as usize + 1;
+        let mut column_of = [None; Modifier::COUNT];
+        for (column, modifier) in columns.iter().enumerate().rev() {
+            if let Some(modifier) = modifier {
+                column_of[modifier.index()] = Some(column as u8);
+            }
+        }
+        KeyTable {
+            levels: vec![KeySym::KEY_NONE; keycodes * columns.len()],
+            width: columns.len(),
+            lens: vec![None; keycodes],
+            keysym_to_key: HashMap::new(),
+            columns: columns.to_vec(),
+            column_of,
+        }
+    }
+
+    /// Widen every keycode to `width` levels, keeping the keysyms already stored
+    fn widen(&mut self, width: usize) {
+        let mut levels = vec![KeySym::KEY_NONE; self.lens.len() * width];
+        for (keycode, len) in self.lens.iter().enumerate() {
+            let Some(len) = len else { continue };
+            let old = keycode * self.width;
+            levels[keycode * width..][..*len].clone_from_slice(&self.levels[old..old + len]);
+        }
+        self.levels = levels;
+        self.width = width;
+    }
+
+    #[cfg(any(feature = "native", feature = "serde"))]
+    fn from_keys(keys: Vec<(KeyCode, Vec<KeySym>)>, columns: &[Option<Modifier>]) -> Self {
+        let mut table = KeyTable::empty(columns);
+        for (keycode, syms) in keys {
+            table.insert_syms(keycode, syms);
+        }
+        table
+    }
+
+    /// Parse a single line of `xmodmap -pke` output into the table
+    fn insert_line(&mut self, l: &str) -> Result<(), Error> {
+        let invalid = || Error::invalid(l.trim_end());
+        let mut split = l.split_ascii_whitespace();
+
+        if split.next() != Some("keycode") {
+            return Err(invalid());
+        }
+        let keycode = split
+            .next()
+            .ok_or_else(invalid)?
+            .parse::<u8>()
+            .map_err(|_| invalid())?;
+        if split.next() != Some("=") {
+            return Err(invalid());
+        }
+
+        let syms = split
+            .map(|s| KeySym::from_str(s).unwrap_or(KeySym::KEY_NONE))
+            .collect::<Vec<_>>();
+        self.insert_syms(keycode, syms);
+
+        Ok(())
+    }
+
+    fn insert_syms(&mut self, keycode: KeyCode, syms: Vec<KeySym>) {
+        // keysyms indexed at the old binding move to the next keycode still bound to them
+        let stale = self
+            .syms(keycode)
+            .unwrap_or_default()
+            .iter()
+            .filter(
+                |sym| matches!(self.keysym_to_key.get(*sym), Some((_, code)) if *code == keycode),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        for sym in stale {
+            self.keysym_to_key.remove(&sym);
+            if let Some(key) = self.find_key(&sym, keycode) {
+                self.keysym_to_key.insert(sym, key);
+            }
+        }
+        for (column, (modifier, sym)) in self.columns.iter().zip(syms.iter()).enumerate() {
+            // NoSymbol fillers are gaps, not mappings
+            if modifier.is_none() || *sym == KeySym::KEY_NONE {
+                continue;
+            }
+            self.keysym_to_key
+                .entry(sym.clone())
+                .or_insert((column as u8, keycode));
+        }
+        if syms.len() > self.width {
+            self.widen(syms.len());
+        }
+        let start = keycode as usize * self.width;
+        let levels = &mut self.levels[start..start + self.width];
+        levels.fill(KeySym::KEY_NONE);
+        self.lens[keycode as usize] = Some(syms.len());
+        for (level, sym) in levels.iter_mut().zip(syms) {
+            *level = sym;
+        }
+    }
+
+    /// First column and keycode other than `skip` the keysym is bound to
+    fn find_key(&self, sym: &KeySym, skip: KeyCode) -> Option<(u8, KeyCode)> {
+        self.iter_keys()
+            .filter(|(keycode, _)| *keycode != skip)
+            .find_map(|(keycode, syms)| {
+                let column = syms
+                    .iter()
+                    .zip(&self.columns)
+                    .position(|(s, modifier)| modifier.is_some() && s == sym)?;
+                Some((column as u8, keycode))
+            })
+    }
+
+    /// Keysyms bound to a keycode, `None` if it is not in the table
+    fn syms(&self, code: KeyCode) -> Option<&[KeySym]> {
+        let len = self.lens[code as usize]?;
+        Some(&self.levels[code as usize * self.width..][..len])
+    }
+
+    /// Column the modifier is interpreted from
+    fn column(&self, modifier: &Modifier) -> Option<usize> {
+        self.column_of[modifier.index()].map(usize::from)
+    }
+
+    /// Every keycode present in the table along with its keysyms, in ascending keycode order
+    pub fn iter_keys(&self) -> impl Iterator<Item = (KeyCode, &[KeySym])> {
+        (0..=KeyCode::MAX).filter_map(|keycode| Some((keycode, self.syms(keycode)?)))
+    }
+
+    /// Check if a keycode has any keysym bound to it
+    ///
+    /// Keycodes that are blank (`keycode N =`) or only bound to `NoSymbol` are not mapped.
+    pub fn is_mapped(&self, code: KeyCode) -> bool {
+        self.syms(code)
+            .map(|syms| syms.iter().any(|sym| *sym != KeySym::KEY_NONE))
+            .unwrap_or(false)
     }
 
     /// Query a keysym
+    ///
+    /// Columns holding `NoSymbol` are treated as not existing.
     pub fn get_keysym(&self, modifier: Modifier, code: KeyCode) -> Result<KeySym, Error> {
-        match self.key_to_keysym.get(&(modifier, code)) {
-            Some(k) => Ok(k.clone()),
-            None => Err(Error::NonExistentKeyCode),
-        }
+        let column = self
+            .column(&modifier)
+            .ok_or(Error::NonExistentKeyCode(code))?;
+        self.syms(code)
+            .and_then(|syms| syms.get(column))
+            .filter(|sym| **sym != KeySym::KEY_NONE)
+            .cloned()
+            .ok_or(Error::NonExistentKeyCode(code))
     }
 
     /// Query a keycode
     pub fn get_key(&self, keysym: KeySym) -> Result<Key, Error> {
         match self.keysym_to_key.get(&keysym) {
-            Some(k) => Ok(k.clone()),
-            None => Err(Error::NonExistentKeySym),
+            Some((column, code)) => {
+                let modifier = self.columns[*column as usize].clone();
+                Ok((
+                    modifier.ok_or_else(|| Error::NonExistentKeySym(keysym.to_string()))?,
+                    *code,
+                ))
+            },
+            None => Err(Error::NonExistentKeySym(keysym.to_string())),
+        }
+    }
+
+    /// Every keycode that has the keysym bound in any column, in ascending order
+    pub fn get_keycodes(&self, keysym: &KeySym) -> Vec<KeyCode> {
+        self.iter_keys()
+            .filter(|(_, syms)| syms.contains(keysym))
+            .map(|(keycode, _)| keycode)
+            .collect()
+    }
+
+    /// Every keysym bound to a keycode, in the column order output by xmodmap
+    pub fn get_raw(&self, code: KeyCode) -> Result<&[KeySym], Error> {
+        self.syms(code).ok_or(Error::NonExistentKeyCode(code))
+    }
+
+    /// Keysym a keycode produces under a raw modifier state
+    ///
+    /// Follows the keysym selection rules of the core protocol: the group is taken from bits 13-14
+    /// of the state and wraps around when the key has a single group, `Lock` acts as CapsLock and
+    /// `Mod2` as NumLock for keypad keys. `Mod5` selects the ISO level 3 columns and `Mod3` the ISO
+    /// level 5 columns, falling back to the group columns when those are empty.
+    ///
+    /// ```rust
+    /// use pino_xmodmap::{KeySym, KeyTable, ModMask, DEFAULT_COLUMNS};
+    ///
+    /// let table = KeyTable::parse("keycode  38 = a A", DEFAULT_COLUMNS).unwrap();
+    /// assert_eq!(table.resolve(38, ModMask::LOCK).unwrap(), KeySym::KEY_A);
+    /// ```
+    pub fn resolve(&self, code: KeyCode, state: ModMask) -> Result<KeySym, Error> {
+        let syms = self.get_raw(code)?;
+        let column = |modifier: Modifier| {
+            self.column(&modifier)
+                .and_then(|i| syms.get(i))
+                .cloned()
+                .unwrap_or(KeySym::KEY_NONE)
+        };
+
+        let has_group2 = column(Modifier::ModeSwitchKey) != KeySym::KEY_NONE
+            || column(Modifier::ModeSwitchShiftKey) != KeySym::KEY_NONE;
+        let group2 = has_group2 && (state.0 >> 13) % 2 == 1;
+        let mut pair = match (state.contains(ModMask::MOD5), state.contains(ModMask::MOD3)) {
+            (true, false) => (
+                column(Modifier::ISOLevel3ShiftKey),
+                column(Modifier::ISOLevel3ShiftShiftKey),
+            ),
+            (false, true) => (
+                column(Modifier::ISOLevel5ShiftKey),
+                column(Modifier::ISOLevel5ShiftShiftKey),
+            ),
+            (true, true) => (
+                column(Modifier::ISOLevel3ISOLevel5ShiftKey),
+                column(Modifier::ISOLevel3ISOLevel5ShiftShiftKey),
+            ),
+            (false, false) => (KeySym::KEY_NONE, KeySym::KEY_NONE),
+        };
+        if pair == (KeySym::KEY_NONE, KeySym::KEY_NONE) {
+            pair = if group2 {
+                (
+                    column(Modifier::ModeSwitchKey),
+                    column(Modifier::ModeSwitchShiftKey),
+                )
+            } else {
+                (column(Modifier::Key), column(Modifier::ShiftKey))
+            };
+        }
+
+        // a single keysym stands for its lower and upper case
+        let (lower, upper) = match pair {
+            (lower, KeySym::KEY_NONE) => (lower.clone(), upper_case(&lower)),
+            pair => pair,
+        };
+
+        let shift = state.contains(ModMask::SHIFT);
+        let lock = state.contains(ModMask::LOCK);
+        let sym = if state.contains(ModMask::MOD2) && upper.is_keypad() {
+            if shift {
+                lower
+            } else {
+                upper
+            }
+        } else {
+            match (shift, lock) {
+                (false, false) => lower,
+                (false, true) => upper_case(&lower),
+                (true, false) => upper,
+                (true, true) => upper_case(&upper),
+            }
+        };
+
+        match sym {
+            KeySym::KEY_NONE => Err(Error::NonExistentKeySym(sym.to_string())),
+            sym => Ok(sym),
         }
     }
 }
 
+/// Upper case of a lower case letter, other keysyms are returned as is
+fn upper_case(sym: &KeySym) -> KeySym {
+    ALL_LOWER_CASE
+        .iter()
+        .position(|lower| lower == sym)
+        .map(|i| ALL_UPPER_CASE[i].clone())
+        .unwrap_or_else(|| sym.clone())
+}
+
 /// Each lower case key sym
 pub static ALL_LOWER_CASE: &[KeySym] = &[
     KeySym::KEY_a,
@@ -181,6 +980,159 @@ pub static ALL_UPPER_CASE: &[KeySym] = &[
     KeySym::KEY_Z,
 ];
 
+/// Each digit key sym
+pub static ALL_DIGITS: &[KeySym] = &[
+    KeySym::KEY_0,
+    KeySym::KEY_1,
+    KeySym::KEY_2,
+    KeySym::KEY_3,
+    KeySym::KEY_4,
+    KeySym::KEY_5,
+    KeySym::KEY_6,
+    KeySym::KEY_7,
+    KeySym::KEY_8,
+    KeySym::KEY_9,
+];
+
+/// Each function key sym
+pub static ALL_FUNCTION_KEYS: &[KeySym] = &[
+    KeySym::KEY_F1,
+    KeySym::KEY_F2,
+    KeySym::KEY_F3,
+    KeySym::KEY_F4,
+    KeySym::KEY_F5,
+    KeySym::KEY_F6,
+    KeySym::KEY_F7,
+    KeySym::KEY_F8,
+    KeySym::KEY_F9,
+    KeySym::KEY_F10,
+    KeySym::KEY_F11,
+    KeySym::KEY_F12,
+];
+
+/// Each punctuation key sym, in ASCII order
+pub static ALL_PUNCTUATION: &[KeySym] = &[
+    KeySym::KEY_EXCLAMATION,
+    KeySym::KEY_DOUBLEQUOTE,
+    KeySym::KEY_NUMBERSIGN,
+    KeySym::KEY_DOLLAR,
+    KeySym::KEY_PERCENT,
+    KeySym::KEY_AMPERSAND,
+    KeySym::KEY_APOSTROPHE,
+    KeySym::KEY_PARENLEFT,
+    KeySym::KEY_PARENRIGHT,
+    KeySym::KEY_ASTERISK,
+    KeySym::KEY_PLUS,
+    KeySym::KEY_COMMA,
+    KeySym::KEY_MINUS,
+    KeySym::KEY_PERIOD,
+    KeySym::KEY_SLASH,
+    KeySym::KEY_COLON,
+    KeySym::KEY_SEMICOLON,
+    KeySym::KEY_LESS,
+    KeySym::KEY_EQUAL,
+    KeySym::KEY_GREATER,
+    KeySym::KEY_QUESTION,
+    KeySym::KEY_AT,
+    KeySym::KEY_BRACKETLEFT,
+    KeySym::KEY_BACKSLASH,
+    KeySym::KEY_BRACKETRIGHT,
+    KeySym::KEY_CIRCUM,
+    KeySym::KEY_UNDERSCORE,
+    KeySym::KEY_GRAVE,
+    KeySym::KEY_BRACELEFT,
+    KeySym::KEY_BAR,
+    KeySym::KEY_BRACERIGHT,
+    KeySym::KEY_TILDE,
+];
+
+/// Each modifier key sym
+pub static ALL_MODIFIERS: &[KeySym] = &[
+    KeySym::KEY_SHIFT_L,
+    KeySym::KEY_SHIFT_R,
+    KeySym::KEY_CONTROL_L,
+    KeySym::KEY_CONTROL_R,
+    KeySym::KEY_CAPS_LOCK,
+    KeySym::KEY_SHIFT_LOCK,
+    KeySym::KEY_META_L,
+    KeySym::KEY_META_R,
+    KeySym::KEY_ALT_L,
+    KeySym::KEY_ALT_R,
+    KeySym::KEY_SUPER_L,
+    KeySym::KEY_SUPER_R,
+    KeySym::KEY_HYPER_L,
+    KeySym::KEY_HYPER_R,
+    KeySym::KEY_NUM_LOCK,
+    KeySym::KEY_MODE_SWITCH,
+    KeySym::KEY_ISO_LEVEL3_SHIFT,
+    KeySym::KEY_ISO_LEVEL5_SHIFT,
+];
+
+/// Names of the keysyms that act as modifier keys, in the order of [ALL_MODIFIERS]
+pub static MODIFIER_NAMES: &[&str] = &[
+    "Shift_L",
+    "Shift_R",
+    "Control_L",
+    "Control_R",
+    "Caps_Lock",
+    "Shift_Lock",
+    "Meta_L",
+    "Meta_R",
+    "Alt_L",
+    "Alt_R",
+    "Super_L",
+    "Super_R",
+    "Hyper_L",
+    "Hyper_R",
+    "Num_Lock",
+    "Mode_switch",
+    "ISO_Level3_Shift",
+    "ISO_Level5_Shift",
+];
+
+/// Key syms not covered by any of the other sets
+static ALL_SPECIAL: &[KeySym] = &[
+    KeySym::KEY_SPACE,
+    KeySym::KEY_RETURN,
+    KeySym::KEY_BACKSPACE,
+    KeySym::KEY_TAB,
+    KeySym::KEY_ESCAPE,
+    KeySym::KEY_HOME,
+    KeySym::KEY_END,
+    KeySym::KEY_PRIOR,
+    KeySym::KEY_NEXT,
+    KeySym::KEY_INSERT,
+    KeySym::KEY_DELETE,
+    KeySym::KEY_LEFT,
+    KeySym::KEY_RIGHT,
+    KeySym::KEY_UP,
+    KeySym::KEY_DOWN,
+];
+
+/// Historical keysym names, and the canonical name they are an alias of
+pub static KEYSYM_ALIASES: &[(&str, &str)] = &[
+    ("Page_Up", "Prior"),
+    ("Page_Down", "Next"),
+    ("quoteleft", "grave"),
+    ("quoteright", "apostrophe"),
+    ("script_switch", "Mode_switch"),
+];
+
+/// Resolve a keysym name to its canonical name, names without an alias are returned unchanged
+///
+/// ```rust
+/// # use pino_xmodmap::canonical_name;
+/// assert_eq!(canonical_name("Page_Up"), "Prior");
+/// assert_eq!(canonical_name("Prior"), "Prior");
+/// ```
+pub fn canonical_name(name: &str) -> &str {
+    KEYSYM_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, canonical)| *canonical)
+        .unwrap_or(name)
+}
+
 /// Enum for each possible key sym
 #[allow(non_camel_case_types)]
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -297,14 +1249,47 @@ pub enum KeySym {
     KEY_F10,
     KEY_F11,
     KEY_F12,
+    KEY_HOME,
+    KEY_END,
+    KEY_PRIOR,
+    KEY_NEXT,
+    KEY_INSERT,
+    KEY_DELETE,
+    KEY_LEFT,
+    KEY_RIGHT,
+    KEY_UP,
+    KEY_DOWN,
+    KEY_SHIFT_L,
+    KEY_SHIFT_R,
+    KEY_CONTROL_L,
+    KEY_CONTROL_R,
+    KEY_CAPS_LOCK,
+    KEY_SHIFT_LOCK,
+    KEY_META_L,
+    KEY_META_R,
+    KEY_ALT_L,
+    KEY_ALT_R,
+    KEY_SUPER_L,
+    KEY_SUPER_R,
+    KEY_HYPER_L,
+    KEY_HYPER_R,
+    KEY_NUM_LOCK,
+    KEY_MODE_SWITCH,
+    KEY_ISO_LEVEL3_SHIFT,
+    KEY_ISO_LEVEL5_SHIFT,
+    /// Keysym not known to this library, stored by its interned name
+    Other(Arc<str>),
 }
 
 impl FromStr for KeySym {
     type Err = ();
 
     /// From Xmodmap entry to KeySym
+    ///
+    /// Historical aliases (see [KEYSYM_ALIASES]) are accepted as well. Names that are not known
+    /// are preserved as [KeySym::Other].
     fn from_str(input: &str) -> Result<KeySym, Self::Err> {
-        match input {
+        match canonical_name(input) {
             "a" => Ok(KeySym::KEY_a),
             "b" => Ok(KeySym::KEY_b),
             "c" => Ok(KeySym::KEY_c),
@@ -417,11 +1402,358 @@ impl FromStr for KeySym {
             "F10" => Ok(KeySym::KEY_F10),
             "F11" => Ok(KeySym::KEY_F11),
             "F12" => Ok(KeySym::KEY_F12),
-            _ => Err(()),
+            "Home" => Ok(KeySym::KEY_HOME),
+            "End" => Ok(KeySym::KEY_END),
+            "Prior" => Ok(KeySym::KEY_PRIOR),
+            "Next" => Ok(KeySym::KEY_NEXT),
+            "Insert" => Ok(KeySym::KEY_INSERT),
+            "Delete" => Ok(KeySym::KEY_DELETE),
+            "Left" => Ok(KeySym::KEY_LEFT),
+            "Right" => Ok(KeySym::KEY_RIGHT),
+            "Up" => Ok(KeySym::KEY_UP),
+            "Down" => Ok(KeySym::KEY_DOWN),
+            "Shift_L" => Ok(KeySym::KEY_SHIFT_L),
+            "Shift_R" => Ok(KeySym::KEY_SHIFT_R),
+            "Control_L" => Ok(KeySym::KEY_CONTROL_L),
+            "Control_R" => Ok(KeySym::KEY_CONTROL_R),
+            "Caps_Lock" => Ok(KeySym::KEY_CAPS_LOCK),
+            "Shift_Lock" => Ok(KeySym::KEY_SHIFT_LOCK),
+            "Meta_L" => Ok(KeySym::KEY_META_L),
+            "Meta_R" => Ok(KeySym::KEY_META_R),
+            "Alt_L" => Ok(KeySym::KEY_ALT_L),
+            "Alt_R" => Ok(KeySym::KEY_ALT_R),
+            "Super_L" => Ok(KeySym::KEY_SUPER_L),
+            "Super_R" => Ok(KeySym::KEY_SUPER_R),
+            "Hyper_L" => Ok(KeySym::KEY_HYPER_L),
+            "Hyper_R" => Ok(KeySym::KEY_HYPER_R),
+            "Num_Lock" => Ok(KeySym::KEY_NUM_LOCK),
+            "Mode_switch" => Ok(KeySym::KEY_MODE_SWITCH),
+            "ISO_Level3_Shift" => Ok(KeySym::KEY_ISO_LEVEL3_SHIFT),
+            "ISO_Level5_Shift" => Ok(KeySym::KEY_ISO_LEVEL5_SHIFT),
+            "" => Err(()),
+            name if name.contains(char::is_whitespace) => Err(()),
+            name => Ok(KeySym::Other(intern(name))),
+        }
+    }
+}
+
+impl KeySym {
+    /// Every known key sym, ordered by keysym value
+    ///
+    /// Neither [KeySym::KEY_NONE] nor [KeySym::Other] are included.
+    pub fn all() -> impl Iterator<Item = KeySym> {
+        KeySym::by_code().iter().map(|(_, sym)| sym.clone())
+    }
+
+    /// Every known key sym with its keysym value, sorted by value and built on first use
+    fn by_code() -> &'static [(u32, KeySym)] {
+        static BY_CODE: OnceLock<Vec<(u32, KeySym)>> = OnceLock::new();
+        BY_CODE.get_or_init(|| {
+            let mut all = ALL_LOWER_CASE
+                .iter()
+                .chain(ALL_UPPER_CASE)
+                .chain(ALL_DIGITS)
+                .chain(ALL_FUNCTION_KEYS)
+                .chain(ALL_PUNCTUATION)
+                .chain(ALL_MODIFIERS)
+                .chain(ALL_SPECIAL)
+                .filter_map(|sym| Some((sym.code()?, sym.clone())))
+                .collect::<Vec<_>>();
+            all.sort_by_key(|(code, _)| *code);
+            all
+        })
+    }
+
+    /// Key sym with the given X11 keysym value
+    ///
+    /// Values without a known name are kept as [KeySym::Other], named `U` followed by the code
+    /// point for Unicode keysyms and by their hex value otherwise.
+    ///
+    /// ```rust
+    /// use pino_xmodmap::KeySym;
+    ///
+    /// assert_eq!(KeySym::from_code(0x61), KeySym::KEY_a);
+    /// assert_eq!(KeySym::from_code(0x10020ac).to_string(), "U20AC");
+    /// ```
+    pub fn from_code(code: u32) -> KeySym {
+        if code == 0 {
+            return KeySym::KEY_NONE;
+        }
+        let by_code = KeySym::by_code();
+        if let Ok(i) = by_code.binary_search_by_key(&code, |(code, _)| *code) {
+            return by_code[i].1.clone();
+        }
+        match code {
+            0x1000100..=0x110ffff => KeySym::Other(intern(&format!("U{:04X}", code - 0x1000000))),
+            _ => KeySym::Other(intern(&format!("0x{:08x}", code))),
+        }
+    }
+
+    /// X11 keysym value, `None` for [KeySym::Other] names that do not encode their value
+    ///
+    /// `U` names only encode a value up to the last Unicode code point, U10FFFF.
+    ///
+    /// ```rust
+    /// use pino_xmodmap::KeySym;
+    ///
+    /// assert_eq!(KeySym::KEY_a.code(), Some(0x61));
+    /// assert_eq!(KeySym::KEY_F1.code(), Some(0xffbe));
+    /// ```
+    pub fn code(&self) -> Option<u32> {
+        let code = match self {
+            KeySym::KEY_NONE => 0,
+            KeySym::KEY_BACKSPACE => 0xff08,
+            KeySym::KEY_TAB => 0xff09,
+            KeySym::KEY_RETURN => 0xff0d,
+            KeySym::KEY_ESCAPE => 0xff1b,
+            KeySym::KEY_HOME => 0xff50,
+            KeySym::KEY_LEFT => 0xff51,
+            KeySym::KEY_UP => 0xff52,
+            KeySym::KEY_RIGHT => 0xff53,
+            KeySym::KEY_DOWN => 0xff54,
+            KeySym::KEY_PRIOR => 0xff55,
+            KeySym::KEY_NEXT => 0xff56,
+            KeySym::KEY_END => 0xff57,
+            KeySym::KEY_INSERT => 0xff63,
+            KeySym::KEY_F1 => 0xffbe,
+            KeySym::KEY_F2 => 0xffbf,
+            KeySym::KEY_F3 => 0xffc0,
+            KeySym::KEY_F4 => 0xffc1,
+            KeySym::KEY_F5 => 0xffc2,
+            KeySym::KEY_F6 => 0xffc3,
+            KeySym::KEY_F7 => 0xffc4,
+            KeySym::KEY_F8 => 0xffc5,
+            KeySym::KEY_F9 => 0xffc6,
+            KeySym::KEY_F10 => 0xffc7,
+            KeySym::KEY_F11 => 0xffc8,
+            KeySym::KEY_F12 => 0xffc9,
+            KeySym::KEY_ISO_LEVEL3_SHIFT => 0xfe03,
+            KeySym::KEY_ISO_LEVEL5_SHIFT => 0xfe11,
+            KeySym::KEY_MODE_SWITCH => 0xff7e,
+            KeySym::KEY_NUM_LOCK => 0xff7f,
+            KeySym::KEY_SHIFT_L => 0xffe1,
+            KeySym::KEY_SHIFT_R => 0xffe2,
+            KeySym::KEY_CONTROL_L => 0xffe3,
+            KeySym::KEY_CONTROL_R => 0xffe4,
+            KeySym::KEY_CAPS_LOCK => 0xffe5,
+            KeySym::KEY_SHIFT_LOCK => 0xffe6,
+            KeySym::KEY_META_L => 0xffe7,
+            KeySym::KEY_META_R => 0xffe8,
+            KeySym::KEY_ALT_L => 0xffe9,
+            KeySym::KEY_ALT_R => 0xffea,
+            KeySym::KEY_SUPER_L => 0xffeb,
+            KeySym::KEY_SUPER_R => 0xffec,
+            KeySym::KEY_HYPER_L => 0xffed,
+            KeySym::KEY_HYPER_R => 0xffee,
+            KeySym::KEY_DELETE => 0xffff,
+            KeySym::Other(name) => {
+                return match (name.strip_prefix("0x"), name.strip_prefix('U')) {
+                    (Some(hex), _) => u32::from_str_radix(hex, 16).ok(),
+                    (_, Some(hex)) => u32::from_str_radix(hex, 16)
+                        .ok()
+                        .filter(|c| *c <= 0x10ffff)
+                        .and_then(|c| c.checked_add(0x1000000)),
+                    _ => None,
+                }
+            },
+            // latin-1 keysyms share their value with the character
+            _ => char::try_from(self.clone()).ok()? as u32,
+        };
+        Some(code)
+    }
+
+    /// Check if the keysym is on the keypad (`KP_*`)
+    pub fn is_keypad(&self) -> bool {
+        self.canonical_name().starts_with("KP_")
+    }
+
+    /// Check if the keysym is a modifier key (see [ALL_MODIFIERS])
+    pub fn is_modifier(&self) -> bool {
+        ALL_MODIFIERS.contains(self)
+    }
+
+    /// Canonical name of the keysym, as output by xmodmap
+    pub fn canonical_name(&self) -> &str {
+        match self {
+            KeySym::KEY_a => "a",
+            KeySym::KEY_b => "b",
+            KeySym::KEY_c => "c",
+            KeySym::KEY_d => "d",
+            KeySym::KEY_e => "e",
+            KeySym::KEY_f => "f",
+            KeySym::KEY_g => "g",
+            KeySym::KEY_h => "h",
+            KeySym::KEY_i => "i",
+            KeySym::KEY_j => "j",
+            KeySym::KEY_k => "k",
+            KeySym::KEY_l => "l",
+            KeySym::KEY_m => "m",
+            KeySym::KEY_n => "n",
+            KeySym::KEY_o => "o",
+            KeySym::KEY_p => "p",
+            KeySym::KEY_q => "q",
+            KeySym::KEY_r => "r",
+            KeySym::KEY_s => "s",
+            KeySym::KEY_t => "t",
+            KeySym::KEY_u => "u",
+            KeySym::KEY_v => "v",
+            KeySym::KEY_w => "w",
+            KeySym::KEY_x => "x",
+            KeySym::KEY_y => "y",
+            KeySym::KEY_z => "z",
+            KeySym::KEY_A => "A",
+            KeySym::KEY_B => "B",
+            KeySym::KEY_C => "C",
+            KeySym::KEY_D => "D",
+            KeySym::KEY_E => "E",
+            KeySym::KEY_F => "F",
+            KeySym::KEY_G => "G",
+            KeySym::KEY_H => "H",
+            KeySym::KEY_I => "I",
+            KeySym::KEY_J => "J",
+            KeySym::KEY_K => "K",
+            KeySym::KEY_L => "L",
+            KeySym::KEY_M => "M",
+            KeySym::KEY_N => "N",
+            KeySym::KEY_O => "O",
+            KeySym::KEY_P => "P",
+            KeySym::KEY_Q => "Q",
+            KeySym::KEY_R => "R",
+            KeySym::KEY_S => "S",
+            KeySym::KEY_T => "T",
+            KeySym::KEY_U => "U",
+            KeySym::KEY_V => "V",
+            KeySym::KEY_W => "W",
+            KeySym::KEY_X => "X",
+            KeySym::KEY_Y => "Y",
+            KeySym::KEY_Z => "Z",
+            KeySym::KEY_0 => "0",
+            KeySym::KEY_1 => "1",
+            KeySym::KEY_2 => "2",
+            KeySym::KEY_3 => "3",
+            KeySym::KEY_4 => "4",
+            KeySym::KEY_5 => "5",
+            KeySym::KEY_6 => "6",
+            KeySym::KEY_7 => "7",
+            KeySym::KEY_8 => "8",
+            KeySym::KEY_9 => "9",
+            KeySym::KEY_EXCLAMATION => "exclam",
+            KeySym::KEY_AT => "at",
+            KeySym::KEY_NUMBERSIGN => "numbersign",
+            KeySym::KEY_DOLLAR => "dollar",
+            KeySym::KEY_PERCENT => "percent",
+            KeySym::KEY_CIRCUM => "asciicircum",
+            KeySym::KEY_AMPERSAND => "ampersand",
+            KeySym::KEY_ASTERISK => "asterisk",
+            KeySym::KEY_PARENLEFT => "parenleft",
+            KeySym::KEY_PARENRIGHT => "parenright",
+            KeySym::KEY_MINUS => "minus",
+            KeySym::KEY_UNDERSCORE => "underscore",
+            KeySym::KEY_PLUS => "plus",
+            KeySym::KEY_EQUAL => "equal",
+            KeySym::KEY_BRACKETLEFT => "bracketleft",
+            KeySym::KEY_BRACKETRIGHT => "bracketright",
+            KeySym::KEY_BRACELEFT => "braceleft",
+            KeySym::KEY_BRACERIGHT => "braceright",
+            KeySym::KEY_SEMICOLON => "semicolon",
+            KeySym::KEY_COLON => "colon",
+            KeySym::KEY_APOSTROPHE => "apostrophe",
+            KeySym::KEY_DOUBLEQUOTE => "quotedbl",
+            KeySym::KEY_BACKSLASH => "backslash",
+            KeySym::KEY_BAR => "bar",
+            KeySym::KEY_COMMA => "comma",
+            KeySym::KEY_LESS => "less",
+            KeySym::KEY_PERIOD => "period",
+            KeySym::KEY_GREATER => "greater",
+            KeySym::KEY_SLASH => "slash",
+            KeySym::KEY_QUESTION => "question",
+            KeySym::KEY_GRAVE => "grave",
+            KeySym::KEY_TILDE => "asciitilde",
+            KeySym::KEY_SPACE => "space",
+            KeySym::KEY_RETURN => "Return",
+            KeySym::KEY_BACKSPACE => "BackSpace",
+            KeySym::KEY_TAB => "Tab",
+            KeySym::KEY_ESCAPE => "Escape",
+            KeySym::KEY_NONE => "NoSymbol",
+            KeySym::KEY_F1 => "F1",
+            KeySym::KEY_F2 => "F2",
+            KeySym::KEY_F3 => "F3",
+            KeySym::KEY_F4 => "F4",
+            KeySym::KEY_F5 => "F5",
+            KeySym::KEY_F6 => "F6",
+            KeySym::KEY_F7 => "F7",
+            KeySym::KEY_F8 => "F8",
+            KeySym::KEY_F9 => "F9",
+            KeySym::KEY_F10 => "F10",
+            KeySym::KEY_F11 => "F11",
+            KeySym::KEY_F12 => "F12",
+            KeySym::KEY_HOME => "Home",
+            KeySym::KEY_END => "End",
+            KeySym::KEY_PRIOR => "Prior",
+            KeySym::KEY_NEXT => "Next",
+            KeySym::KEY_INSERT => "Insert",
+            KeySym::KEY_DELETE => "Delete",
+            KeySym::KEY_LEFT => "Left",
+            KeySym::KEY_RIGHT => "Right",
+            KeySym::KEY_UP => "Up",
+            KeySym::KEY_DOWN => "Down",
+            KeySym::KEY_SHIFT_L => "Shift_L",
+            KeySym::KEY_SHIFT_R => "Shift_R",
+            KeySym::KEY_CONTROL_L => "Control_L",
+            KeySym::KEY_CONTROL_R => "Control_R",
+            KeySym::KEY_CAPS_LOCK => "Caps_Lock",
+            KeySym::KEY_SHIFT_LOCK => "Shift_Lock",
+            KeySym::KEY_META_L => "Meta_L",
+            KeySym::KEY_META_R => "Meta_R",
+            KeySym::KEY_ALT_L => "Alt_L",
+            KeySym::KEY_ALT_R => "Alt_R",
+            KeySym::KEY_SUPER_L => "Super_L",
+            KeySym::KEY_SUPER_R => "Super_R",
+            KeySym::KEY_HYPER_L => "Hyper_L",
+            KeySym::KEY_HYPER_R => "Hyper_R",
+            KeySym::KEY_NUM_LOCK => "Num_Lock",
+            KeySym::KEY_MODE_SWITCH => "Mode_switch",
+            KeySym::KEY_ISO_LEVEL3_SHIFT => "ISO_Level3_Shift",
+            KeySym::KEY_ISO_LEVEL5_SHIFT => "ISO_Level5_Shift",
+            KeySym::Other(name) => name,
+        }
+    }
+}
+
+/// Keysyms are ordered by their keysym value, with [KeySym::Other] sorted after every known keysym
+/// by name
+///
+/// Different spellings of the same value, such as `0x00000061` and `a`, are ordered by name so
+/// that only equal keysyms compare equal.
+impl Ord for KeySym {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.code(), other.code()) {
+            (Some(a), Some(b)) => a
+                .cmp(&b)
+                .then_with(|| self.canonical_name().cmp(other.canonical_name())),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => self.canonical_name().cmp(other.canonical_name()),
         }
     }
 }
 
+impl PartialOrd for KeySym {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "serde")]
+pino_xcommon::serde_as_str!(KeySym, "keysym name as used by xmodmap");
+
+impl fmt::Display for KeySym {
+    /// From KeySym to Xmodmap entry
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.canonical_name())
+    }
+}
+
 impl TryFrom<char> for KeySym {
     type Error = ();
     fn try_from(value: char) -> Result<Self, Self::Error> {
@@ -521,7 +1853,7 @@ impl TryFrom<char> for KeySym {
             '?' => Ok(KeySym::KEY_QUESTION),
             '`' => Ok(KeySym::KEY_GRAVE),
             '~' => Ok(KeySym::KEY_TILDE),
-            _ => return Err(()),
+            _ => Err(()),
         }
     }
 }
@@ -592,6 +1924,12 @@ impl TryFrom<KeySym> for char {
             KeySym::KEY_X => 0x58,
             KeySym::KEY_Y => 0x59,
             KeySym::KEY_Z => 0x5a,
+            KeySym::KEY_BRACKETLEFT => 0x5b,
+            KeySym::KEY_BACKSLASH => 0x5c,
+            KeySym::KEY_BRACKETRIGHT => 0x5d,
+            KeySym::KEY_CIRCUM => 0x5e,
+            KeySym::KEY_UNDERSCORE => 0x5f,
+            KeySym::KEY_GRAVE => 0x60,
             KeySym::KEY_a => 0x61,
             KeySym::KEY_b => 0x62,
             KeySym::KEY_c => 0x63,
@@ -633,7 +1971,159 @@ impl TryFrom<KeySym> for char {
 mod tests {
     use std::str::FromStr;
 
-    use crate::KeySym;
+    use crate::{
+        runner::{Cmd, MockRunner, Output},
+        Error, KeySym, KeyTable, ModMask, Modifier, Version, ALL_MODIFIERS, DEFAULT_COLUMNS,
+        MODIFIER_NAMES,
+    };
+
+    const XMODMAP: &str = "keycode  24 = q Q q Q at Greek_OMEGA at
+keycode  38 = a A a A ae AE ae
+keycode  50 = Shift_L NoSymbol Shift_L";
+
+    #[test]
+    fn parse_columns() {
+        let table = KeyTable::parse(XMODMAP, DEFAULT_COLUMNS).unwrap();
+        assert_eq!(table.get_keysym(Modifier::Key, 24).unwrap(), KeySym::KEY_q);
+        assert_eq!(
+            table.get_keysym(Modifier::ShiftKey, 24).unwrap(),
+            KeySym::KEY_Q
+        );
+        assert_eq!(
+            table.get_keysym(Modifier::ISOLevel3ShiftKey, 24).unwrap(),
+            KeySym::KEY_AT
+        );
+        assert_eq!(
+            table.get_key(KeySym::KEY_A).unwrap(),
+            (Modifier::ShiftKey, 38)
+        );
+    }
+
+    #[test]
+    fn parse_custom_columns() {
+        let columns = [
+            Some(Modifier::Key),
+            Some(Modifier::ShiftKey),
+            None,
+            None,
+            Some(Modifier::ModeSwitchKey),
+        ];
+        let table = KeyTable::parse(XMODMAP, &columns).unwrap();
+        assert_eq!(
+            table.get_keysym(Modifier::ModeSwitchKey, 24).unwrap(),
+            KeySym::KEY_AT
+        );
+        assert!(table.get_keysym(Modifier::ISOLevel3ShiftKey, 24).is_err());
+        assert_eq!(table.get_raw(24).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn parse_level5() {
+        let raw = "keycode  38 = a A a A 1 2 3 4 5 6";
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS).unwrap();
+        assert_eq!(
+            table.get_keysym(Modifier::ISOLevel5ShiftKey, 38).unwrap(),
+            KeySym::KEY_3
+        );
+        assert_eq!(
+            table
+                .get_keysym(Modifier::ISOLevel3ISOLevel5ShiftShiftKey, 38)
+                .unwrap(),
+            KeySym::KEY_6
+        );
+    }
+
+    #[test]
+    fn parse_gaps() {
+        let raw = "keycode   8 =\nkeycode   9 = NoSymbol\nkeycode  10 = 1 NoSymbol";
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS).unwrap();
+        assert!(!table.is_mapped(8));
+        assert!(!table.is_mapped(9));
+        assert!(table.is_mapped(10));
+        assert!(!table.is_mapped(11));
+        assert!(table.get_keysym(Modifier::ShiftKey, 10).is_err());
+        assert!(table.get_key(KeySym::KEY_NONE).is_err());
+        assert_eq!(table.get_raw(8).unwrap(), &[]);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(KeyTable::parse("keysym a = b", DEFAULT_COLUMNS).is_err());
+    }
+
+    #[test]
+    fn mock_runner() {
+        let pke = Cmd::new("xmodmap").arg("-pke");
+        let runner = MockRunner::new().respond(pke.clone(), Output::from_stdout(XMODMAP));
+        let table = KeyTable::with_runner(&runner, DEFAULT_COLUMNS).unwrap();
+        assert_eq!(table.get_key(KeySym::KEY_a).unwrap(), (Modifier::Key, 38));
+
+        let runner = MockRunner::new().respond(pke, Output::from_failure(1, "no display"));
+        assert!(KeyTable::with_runner(&runner, DEFAULT_COLUMNS).is_err());
+    }
+
+    #[test]
+    fn mock_capabilities() {
+        let usage = "usage:  xmodmap [-options ...] [filename]\n    -pk    print keymap table\n";
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xmodmap").arg("-version"),
+                Output::from_stdout("xmodmap 1.0.9\n"),
+            )
+            .respond(
+                Cmd::new("xmodmap").arg("-help"),
+                Output::from_failure(1, usage),
+            )
+            .respond(
+                Cmd::new("xmodmap").arg("-pke"),
+                Output::from_failure(1, "xmodmap:  unrecognized argument -pke\n\nusage: ..."),
+            );
+        let caps = KeyTable::capabilities_with_runner(&runner);
+        assert_eq!(caps.version, Some(Version::new(1, 0, 9)));
+        assert_eq!(caps.flags, vec![String::from("-pk")]);
+
+        let err = KeyTable::with_runner(&runner, DEFAULT_COLUMNS)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "xmodmap: -pke is not supported");
+    }
+
+    #[test]
+    fn mock_timeout() {
+        let runner = MockRunner::new().time_out(Cmd::new("xmodmap").arg("-pke"));
+        assert!(matches!(
+            KeyTable::with_runner(&runner, DEFAULT_COLUMNS),
+            Err(Error::TimedOut { program: "xmodmap" })
+        ));
+    }
+
+    #[test]
+    fn widen_levels() {
+        let raw = "keycode  38 = a A\nkeycode  39 = s S ssharp section\nkeycode  40 = d";
+        let table = KeyTable::parse(raw, &DEFAULT_COLUMNS[..2]).unwrap();
+        assert_eq!(table.get_raw(38).unwrap(), &[KeySym::KEY_a, KeySym::KEY_A]);
+        assert_eq!(table.get_raw(39).unwrap().len(), 4);
+        assert_eq!(table.get_raw(40).unwrap(), &[KeySym::KEY_d]);
+        assert_eq!(
+            table.get_keysym(Modifier::ShiftKey, 38).unwrap(),
+            KeySym::KEY_A
+        );
+        assert!(table.get_keysym(Modifier::ShiftKey, 40).is_err());
+        assert!(table.get_keysym(Modifier::ModeSwitchKey, 39).is_err());
+    }
+
+    #[test]
+    fn rebind_shared_keysym() {
+        let raw = "keycode  10 = a A\nkeycode  20 = b a\nkeycode  10 = c C";
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS).unwrap();
+        assert_eq!(
+            table.get_key(KeySym::KEY_a).unwrap(),
+            (Modifier::ShiftKey, 20)
+        );
+        assert_eq!(table.get_key(KeySym::KEY_c).unwrap(), (Modifier::Key, 10));
+        assert!(table.get_key(KeySym::KEY_A).is_err());
+        assert_eq!(table.get_key(KeySym::KEY_b).unwrap(), (Modifier::Key, 20));
+    }
 
     #[test]
     fn str_to_keysym() {
@@ -641,6 +2131,123 @@ mod tests {
         assert_eq!(KeySym::from_str("asciitilde"), Ok(KeySym::KEY_TILDE));
     }
 
+    #[test]
+    fn keysym_aliases() {
+        assert_eq!(KeySym::from_str("Page_Up"), KeySym::from_str("Prior"));
+        assert_eq!(KeySym::from_str("Page_Down"), Ok(KeySym::KEY_NEXT));
+        assert_eq!(KeySym::KEY_PRIOR.canonical_name(), "Prior");
+    }
+
+    #[test]
+    fn unknown_keysym() {
+        let sym = KeySym::from_str("XF86AudioPlay").unwrap();
+        assert_eq!(sym, KeySym::Other("XF86AudioPlay".into()));
+        assert_eq!(sym.to_string(), "XF86AudioPlay");
+        assert!(KeySym::from_str("").is_err());
+    }
+
+    #[test]
+    fn modifier_keysyms() {
+        let table = KeyTable::parse(XMODMAP, DEFAULT_COLUMNS).unwrap();
+        let shift = table.get_keysym(Modifier::Key, 50).unwrap();
+        assert_eq!(shift, KeySym::KEY_SHIFT_L);
+        assert!(shift.is_modifier());
+        assert_eq!(shift.to_string(), "Shift_L");
+        assert_eq!(
+            KeySym::from_str("script_switch"),
+            Ok(KeySym::KEY_MODE_SWITCH)
+        );
+        assert!(!KeySym::KEY_a.is_modifier());
+        let names = ALL_MODIFIERS.iter().map(KeySym::canonical_name);
+        assert!(names.eq(MODIFIER_NAMES.iter().copied()));
+    }
+
+    #[test]
+    fn keysym_codes() {
+        for sym in KeySym::all() {
+            assert_eq!(KeySym::from_code(sym.code().unwrap()), sym);
+        }
+        assert_eq!(KeySym::from_code(0), KeySym::KEY_NONE);
+        assert_eq!(KeySym::from_code(0x1008ff13).code(), Some(0x1008ff13));
+        assert_eq!(KeySym::from_str("U00E4").unwrap().code(), Some(0x10000e4));
+        assert_eq!(KeySym::from_str("Greek_OMEGA").unwrap().code(), None);
+        // past the last code point, so not a Unicode keysym
+        let sym = KeySym::from_str("UFFFFFFFF").unwrap();
+        assert_eq!(sym.code(), None);
+        assert_eq!(KeySym::from_str("U110000").unwrap().code(), None);
+        assert_eq!(KeySym::from_str("U10FFFF").unwrap().code(), Some(0x110ffff));
+        let mut syms = vec![sym.clone(), KeySym::KEY_a];
+        syms.sort();
+        assert_eq!(syms, [KeySym::KEY_a, sym]);
+    }
+
+    #[test]
+    fn keysym_order() {
+        let mut syms = vec![
+            KeySym::Other("b".into()),
+            KeySym::KEY_F1,
+            KeySym::Other("a".into()),
+            KeySym::KEY_a,
+            KeySym::KEY_A,
+            KeySym::KEY_NONE,
+        ];
+        syms.sort();
+        assert_eq!(
+            syms,
+            vec![
+                KeySym::KEY_NONE,
+                KeySym::KEY_A,
+                KeySym::KEY_a,
+                KeySym::KEY_F1,
+                KeySym::Other("a".into()),
+                KeySym::Other("b".into()),
+            ]
+        );
+
+        // the same value spelled differently is not equal, so it does not compare equal either
+        let hex = KeySym::from_str("0x00000061").unwrap();
+        assert_eq!(hex.code(), KeySym::KEY_a.code());
+        assert_ne!(hex, KeySym::KEY_a);
+        assert_ne!(hex.cmp(&KeySym::KEY_a), std::cmp::Ordering::Equal);
+        let mut syms = vec![KeySym::KEY_a, hex.clone(), KeySym::KEY_a];
+        syms.sort();
+        syms.dedup();
+        assert_eq!(syms, [hex, KeySym::KEY_a]);
+    }
+
+    #[test]
+    fn all_keysyms() {
+        let all = KeySym::all().collect::<Vec<_>>();
+        assert_eq!(all.len(), 139);
+        assert_eq!(all.first(), Some(&KeySym::KEY_SPACE));
+        assert_eq!(all.last(), Some(&KeySym::KEY_DELETE));
+        for sym in all {
+            assert_eq!(KeySym::from_str(&sym.to_string()), Ok(sym));
+        }
+    }
+
+    #[test]
+    fn resolve_state() {
+        let raw = "keycode  24 = q Q q Q at Greek_OMEGA at
+keycode  10 = 1 exclam
+keycode  87 = KP_End KP_1
+keycode  90 = adiaeresis";
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS).unwrap();
+        let resolve = |code, state| table.resolve(code, state).unwrap().to_string();
+
+        assert_eq!(resolve(24, ModMask::NONE), "q");
+        assert_eq!(resolve(24, ModMask::SHIFT | ModMask::LOCK), "Q");
+        assert_eq!(resolve(24, ModMask::MOD5), "at");
+        assert_eq!(resolve(24, ModMask::MOD5 | ModMask::SHIFT), "Greek_OMEGA");
+        assert_eq!(resolve(10, ModMask::LOCK), "1");
+        assert_eq!(resolve(10, ModMask(1 << 13)), "1");
+        assert_eq!(resolve(87, ModMask::NONE), "KP_End");
+        assert_eq!(resolve(87, ModMask::MOD2), "KP_1");
+        assert_eq!(resolve(87, ModMask::MOD2 | ModMask::SHIFT), "KP_End");
+        assert_eq!(resolve(90, ModMask::SHIFT), "adiaeresis");
+        assert!(table.resolve(11, ModMask::NONE).is_err());
+    }
+
     #[test]
     fn char_to_keysym() {
         assert_eq!(KeySym::try_from('a'), Ok(KeySym::KEY_a));
@@ -0,0 +1,161 @@
+//! Report common keymap misconfigurations
+//!
+//! ```rust
+//! use pino_xmodmap::{lint::Lint, modmap::ModifierMap, KeySym, KeyTable, DEFAULT_COLUMNS};
+//!
+//! let table = KeyTable::parse("keycode   9 = Escape\nkeycode  66 = Escape", DEFAULT_COLUMNS).unwrap();
+//! let lints = table.lint(&ModifierMap::default());
+//! assert_eq!(
+//!     lints,
+//!     vec![Lint::DuplicateKeySym {
+//!         keysym: KeySym::KEY_ESCAPE,
+//!         keycodes: vec![9, 66],
+//!     }]
+//! );
+//! ```
+
+use std::{collections::HashMap, fmt};
+
+use crate::{modmap::ModifierMap, KeyCode, KeySym, KeyTable};
+
+/// A possible misconfiguration in a keytable
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Lint {
+    /// Keysym is bound to the first level of more than one keycode
+    DuplicateKeySym {
+        keysym: KeySym,
+        keycodes: Vec<KeyCode>,
+    },
+    /// First and second level of a keycode are bound to the same keysym
+    IdenticalLevels { keycode: KeyCode, keysym: KeySym },
+    /// Keycode is bound to a modifier keysym, but is not in the modifier map
+    ModifierNotInMap { keycode: KeyCode, keysym: KeySym },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::DuplicateKeySym { keysym, keycodes } => {
+                let keycodes = keycodes.iter().map(KeyCode::to_string).collect::<Vec<_>>();
+                write!(f, "{} is bound to keycodes {}", keysym, keycodes.join(", "))
+            },
+            Lint::IdenticalLevels { keycode, keysym } => write!(
+                f,
+                "keycode {} has {} on both its first and second level",
+                keycode, keysym
+            ),
+            Lint::ModifierNotInMap { keycode, keysym } => write!(
+                f,
+                "keycode {} is bound to {} but is not in the modifier map",
+                keycode, keysym
+            ),
+        }
+    }
+}
+
+impl KeyTable {
+    /// Report keysyms bound to multiple keycodes, keycodes with identical first and second
+    /// levels, and modifier keys missing from the modifier map
+    pub fn lint(&self, modmap: &ModifierMap) -> Vec<Lint> {
+        let mut lints = vec![];
+
+        let mut first_levels: HashMap<&KeySym, Vec<KeyCode>> = HashMap::new();
+        for (keycode, syms) in self.iter_keys() {
+            let Some(first) = syms.first().filter(|sym| **sym != KeySym::KEY_NONE) else {
+                continue;
+            };
+            first_levels.entry(first).or_default().push(keycode);
+
+            if syms.get(1) == Some(first) {
+                lints.push(Lint::IdenticalLevels {
+                    keycode,
+                    keysym: first.clone(),
+                });
+            }
+            if first.is_modifier() && modmap.get_modifier(keycode).is_none() {
+                lints.push(Lint::ModifierNotInMap {
+                    keycode,
+                    keysym: first.clone(),
+                });
+            }
+        }
+
+        let mut duplicates = first_levels
+            .into_iter()
+            .filter(|(_, keycodes)| keycodes.len() > 1)
+            .map(|(keysym, keycodes)| Lint::DuplicateKeySym {
+                keysym: keysym.clone(),
+                keycodes,
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_by_key(|lint| match lint {
+            Lint::DuplicateKeySym { keycodes, .. } => keycodes[0],
+            _ => 0,
+        });
+        lints.append(&mut duplicates);
+
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_COLUMNS;
+
+    #[test]
+    fn lint_table() {
+        let raw =
+            "keycode  10 = 1 exclam\nkeycode  38 = a a\nkeycode  39 = a A\nkeycode  40 = NoSymbol";
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS).unwrap();
+        assert_eq!(
+            table.lint(&ModifierMap::default()),
+            vec![
+                Lint::IdenticalLevels {
+                    keycode: 38,
+                    keysym: KeySym::KEY_a,
+                },
+                Lint::DuplicateKeySym {
+                    keysym: KeySym::KEY_a,
+                    keycodes: vec![38, 39],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_modifiers() {
+        let table = KeyTable::parse(
+            "keycode  50 = Shift_L\nkeycode  66 = Caps_Lock",
+            DEFAULT_COLUMNS,
+        )
+        .unwrap();
+        let modmap = ModifierMap::parse("shift       Shift_L (0x32)").unwrap();
+        assert_eq!(
+            table.lint(&modmap),
+            vec![Lint::ModifierNotInMap {
+                keycode: 66,
+                keysym: KeySym::KEY_CAPS_LOCK,
+            }]
+        );
+    }
+
+    #[test]
+    fn lint_every_modifier() {
+        for name in crate::MODIFIER_NAMES {
+            let table =
+                KeyTable::parse(&format!("keycode  50 = {}", name), DEFAULT_COLUMNS).unwrap();
+            assert_eq!(
+                table.lint(&ModifierMap::default()),
+                vec![Lint::ModifierNotInMap {
+                    keycode: 50,
+                    keysym: name.parse().unwrap(),
+                }],
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Semantic actions of the XF86 media keysyms
+//!
+//! ```rust
+//! use pino_xmodmap::{media::MediaKey, FromStr, KeySym};
+//!
+//! let keysym = KeySym::from_str("XF86AudioRaiseVolume").unwrap();
+//! assert_eq!(MediaKey::try_from(&keysym), Ok(MediaKey::VolumeUp));
+//! assert_eq!(KeySym::from(MediaKey::Play).to_string(), "XF86AudioPlay");
+//! ```
+
+use pino_xcommon::intern::intern;
+
+use crate::KeySym;
+
+/// Action of a media key
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum MediaKey {
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    MicMute,
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Prev,
+    Record,
+    Rewind,
+    Forward,
+    Eject,
+    BrightnessUp,
+    BrightnessDown,
+    KbdBrightnessUp,
+    KbdBrightnessDown,
+    KbdLightOnOff,
+    Display,
+    Sleep,
+    Suspend,
+    PowerOff,
+    WakeUp,
+    ScreenSaver,
+    Calculator,
+    Mail,
+    WWW,
+    Search,
+    HomePage,
+    Explorer,
+    Favorites,
+    Back,
+    Refresh,
+    TouchpadToggle,
+    WLAN,
+    Bluetooth,
+}
+
+/// Media key and the XF86 keysym producing it, in the order the keys are declared
+pub static MEDIA_KEYS: &[(MediaKey, &str)] = &[
+    (MediaKey::VolumeUp, "XF86AudioRaiseVolume"),
+    (MediaKey::VolumeDown, "XF86AudioLowerVolume"),
+    (MediaKey::Mute, "XF86AudioMute"),
+    (MediaKey::MicMute, "XF86AudioMicMute"),
+    (MediaKey::Play, "XF86AudioPlay"),
+    (MediaKey::Pause, "XF86AudioPause"),
+    (MediaKey::Stop, "XF86AudioStop"),
+    (MediaKey::Next, "XF86AudioNext"),
+    (MediaKey::Prev, "XF86AudioPrev"),
+    (MediaKey::Record, "XF86AudioRecord"),
+    (MediaKey::Rewind, "XF86AudioRewind"),
+    (MediaKey::Forward, "XF86AudioForward"),
+    (MediaKey::Eject, "XF86Eject"),
+    (MediaKey::BrightnessUp, "XF86MonBrightnessUp"),
+    (MediaKey::BrightnessDown, "XF86MonBrightnessDown"),
+    (MediaKey::KbdBrightnessUp, "XF86KbdBrightnessUp"),
+    (MediaKey::KbdBrightnessDown, "XF86KbdBrightnessDown"),
+    (MediaKey::KbdLightOnOff, "XF86KbdLightOnOff"),
+    (MediaKey::Display, "XF86Display"),
+    (MediaKey::Sleep, "XF86Sleep"),
+    (MediaKey::Suspend, "XF86Suspend"),
+    (MediaKey::PowerOff, "XF86PowerOff"),
+    (MediaKey::WakeUp, "XF86WakeUp"),
+    (MediaKey::ScreenSaver, "XF86ScreenSaver"),
+    (MediaKey::Calculator, "XF86Calculator"),
+    (MediaKey::Mail, "XF86Mail"),
+    (MediaKey::WWW, "XF86WWW"),
+    (MediaKey::Search, "XF86Search"),
+    (MediaKey::HomePage, "XF86HomePage"),
+    (MediaKey::Explorer, "XF86Explorer"),
+    (MediaKey::Favorites, "XF86Favorites"),
+    (MediaKey::Back, "XF86Back"),
+    (MediaKey::Refresh, "XF86Refresh"),
+    (MediaKey::TouchpadToggle, "XF86TouchpadToggle"),
+    (MediaKey::WLAN, "XF86WLAN"),
+    (MediaKey::Bluetooth, "XF86Bluetooth"),
+];
+
+impl MediaKey {
+    /// Name of the XF86 keysym producing the media key
+    pub fn keysym_name(&self) -> &'static str {
+        MEDIA_KEYS[*self as usize].1
+    }
+}
+
+impl TryFrom<&KeySym> for MediaKey {
+    type Error = ();
+    fn try_from(value: &KeySym) -> Result<Self, Self::Error> {
+        let name = value.canonical_name();
+        MEDIA_KEYS
+            .iter()
+            .find(|(_, keysym)| *keysym == name)
+            .map(|(key, _)| *key)
+            .ok_or(())
+    }
+}
+
+impl From<MediaKey> for KeySym {
+    fn from(value: MediaKey) -> Self {
+        KeySym::Other(intern(value.keysym_name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_key_round_trip() {
+        for (key, _) in MEDIA_KEYS {
+            assert_eq!(MediaKey::try_from(&KeySym::from(*key)), Ok(*key));
+        }
+        assert_eq!(MediaKey::try_from(&KeySym::KEY_a), Err(()));
+    }
+
+    #[test]
+    fn media_key_names() {
+        for (i, (key, name)) in MEDIA_KEYS.iter().enumerate() {
+            assert_eq!(*key as usize, i);
+            assert_eq!(key.keysym_name(), *name);
+        }
+        assert_eq!(MEDIA_KEYS.len(), MediaKey::Bluetooth as usize + 1);
+    }
+}
@@ -0,0 +1,151 @@
+//! Modifier map, as output by `xmodmap -pm`
+//!
+//! ```rust
+//! use pino_xmodmap::{modmap::ModifierMap, ModMask};
+//!
+//! let modmap = ModifierMap::parse("shift       Shift_L (0x32),  Shift_R (0x3e)").unwrap();
+//! assert_eq!(modmap.get_modifier(0x32), Some(ModMask::SHIFT));
+//! ```
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    runner::{Cmd, CommandRunner},
+    Error, KeyCode, ModMask,
+};
+
+/// Keycodes assigned to each of the eight modifiers, ordered by modifier
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ModifierMap {
+    map: BTreeMap<ModMask, Vec<KeyCode>>,
+}
+
+impl ModifierMap {
+    /// Reads the modifier map from xmodmap
+    ///
+    /// With the `native` feature the map is requested from the X server directly instead.
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn new() -> Result<Self, Error> {
+        Self::with_display(&crate::DisplayHandle::default())
+    }
+
+    /// Reads the modifier map of the given display
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn with_display(display: &crate::DisplayHandle) -> Result<Self, Error> {
+        use pino_xcommon::backend::{dispatch, Backend};
+
+        pino_xcommon::session::require_x(display).map_err(Error::NoXServer)?;
+        dispatch(|backend| match backend {
+            #[cfg(feature = "native")]
+            Backend::Native => {
+                Some(crate::native::modifier_mapping(display).map(ModifierMap::from_rows))
+            },
+            #[cfg(feature = "process")]
+            Backend::Binary => Some(Self::with_runner(
+                &display.runner(&crate::runner::SystemRunner::default()),
+            )),
+            _ => None,
+        })
+        .unwrap_or_else(|| Err(Error::no_backend()))
+    }
+
+    #[cfg(feature = "native")]
+    pub(crate) fn from_rows(rows: Vec<(ModMask, Vec<KeyCode>)>) -> Self {
+        ModifierMap {
+            map: rows.into_iter().collect(),
+        }
+    }
+
+    /// Reads the modifier map from xmodmap, run through the given runner
+    pub fn with_runner(runner: &dyn CommandRunner) -> Result<Self, Error> {
+        let output = runner
+            .output(&Cmd::new("xmodmap").arg("-pm"))
+            .map_err(Error::run("xmodmap"))?;
+        if !output.success() {
+            return Err(Error::exited("xmodmap", &output));
+        }
+        let raw = String::from_utf8(output.stdout)
+            .map_err(|_| Error::invalid("xmodmap output is not UTF-8"))?;
+
+        Self::parse(&raw)
+    }
+
+    /// Parse the output of `xmodmap -pm`
+    ///
+    /// Lines that do not start with a modifier name (such as the header) are skipped.
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse_modmap").entered();
+
+        let mut map = BTreeMap::new();
+
+        for (number, l) in raw.lines().enumerate() {
+            let Some((name, rest)) = l.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Ok(modifier) = ModMask::from_str(name) else {
+                continue;
+            };
+
+            // each entry looks like `Shift_L (0x32)`
+            let mut keycodes = vec![];
+            for entry in rest.split(',') {
+                let Some((_, code)) = entry.split_once('(') else {
+                    continue;
+                };
+                let code = code
+                    .trim_end_matches(|c: char| c == ')' || c.is_whitespace())
+                    .trim_start_matches("0x");
+                let keycode =
+                    KeyCode::from_str_radix(code, 16).map_err(|_| Error::InvalidFormat {
+                        line: Some(number + 1),
+                        content: l.to_owned(),
+                    })?;
+                keycodes.push(keycode);
+            }
+            map.insert(modifier, keycodes);
+        }
+
+        Ok(ModifierMap { map })
+    }
+
+    /// Keycodes assigned to a modifier
+    pub fn get_keycodes(&self, modifier: ModMask) -> &[KeyCode] {
+        self.map.get(&modifier).map(|k| k.as_slice()).unwrap_or(&[])
+    }
+
+    /// Modifier a keycode is assigned to
+    pub fn get_modifier(&self, keycode: KeyCode) -> Option<ModMask> {
+        self.map
+            .iter()
+            .find(|(_, keycodes)| keycodes.contains(&keycode))
+            .map(|(modifier, _)| *modifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_modmap() {
+        let raw = "xmodmap:  up to 4 keys per modifier, (keycodes in parentheses):
+
+shift       Shift_L (0x32),  Shift_R (0x3e)
+lock        Caps_Lock (0x42)
+control     Control_L (0x25),  Control_R (0x69)
+mod1        Alt_L (0x40),  Alt_R (0x6c),  Meta_L (0xcd)
+mod2        Num_Lock (0x4d)
+mod3
+mod4        Super_L (0x85),  Super_R (0x86),  Super_L (0xce),  Hyper_L (0xcf)
+mod5        ISO_Level3_Shift (0x5c),  Mode_switch (0xcb)
+";
+        let modmap = ModifierMap::parse(raw).unwrap();
+        assert_eq!(modmap.get_keycodes(ModMask::SHIFT), &[0x32, 0x3e]);
+        assert!(modmap.get_keycodes(ModMask::MOD3).is_empty());
+        assert_eq!(modmap.get_modifier(0x85), Some(ModMask::MOD4));
+        assert_eq!(modmap.get_modifier(0x26), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Requests to the X server replacing the xmodmap binary, enabled by the `native` feature
+
+use pino_xcommon::{display::DisplayHandle, native::connect_display};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt, MappingStatus},
+    rust_connection::RustConnection,
+};
+
+use crate::{expr::Expression, Error, KeyCode, KeySym, KeyTable, ModMask};
+
+/// Modifiers in the order of the rows of the modifier mapping
+static MODIFIER_ROWS: &[ModMask] = &[
+    ModMask::SHIFT,
+    ModMask::LOCK,
+    ModMask::CONTROL,
+    ModMask::MOD1,
+    ModMask::MOD2,
+    ModMask::MOD3,
+    ModMask::MOD4,
+    ModMask::MOD5,
+];
+
+fn conn(display: &DisplayHandle) -> Result<RustConnection, Error> {
+    connect_display(display)
+        .map(|(conn, _)| conn)
+        .map_err(|e| Error::Connection(e.to_string()))
+}
+
+/// Keysyms bound to every keycode, equivalent to `xmodmap -pke`
+pub(crate) fn keyboard_mapping(
+    display: &DisplayHandle,
+) -> Result<Vec<(KeyCode, Vec<KeySym>)>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("get_keyboard_mapping").entered();
+    let conn = conn(display)?;
+    let (min, max) = (conn.setup().min_keycode, conn.setup().max_keycode);
+    let reply = conn
+        .get_keyboard_mapping(min, max - min + 1)
+        .map_err(|e| Error::Connection(e.to_string()))?
+        .reply()
+        .map_err(|e| Error::Connection(e.to_string()))?;
+
+    let per_keycode = reply.keysyms_per_keycode.max(1) as usize;
+    let keys = reply
+        .keysyms
+        .chunks(per_keycode)
+        .zip(min..=max)
+        .map(|(codes, keycode)| {
+            // xmodmap leaves out trailing NoSymbol columns
+            let len = codes.iter().rposition(|c| *c != 0).map_or(0, |i| i + 1);
+            let syms = codes[..len].iter().map(|c| KeySym::from_code(*c)).collect();
+            (keycode, syms)
+        })
+        .collect();
+
+    Ok(keys)
+}
+
+/// Keycodes assigned to each modifier, equivalent to `xmodmap -pm`
+pub(crate) fn modifier_mapping(
+    display: &DisplayHandle,
+) -> Result<Vec<(ModMask, Vec<KeyCode>)>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("get_modifier_mapping").entered();
+    let conn = conn(display)?;
+    let reply = conn
+        .get_modifier_mapping()
+        .map_err(|e| Error::Connection(e.to_string()))?
+        .reply()
+        .map_err(|e| Error::Connection(e.to_string()))?;
+
+    let per_modifier = reply.keycodes_per_modifier().max(1) as usize;
+    let rows = reply
+        .keycodes
+        .chunks(per_modifier)
+        .zip(MODIFIER_ROWS)
+        .map(|(keycodes, modifier)| {
+            let keycodes = keycodes.iter().copied().filter(|k| *k != 0).collect();
+            (*modifier, keycodes)
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// Apply resolved expressions, equivalent to `xmodmap -e`
+pub(crate) fn apply(table: &KeyTable, exprs: &[Expression]) -> Result<(), Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("apply_expressions", count = exprs.len()).entered();
+    let display = DisplayHandle::default();
+    let conn = conn(&display)?;
+
+    let mut rows = modifier_mapping(&display)?;
+    let mut modmap_changed = false;
+    let keycodes_of = |syms: &[KeySym]| {
+        syms.iter()
+            .flat_map(|sym| table.get_keycodes(sym))
+            .collect::<Vec<_>>()
+    };
+
+    for expr in exprs {
+        match expr {
+            Expression::Keycode(keycode, syms) => {
+                let codes = syms
+                    .iter()
+                    .map(|sym| {
+                        sym.code()
+                            .ok_or_else(|| Error::NonExistentKeySym(sym.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                conn.change_keyboard_mapping(1, *keycode, codes.len().max(1) as u8, &codes)
+                    .map_err(|e| Error::Connection(e.to_string()))?
+                    .check()
+                    .map_err(|e| Error::Connection(e.to_string()))?;
+            },
+            Expression::Keysym(..) => return Err(Error::invalid(expr.to_string())),
+            Expression::Clear(index) => {
+                for (modifier, keycodes) in rows.iter_mut() {
+                    if index.mask() == *modifier {
+                        keycodes.clear();
+                    }
+                }
+                modmap_changed = true;
+            },
+            Expression::Add(index, syms) => {
+                for (modifier, keycodes) in rows.iter_mut() {
+                    if index.mask() == *modifier {
+                        for keycode in keycodes_of(syms) {
+                            if !keycodes.contains(&keycode) {
+                                keycodes.push(keycode);
+                            }
+                        }
+                    }
+                }
+                modmap_changed = true;
+            },
+            Expression::Remove(index, syms) => {
+                let removed = keycodes_of(syms);
+                for (modifier, keycodes) in rows.iter_mut() {
+                    if index.mask() == *modifier {
+                        keycodes.retain(|keycode| !removed.contains(keycode));
+                    }
+                }
+                modmap_changed = true;
+            },
+        }
+    }
+
+    if modmap_changed {
+        let per_modifier = rows.iter().map(|(_, k)| k.len()).max().unwrap_or(0);
+        let mut keycodes = vec![];
+        for (_, row) in rows.iter() {
+            keycodes.extend(row.iter().copied());
+            keycodes.resize(keycodes.len() + per_modifier - row.len(), 0);
+        }
+        let reply = conn
+            .set_modifier_mapping(&keycodes)
+            .map_err(|e| Error::Connection(e.to_string()))?
+            .reply()
+            .map_err(|e| Error::Connection(e.to_string()))?;
+        if reply.status != MappingStatus::SUCCESS {
+            return Err(Error::Connection(String::from(
+                "modifier mapping was rejected by the server",
+            )));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Async variants of the operations that run xmodmap, enabled by the `tokio` feature
+//!
+//! With the `native` feature the blocking X requests are moved to tokio's blocking thread pool.
+
+#[cfg(not(feature = "native"))]
+use pino_xcommon::{nonblocking::output, runner::Cmd};
+
+#[cfg(feature = "geometry")]
+use crate::geometry::Geometry;
+use crate::{expr::Expression, modmap::ModifierMap, Error, KeyTable, Modifier, DEFAULT_COLUMNS};
+
+impl KeyTable {
+    /// Async variant of [KeyTable::new]
+    ///
+    /// ```rust,no_run
+    /// # use pino_xmodmap::{KeySym, KeyTable};
+    /// # async fn run() {
+    /// let table = KeyTable::new_async().await.unwrap();
+    /// let a_key = table.get_key(KeySym::KEY_a).unwrap();
+    /// # }
+    /// ```
+    pub async fn new_async() -> Result<Self, Error> {
+        Self::with_columns_async(DEFAULT_COLUMNS).await
+    }
+
+    /// Async variant of [KeyTable::with_columns]
+    pub async fn with_columns_async(columns: &[Option<Modifier>]) -> Result<Self, Error> {
+        #[cfg(feature = "native")]
+        {
+            let keys = tokio::task::spawn_blocking(|| {
+                crate::native::keyboard_mapping(&crate::DisplayHandle::default())
+            })
+            .await
+            .map_err(|e| Error::Connection(e.to_string()))??;
+            Ok(KeyTable::from_keys(keys, columns))
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            let output = output(&Cmd::new("xmodmap").arg("-pke"))
+                .await
+                .map_err(Error::run("xmodmap"))?;
+            if !output.success() {
+                return Err(Error::exited("xmodmap", &output));
+            }
+            KeyTable::from_reader(output.stdout.as_slice(), columns)
+        }
+    }
+
+    /// Async variant of [KeyTable::apply]
+    pub async fn apply_async(&self, exprs: &[Expression]) -> Result<Vec<Expression>, Error> {
+        let mut applied = vec![];
+        for expr in exprs {
+            applied.append(&mut self.resolve_expression(expr)?);
+        }
+
+        #[cfg(feature = "native")]
+        {
+            let table = self.clone();
+            tokio::task::spawn_blocking(move || {
+                crate::native::apply(&table, &applied).map(|_| applied)
+            })
+            .await
+            .map_err(|e| Error::Connection(e.to_string()))?
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            let mut cmd = Cmd::new("xmodmap");
+            for expr in applied.iter() {
+                cmd = cmd.arg("-e").arg(expr.to_string());
+            }
+            let output = output(&cmd).await.map_err(Error::run("xmodmap"))?;
+            if !output.success() {
+                return Err(Error::exited("xmodmap", &output));
+            }
+            Ok(applied)
+        }
+    }
+}
+
+impl ModifierMap {
+    /// Async variant of [ModifierMap::new]
+    pub async fn new_async() -> Result<Self, Error> {
+        #[cfg(feature = "native")]
+        {
+            let rows = tokio::task::spawn_blocking(|| {
+                crate::native::modifier_mapping(&crate::DisplayHandle::default())
+            })
+            .await
+            .map_err(|e| Error::Connection(e.to_string()))??;
+            Ok(ModifierMap::from_rows(rows))
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            let output = output(&Cmd::new("xmodmap").arg("-pm"))
+                .await
+                .map_err(Error::run("xmodmap"))?;
+            if !output.success() {
+                return Err(Error::exited("xmodmap", &output));
+            }
+            let raw = String::from_utf8(output.stdout)
+                .map_err(|_| Error::invalid("xmodmap output is not UTF-8"))?;
+            ModifierMap::parse(&raw)
+        }
+    }
+}
+
+#[cfg(feature = "geometry")]
+impl Geometry {
+    /// Async variant of [Geometry::read]
+    pub async fn read_async() -> Result<Self, Error> {
+        let display = Geometry::source(&crate::DisplayHandle::default())?;
+        let output = pino_xcommon::nonblocking::output(
+            &pino_xcommon::runner::Cmd::new("xkbcomp")
+                .arg("-xkb")
+                .arg(display)
+                .arg("-"),
+        )
+        .await
+        .map_err(Error::run("xkbcomp"))?;
+        if !output.success() {
+            return Err(Error::exited("xkbcomp", &output));
+        }
+        let raw = String::from_utf8(output.stdout)
+            .map_err(|_| Error::invalid("xkbcomp output is not UTF-8"))?;
+
+        Self::parse(&raw)
+    }
+}
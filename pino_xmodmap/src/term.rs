@@ -0,0 +1,183 @@
+//! Byte sequences a terminal sends for a key press
+//!
+//! Follows the encoding used by xterm (and most terminals emulating it), so input synthesized for
+//! TUI tests matches what a real terminal would produce.
+//!
+//! ```rust
+//! use pino_xmodmap::{term::escape_sequence, KeySym, ModMask};
+//!
+//! assert_eq!(escape_sequence(&KeySym::KEY_F5, ModMask::NONE).unwrap(), b"\x1b[15~");
+//! assert_eq!(escape_sequence(&KeySym::KEY_LEFT, ModMask::CONTROL).unwrap(), b"\x1b[1;5D");
+//! assert_eq!(escape_sequence(&KeySym::KEY_c, ModMask::CONTROL).unwrap(), b"\x03");
+//! ```
+
+use crate::{KeySym, ModMask};
+
+/// xterm modifier parameter, `None` if no modifier affecting special keys is held
+fn modifier_param(mods: ModMask) -> Option<u8> {
+    let mut param = 1;
+    if mods.contains(ModMask::SHIFT) {
+        param += 1;
+    }
+    if mods.contains(ModMask::MOD1) {
+        param += 2;
+    }
+    if mods.contains(ModMask::CONTROL) {
+        param += 4;
+    }
+    if mods.contains(ModMask::MOD4) {
+        param += 8;
+    }
+    (param > 1).then_some(param)
+}
+
+/// Cursor style sequence, `CSI 1 ; m X` when modified and `CSI X` otherwise
+fn cursor(final_byte: char, mods: ModMask) -> Vec<u8> {
+    match modifier_param(mods) {
+        Some(m) => format!("\x1b[1;{}{}", m, final_byte).into_bytes(),
+        None => format!("\x1b[{}", final_byte).into_bytes(),
+    }
+}
+
+/// Tilde style sequence, `CSI n ; m ~` when modified and `CSI n ~` otherwise
+fn tilde(code: u8, mods: ModMask) -> Vec<u8> {
+    match modifier_param(mods) {
+        Some(m) => format!("\x1b[{};{}~", code, m).into_bytes(),
+        None => format!("\x1b[{}~", code).into_bytes(),
+    }
+}
+
+/// Function keys F1-F4 use SS3 when unmodified
+fn ss3(final_byte: char, mods: ModMask) -> Vec<u8> {
+    match modifier_param(mods) {
+        Some(m) => format!("\x1b[1;{}{}", m, final_byte).into_bytes(),
+        None => format!("\x1bO{}", final_byte).into_bytes(),
+    }
+}
+
+/// Control character produced by holding control with a printable character
+fn control_char(c: char) -> Option<u8> {
+    match c {
+        'a'..='z' | 'A'..='Z' => Some(c.to_ascii_lowercase() as u8 & 0x1f),
+        '@' | '2' | ' ' => Some(0x00),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '-' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    }
+}
+
+/// Byte sequence sent by a terminal when the keysym is pressed with the given modifiers
+///
+/// `Mod1` is treated as Alt and `Mod4` as Super. Returns `None` for keysyms that a terminal does
+/// not send anything for.
+pub fn escape_sequence(keysym: &KeySym, mods: ModMask) -> Option<Vec<u8>> {
+    let alt = mods.contains(ModMask::MOD1);
+    let ctrl = mods.contains(ModMask::CONTROL);
+
+    let special = match keysym {
+        KeySym::KEY_UP => cursor('A', mods),
+        KeySym::KEY_DOWN => cursor('B', mods),
+        KeySym::KEY_RIGHT => cursor('C', mods),
+        KeySym::KEY_LEFT => cursor('D', mods),
+        KeySym::KEY_HOME => cursor('H', mods),
+        KeySym::KEY_END => cursor('F', mods),
+        KeySym::KEY_INSERT => tilde(2, mods),
+        KeySym::KEY_DELETE => tilde(3, mods),
+        KeySym::KEY_PRIOR => tilde(5, mods),
+        KeySym::KEY_NEXT => tilde(6, mods),
+        KeySym::KEY_F1 => ss3('P', mods),
+        KeySym::KEY_F2 => ss3('Q', mods),
+        KeySym::KEY_F3 => ss3('R', mods),
+        KeySym::KEY_F4 => ss3('S', mods),
+        KeySym::KEY_F5 => tilde(15, mods),
+        KeySym::KEY_F6 => tilde(17, mods),
+        KeySym::KEY_F7 => tilde(18, mods),
+        KeySym::KEY_F8 => tilde(19, mods),
+        KeySym::KEY_F9 => tilde(20, mods),
+        KeySym::KEY_F10 => tilde(21, mods),
+        KeySym::KEY_F11 => tilde(23, mods),
+        KeySym::KEY_F12 => tilde(24, mods),
+        KeySym::KEY_TAB if mods.contains(ModMask::SHIFT) => b"\x1b[Z".to_vec(),
+        _ => vec![],
+    };
+    if !special.is_empty() {
+        return Some(special);
+    }
+
+    let byte = match keysym {
+        KeySym::KEY_RETURN => b'\r',
+        KeySym::KEY_TAB => b'\t',
+        KeySym::KEY_ESCAPE => 0x1b,
+        KeySym::KEY_BACKSPACE if ctrl => 0x08,
+        KeySym::KEY_BACKSPACE => 0x7f,
+        _ => {
+            let c = char::try_from(keysym.clone()).ok()?;
+            if ctrl {
+                control_char(c)?
+            } else {
+                let mut bytes = vec![];
+                if alt {
+                    bytes.push(0x1b);
+                }
+                bytes.extend(c.to_string().into_bytes());
+                return Some(bytes);
+            }
+        },
+    };
+
+    Some(if alt { vec![0x1b, byte] } else { vec![byte] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_keys() {
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_UP, ModMask::NONE).unwrap(),
+            b"\x1b[A"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_F1, ModMask::NONE).unwrap(),
+            b"\x1bOP"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_F1, ModMask::SHIFT | ModMask::MOD1).unwrap(),
+            b"\x1b[1;4P"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_DELETE, ModMask::CONTROL).unwrap(),
+            b"\x1b[3;5~"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_TAB, ModMask::SHIFT).unwrap(),
+            b"\x1b[Z"
+        );
+    }
+
+    #[test]
+    fn printable_keys() {
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_a, ModMask::NONE).unwrap(),
+            b"a"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_a, ModMask::MOD1).unwrap(),
+            b"\x1ba"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_BRACKETLEFT, ModMask::CONTROL).unwrap(),
+            b"\x1b"
+        );
+        assert_eq!(
+            escape_sequence(&KeySym::KEY_RETURN, ModMask::MOD1).unwrap(),
+            b"\x1b\r"
+        );
+        assert_eq!(escape_sequence(&KeySym::KEY_NONE, ModMask::NONE), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Follow the window that has the focus, from `_NET_ACTIVE_WINDOW` of the root window
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xprop::focus::FocusWatcher;
+//!
+//! for focus in FocusWatcher::new().unwrap() {
+//!     let focus = focus.unwrap();
+//!     let class = focus.class.map(|class| class.class).unwrap_or_default();
+//!     println!("{} {}", class, focus.title.unwrap_or_default());
+//! }
+//! # }
+//! ```
+//!
+//! The property is kept up to date by EWMH window managers, so nothing is reported under those
+//! that do not set it.
+
+use pino_xcommon::runner::{CommandRunner, RunError};
+
+use crate::{
+    hints::WmClass,
+    spy::{Change, Spy},
+    DisplayHandle, Error, Properties, Target, WindowId,
+};
+
+/// Properties read from a window when it gets the focus
+pub static FOCUS_PROPERTIES: &[&str] = &["_NET_WM_NAME", "WM_NAME", "WM_CLASS"];
+
+/// Window that got the focus
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Focus {
+    /// Focused window, `None` when no window has the focus such as on an empty desktop
+    pub id: Option<WindowId>,
+    /// Title of the window when it got the focus
+    pub title: Option<String>,
+    pub class: Option<WmClass>,
+}
+
+/// Stream of the changes of focus, see the [module](self) documentation
+///
+/// The window focused when the watcher starts is yielded first. A window that is closed before
+/// its properties can be read is reported without title and class.
+pub struct FocusWatcher {
+    runner: Box<dyn CommandRunner>,
+    display: DisplayHandle,
+    spy: Spy,
+    current: Option<Option<WindowId>>,
+}
+
+impl FocusWatcher {
+    /// Watch the focus on the default display
+    #[cfg(feature = "process")]
+    pub fn new() -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::with_runner(crate::runner::SystemRunner::new().timeout(None), display)
+    }
+
+    /// Watch the focus on the display, running `xprop` through the given runner
+    ///
+    /// The spying xprop runs until the watcher is dropped, so the runner should not time it out.
+    pub fn with_runner(
+        runner: impl CommandRunner + 'static,
+        display: DisplayHandle,
+    ) -> Result<Self, Error> {
+        let runner: Box<dyn CommandRunner> = Box::new(runner);
+        let spy = Spy::with_runner(
+            &display.runner(runner.as_ref()),
+            &Target::Root,
+            &["_NET_ACTIVE_WINDOW"],
+        )?;
+        Ok(FocusWatcher {
+            runner,
+            display,
+            spy,
+            current: None,
+        })
+    }
+
+    /// Read the title and class of the newly focused window
+    fn focus(&self, id: Option<WindowId>) -> Result<Focus, Error> {
+        let Some(window) = id else {
+            return Ok(Focus {
+                id,
+                title: None,
+                class: None,
+            });
+        };
+        let runner = self.display.runner(self.runner.as_ref());
+        let props =
+            match Properties::read_with_runner(&runner, &Target::Id(window), FOCUS_PROPERTIES) {
+                // the window is already gone
+                Err(Error::Run(RunError::Exited { .. })) => Properties::default(),
+                props => props?,
+            };
+        Ok(Focus {
+            id,
+            title: props.title().map(str::to_owned),
+            class: props.wm_class(),
+        })
+    }
+}
+
+impl Iterator for FocusWatcher {
+    type Item = Result<Focus, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let id = match self.spy.next()? {
+                Ok(Change::Set(prop)) => {
+                    let props = Properties { props: vec![prop] };
+                    props
+                        .windows("_NET_ACTIVE_WINDOW")
+                        .and_then(|windows| windows.first().copied())
+                        .filter(|id| id.0 != 0)
+                },
+                Ok(Change::Removed(_)) => None,
+                Err(e) => return Some(Err(e)),
+            };
+            // window managers set the property again on every click into the focused window
+            if self.current.replace(id) != Some(id) {
+                return Some(self.focus(id));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{Cmd, MockRunner, Output};
+
+    use super::*;
+    use crate::spy::RAW_FORMATS;
+
+    #[test]
+    fn follow_focus() {
+        let mut spy = Cmd::new("xprop").args(["-root", "-spy"]);
+        for (name, format) in RAW_FORMATS {
+            spy = spy.args(["-f", name, format, " = $0+\n"]);
+        }
+        let read = |id: &str| {
+            Cmd::new("xprop")
+                .args(["-id", id])
+                .args(FOCUS_PROPERTIES.iter().copied())
+        };
+        let runner = MockRunner::new()
+            .respond(
+                spy.arg("_NET_ACTIVE_WINDOW"),
+                Output::from_stdout(concat!(
+                    "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x2a00006\n",
+                    "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x2a00006\n",
+                    "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n",
+                    "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x1a00002\n",
+                )),
+            )
+            .respond(
+                read("0x2a00006"),
+                Output::from_stdout(concat!(
+                    "_NET_WM_NAME(UTF8_STRING) = \"vim\"\n",
+                    "WM_NAME(STRING) = \"vim\"\n",
+                    "WM_CLASS(STRING) = \"st-256color\", \"st-256color\"\n",
+                )),
+            )
+            .respond(
+                read("0x1a00002"),
+                Output::from_failure(1, "X Error of failed request:  BadWindow\n"),
+            );
+        let focus = FocusWatcher::with_runner(runner, DisplayHandle::default())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(focus.len(), 3);
+        assert_eq!(focus[0].id, Some(WindowId(0x2a00006)));
+        assert_eq!(focus[0].title.as_deref(), Some("vim"));
+        assert_eq!(focus[0].class.as_ref().unwrap().class, "st-256color");
+        assert_eq!(focus[1].id, None);
+        assert_eq!(focus[2].id, Some(WindowId(0x1a00002)));
+        assert_eq!(focus[2].title, None);
+    }
+}
@@ -0,0 +1,91 @@
+//! Typed forms of the properties clients set for the window manager
+
+/// Instance and class of a window, from `WM_CLASS`
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WmClass {
+    /// Usually the name the program was run as, such as `st-256color`
+    pub instance: String,
+    /// Name of the application, as matched by window manager rules
+    pub class: String,
+}
+
+/// State a window asks to be mapped in
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum InitialState {
+    DontCare,
+    Normal,
+    Zoomed,
+    Iconic,
+    Inactive,
+}
+
+/// Hints of a client to the window manager, from `WM_HINTS`
+///
+/// Hints the client did not set are `None`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WmHints {
+    /// Whether the client accepts input focus
+    pub input: Option<bool>,
+    pub initial_state: Option<InitialState>,
+    /// Leader of the group of windows of the client
+    pub group_leader: Option<crate::WindowId>,
+    /// Whether the window asks for the attention of the user
+    pub urgent: bool,
+}
+
+impl WmHints {
+    /// Parse the lines xprop describes `WM_HINTS` with
+    pub(crate) fn parse(fields: &[String]) -> Self {
+        let mut hints = WmHints::default();
+        for field in fields {
+            if let Some(input) = field.strip_prefix("Client accepts input or input focus:") {
+                hints.input = Some(input.trim() == "True");
+            } else if let Some(state) = field.strip_prefix("Initial state is ") {
+                hints.initial_state = match state.trim_end_matches('.') {
+                    "Don't Care State" => Some(InitialState::DontCare),
+                    "Normal State" => Some(InitialState::Normal),
+                    "Zoomed State" => Some(InitialState::Zoomed),
+                    "Iconic State" => Some(InitialState::Iconic),
+                    "Inactive State" => Some(InitialState::Inactive),
+                    _ => None,
+                };
+            } else if let Some(id) = field.strip_prefix("window id # of group leader:") {
+                hints.group_leader = id.parse().ok();
+            } else if field.contains("urgency hint") {
+                hints.urgent = true;
+            }
+        }
+        hints
+    }
+
+    /// Hints from the raw fields of `WM_HINTS`, starting with the flags of the fields that are set
+    pub(crate) fn from_raw(fields: &[i64]) -> Self {
+        let flags = fields.first().copied().unwrap_or_default();
+        let field = |flag: i64, i: usize| {
+            (flags & flag != 0)
+                .then(|| fields.get(i).copied())
+                .flatten()
+        };
+        WmHints {
+            input: field(1 << 0, 1).map(|input| input != 0),
+            initial_state: field(1 << 1, 2).and_then(|state| match state {
+                0 => Some(InitialState::DontCare),
+                1 => Some(InitialState::Normal),
+                2 => Some(InitialState::Zoomed),
+                3 => Some(InitialState::Iconic),
+                4 => Some(InitialState::Inactive),
+                _ => None,
+            }),
+            group_leader: field(1 << 6, 8)
+                .and_then(|id| u32::try_from(id).ok())
+                .map(crate::WindowId),
+            urgent: flags & (1 << 8) != 0,
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! Read and write window properties through `xprop`
+//!
+//! ```rust,no_run
+//! use pino_xprop::{Properties, Target};
+//!
+//! # #[cfg(feature = "process")]
+//! fn main() {
+//!     let props = Properties::read(&Target::Select).unwrap();
+//!     if let Some(class) = props.wm_class() {
+//!         println!("{} ({})", class.class, class.instance);
+//!     }
+//! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    display::DisplayHandle,
+    platform, runner,
+    session::Session,
+    window::{Target, WindowId},
+};
+
+pub mod focus;
+pub mod hints;
+pub mod spy;
+
+use hints::{WmClass, WmHints};
+
+/// Errors of running xprop
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xprop could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xprop could not be parsed, holding the offending line
+    Malformed(String),
+    /// Property can not be written by xprop, holding the reason
+    InvalidValue(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xprop output: {}", line),
+            Error::InvalidValue(e) => write!(f, "invalid property value: {}", e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Value of a property, depending on its type
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Value {
+    /// `STRING`, `UTF8_STRING` and `COMPOUND_TEXT` properties
+    Strings(Vec<String>),
+    /// `CARDINAL` and `INTEGER` properties
+    Numbers(Vec<i64>),
+    /// Names of the atoms of `ATOM` properties
+    Atoms(Vec<String>),
+    /// `WINDOW` properties
+    Windows(Vec<WindowId>),
+    /// Lines of a property xprop describes in words, such as `WM_HINTS`
+    Fields(Vec<String>),
+    /// Any other property, as printed by xprop
+    Other(String),
+}
+
+/// Property of a window
+///
+/// Formatted the way xprop prints it, so it can be read back with [Properties::parse].
+///
+/// ```rust
+/// use pino_xprop::{Properties, Property, Value};
+///
+/// let prop = Property::utf8("_NET_WM_NAME", "say \"hi\"");
+/// assert_eq!(prop.to_string(), r#"_NET_WM_NAME(UTF8_STRING) = "say \"hi\"""#);
+/// assert_eq!(Properties::parse(&prop.to_string()).unwrap().get("_NET_WM_NAME"), Some(&prop));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Property {
+    pub name: String,
+    /// Type of the property, such as `STRING` or `CARDINAL`
+    pub kind: String,
+    pub value: Value,
+}
+
+impl Property {
+    /// Construct a `STRING` property
+    pub fn string(name: &str, value: &str) -> Self {
+        Property::new(name, "STRING", Value::Strings(vec![value.to_owned()]))
+    }
+
+    /// Construct a `UTF8_STRING` property
+    pub fn utf8(name: &str, value: &str) -> Self {
+        Property::new(name, "UTF8_STRING", Value::Strings(vec![value.to_owned()]))
+    }
+
+    /// Construct a `CARDINAL` property
+    pub fn cardinal(name: &str, value: u32) -> Self {
+        Property::new(name, "CARDINAL", Value::Numbers(vec![value.into()]))
+    }
+
+    /// Construct an `ATOM` property
+    pub fn atom(name: &str, value: &str) -> Self {
+        Property::new(name, "ATOM", Value::Atoms(vec![value.to_owned()]))
+    }
+
+    fn new(name: &str, kind: &str, value: Value) -> Self {
+        Property {
+            name: name.to_owned(),
+            kind: kind.to_owned(),
+            value,
+        }
+    }
+
+    /// Parse the line of a property and the indented lines following it
+    ///
+    /// Properties xprop reports as not set are `None`.
+    fn parse(line: &str, rest: &[&str]) -> Result<Option<Self>, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let Some((name, tail)) = line.split_once('(') else {
+            if line.contains("not found") || line.contains("no such atom") {
+                return Ok(None);
+            }
+            return Err(malformed());
+        };
+        let (kind, tail) = tail.split_once(')').ok_or_else(malformed)?;
+        let text = tail
+            .trim_start()
+            .strip_prefix(['=', ':'])
+            .ok_or_else(malformed)?
+            .trim();
+
+        let value = if !rest.is_empty() {
+            let lines = std::iter::once(text).filter(|t| !t.is_empty());
+            Value::Fields(
+                lines
+                    .chain(rest.iter().copied())
+                    .map(String::from)
+                    .collect(),
+            )
+        } else {
+            match kind {
+                "STRING" | "UTF8_STRING" | "COMPOUND_TEXT" | "TEXT" => parse_strings(text)
+                    .map(Value::Strings)
+                    .unwrap_or_else(|| Value::Other(text.to_owned())),
+                // xprop labels some atom lists, as in `WM_PROTOCOLS(ATOM): protocols  WM_TAKE_FOCUS`
+                "ATOM" => {
+                    let atoms = match tail.trim_start().starts_with(':') {
+                        true => text.split_once("  ").map_or(text, |(_, atoms)| atoms),
+                        false => text,
+                    };
+                    Value::Atoms(split_list(atoms).map(String::from).collect())
+                },
+                "WINDOW" => split_list(text.trim_start_matches("window id #"))
+                    .map(|id| id.parse())
+                    .collect::<Result<_, _>>()
+                    .map(Value::Windows)
+                    .map_err(|_| malformed())?,
+                _ => split_list(text)
+                    .map(parse_number)
+                    .collect::<Option<_>>()
+                    .map(Value::Numbers)
+                    .unwrap_or_else(|| Value::Other(text.to_owned())),
+            }
+        };
+        Ok(Some(Property::new(name.trim(), kind, value)))
+    }
+
+    /// Format and value of `xprop -f NAME FORMAT -set NAME VALUE`
+    fn set_args(&self) -> Result<(&'static str, String), Error> {
+        let format = match self.kind.as_str() {
+            "STRING" => "8s",
+            "UTF8_STRING" => "8u",
+            "COMPOUND_TEXT" => "8t",
+            "CARDINAL" => "32c",
+            "INTEGER" => "32i",
+            "ATOM" => "32a",
+            kind => {
+                return Err(Error::InvalidValue(format!(
+                    "xprop can not set {} properties",
+                    kind
+                )))
+            },
+        };
+        let value = match &self.value {
+            Value::Strings(v) | Value::Atoms(v) if v.len() == 1 => v[0].clone(),
+            Value::Numbers(v) if v.len() == 1 => v[0].to_string(),
+            _ => {
+                return Err(Error::InvalidValue(String::from(
+                    "xprop only sets a single string, number or atom",
+                )))
+            },
+        };
+        Ok((format, value))
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, self.kind)?;
+        match &self.value {
+            Value::Strings(v) => {
+                let quoted = v.iter().map(|s| format!("{:?}", s)).collect::<Vec<_>>();
+                write!(f, " = {}", quoted.join(", "))
+            },
+            Value::Numbers(v) => {
+                let numbers = v.iter().map(i64::to_string).collect::<Vec<_>>();
+                write!(f, " = {}", numbers.join(", "))
+            },
+            Value::Atoms(v) => write!(f, " = {}", v.join(", ")),
+            Value::Windows(v) => {
+                let ids = v.iter().map(WindowId::to_string).collect::<Vec<_>>();
+                write!(f, ": window id # {}", ids.join(", "))
+            },
+            Value::Fields(v) => {
+                write!(f, ":")?;
+                for field in v {
+                    write!(f, "\n\t\t{}", field)?;
+                }
+                Ok(())
+            },
+            Value::Other(text) => write!(f, " = {}", text),
+        }
+    }
+}
+
+/// Items of a list printed by xprop, separated by commas
+fn split_list(text: &str) -> impl Iterator<Item = &str> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Decimal or `0x` prefixed hexadecimal number
+fn parse_number(text: &str) -> Option<i64> {
+    match text.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Quoted strings separated by commas, `None` if they are not quoted
+fn parse_strings(text: &str) -> Option<Vec<String>> {
+    let mut strings = vec![];
+    let mut chars = text.chars();
+    loop {
+        match chars.by_ref().find(|c| !c.is_whitespace() && *c != ',') {
+            Some('"') => {},
+            Some(_) => return None,
+            None => return Some(strings),
+        }
+        let mut s = String::new();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+        strings.push(s);
+    }
+}
+
+/// Properties of a window, as read by xprop
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Properties {
+    props: Vec<Property>,
+}
+
+impl Properties {
+    /// Parse the output of xprop
+    ///
+    /// ```rust
+    /// use pino_xprop::{Properties, WindowId};
+    ///
+    /// let props = Properties::parse(concat!(
+    ///     "WM_CLASS(STRING) = \"st-256color\", \"st-256color\"\n",
+    ///     "_NET_WM_PID(CARDINAL) = 4242\n",
+    ///     "WM_TRANSIENT_FOR(WINDOW): window id # 0x2a00006\n",
+    ///     "WM_NAME:  not found.\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(props.wm_class().unwrap().class, "st-256color");
+    /// assert_eq!(props.pid(), Some(4242));
+    /// assert_eq!(props.transient_for(), Some(WindowId(0x2a00006)));
+    /// assert_eq!(props.get("WM_NAME"), None);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut props = vec![];
+        let mut lines = raw.lines().filter(|l| !l.trim().is_empty()).peekable();
+        while let Some(line) = lines.next() {
+            let mut rest = vec![];
+            while let Some(next) = lines.next_if(|l| l.starts_with(char::is_whitespace)) {
+                rest.push(next.trim());
+            }
+            props.extend(Property::parse(line, &rest)?);
+        }
+        Ok(Properties { props })
+    }
+
+    /// Read every property of the target window
+    ///
+    /// [Target::Select] waits for the user to click a window.
+    #[cfg(feature = "process")]
+    pub fn read(target: &Target) -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::read_with_runner(
+            &display.runner(&runner::SystemRunner::default()),
+            target,
+            &[],
+        )
+    }
+
+    /// Read the given properties of the target window, or all of them if `names` is empty,
+    /// running `xprop` through the given runner
+    ///
+    /// ```rust
+    /// use pino_xprop::{runner::{Cmd, MockRunner, Output}, Properties, Target, WindowId};
+    ///
+    /// let runner = MockRunner::new().respond(
+    ///     Cmd::new("xprop").args(["-id", "0x2a00006", "_NET_WM_NAME"]),
+    ///     Output::from_stdout("_NET_WM_NAME(UTF8_STRING) = \"~ - st\"\n"),
+    /// );
+    /// let target = Target::Id(WindowId(0x2a00006));
+    /// let props = Properties::read_with_runner(&runner, &target, &["_NET_WM_NAME"]).unwrap();
+    ///
+    /// assert_eq!(props.title(), Some("~ - st"));
+    /// ```
+    pub fn read_with_runner(
+        runner: &dyn CommandRunner,
+        target: &Target,
+        names: &[&str],
+    ) -> Result<Self, Error> {
+        let output = run_checked(runner, &xprop(target).args(names.iter().copied()))?;
+        let raw = String::from_utf8_lossy(&output.stdout);
+        Self::parse(&raw)
+    }
+
+    /// Property with the given name
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.props.iter().find(|prop| prop.name == name)
+    }
+
+    /// Every property, in the order xprop printed them
+    pub fn iter(&self) -> impl Iterator<Item = &Property> {
+        self.props.iter()
+    }
+
+    /// Strings of a string property
+    pub fn strings(&self, name: &str) -> Option<&[String]> {
+        match &self.get(name)?.value {
+            Value::Strings(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Numbers of a `CARDINAL` or `INTEGER` property
+    pub fn numbers(&self, name: &str) -> Option<&[i64]> {
+        match &self.get(name)?.value {
+            Value::Numbers(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Atom names of an `ATOM` property
+    pub fn atoms(&self, name: &str) -> Option<&[String]> {
+        match &self.get(name)?.value {
+            Value::Atoms(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Windows of a `WINDOW` property
+    pub fn windows(&self, name: &str) -> Option<&[WindowId]> {
+        match &self.get(name)?.value {
+            Value::Windows(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Instance and class of the window, from `WM_CLASS`
+    pub fn wm_class(&self) -> Option<WmClass> {
+        match self.strings("WM_CLASS")? {
+            [instance, class, ..] => Some(WmClass {
+                instance: instance.clone(),
+                class: class.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Title of the window, from `_NET_WM_NAME` or else `WM_NAME`
+    pub fn title(&self) -> Option<&str> {
+        self.strings("_NET_WM_NAME")
+            .or_else(|| self.strings("WM_NAME"))?
+            .first()
+            .map(String::as_str)
+    }
+
+    /// Process id of the client, from `_NET_WM_PID`
+    pub fn pid(&self) -> Option<u32> {
+        self.numbers("_NET_WM_PID")?
+            .first()?
+            .to_owned()
+            .try_into()
+            .ok()
+    }
+
+    /// Desktop the window is on, from `_NET_WM_DESKTOP`
+    pub fn desktop(&self) -> Option<u32> {
+        self.numbers("_NET_WM_DESKTOP")?
+            .first()?
+            .to_owned()
+            .try_into()
+            .ok()
+    }
+
+    /// Types of the window, such as `_NET_WM_WINDOW_TYPE_DIALOG`, from `_NET_WM_WINDOW_TYPE`
+    pub fn window_type(&self) -> &[String] {
+        self.atoms("_NET_WM_WINDOW_TYPE").unwrap_or_default()
+    }
+
+    /// States of the window, such as `_NET_WM_STATE_FULLSCREEN`, from `_NET_WM_STATE`
+    pub fn state(&self) -> &[String] {
+        self.atoms("_NET_WM_STATE").unwrap_or_default()
+    }
+
+    /// Window this one is a dialog of, from `WM_TRANSIENT_FOR`
+    pub fn transient_for(&self) -> Option<WindowId> {
+        self.windows("WM_TRANSIENT_FOR")?.first().copied()
+    }
+
+    /// Hints of the client to the window manager, from `WM_HINTS`
+    ///
+    /// Both the description xprop prints by default and the raw fields printed by [spy::Spy]
+    /// are understood.
+    pub fn wm_hints(&self) -> Option<WmHints> {
+        match &self.get("WM_HINTS")?.value {
+            Value::Fields(fields) => Some(WmHints::parse(fields)),
+            Value::Numbers(fields) => Some(WmHints::from_raw(fields)),
+            _ => None,
+        }
+    }
+}
+
+/// Set a property of the target window
+///
+/// ```rust,no_run
+/// use pino_xprop::{Property, Target};
+///
+/// pino_xprop::set(&Target::Root, &Property::utf8("_PINO_THEME", "gruvbox")).unwrap();
+/// ```
+#[cfg(feature = "process")]
+pub fn set(target: &Target, prop: &Property) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        target,
+        prop,
+    )
+}
+
+/// Set a property of the target window, running `xprop` through the given runner
+///
+/// Only a single string, number or atom can be set, other properties fail with
+/// [Error::InvalidValue].
+///
+/// ```rust
+/// use pino_xprop::{runner::{Cmd, MockRunner, Output}, Property, Target};
+///
+/// let cmd = Cmd::new("xprop").args(["-root", "-f", "_PINO_GAP", "32c", "-set", "_PINO_GAP", "8"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// pino_xprop::set_with_runner(&runner, &Target::Root, &Property::cardinal("_PINO_GAP", 8)).unwrap();
+/// ```
+pub fn set_with_runner(
+    runner: &dyn CommandRunner,
+    target: &Target,
+    prop: &Property,
+) -> Result<(), Error> {
+    let (format, value) = prop.set_args()?;
+    let cmd = xprop(target)
+        .args(["-f", &prop.name, format])
+        .args(["-set", &prop.name, &value]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Delete a property of the target window
+#[cfg(feature = "process")]
+pub fn remove(target: &Target, name: &str) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    remove_with_runner(
+        &display.runner(&runner::SystemRunner::default()),
+        target,
+        name,
+    )
+}
+
+/// Delete a property of the target window, running `xprop` through the given runner
+pub fn remove_with_runner(
+    runner: &dyn CommandRunner,
+    target: &Target,
+    name: &str,
+) -> Result<(), Error> {
+    run_checked(runner, &xprop(target).args(["-remove", name]))?;
+    Ok(())
+}
+
+/// Command running xprop on the target window
+fn xprop(target: &Target) -> Cmd {
+    let cmd = Cmd::new("xprop").args(target.args());
+    match target.timeout() {
+        Some(timeout) => cmd.timeout(timeout),
+        None => cmd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::MockRunner;
+
+    use super::*;
+
+    const WINDOW: &str = r#"_NET_WM_STATE(ATOM) = _NET_WM_STATE_FOCUSED, _NET_WM_STATE_FULLSCREEN
+WM_HINTS(WM_HINTS):
+		Client accepts input or input focus: True
+		Initial state is Normal State.
+		window id # of group leader: 0x2a00001
+		The urgency hint bit is set
+_NET_WM_NAME(UTF8_STRING) = "a \"quoted\", title"
+_MOTIF_WM_HINTS(_MOTIF_WM_HINTS) = 0x2, 0x0, 0x0, 0x0, 0x0
+WM_ICON_NAME(STRING) =
+WM_PROTOCOLS(ATOM): protocols  WM_DELETE_WINDOW, WM_TAKE_FOCUS, _NET_WM_PING
+"#;
+
+    #[test]
+    fn parse_properties() {
+        let props = Properties::parse(WINDOW).unwrap();
+        assert_eq!(
+            props.state(),
+            ["_NET_WM_STATE_FOCUSED", "_NET_WM_STATE_FULLSCREEN"]
+        );
+        assert_eq!(props.title(), Some("a \"quoted\", title"));
+        assert_eq!(props.numbers("_MOTIF_WM_HINTS"), Some(&[2, 0, 0, 0, 0][..]));
+        assert_eq!(props.strings("WM_ICON_NAME"), Some(&[][..]));
+        assert_eq!(
+            props.atoms("WM_PROTOCOLS").unwrap(),
+            ["WM_DELETE_WINDOW", "WM_TAKE_FOCUS", "_NET_WM_PING"]
+        );
+
+        let hints = props.wm_hints().unwrap();
+        assert_eq!(hints.input, Some(true));
+        assert_eq!(hints.initial_state, Some(hints::InitialState::Normal));
+        assert_eq!(hints.group_leader, Some(WindowId(0x2a00001)));
+        assert!(hints.urgent);
+
+        assert!(Properties::parse("garbage").is_err());
+    }
+
+    #[test]
+    fn set_invalid() {
+        let runner = MockRunner::new();
+        let prop = Property {
+            name: String::from("WM_CLASS"),
+            kind: String::from("STRING"),
+            value: Value::Strings(vec![String::from("a"), String::from("b")]),
+        };
+        let err = set_with_runner(&runner, &Target::Select, &prop);
+        assert!(matches!(err, Err(Error::InvalidValue(_))));
+        assert!(runner.calls().is_empty());
+
+        let err = remove_with_runner(&runner, &Target::Name(String::from("st")), "WM_NAME");
+        assert!(matches!(err, Err(Error::Run(RunError::Spawn { .. }))));
+        assert_eq!(
+            runner.calls()[0].args,
+            ["-name", "st", "-remove", "WM_NAME"]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Follow changes to the properties of a window, like `xprop -spy`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xprop::{spy::{Change, Spy}, Target, WindowId};
+//!
+//! let spy = Spy::new(&Target::Id(WindowId(0x2a00006)), &["_NET_WM_NAME", "WM_HINTS"]).unwrap();
+//! for change in spy {
+//!     match change.unwrap() {
+//!         Change::Set(prop) => println!("{}", prop),
+//!         Change::Removed(name) => println!("{} removed", name),
+//!     }
+//! }
+//! # }
+//! ```
+
+use pino_xcommon::runner::{check_output, Cmd, CommandRunner, Process, RunError};
+
+use crate::{Error, Property, Target};
+
+/// Properties xprop describes over several lines, printed as their raw fields instead so every
+/// change is a single line
+pub static RAW_FORMATS: &[(&str, &str)] = &[
+    ("WM_HINTS", "32i"),
+    ("WM_NORMAL_HINTS", "32i"),
+    ("WM_ICON_SIZE", "32c"),
+    ("_NET_WM_ICON", "32c"),
+];
+
+/// Change to a property of the spied on window
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Change {
+    /// Property was set to a new value
+    Set(Property),
+    /// Property was deleted, holding its name
+    Removed(String),
+}
+
+/// Stream of the changes to the properties of a window
+///
+/// The current value of each property is yielded first, then every change as it happens. The
+/// stream ends when the window is destroyed, and xprop is killed when it is dropped.
+pub struct Spy {
+    process: Box<dyn Process>,
+    line: String,
+    done: bool,
+}
+
+impl Spy {
+    /// Spy on the given properties of the target window, or all of them if `names` is empty
+    #[cfg(feature = "process")]
+    pub fn new(target: &Target, names: &[&str]) -> Result<Self, Error> {
+        let display = crate::DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        let runner = crate::runner::SystemRunner::new().timeout(None);
+        Self::with_runner(&display.runner(&runner), target, names)
+    }
+
+    /// Spy on the target window, running `xprop` through the given runner
+    ///
+    /// xprop runs until the window is destroyed, so the runner should not time it out.
+    ///
+    /// ```rust
+    /// use pino_xprop::{runner::{Cmd, MockRunner, Output}, spy::{Change, Spy, RAW_FORMATS}, Target};
+    ///
+    /// let mut cmd = Cmd::new("xprop").args(["-root", "-spy"]);
+    /// for (name, format) in RAW_FORMATS {
+    ///     cmd = cmd.args(["-f", name, format, " = $0+\n"]);
+    /// }
+    /// let runner = MockRunner::new().respond(
+    ///     cmd.arg("_NET_ACTIVE_WINDOW"),
+    ///     Output::from_stdout("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x2a00006\n"),
+    /// );
+    /// let mut spy = Spy::with_runner(&runner, &Target::Root, &["_NET_ACTIVE_WINDOW"]).unwrap();
+    ///
+    /// assert!(matches!(spy.next(), Some(Ok(Change::Set(_)))));
+    /// assert!(spy.next().is_none());
+    /// ```
+    pub fn with_runner(
+        runner: &dyn CommandRunner,
+        target: &Target,
+        names: &[&str],
+    ) -> Result<Self, Error> {
+        let mut cmd = Cmd::new("xprop").args(target.args()).arg("-spy");
+        for (name, format) in RAW_FORMATS {
+            cmd = cmd.args(["-f", name, format, " = $0+\n"]);
+        }
+        let process = runner
+            .spawn(&cmd.args(names.iter().copied()))
+            .map_err(|e| RunError::from_io("xprop", e))?;
+        Ok(Spy {
+            process,
+            line: String::new(),
+            done: false,
+        })
+    }
+
+    /// Error xprop exited with, once its output is exhausted
+    fn finish(&mut self) -> Option<Result<Change, Error>> {
+        self.done = true;
+        match self.process.wait() {
+            Ok(output) => check_output("xprop", output).err().map(|e| Err(e.into())),
+            Err(e) => Some(Err(RunError::from_io("xprop", e).into())),
+        }
+    }
+}
+
+impl Iterator for Spy {
+    type Item = Result<Change, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            self.line.clear();
+            match self.process.stdout().read_line(&mut self.line) {
+                Ok(0) => return self.finish(),
+                Ok(_) => {},
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(RunError::from_io("xprop", e).into()));
+                },
+            }
+            // continuation lines of properties missing from RAW_FORMATS can not be told apart
+            // from the end of a change without blocking, so they are skipped
+            let line = self.line.trim_end();
+            if line.is_empty() || line.starts_with(char::is_whitespace) {
+                continue;
+            }
+            return Some(match Property::parse(line, &[]) {
+                Ok(Some(prop)) => Ok(Change::Set(prop)),
+                Ok(None) => Ok(Change::Removed(
+                    line.split_once(':')
+                        .map_or(line, |(name, _)| name)
+                        .to_owned(),
+                )),
+                Err(e) => Err(e),
+            });
+        }
+    }
+}
+
+impl Drop for Spy {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.process.kill();
+            let _ = self.process.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn spy_changes() {
+        let mut cmd = Cmd::new("xprop").args(["-name", "st", "-spy"]);
+        for (name, format) in RAW_FORMATS {
+            cmd = cmd.args(["-f", name, format, " = $0+\n"]);
+        }
+        let stdout = concat!(
+            "WM_HINTS(WM_HINTS) = 67, 1, 1, 0, 0, 0, 0, 0, 44040193\n",
+            "_NET_WM_NAME(UTF8_STRING) = \"vim\"\n",
+            "WM_HINTS(WM_HINTS) = 259, 1, 1, 0, 0, 0, 0, 0, 44040193\n",
+            "_NET_WM_NAME:  not found.\n",
+        );
+        let runner = MockRunner::new().respond(cmd, Output::from_stdout(stdout));
+        let target = Target::Name(String::from("st"));
+        let changes = Spy::with_runner(&runner, &target, &[])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes[1],
+            Change::Set(Property::utf8("_NET_WM_NAME", "vim"))
+        );
+        assert_eq!(changes[3], Change::Removed(String::from("_NET_WM_NAME")));
+
+        let hints = |change: &Change| match change {
+            Change::Set(prop) => crate::Properties {
+                props: vec![prop.clone()],
+            }
+            .wm_hints()
+            .unwrap(),
+            _ => unreachable!(),
+        };
+        assert!(!hints(&changes[0]).urgent);
+        assert_eq!(
+            hints(&changes[0]).group_leader,
+            Some(crate::WindowId(0x2a00001))
+        );
+        assert!(hints(&changes[2]).urgent);
+    }
+}
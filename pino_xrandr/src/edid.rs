@@ -0,0 +1,241 @@
+//! Identify monitors by their EDID, as printed by `xrandr --verbose`
+//!
+//! Output names change with the port a monitor is plugged into, while the manufacturer, product
+//! code and serial number of its EDID do not.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xrandr::{edid::Edid, Screen};
+//!
+//! for screen in Screen::query_verbose().unwrap() {
+//!     for output in screen.connected() {
+//!         if let Some(edid) = output.edid.as_deref().and_then(|raw| Edid::parse(raw).ok()) {
+//!             println!("{} is {} ({})", output.name, edid.name.as_deref().unwrap_or("unknown"), edid.id());
+//!         }
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::{Error, Size};
+
+/// Header every EDID starts with
+const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Mode a monitor supports, from the timings of its EDID
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Timing {
+    pub size: Size,
+    /// Refresh rate in Hz
+    pub refresh: f32,
+}
+
+/// Contents of the base block of an EDID
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Edid {
+    /// Three letter PNP id of the manufacturer, such as `DEL` or `SAM`
+    pub manufacturer: String,
+    pub product: u16,
+    /// Serial number, `0` if the monitor only reports [Edid::serial_text]
+    pub serial: u32,
+    /// Serial number given as text
+    pub serial_text: Option<String>,
+    /// Model name, such as `DELL U2720Q`
+    pub name: Option<String>,
+    /// Year of manufacture
+    pub year: u16,
+    /// Version and revision of the EDID, such as `(1, 4)`
+    pub version: (u8, u8),
+    /// Physical size in centimetres, `None` for projectors
+    pub physical_size: Option<Size>,
+    /// Modes from the detailed timings, the preferred one first, followed by the standard timings
+    pub timings: Vec<Timing>,
+}
+
+impl Edid {
+    /// Parse the base block of an EDID, extension blocks are ignored
+    ///
+    /// ```rust
+    /// use pino_xrandr::edid::Edid;
+    ///
+    /// let mut raw = vec![0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x10, 0xac];
+    /// raw.resize(128, 0);
+    /// raw[127] = 0u8.wrapping_sub(raw.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)));
+    ///
+    /// assert_eq!(Edid::parse(&raw).unwrap().manufacturer, "DEL");
+    /// raw[127] ^= 1;
+    /// assert!(Edid::parse(&raw).is_err());
+    /// ```
+    pub fn parse(raw: &[u8]) -> Result<Self, Error> {
+        let invalid = |reason: &str| Error::Malformed(format!("invalid EDID: {}", reason));
+        let base = raw
+            .get(..128)
+            .ok_or_else(|| invalid("shorter than 128 bytes"))?;
+        if base[..8] != HEADER {
+            return Err(invalid("missing header"));
+        }
+        if base.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+            return Err(invalid("wrong checksum"));
+        }
+
+        // three letters of five bits each, starting at `A` = 1
+        let id = u16::from_be_bytes([base[8], base[9]]);
+        let manufacturer = [10, 5, 0]
+            .iter()
+            .map(|shift| char::from(b'@' + ((id >> shift) & 0x1f) as u8))
+            .collect();
+        let mut edid = Edid {
+            manufacturer,
+            product: u16::from_le_bytes([base[10], base[11]]),
+            serial: u32::from_le_bytes([base[12], base[13], base[14], base[15]]),
+            year: 1990 + u16::from(base[17]),
+            version: (base[18], base[19]),
+            physical_size: (base[21] != 0 && base[22] != 0).then(|| Size {
+                width: base[21].into(),
+                height: base[22].into(),
+            }),
+            ..Default::default()
+        };
+
+        for desc in base[54..126].chunks(18) {
+            if desc[0] != 0 || desc[1] != 0 {
+                edid.timings.extend(detailed_timing(desc));
+                continue;
+            }
+            let text = desc[5..]
+                .split(|b| *b == b'\n')
+                .next()
+                .map(|text| String::from_utf8_lossy(text).trim().to_owned());
+            match desc[3] {
+                0xff => edid.serial_text = text,
+                0xfc => edid.name = text,
+                _ => {},
+            }
+        }
+        for std in base[38..54].chunks(2) {
+            edid.timings
+                .extend(standard_timing(std[0], std[1], edid.version));
+        }
+        Ok(edid)
+    }
+
+    /// Identifier of the monitor that does not depend on the port it is plugged into
+    ///
+    /// Made of the manufacturer, product code and serial number, so two monitors of the same
+    /// model are told apart as long as they report a serial number.
+    pub fn id(&self) -> String {
+        let serial = match &self.serial_text {
+            Some(serial) => serial.clone(),
+            None => self.serial.to_string(),
+        };
+        format!("{}-{:04X}-{}", self.manufacturer, self.product, serial)
+    }
+}
+
+/// Mode of an 18 byte detailed timing descriptor
+fn detailed_timing(desc: &[u8]) -> Option<Timing> {
+    let clock = u32::from(u16::from_le_bytes([desc[0], desc[1]])) * 10_000;
+    let width = u32::from(desc[2]) | (u32::from(desc[4] & 0xf0) << 4);
+    let hblank = u32::from(desc[3]) | (u32::from(desc[4] & 0x0f) << 8);
+    let height = u32::from(desc[5]) | (u32::from(desc[7] & 0xf0) << 4);
+    let vblank = u32::from(desc[6]) | (u32::from(desc[7] & 0x0f) << 8);
+    let total = (width + hblank) * (height + vblank);
+    (total > 0).then(|| Timing {
+        size: Size { width, height },
+        refresh: clock as f32 / total as f32,
+    })
+}
+
+/// Mode of a two byte standard timing, `None` if the slot is unused
+fn standard_timing(first: u8, second: u8, version: (u8, u8)) -> Option<Timing> {
+    if first <= 1 {
+        return None;
+    }
+    let width = (u32::from(first) + 31) * 8;
+    let height = match second >> 6 {
+        // EDID before 1.3 used this for 1:1
+        0 if version < (1, 3) => width,
+        0 => width * 10 / 16,
+        1 => width * 3 / 4,
+        2 => width * 4 / 5,
+        _ => width * 9 / 16,
+    };
+    Some(Timing {
+        size: Size { width, height },
+        refresh: f32::from(second & 0x3f) + 60.0,
+    })
+}
+
+/// Bytes of an EDID printed in hexadecimal, such as the lines following `EDID:`
+pub(crate) fn parse_hex(line: &str) -> Option<Vec<u8>> {
+    if line.is_empty() || !line.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..line.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(line.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // verbose output for a Dell U2415
+    const VERBOSE: &str = "\
+Screen 0: minimum 320 x 200, current 1920 x 1200, maximum 16384 x 16384
+DP-1 connected 1920x1200+0+0 (0x45) normal (normal left inverted right x axis y axis) 518mm x 324mm
+\tEDID: 
+\t\t00ffffffffffff0010acbaa04c4b3530
+\t\t2a1b0104a5342078e2ca95a6554ea126
+\t\t0f5054a54b00714f8180a9c0d1c00101
+\t\t010101010101283c80a070b023403020
+\t\t360040846300001a000000ff00434b4b
+\t\t50343736543035354c0a000000fc0044
+\t\t454c4c2055323431350a2020000000fd
+\t\t00313d1e5311000a2020202020200147
+\tBrightness: 1.0
+";
+
+    #[test]
+    fn parse_edid() {
+        let screens = crate::Screen::parse(VERBOSE).unwrap();
+        let output = &screens[0].outputs[0];
+        assert_eq!(output.brightness, Some(1.0));
+        let edid = Edid::parse(output.edid.as_ref().unwrap()).unwrap();
+        assert_eq!(edid.manufacturer, "DEL");
+        assert_eq!(edid.product, 0xa0ba);
+        assert_eq!(edid.name.as_deref(), Some("DELL U2415"));
+        assert_eq!(edid.id(), "DEL-A0BA-CKKP476T055L");
+        assert_eq!(edid.year, 2017);
+        assert_eq!(edid.version, (1, 4));
+        assert_eq!(
+            edid.physical_size,
+            Some(Size {
+                width: 52,
+                height: 32
+            })
+        );
+
+        let preferred = edid.timings[0];
+        assert_eq!(
+            preferred.size,
+            Size {
+                width: 1920,
+                height: 1200
+            }
+        );
+        assert!((preferred.refresh - 59.95).abs() < 0.01);
+        assert_eq!(
+            edid.timings[1].size,
+            Size {
+                width: 1152,
+                height: 864
+            }
+        );
+    }
+}
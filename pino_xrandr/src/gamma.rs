@@ -0,0 +1,76 @@
+//! Adjust the brightness and gamma of an output, for night light and dimming tools
+//!
+//! These are applied in software by scaling the gamma ramp of the output, they do not change the
+//! backlight. The current values are read back through [Screen::query_verbose](crate::Screen).
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xrandr::gamma::{self, Gamma};
+//!
+//! gamma::set_brightness("eDP-1", 0.8).unwrap();
+//! gamma::set_gamma("eDP-1", Gamma { red: 1.0, green: 0.85, blue: 0.7 }).unwrap();
+//! # }
+//! ```
+
+pub use pino_xcommon::gamma::Gamma;
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::Error;
+
+/// Scale the colors of an output by `brightness`, `1.0` being unchanged
+#[cfg(feature = "process")]
+pub fn set_brightness(output: &str, brightness: f32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_brightness_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        output,
+        brightness,
+    )
+}
+
+/// Scale the colors of an output, running `xrandr` through the given runner
+///
+/// ```rust
+/// use pino_xrandr::{gamma, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xrandr").args(["--output", "eDP-1", "--brightness", "0.8"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// gamma::set_brightness_with_runner(&runner, "eDP-1", 0.8).unwrap();
+/// ```
+pub fn set_brightness_with_runner(
+    runner: &dyn CommandRunner,
+    output: &str,
+    brightness: f32,
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xrandr")
+        .args(["--output", output])
+        .args(["--brightness", &brightness.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Set the gamma correction of an output
+#[cfg(feature = "process")]
+pub fn set_gamma(output: &str, gamma: Gamma) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_gamma_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        output,
+        gamma,
+    )
+}
+
+/// Set the gamma correction of an output, running `xrandr` through the given runner
+pub fn set_gamma_with_runner(
+    runner: &dyn CommandRunner,
+    output: &str,
+    gamma: Gamma,
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xrandr")
+        .args(["--output", output])
+        .args(["--gamma", &gamma.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
@@ -0,0 +1,307 @@
+//! Apply a layout of outputs in a single run of xrandr, such as the profile of a docking station
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xrandr::{layout::{Layout, OutputLayout}, Rotation, Size};
+//!
+//! let docked = Layout::new()
+//!     .output(OutputLayout::new("eDP-1").off())
+//!     .output(
+//!         OutputLayout::new("DP-1")
+//!             .mode(Size { width: 2560, height: 1440 })
+//!             .rate(144.0)
+//!             .primary(),
+//!     )
+//!     .output(
+//!         OutputLayout::new("HDMI-1")
+//!             .position(2560, 0)
+//!             .rotation(Rotation::Left),
+//!     );
+//! docked.apply().unwrap();
+//! # }
+//! ```
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{Error, Rotation, Screen, Size};
+
+/// Configuration of a single output, the settings left unset are chosen by xrandr
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OutputLayout {
+    pub name: String,
+    /// Whether the output is turned on
+    pub enabled: bool,
+    /// Size of the mode, the preferred one if `None`
+    pub mode: Option<Size>,
+    /// Name of the mode, used instead of its size for modes not named after it such as
+    /// `1920x1080i`
+    pub mode_name: Option<String>,
+    /// Refresh rate in Hz
+    pub rate: Option<f32>,
+    /// Position of the top left corner on the screen
+    pub position: Option<(i32, i32)>,
+    pub rotation: Option<Rotation>,
+    /// Horizontal and vertical scale, above 1 to show more of the screen
+    pub scale: Option<(f32, f32)>,
+    pub primary: bool,
+}
+
+impl OutputLayout {
+    /// Construct the layout of an output turned on at its preferred mode
+    pub fn new(name: &str) -> Self {
+        OutputLayout {
+            name: name.to_owned(),
+            enabled: true,
+            mode: None,
+            mode_name: None,
+            rate: None,
+            position: None,
+            rotation: None,
+            scale: None,
+            primary: false,
+        }
+    }
+
+    /// Turn the output off, ignoring every other setting
+    pub fn off(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Drive the output at the mode of the given size
+    pub fn mode(mut self, mode: Size) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Drive the output at the mode of the given name, as listed by xrandr
+    pub fn mode_name(mut self, name: &str) -> Self {
+        self.mode_name = Some(name.to_owned());
+        self
+    }
+
+    /// Pick the mode with the given refresh rate
+    pub fn rate(mut self, rate: f32) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Place the top left corner of the output at `x`, `y` on the screen
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Rotate the output
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Scale both directions by `scale`
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = Some((scale, scale));
+        self
+    }
+
+    /// Make the output the primary one
+    pub fn primary(mut self) -> Self {
+        self.primary = true;
+        self
+    }
+
+    /// Arguments of xrandr configuring the output
+    fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("--output"), self.name.clone()];
+        if !self.enabled {
+            args.push(String::from("--off"));
+            return args;
+        }
+        match (&self.mode_name, self.mode) {
+            (Some(name), _) => args.extend([String::from("--mode"), name.clone()]),
+            (None, Some(mode)) => args.extend([String::from("--mode"), mode.to_string()]),
+            (None, None) => args.push(String::from("--auto")),
+        }
+        if let Some(rate) = self.rate {
+            args.extend([String::from("--rate"), rate.to_string()]);
+        }
+        if let Some((x, y)) = self.position {
+            args.extend([String::from("--pos"), format!("{}x{}", x, y)]);
+        }
+        if let Some(rotation) = self.rotation {
+            args.extend([String::from("--rotate"), rotation.to_string()]);
+        }
+        if let Some((x, y)) = self.scale {
+            args.extend([String::from("--scale"), format!("{}x{}", x, y)]);
+        }
+        if self.primary {
+            args.push(String::from("--primary"));
+        }
+        args
+    }
+}
+
+/// Configuration of several outputs, outputs that are not part of it are left as they are
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Layout {
+    pub outputs: Vec<OutputLayout>,
+}
+
+impl Layout {
+    /// Construct a layout without any outputs
+    pub fn new() -> Self {
+        Layout::default()
+    }
+
+    /// Add an output to the layout
+    pub fn output(mut self, output: OutputLayout) -> Self {
+        self.outputs.push(output);
+        self
+    }
+
+    /// Current layout of the connected outputs of a screen, to be saved and applied again later
+    ///
+    /// The scale of an output is recovered from how much of the screen it shows compared to the
+    /// size of its mode.
+    ///
+    /// ```rust
+    /// use pino_xrandr::{layout::Layout, Screen};
+    ///
+    /// let screens = Screen::parse(concat!(
+    ///     "Screen 0: minimum 320 x 200, current 1920 x 1080, maximum 16384 x 16384\n",
+    ///     "eDP-1 connected primary 1920x1080+0+0 (normal left inverted right x axis y axis) 344mm x 194mm\n",
+    ///     "   1920x1080     60.02*+  48.00  \n",
+    ///     "HDMI-1 connected (normal left inverted right x axis y axis)\n",
+    ///     "   1920x1080     60.00 +\n",
+    /// ))
+    /// .unwrap();
+    /// let layout = Layout::from_screen(&screens[0]);
+    ///
+    /// assert_eq!(
+    ///     layout.args().join(" "),
+    ///     "--output eDP-1 --mode 1920x1080 --rate 60.02 --pos 0x0 --rotate normal --primary \
+    ///      --output HDMI-1 --off"
+    /// );
+    /// ```
+    pub fn from_screen(screen: &Screen) -> Self {
+        let outputs = screen.connected().map(|output| {
+            let Some(geometry) = output.geometry else {
+                return OutputLayout::new(&output.name).off();
+            };
+            let mut layout = OutputLayout::new(&output.name)
+                .position(geometry.x, geometry.y)
+                .rotation(output.rotation);
+            if let Some(mode) = output.current_mode() {
+                layout = layout.mode(mode.size).rate(mode.refresh);
+                if mode.name != mode.size.to_string() {
+                    layout = layout.mode_name(&mode.name);
+                }
+                // the area of a rotated output is rotated as well
+                let size = match output.rotation {
+                    Rotation::Left | Rotation::Right => Size {
+                        width: mode.size.height,
+                        height: mode.size.width,
+                    },
+                    Rotation::Normal | Rotation::Inverted => mode.size,
+                };
+                let scale = (
+                    geometry.size.width as f32 / size.width as f32,
+                    geometry.size.height as f32 / size.height as f32,
+                );
+                if scale != (1.0, 1.0) && size.width > 0 && size.height > 0 {
+                    layout.scale = Some(scale);
+                }
+            }
+            layout.primary = output.primary;
+            layout
+        });
+        Layout {
+            outputs: outputs.collect(),
+        }
+    }
+
+    /// Arguments of xrandr applying the layout
+    pub fn args(&self) -> Vec<String> {
+        self.outputs.iter().flat_map(OutputLayout::args).collect()
+    }
+
+    /// Apply the layout
+    #[cfg(feature = "process")]
+    pub fn apply(&self) -> Result<(), Error> {
+        let display = crate::DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        self.apply_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+    }
+
+    /// Apply the layout, running `xrandr` through the given runner
+    ///
+    /// Every output is configured by the same run of xrandr, so the screen is resized once.
+    pub fn apply_with_runner(&self, runner: &dyn CommandRunner) -> Result<(), Error> {
+        if self.outputs.is_empty() {
+            return Ok(());
+        }
+        run_checked(runner, &Cmd::new("xrandr").args(self.args()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn apply_layout() {
+        let layout = Layout::new()
+            .output(OutputLayout::new("eDP-1").scale(1.5).primary())
+            .output(OutputLayout::new("DP-1").off());
+        let cmd = Cmd::new("xrandr").args([
+            "--output",
+            "eDP-1",
+            "--auto",
+            "--scale",
+            "1.5x1.5",
+            "--primary",
+            "--output",
+            "DP-1",
+            "--off",
+        ]);
+        let runner =
+            MockRunner::new().respond(cmd, Output::from_failure(1, "xrandr: cannot find crtc\n"));
+        let err = layout.apply_with_runner(&runner).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "xrandr exited with status 1: xrandr: cannot find crtc"
+        );
+
+        Layout::new().apply_with_runner(&runner).unwrap();
+        assert_eq!(runner.calls().len(), 1);
+    }
+
+    #[test]
+    fn capture_interlaced_and_scaled() {
+        let screens = Screen::parse(concat!(
+            "Screen 0: minimum 320 x 200, current 5760 x 3840, maximum 16384 x 16384\n",
+            "HDMI-1 connected 1920x1080+0+0 (normal left inverted right x axis y axis) 0mm x 0mm\n",
+            "   1920x1080i    60.00*+\n",
+            "DP-1 connected 2160x3840+1920+0 left (normal left inverted right x axis y axis) 597mm x 336mm\n",
+            "   2560x1440     59.95*+\n",
+        ))
+        .unwrap();
+        let layout = Layout::from_screen(&screens[0]);
+        assert_eq!(layout.outputs[0].mode_name.as_deref(), Some("1920x1080i"));
+        assert_eq!(layout.outputs[0].scale, None);
+        assert_eq!(layout.outputs[1].mode_name, None);
+        assert_eq!(layout.outputs[1].scale, Some((1.5, 1.5)));
+        assert_eq!(
+            layout.args().join(" "),
+            "--output HDMI-1 --mode 1920x1080i --rate 60 --pos 0x0 --rotate normal \
+             --output DP-1 --mode 2560x1440 --rate 59.95 --pos 1920x0 --rotate left --scale 1.5x1.5"
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! Query the outputs of the X server through `xrandr`
+//!
+//! ```rust,no_run
+//! use pino_xrandr::Screen;
+//!
+//! # #[cfg(feature = "process")]
+//! fn main() {
+//!     for screen in Screen::query().unwrap() {
+//!         for output in screen.connected() {
+//!             if let Some(mode) = output.current_mode() {
+//!                 println!("{}: {} at {:.2}Hz", output.name, mode.size, mode.refresh);
+//!             }
+//!         }
+//!     }
+//! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+pub mod edid;
+pub mod gamma;
+pub mod layout;
+pub mod watch;
+
+use gamma::Gamma;
+
+/// Errors of running xrandr
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xrandr could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xrandr could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xrandr output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Width and height, in pixels or millimetres
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl FromStr for Size {
+    type Err = ();
+
+    /// From `WIDTHxHEIGHT`, ignoring anything following the height such as the `i` of
+    /// interlaced modes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.split_once('x').ok_or(())?;
+        let end = height
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(height.len());
+        Ok(Size {
+            width: width.trim().parse().map_err(|_| ())?,
+            height: height[..end].parse().map_err(|_| ())?,
+        })
+    }
+}
+
+/// Area of the screen an output shows
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Geometry {
+    pub size: Size,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:+}{:+}", self.size, self.x, self.y)
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = ();
+
+    /// From `WIDTHxHEIGHT+X+Y`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pos = s.find(['+', '-']).ok_or(())?;
+        let (size, offset) = s.split_at(pos);
+        let y_pos = offset[1..].find(['+', '-']).ok_or(())? + 1;
+        let (x, y) = offset.split_at(y_pos);
+        Ok(Geometry {
+            size: size.parse()?,
+            x: x.parse().map_err(|_| ())?,
+            y: y.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+/// Rotation of an output
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Rotation {
+    #[default]
+    Normal,
+    Left,
+    Inverted,
+    Right,
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rotation::Normal => write!(f, "normal"),
+            Rotation::Left => write!(f, "left"),
+            Rotation::Inverted => write!(f, "inverted"),
+            Rotation::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Rotation::Normal),
+            "left" => Ok(Rotation::Left),
+            "inverted" => Ok(Rotation::Inverted),
+            "right" => Ok(Rotation::Right),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Mode an output can be driven at
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Mode {
+    /// Name of the mode, usually its size such as `1920x1080`
+    pub name: String,
+    pub size: Size,
+    /// Refresh rate in Hz
+    pub refresh: f32,
+    pub interlaced: bool,
+    /// Whether the output is currently driven at this mode
+    pub current: bool,
+    /// Whether this is the mode preferred by the monitor
+    pub preferred: bool,
+}
+
+/// Output of the graphics card, such as a connector a monitor can be plugged into
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Output {
+    /// Name of the output, such as `eDP-1` or `HDMI-1`
+    pub name: String,
+    /// Whether a monitor is plugged in
+    pub connected: bool,
+    pub primary: bool,
+    /// Area of the screen the output shows, `None` if it is disabled
+    pub geometry: Option<Geometry>,
+    pub rotation: Rotation,
+    /// Physical size of the monitor in millimetres, as reported by it
+    pub physical_size: Option<Size>,
+    pub modes: Vec<Mode>,
+    /// Brightness the colors are scaled by, only read by [Screen::query_verbose]
+    pub brightness: Option<f32>,
+    /// Gamma correction of each channel, only read by [Screen::query_verbose]
+    pub gamma: Option<Gamma>,
+    /// EDID of the monitor, parsed by [Edid::parse](edid::Edid::parse), only read by
+    /// [Screen::query_verbose]
+    pub edid: Option<Vec<u8>>,
+}
+
+impl Output {
+    /// Check if the output is enabled, showing part of the screen
+    pub fn enabled(&self) -> bool {
+        self.geometry.is_some()
+    }
+
+    /// Mode the output is currently driven at
+    pub fn current_mode(&self) -> Option<&Mode> {
+        self.modes.iter().find(|mode| mode.current)
+    }
+
+    /// Mode preferred by the monitor
+    pub fn preferred_mode(&self) -> Option<&Mode> {
+        self.modes.iter().find(|mode| mode.preferred)
+    }
+
+    /// Parse the line of an output, `NAME connected [primary] [GEOMETRY] [ROTATION] (...) [SIZE]`
+    fn parse(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        // the supported rotations and reflections are listed in the last parentheses, verbose
+        // output puts the id of the current mode in parentheses before them
+        let (head, tail) = match line.rsplit_once('(') {
+            Some((head, tail)) => (head, tail.split_once(')').map_or("", |(_, tail)| tail)),
+            None => (line, ""),
+        };
+
+        let mut words = head.split_whitespace();
+        let mut output = Output {
+            name: words.next().ok_or_else(malformed)?.to_owned(),
+            ..Default::default()
+        };
+        output.connected = match words.next() {
+            Some("connected") => true,
+            Some("disconnected" | "unknown") => false,
+            _ => return Err(malformed()),
+        };
+        for word in words {
+            if word == "primary" {
+                output.primary = true;
+            } else if let Ok(geometry) = word.parse() {
+                output.geometry = Some(geometry);
+            } else if let Ok(rotation) = word.parse() {
+                output.rotation = rotation;
+            }
+        }
+
+        // `344mm x 194mm`, which is `0mm x 0mm` for monitors that do not report it
+        let size = tail.replace("mm", "").replace(' ', "");
+        output.physical_size = size.parse().ok().filter(|size: &Size| size.width > 0);
+        Ok(output)
+    }
+
+    /// Parse the line of a resolution, listing a mode for each refresh rate it supports
+    ///
+    /// Each rate is followed by `*` if it is current and `+` if it is preferred. Verbose output
+    /// lists a single mode per line instead, with its refresh rate on the `v:` line below.
+    fn parse_modes(&mut self, line: &str) -> Result<(), Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let mut words = line.split_whitespace().peekable();
+        let name = words.next().ok_or_else(malformed)?;
+        let size = name.parse::<Size>().map_err(|_| malformed())?;
+        let interlaced = name.trim_start_matches(|c: char| c != 'x')[1..]
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .starts_with('i');
+
+        if words.next_if(|word| word.starts_with("(0x")).is_some() {
+            let words = words.collect::<Vec<_>>();
+            self.modes.push(Mode {
+                name: name.to_owned(),
+                size,
+                refresh: 0.0,
+                interlaced: interlaced || words.contains(&"Interlace"),
+                current: words.contains(&"*current"),
+                preferred: words.contains(&"+preferred"),
+            });
+            return Ok(());
+        }
+
+        let first = self.modes.len();
+        for word in words {
+            let rate = word.trim_end_matches(['*', '+']);
+            if !rate.is_empty() {
+                self.modes.push(Mode {
+                    name: name.to_owned(),
+                    size,
+                    refresh: rate.parse().map_err(|_| malformed())?,
+                    interlaced,
+                    current: false,
+                    preferred: false,
+                });
+            }
+            // the flags of a rate can be separated from it by a space
+            let mode = self.modes[first..].last_mut().ok_or_else(malformed)?;
+            mode.current |= word.contains('*');
+            mode.preferred |= word.contains('+');
+        }
+        Ok(())
+    }
+
+    /// Parse an indented line of verbose output following the output, which is either part of
+    /// the last mode, a property or the continuation of the property named `key`
+    fn parse_verbose(&mut self, line: &str, key: &mut String) {
+        let line = line.trim();
+        if let Some(timings) = line.strip_prefix("v:") {
+            // `v: height 1080 start 1083 end 1088 total 1120 clock  60.02Hz`
+            let clock = timings.rsplit_once("clock").map(|(_, clock)| clock);
+            let refresh = clock.and_then(|clock| clock.trim().trim_end_matches("Hz").parse().ok());
+            if let (Some(mode), Some(refresh)) = (self.modes.last_mut(), refresh) {
+                mode.refresh = refresh;
+            }
+            return;
+        }
+        let Some((name, val)) = line.split_once(':') else {
+            // the EDID is printed as lines of hexadecimal bytes below the property
+            if let (Some(edid), Some(bytes)) = (&mut self.edid, edid::parse_hex(line)) {
+                if key == "EDID" {
+                    edid.extend(bytes);
+                }
+            }
+            return;
+        };
+        *key = name.to_owned();
+        match name {
+            "Brightness" => self.brightness = val.trim().parse().ok(),
+            "Gamma" => self.gamma = val.trim().parse().ok(),
+            "EDID" => self.edid = Some(vec![]),
+            _ => {},
+        }
+    }
+}
+
+/// Screen of the X server, spanning every enabled output
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Screen {
+    pub number: u32,
+    pub minimum: Size,
+    pub current: Size,
+    pub maximum: Size,
+    pub outputs: Vec<Output>,
+}
+
+impl Screen {
+    /// Query the screens of the X server
+    #[cfg(feature = "process")]
+    pub fn query() -> Result<Vec<Self>, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// Query the screens of the X server, running `xrandr` through the given runner
+    pub fn query_with_runner(runner: &dyn CommandRunner) -> Result<Vec<Self>, Error> {
+        let output = run_checked(runner, &Cmd::new("xrandr").arg("--query"))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Query the screens of the X server along with the properties of each output, such as their
+    /// brightness and gamma
+    #[cfg(feature = "process")]
+    pub fn query_verbose() -> Result<Vec<Self>, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_verbose_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// Query the screens along with the properties of each output, running `xrandr --verbose`
+    /// through the given runner
+    ///
+    /// ```rust
+    /// use pino_xrandr::{gamma::Gamma, runner::{Cmd, MockRunner, Output}, Screen};
+    ///
+    /// let runner = MockRunner::new().respond(
+    ///     Cmd::new("xrandr").arg("--verbose"),
+    ///     Output::from_stdout(concat!(
+    ///         "Screen 0: minimum 320 x 200, current 1920 x 1080, maximum 16384 x 16384\n",
+    ///         "eDP-1 connected primary 1920x1080+0+0 (0x47) normal (normal left inverted right x axis y axis) 344mm x 194mm\n",
+    ///         "\tGamma:      1.0:0.9:0.8\n",
+    ///         "\tBrightness: 0.75\n",
+    ///         "  1920x1080 (0x47) 138.500MHz +HSync -VSync *current +preferred\n",
+    ///         "        h: width  1920 start 1968 end 2000 total 2080 skew    0 clock  66.59KHz\n",
+    ///         "        v: height 1080 start 1083 end 1088 total 1111           clock  59.93Hz\n",
+    ///     )),
+    /// );
+    /// let screens = Screen::query_verbose_with_runner(&runner).unwrap();
+    /// let output = screens[0].output("eDP-1").unwrap();
+    ///
+    /// assert_eq!(output.brightness, Some(0.75));
+    /// assert_eq!(output.gamma, Some(Gamma { red: 1.0, green: 0.9, blue: 0.8 }));
+    /// assert_eq!(output.current_mode().unwrap().refresh, 59.93);
+    /// ```
+    pub fn query_verbose_with_runner(runner: &dyn CommandRunner) -> Result<Vec<Self>, Error> {
+        let output = run_checked(runner, &Cmd::new("xrandr").arg("--verbose"))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `xrandr --query`
+    ///
+    /// ```rust
+    /// use pino_xrandr::{Geometry, Rotation, Screen, Size};
+    ///
+    /// let screens = Screen::parse(concat!(
+    ///     "Screen 0: minimum 320 x 200, current 3000 x 1920, maximum 16384 x 16384\n",
+    ///     "eDP-1 connected primary 1920x1080+0+0 (normal left inverted right x axis y axis) 344mm x 194mm\n",
+    ///     "   1920x1080     60.02*+  48.00  \n",
+    ///     "HDMI-1 connected 1080x1920+1920+0 left (normal left inverted right x axis y axis) 527mm x 296mm\n",
+    ///     "   1920x1080     60.00*+  50.00    59.94  \n",
+    ///     "DP-1 disconnected (normal left inverted right x axis y axis)\n",
+    /// ))
+    /// .unwrap();
+    /// let screen = &screens[0];
+    ///
+    /// assert_eq!(screen.current, Size { width: 3000, height: 1920 });
+    /// assert_eq!(screen.primary().unwrap().current_mode().unwrap().refresh, 60.02);
+    ///
+    /// let hdmi = screen.output("HDMI-1").unwrap();
+    /// assert_eq!(hdmi.rotation, Rotation::Left);
+    /// assert_eq!(hdmi.geometry.unwrap().to_string(), "1080x1920+1920+0");
+    /// assert_eq!(screen.connected().count(), 2);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Vec<Self>, Error> {
+        let mut screens = Vec::<Screen>::new();
+        let mut key = String::new();
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            let malformed = || Error::Malformed(line.to_owned());
+            if let Some(screen) = line.strip_prefix("Screen ") {
+                screens.push(Screen::parse_header(screen).ok_or_else(malformed)?);
+            } else if !line.starts_with(char::is_whitespace) {
+                let screen = screens.last_mut().ok_or_else(malformed)?;
+                screen.outputs.push(Output::parse(line)?);
+            } else {
+                let screen = screens.last_mut().ok_or_else(malformed)?;
+                let output = screen.outputs.last_mut().ok_or_else(malformed)?;
+                // modes are indented with spaces, properties printed by `--verbose` with tabs
+                if line.starts_with(' ')
+                    && line.trim_start().starts_with(|c: char| c.is_ascii_digit())
+                {
+                    output.parse_modes(line)?;
+                } else {
+                    output.parse_verbose(line, &mut key);
+                }
+            }
+        }
+        Ok(screens)
+    }
+
+    /// Parse `0: minimum 320 x 200, current 3840 x 1080, maximum 16384 x 16384`
+    fn parse_header(line: &str) -> Option<Self> {
+        let (number, sizes) = line.split_once(':')?;
+        let mut screen = Screen {
+            number: number.trim().parse().ok()?,
+            ..Default::default()
+        };
+        for size in sizes.split(',') {
+            let (name, size) = size.trim().split_once(' ')?;
+            let size = size.replace(' ', "").parse().ok()?;
+            match name {
+                "minimum" => screen.minimum = size,
+                "current" => screen.current = size,
+                "maximum" => screen.maximum = size,
+                _ => {},
+            }
+        }
+        Some(screen)
+    }
+
+    /// Output with the given name
+    pub fn output(&self, name: &str) -> Option<&Output> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+
+    /// Outputs with a monitor plugged in
+    pub fn connected(&self) -> impl Iterator<Item = &Output> {
+        self.outputs.iter().filter(|output| output.connected)
+    }
+
+    /// Primary output, if one is set
+    pub fn primary(&self) -> Option<&Output> {
+        self.outputs.iter().find(|output| output.primary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_modes() {
+        let screens = Screen::parse(concat!(
+            "Screen 0: minimum 8 x 8, current 1920 x 1080, maximum 32767 x 32767\n",
+            "HDMI-2 connected (normal left inverted right x axis y axis)\n",
+            "   1920x1080     60.00 +  50.00  \n",
+            "   1920x1080i    60.00    50.00* \n",
+            "   1920x1080_60.00  59.96  \n",
+            "VIRTUAL1 unknown connection (normal left inverted right x axis y axis)\n",
+        ))
+        .unwrap();
+        let hdmi = &screens[0].outputs[0];
+        assert!(!hdmi.enabled());
+        assert_eq!(hdmi.physical_size, None);
+        assert_eq!(hdmi.modes.len(), 5);
+        assert!(hdmi.preferred_mode().unwrap().refresh == 60.0);
+
+        let current = hdmi.current_mode().unwrap();
+        assert!(current.interlaced);
+        assert!(current.refresh == 50.0);
+        assert!(!hdmi.modes[4].interlaced);
+        assert!(!screens[0].outputs[1].connected);
+
+        assert_eq!("800x600-10+20".parse::<Geometry>().unwrap().x, -10);
+        assert!(Screen::parse("   1920x1080     60.00").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Notify about monitors being plugged in, unplugged or reconfigured
+//!
+//! A [Watcher] polls the outputs from a background thread and sends an [Event] for every change,
+//! so docking station scripts can react without a udev hook.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xrandr::watch::{Event, Watcher};
+//!
+//! let handle = Watcher::new().spawn();
+//! for event in handle.events() {
+//!     match event {
+//!         Event::Connected(output) => println!("{} plugged in", output.name),
+//!         Event::Disconnected(name) => println!("{} unplugged", name),
+//!         _ => {},
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{DisplayHandle, Error, Output, Screen};
+
+/// Time between two polls, unless configured otherwise
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Change to the outputs observed by a [Watcher]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event {
+    /// Monitor was plugged into the output
+    Connected(Output),
+    /// Monitor was unplugged from the output, holding its name
+    Disconnected(String),
+    /// Output was enabled, disabled, moved, rotated or switched to another mode
+    ModeChanged(Output),
+    /// Outputs could not be read, reported once until they can be read again
+    Error(Error),
+}
+
+/// Changes between two readings of the outputs
+///
+/// ```rust
+/// use pino_xrandr::{watch::{changes, Event}, Output};
+///
+/// let old = vec![Output { name: String::from("HDMI-1"), ..Default::default() }];
+/// let new = vec![Output { name: String::from("HDMI-1"), connected: true, ..Default::default() }];
+///
+/// assert!(matches!(&changes(&old, &new)[..], [Event::Connected(output)] if output.name == "HDMI-1"));
+/// ```
+pub fn changes(old: &[Output], new: &[Output]) -> Vec<Event> {
+    let mut events = vec![];
+    for output in new {
+        let prev = old.iter().find(|prev| prev.name == output.name);
+        let was_connected = prev.is_some_and(|prev| prev.connected);
+        if output.connected && !was_connected {
+            events.push(Event::Connected(output.clone()));
+        } else if !output.connected && was_connected {
+            events.push(Event::Disconnected(output.name.clone()));
+        } else if let Some(prev) = prev.filter(|_| output.connected) {
+            let configuration = |o: &Output| (o.geometry, o.rotation, o.current_mode().cloned());
+            if configuration(prev) != configuration(output) {
+                events.push(Event::ModeChanged(output.clone()));
+            }
+        }
+    }
+    // outputs can vanish altogether, such as those of a disconnected DisplayLink dock
+    for prev in old.iter().filter(|prev| prev.connected) {
+        if !new.iter().any(|output| output.name == prev.name) {
+            events.push(Event::Disconnected(prev.name.clone()));
+        }
+    }
+    events
+}
+
+/// Polls the outputs for changes, see the [module](self) documentation
+pub struct Watcher {
+    runner: Box<dyn CommandRunner>,
+    display: DisplayHandle,
+    interval: Duration,
+    last: Option<Vec<Output>>,
+    failing: bool,
+}
+
+impl Watcher {
+    /// Construct a watcher running xrandr on the system
+    #[cfg(feature = "process")]
+    pub fn new() -> Self {
+        Watcher::with_runner(crate::runner::SystemRunner::default())
+    }
+
+    /// Construct a watcher running xrandr through the given runner
+    pub fn with_runner(runner: impl CommandRunner + 'static) -> Self {
+        Watcher {
+            runner: Box::new(runner),
+            display: DisplayHandle::default(),
+            interval: DEFAULT_INTERVAL,
+            last: None,
+            failing: false,
+        }
+    }
+
+    /// Watch the given display instead of the one of the environment
+    pub fn display(mut self, display: DisplayHandle) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Time between two polls
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Read the outputs once, returning what changed since the previous poll
+    ///
+    /// The first poll only records the current state, so it reports nothing but errors. Polls
+    /// run `xrandr --current`, which reports the state the X server was last told about by the
+    /// kernel instead of probing every output again.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let runner = self.display.runner(self.runner.as_ref());
+        let outputs = run_checked(&runner, &Cmd::new("xrandr").arg("--current"))
+            .map_err(Error::from)
+            .and_then(|output| Screen::parse(&String::from_utf8_lossy(&output.stdout)));
+        let outputs = match outputs {
+            Ok(screens) => screens
+                .into_iter()
+                .flat_map(|s| s.outputs)
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                if self.failing {
+                    return vec![];
+                }
+                self.failing = true;
+                return vec![Event::Error(e)];
+            },
+        };
+        self.failing = false;
+        match self.last.replace(outputs) {
+            Some(old) => changes(&old, self.last.as_ref().unwrap()),
+            None => vec![],
+        }
+    }
+
+    /// Poll from a background thread, sending every event to the channel of the returned handle
+    ///
+    /// The thread runs until the handle is stopped or dropped.
+    pub fn spawn(mut self) -> WatchHandle {
+        let (tx, rx) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || loop {
+            for event in self.poll() {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+            match stopped.recv_timeout(self.interval) {
+                Err(RecvTimeoutError::Timeout) => {},
+                _ => return,
+            }
+        });
+        WatchHandle {
+            events: rx,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for Watcher {
+    fn default() -> Self {
+        Watcher::new()
+    }
+}
+
+/// Running [Watcher], stopped when dropped
+pub struct WatchHandle {
+    events: Receiver<Event>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// Events sent by the watcher, the channel disconnects once it stops
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+
+    /// Stop the background thread and wait for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // dropping the sender wakes the thread up from its wait
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Geometry;
+
+    #[test]
+    fn output_changes() {
+        let output = |name: &str, connected: bool, geometry: Option<&str>| Output {
+            name: name.to_owned(),
+            connected,
+            geometry: geometry.map(|g| g.parse::<Geometry>().unwrap()),
+            ..Default::default()
+        };
+        let old = vec![
+            output("eDP-1", true, Some("1920x1080+0+0")),
+            output("HDMI-1", true, Some("1920x1080+1920+0")),
+            output("DVI-I-1-1", true, None),
+        ];
+        let new = vec![
+            output("eDP-1", true, Some("1920x1080+0+0")),
+            output("HDMI-1", true, None),
+            output("DP-1", true, None),
+        ];
+        let events = changes(&old, &new);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Event::ModeChanged(o) if o.name == "HDMI-1"));
+        assert!(matches!(&events[1], Event::Connected(o) if o.name == "DP-1"));
+        assert!(matches!(&events[2], Event::Disconnected(name) if name == "DVI-I-1-1"));
+    }
+}
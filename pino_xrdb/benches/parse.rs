@@ -0,0 +1,87 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pino_xrdb::Xrdb;
+
+/// Counts the bytes currently allocated, to report the memory held by a parsed table
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: Counting = Counting;
+
+/// Bytes still allocated after running `f` and keeping its result
+fn retained<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = LIVE.load(Ordering::Relaxed);
+    let value = f();
+    (value, LIVE.load(Ordering::Relaxed) - before)
+}
+
+/// Database with every program defining the same resources, as generated themes do
+fn large_database(programs: usize) -> String {
+    let mut raw = String::new();
+    for p in 0..programs {
+        for c in 0..16 {
+            raw.push_str(&format!("program{}.color{}: #{:06x}\n", p, c, p * 16 + c));
+        }
+        raw.push_str(&format!("program{}.font: monospace:size=10\n", p));
+    }
+    raw
+}
+
+/// The database as nested maps of owned strings, the layout before names were interned
+fn owned_database(raw: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut db = HashMap::<String, HashMap<String, String>>::new();
+    for line in raw.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some((program, resource)) = name.split_once('.') else {
+            continue;
+        };
+        db.entry(program.to_owned())
+            .or_default()
+            .insert(resource.to_owned(), value.trim().to_owned());
+    }
+    db
+}
+
+fn parse(c: &mut Criterion) {
+    let raw = large_database(500);
+    c.bench_function("parse 500 programs", |b| {
+        b.iter(|| Xrdb::parse(black_box(&raw)))
+    });
+
+    // the interner only holds names weakly, so the names are counted with the database holding
+    // them, once however many programs share them
+    let (xrdb, bytes) = retained(|| Xrdb::parse(&raw));
+    let (_, owned_bytes) = retained(|| owned_database(&raw));
+    println!(
+        "database of 500 programs holds {} KiB, {} KiB with owned strings",
+        bytes / 1024,
+        owned_bytes / 1024
+    );
+    c.bench_function("query palette", |b| {
+        b.iter(|| xrdb.palette(black_box("program250")))
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,131 @@
+//! Command line interface to `pino_xrdb`, enabled by the `cli` feature
+
+use std::{fs, path::PathBuf, process::ExitCode, thread, time::Duration};
+
+use clap::{Parser, Subcommand};
+use pino_xrdb::{Xrdb, XrdbError};
+
+/// Query and modify the xrdb of the system
+#[derive(Parser)]
+#[command(name = "pino-xrdb", version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the value of a resource, `*` as PROG for universal resources
+    Query { prog: String, res: String },
+    /// Set a resource, `*` as PROG for a universal resource
+    Set {
+        prog: String,
+        res: String,
+        value: String,
+    },
+    /// Print color0 through color15 of a program
+    Palette {
+        #[arg(default_value = "*")]
+        prog: String,
+    },
+    /// Print the resources that differ between the system and a resource file
+    Diff { file: PathBuf },
+    /// Merge a resource file into the system and print what changed
+    ApplyTheme { file: PathBuf },
+    /// Print every change to the resources until interrupted
+    Watch {
+        /// Seconds between reads
+        #[arg(long, default_value_t = 1.0)]
+        interval: f64,
+    },
+}
+
+#[derive(Debug)]
+enum Error {
+    Xrdb(XrdbError),
+    Io(PathBuf, std::io::Error),
+    NotFound(String, String),
+}
+
+impl From<XrdbError> for Error {
+    fn from(value: XrdbError) -> Self {
+        Error::Xrdb(value)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Xrdb(e) => write!(f, "{}", e),
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::NotFound(prog, res) => write!(f, "{}.{} is not set", prog, res),
+        }
+    }
+}
+
+fn system() -> Result<Xrdb, Error> {
+    let mut xrdb = Xrdb::new();
+    xrdb.read()?;
+    Ok(xrdb)
+}
+
+fn read_file(path: &PathBuf) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| Error::Io(path.clone(), e))
+}
+
+fn print_diff(old: &Xrdb, new: &Xrdb) {
+    for diff in old.diff(new) {
+        println!("{}", diff);
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    match args.command {
+        Command::Query { prog, res } => {
+            let value = system()?
+                .query(&prog, &res)
+                .ok_or(Error::NotFound(prog, res))?;
+            println!("{}", value);
+        },
+        Command::Set { prog, res, value } => {
+            Xrdb::merge(&format!("{}.{}: {}", prog, res, value))?;
+        },
+        Command::Palette { prog } => {
+            for (i, color) in system()?.palette(&prog).iter().enumerate() {
+                if let Some(color) = color {
+                    println!("color{}\t{}", i, color);
+                }
+            }
+        },
+        Command::Diff { file } => {
+            print_diff(&system()?, &Xrdb::parse(&read_file(&file)?));
+        },
+        Command::ApplyTheme { file } => {
+            let raw = read_file(&file)?;
+            let old = system()?;
+            Xrdb::merge(&raw)?;
+            print_diff(&old, &system()?);
+        },
+        Command::Watch { interval } => {
+            let interval = Duration::from_secs_f64(interval);
+            let mut old = system()?;
+            loop {
+                thread::sleep(interval);
+                let new = system()?;
+                print_diff(&old, &new);
+                old = new;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("pino-xrdb: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
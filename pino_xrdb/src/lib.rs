@@ -1,8 +1,9 @@
 //! Rust wrapper for `xrdb`. Query the system's xrdb or add new values to it. Can handle wildcards
 //! resources.
 //!
-//! ```rust
+//! ```rust,no_run
 //! use pino_xrdb::Xrdb;
+//! # #[cfg(feature = "process")]
 //! fn main() {
 //!
 //!     let mut xrdb = Xrdb::new();
@@ -15,9 +16,32 @@
 //!     }
 //!     
 //! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
 //! ```
+//!
+//! Everything that runs xrdb is behind the default `process` feature. Without it only
+//! [Xrdb::parse] and the `*_with_runner` functions are available, so the crate can be used where
+//! `std::process` is unavailable, such as on wasm32.
+
+use std::{collections::HashMap, fmt, sync::Arc};
 
-use std::{collections::HashMap, process::Command};
+pub use pino_xcommon::{
+    backend,
+    capability::{Capabilities, Version},
+    capture,
+    color::Color,
+    display::DisplayHandle,
+    fallback::Source,
+    platform,
+    retry::RetryPolicy,
+    runner,
+    session::Session,
+};
+use pino_xcommon::{
+    intern::intern,
+    runner::{Cmd, CommandRunner, Output},
+};
 
 /// Error types for xrdb
 #[derive(Debug)]
@@ -30,6 +54,12 @@ pub enum XrdbError {
     Invalid,
     /// xrdb output was not able to be parsed as string
     OutputMalformed,
+    /// xrdb did not finish within its timeout and was killed
+    TimedOut,
+    /// The installed xrdb or the platform can not perform the operation, holding what is missing
+    Unsupported(String),
+    /// The session has no X server to read from, such as Wayland without XWayland
+    NoXServer(Session),
 }
 
 impl std::error::Error for XrdbError {}
@@ -44,15 +74,125 @@ impl std::fmt::Display for XrdbError {
             XrdbError::Errored(e) => write!(f, "xrdb exited with error: {0}", e),
             XrdbError::Invalid => write!(f, "failed to parse line"),
             XrdbError::OutputMalformed => write!(f, "could not parse xrdb output to string"),
+            XrdbError::TimedOut => write!(f, "xrdb timed out"),
+            XrdbError::Unsupported(e) => write!(f, "unsupported: {0}", e),
+            XrdbError::NoXServer(session) => {
+                write!(f, "no X server to read from in a {} session", session)
+            },
+        }
+    }
+}
+
+impl From<std::io::Error> for XrdbError {
+    fn from(value: std::io::Error) -> Self {
+        match value.kind() {
+            std::io::ErrorKind::TimedOut => XrdbError::TimedOut,
+            std::io::ErrorKind::Unsupported => XrdbError::Unsupported(value.to_string()),
+            _ => XrdbError::Missing,
         }
     }
 }
 
+/// Resource whose value differs between two databases, see [Xrdb::diff]
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Diff {
+    /// Program of the resource, `None` for a universal resource
+    pub program: Option<String>,
+    pub res: String,
+    /// Value in the old database, `None` if the resource was added
+    pub old: Option<String>,
+    /// Value in the new database, `None` if the resource was removed
+    pub new: Option<String>,
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = format!("{}.{}", self.program.as_deref().unwrap_or("*"), self.res);
+        match (&self.old, &self.new) {
+            (None, Some(new)) => write!(f, "+ {}:\t{}", name, new),
+            (Some(old), None) => write!(f, "- {}:\t{}", name, old),
+            (old, new) => write!(
+                f,
+                "~ {}:\t{} -> {}",
+                name,
+                old.as_deref().unwrap_or_default(),
+                new.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Resource files in the home directory read by [Xrdb::read_or_fallback], in order of preference
+pub static FALLBACK_FILES: &[&str] = &[".Xresources", ".Xdefaults"];
+
+/// Flags looked for in the usage of xrdb by [Xrdb::capabilities]
+pub static CAPABILITY_FLAGS: &[&str] = &["-query", "-merge", "-load", "-nocpp", "-get"];
+
 /// Xrdb database struct
-#[derive(Default)]
+///
+/// With the `serde` feature it is serialized as a map from program to its resources, universal
+/// resources being under `*`.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use pino_xrdb::Xrdb;
+///
+/// let xrdb = Xrdb::parse("*.color1: #ea6962\ndwm.font: monospace");
+/// let json = serde_json::to_string(&xrdb).unwrap();
+/// assert_eq!(json, r##"{"*":{"color1":"#ea6962"},"dwm":{"font":"monospace"}}"##);
+/// assert_eq!(serde_json::from_str::<Xrdb>(&json).unwrap(), xrdb);
+/// # }
+/// ```
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "XrdbDump", from = "XrdbDump")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Xrdb {
-    db: HashMap<String, HashMap<String, String>>,
-    univeral: HashMap<String, String>,
+    // program and resource names are interned, they repeat across programs and databases
+    db: HashMap<Arc<str>, HashMap<Arc<str>, String>>,
+    univeral: HashMap<Arc<str>, String>,
+}
+
+/// Serialized form of [Xrdb], sorted so the output is stable
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+struct XrdbDump(std::collections::BTreeMap<String, std::collections::BTreeMap<String, String>>);
+
+#[cfg(feature = "serde")]
+impl From<Xrdb> for XrdbDump {
+    fn from(xrdb: Xrdb) -> Self {
+        let mut dump = std::collections::BTreeMap::<_, std::collections::BTreeMap<_, _>>::new();
+        for (program, res, val) in xrdb.entries() {
+            dump.entry(program.unwrap_or("*").to_owned())
+                .or_default()
+                .insert(res.to_owned(), val.to_owned());
+        }
+        XrdbDump(dump)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<XrdbDump> for Xrdb {
+    fn from(dump: XrdbDump) -> Self {
+        let mut xrdb = Xrdb::new();
+        for (program, resources) in &dump.0 {
+            for (res, val) in resources {
+                match program.as_str() {
+                    "*" => xrdb.insert_universal(res, val),
+                    program => xrdb.insert(program, res, val),
+                }
+            }
+        }
+        xrdb
+    }
 }
 
 impl Xrdb {
@@ -61,23 +201,182 @@ impl Xrdb {
         Xrdb::default()
     }
 
-    /// Read system xrdb
+    /// Parse a database in the format of `xrdb -query` or of an `~/.Xresources` file
     ///
+    /// Lines starting with `!` are comments, preprocessor directives are skipped rather than
+    /// expanded.
     /// ```rust
     /// # use pino_xrdb::Xrdb;
     /// # fn main() {
+    /// let xrdb = Xrdb::parse("! theme\n*.color1: #ea6962\n*color2: #a9b665\nst.font: mono");
+    ///
+    /// assert_eq!(xrdb.query("dwm", "color1"), Some(String::from("#ea6962")));
+    /// assert_eq!(xrdb.query("dwm", "color2"), Some(String::from("#a9b665")));
+    /// assert_eq!(xrdb.query("st", "font"), Some(String::from("mono")));
+    /// # }
+    /// ```
+    pub fn parse(raw: &str) -> Self {
+        let mut xrdb = Xrdb::new();
+        xrdb.insert_raw(raw);
+        xrdb
+    }
+
+    /// Read system xrdb
+    ///
+    /// ```rust,no_run
+    /// # use pino_xrdb::Xrdb;
+    /// # fn main() {
     /// let mut xrdb = Xrdb::new();
     /// xrdb.read().unwrap();
     /// # }
     /// ```
+    ///
+    /// With the `native` feature the resources are read straight from the `RESOURCE_MANAGER`
+    /// property of the root window instead of running `xrdb`. When the `process` feature is
+    /// enabled as well, `xrdb` is run if that fails, see [pino_xcommon::backend].
+    #[cfg(any(feature = "process", feature = "native"))]
     pub fn read(&mut self) -> Result<(), XrdbError> {
+        self.read_display(&DisplayHandle::default())
+    }
+
+    /// Read the xrdb of the given display
+    ///
+    /// ```rust,no_run
+    /// # use pino_xrdb::{DisplayHandle, Xrdb};
+    /// # fn main() {
+    /// let mut xrdb = Xrdb::new();
+    /// xrdb.read_display(&DisplayHandle::new().display(":1")).unwrap();
+    /// # }
+    /// ```
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn read_display(&mut self, display: &DisplayHandle) -> Result<(), XrdbError> {
+        use pino_xcommon::backend::{dispatch, Backend};
+
+        pino_xcommon::session::require_x(display).map_err(XrdbError::NoXServer)?;
+        dispatch(|backend| match backend {
+            #[cfg(feature = "native")]
+            Backend::Native => Some(Self::query_native(display).map(|raw| self.insert_raw(&raw))),
+            #[cfg(feature = "process")]
+            Backend::Binary => {
+                Some(self.read_with_runner(&display.runner(&runner::SystemRunner::default())))
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| Err(XrdbError::Errored(String::from("no backend available"))))
+    }
+
+    /// Read system xrdb, or a resource file in the home directory if that fails
+    ///
+    /// The first of [FALLBACK_FILES] that exists is parsed when `$DISPLAY` is unset or reading
+    /// the system xrdb fails, so resources are still available in a TTY, over SSH or in CI. The
+    /// error of reading the system xrdb is returned if none of them exist.
+    ///
+    /// ```rust,no_run
+    /// # use pino_xrdb::{Source, Xrdb};
+    /// # fn main() {
+    /// let mut xrdb = Xrdb::new();
+    /// if let Source::File(path) = xrdb.read_or_fallback().unwrap() {
+    ///     println!("X server unavailable, read {}", path.display());
+    /// }
+    /// # }
+    /// ```
+    #[cfg(any(feature = "process", feature = "native"))]
+    pub fn read_or_fallback(&mut self) -> Result<Source, XrdbError> {
+        let err = if pino_xcommon::fallback::has_display() {
+            match self.read() {
+                Ok(()) => return Ok(Source::Server),
+                Err(e) => e,
+            }
+        } else {
+            XrdbError::Errored(String::from("DISPLAY is not set"))
+        };
+
+        let path = pino_xcommon::fallback::home_file(FALLBACK_FILES).ok_or(err)?;
+        let raw = std::fs::read_to_string(&path).or(Err(XrdbError::OutputMalformed))?;
+        self.insert_raw(&raw);
+        Ok(Source::File(path))
+    }
+
+    /// Read system xrdb without blocking, enabled by the `tokio` feature
+    ///
+    /// ```rust,no_run
+    /// # use pino_xrdb::Xrdb;
+    /// # async fn run() {
+    /// let mut xrdb = Xrdb::new();
+    /// xrdb.read_async().await.unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn read_async(&mut self) -> Result<(), XrdbError> {
+        #[cfg(feature = "native")]
+        {
+            let raw = tokio::task::spawn_blocking(|| Self::query_native(&DisplayHandle::default()))
+                .await
+                .map_err(|e| XrdbError::Errored(e.to_string()))??;
+            self.insert_raw(&raw);
+            Ok(())
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            let output = pino_xcommon::nonblocking::output(&Cmd::new("xrdb").arg("-query")).await?;
+            self.insert_output(output)
+        }
+    }
+
+    /// Contents of the `RESOURCE_MANAGER` property
+    #[cfg(feature = "native")]
+    fn query_native(display: &DisplayHandle) -> Result<String, XrdbError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("get_resource_manager").entered();
+        use x11rb::{
+            connection::Connection,
+            protocol::xproto::{AtomEnum, ConnectionExt},
+        };
+
+        let (conn, screen) = pino_xcommon::native::connect_display(display)
+            .map_err(|e| XrdbError::Errored(e.to_string()))?;
+        let root = conn.setup().roots[screen].root;
+        let reply = conn
+            .get_property(
+                false,
+                root,
+                AtomEnum::RESOURCE_MANAGER,
+                AtomEnum::STRING,
+                0,
+                u32::MAX,
+            )
+            .map_err(|e| XrdbError::Errored(e.to_string()))?
+            .reply()
+            .map_err(|e| XrdbError::Errored(e.to_string()))?;
+
+        String::from_utf8(reply.value).map_err(|_| XrdbError::OutputMalformed)
+    }
+
+    /// Read system xrdb, running `xrdb` through the given runner
+    ///
+    /// ```rust
+    /// # use pino_xrdb::{runner::{Cmd, MockRunner, Output}, Xrdb};
+    /// # fn main() {
+    /// let runner = MockRunner::new().respond(
+    ///     Cmd::new("xrdb").arg("-query"),
+    ///     Output::from_stdout("*.color1:\t#ea6962\n"),
+    /// );
+    /// let mut xrdb = Xrdb::new();
+    /// xrdb.read_with_runner(&runner).unwrap();
+    ///
+    /// assert_eq!(xrdb.query("dwm", "color1"), Some(String::from("#ea6962")));
+    /// # }
+    /// ```
+    pub fn read_with_runner(&mut self, runner: &dyn CommandRunner) -> Result<(), XrdbError> {
         // run xrdb command
-        let output = Command::new("xrdb")
-            .arg("-query")
-            .output()
-            .map_err(|_| XrdbError::Missing)?;
+        let output = runner.output(&Cmd::new("xrdb").arg("-query"))?;
 
-        if !output.status.success() {
+        self.insert_output(output)
+    }
+
+    /// Insert every resource printed by a run of `xrdb -query`
+    fn insert_output(&mut self, output: Output) -> Result<(), XrdbError> {
+        if !output.success() {
             let error_str =
                 String::from_utf8(output.stderr).map_err(|_| XrdbError::OutputMalformed)?;
             return Err(XrdbError::Errored(error_str));
@@ -85,26 +384,37 @@ impl Xrdb {
 
         let output_str =
             String::from_utf8(output.stdout).map_err(|_| XrdbError::OutputMalformed)?;
+        self.insert_raw(&output_str);
+
+        Ok(())
+    }
 
-        // parse output
-        for line in output_str.lines() {
-            let (prog, rest) = match line.split_once('.') {
+    /// Insert every resource of a database in the format output by `xrdb -query`
+    fn insert_raw(&mut self, raw: &str) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse_xrdb").entered();
+
+        for line in raw.lines() {
+            if line.trim_start().starts_with(['!', '#']) {
+                continue;
+            }
+            let (name, val) = match line.split_once(':') {
                 Some(x) => x,
                 None => continue,
             };
-            let (res, val) = match rest.split_once(':') {
+            let name = name.trim();
+
+            // both `*.res` and the `*res` common in resource files bind every program
+            if let Some(res) = name.strip_prefix('*') {
+                self.insert_universal(res.trim_start_matches('.'), val.trim());
+                continue;
+            }
+            let (prog, res) = match name.split_once(['.', '*']) {
                 Some(x) => x,
                 None => continue,
             };
-
-            if prog.trim() == "*" {
-                self.insert_universal(res.trim(), val.trim());
-            } else {
-                self.insert(prog.trim(), res.trim(), val.trim());
-            }
+            self.insert(prog, res, val.trim());
         }
-
-        Ok(())
     }
 
     /// Insert a new resource
@@ -120,7 +430,7 @@ impl Xrdb {
     /// # }
     /// ```
     pub fn insert(&mut self, program: &str, res: &str, val: &str) {
-        self.get_prog_mut(program).insert(res.into(), val.into());
+        self.get_prog_mut(program).insert(intern(res), val.into());
     }
 
     /// Insert a universal resource.
@@ -139,7 +449,7 @@ impl Xrdb {
     /// # }
     /// ```
     pub fn insert_universal(&mut self, res: &str, val: &str) {
-        self.univeral.insert(res.into(), val.into());
+        self.univeral.insert(intern(res), val.into());
     }
 
     /// Query a given resource
@@ -175,19 +485,222 @@ impl Xrdb {
         self.univeral.get(res).map(|v| v.to_owned())
     }
 
-    /// Return reference to query table or creates it if not exist
-    fn get_prog(&mut self, program: &str) -> &HashMap<String, String> {
-        if !self.db.contains_key(program) {
-            self.db.insert(program.to_owned(), HashMap::new());
+    /// Query a resource and parse it as a [Color], `None` if it is not set or not a color
+    ///
+    /// ```rust
+    /// # use pino_xrdb::{Color, Xrdb};
+    /// let xrdb = Xrdb::parse("*.color1: #ea6962\n*.font: mono");
+    ///
+    /// assert_eq!(xrdb.query_color("dwm", "color1"), Some(Color::rgb(0xea, 0x69, 0x62)));
+    /// assert_eq!(xrdb.query_color("dwm", "font"), None);
+    /// ```
+    pub fn query_color(&self, program: &str, res: &str) -> Option<Color> {
+        self.query(program, res)?.parse().ok()
+    }
+
+    /// Colors `color0` through `color15` of a program, falling back to the universal ones
+    ///
+    /// Colors that are not set or can not be parsed are `None`.
+    ///
+    /// ```rust
+    /// # use pino_xrdb::{Color, Xrdb};
+    /// # fn main() {
+    /// let xrdb = Xrdb::parse("*.color0: #282828\nst.color1: #ea6962");
+    /// let palette = xrdb.palette("st");
+    ///
+    /// assert_eq!(palette[0], Some(Color::rgb(0x28, 0x28, 0x28)));
+    /// assert_eq!(palette[1].map(|c| c.to_string()).as_deref(), Some("#ea6962"));
+    /// assert_eq!(palette[2], None);
+    /// # }
+    /// ```
+    pub fn palette(&self, program: &str) -> [Option<Color>; 16] {
+        std::array::from_fn(|i| self.query_color(program, &format!("color{}", i)))
+    }
+
+    /// Every resource along with its program, `None` for universal resources, sorted by program
+    /// and name
+    pub fn entries(&self) -> Vec<(Option<&str>, &str, &str)> {
+        let mut entries = self
+            .univeral
+            .iter()
+            .map(|(res, val)| (None, &**res, val.as_str()))
+            .chain(self.db.iter().flat_map(|(prog, table)| {
+                table
+                    .iter()
+                    .map(move |(res, val)| (Some(&**prog), &**res, val.as_str()))
+            }))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    /// Resources that were added, removed or changed in `new` compared to `self`
+    ///
+    /// ```rust
+    /// # use pino_xrdb::{Diff, Xrdb};
+    /// # fn main() {
+    /// let old = Xrdb::parse("*.color1: #ea6962\n*.color2: #a9b665");
+    /// let new = Xrdb::parse("*.color1: #cc241d\n*.color2: #a9b665");
+    ///
+    /// assert_eq!(
+    ///     old.diff(&new),
+    ///     vec![Diff {
+    ///         program: None,
+    ///         res: String::from("color1"),
+    ///         old: Some(String::from("#ea6962")),
+    ///         new: Some(String::from("#cc241d")),
+    ///     }]
+    /// );
+    /// # }
+    /// ```
+    pub fn diff(&self, new: &Xrdb) -> Vec<Diff> {
+        let lookup = |xrdb: &Xrdb, program: Option<&str>, res: &str| {
+            match program {
+                Some(program) => xrdb.db.get(program).and_then(|table| table.get(res)),
+                None => xrdb.univeral.get(res),
+            }
+            .cloned()
+        };
+
+        let mut diffs = vec![];
+        for (program, res, val) in self.entries() {
+            let new_val = lookup(new, program, res);
+            if new_val.as_deref() != Some(val) {
+                diffs.push(Diff {
+                    program: program.map(str::to_owned),
+                    res: res.to_owned(),
+                    old: Some(val.to_owned()),
+                    new: new_val,
+                });
+            }
+        }
+        for (program, res, val) in new.entries() {
+            if lookup(self, program, res).is_none() {
+                diffs.push(Diff {
+                    program: program.map(str::to_owned),
+                    res: res.to_owned(),
+                    old: None,
+                    new: Some(val.to_owned()),
+                });
+            }
         }
-        self.db.get(program).unwrap()
+        diffs.sort_by(|a, b| (&a.program, &a.res).cmp(&(&b.program, &b.res)));
+        diffs
     }
 
-    /// Mutable version of [get_prog]
-    fn get_prog_mut(&mut self, program: &str) -> &mut HashMap<String, String> {
+    /// Merge resources into the system xrdb, equivalent to `xrdb -merge`
+    ///
+    /// `raw` is in the format of an `~/.Xresources` file and is run through the preprocessor.
+    /// ```rust,no_run
+    /// # use pino_xrdb::Xrdb;
+    /// # fn main() {
+    /// Xrdb::merge("dwm.color1: #ea6962").unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "process")]
+    pub fn merge(raw: &str) -> Result<(), XrdbError> {
+        Self::merge_with_runner(&runner::SystemRunner::default(), raw)
+    }
+
+    /// Merge resources into the system xrdb, running `xrdb` through the given runner
+    ///
+    /// Fails with [XrdbError::Unsupported] if xrdb could not run the preprocessor.
+    pub fn merge_with_runner(runner: &dyn CommandRunner, raw: &str) -> Result<(), XrdbError> {
+        Self::run_merge(runner, Cmd::new("xrdb").arg("-merge").stdin(raw))
+    }
+
+    /// Merge resources like [Xrdb::merge_with_runner], adjusting to what the installed xrdb supports
+    ///
+    /// The preprocessor is skipped with `-nocpp` when `raw` has no preprocessor directives, so
+    /// merging works on systems without `cpp`.
+    ///
+    /// ```rust
+    /// # use pino_xrdb::{runner::{Cmd, MockRunner, Output}, Capabilities, Xrdb};
+    /// # fn main() {
+    /// let caps = Capabilities {
+    ///     program: String::from("xrdb"),
+    ///     installed: true,
+    ///     version: None,
+    ///     flags: vec![String::from("-nocpp")],
+    /// };
+    /// let merge = Cmd::new("xrdb").args(["-merge", "-nocpp"]).stdin("*.color1: #ea6962");
+    /// let runner = MockRunner::new().respond(merge, Output::default());
+    /// Xrdb::merge_with_capabilities(&runner, "*.color1: #ea6962", &caps).unwrap();
+    /// # }
+    /// ```
+    pub fn merge_with_capabilities(
+        runner: &dyn CommandRunner,
+        raw: &str,
+        caps: &Capabilities,
+    ) -> Result<(), XrdbError> {
+        if !caps.installed {
+            return Err(XrdbError::Missing);
+        }
+        let mut cmd = Cmd::new("xrdb").arg("-merge");
+        let directives = raw.lines().any(|l| l.trim_start().starts_with('#'));
+        if !directives && caps.supports("-nocpp") {
+            cmd = cmd.arg("-nocpp");
+        }
+        Self::run_merge(runner, cmd.stdin(raw))
+    }
+
+    fn run_merge(runner: &dyn CommandRunner, cmd: Cmd) -> Result<(), XrdbError> {
+        let output = runner.output(&cmd)?;
+        if !output.success() {
+            let error_str =
+                String::from_utf8(output.stderr).map_err(|_| XrdbError::OutputMalformed)?;
+            if error_str.contains("preprocessor") || error_str.contains("cpp: not found") {
+                return Err(XrdbError::Unsupported(String::from(
+                    "preprocessing, cpp is not installed",
+                )));
+            }
+            return Err(XrdbError::Errored(error_str));
+        }
+        Ok(())
+    }
+
+    /// Version and supported flags of the installed xrdb
+    ///
+    /// ```rust,no_run
+    /// # use pino_xrdb::Xrdb;
+    /// # fn main() {
+    /// let caps = Xrdb::capabilities();
+    /// if !caps.supports("-nocpp") {
+    ///     println!("{} always runs the preprocessor", caps);
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "process")]
+    pub fn capabilities() -> Capabilities {
+        Self::capabilities_with_runner(&runner::SystemRunner::default())
+    }
+
+    /// Version and supported flags of xrdb, running it through the given runner
+    pub fn capabilities_with_runner(runner: &dyn CommandRunner) -> Capabilities {
+        pino_xcommon::capability::probe(runner, "xrdb", CAPABILITY_FLAGS)
+    }
+
+    /// Return reference to query table or creates it if not exist
+    #[allow(dead_code)]
+    fn get_prog(&mut self, program: &str) -> &HashMap<Arc<str>, String> {
+        self.get_prog_mut(program)
+    }
+
+    /// Mutable version of [get_prog](Xrdb::get_prog)
+    fn get_prog_mut(&mut self, program: &str) -> &mut HashMap<Arc<str>, String> {
         if !self.db.contains_key(program) {
-            self.db.insert(program.to_owned(), HashMap::new());
+            self.db.insert(intern(program), HashMap::new());
         }
         self.db.get_mut(program).unwrap()
     }
 }
+
+impl fmt::Display for Xrdb {
+    /// Format the database like `xrdb -query`, so it can be read back with [Xrdb::parse]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (program, res, val) in self.entries() {
+            writeln!(f, "{}.{}:\t{}", program.unwrap_or("*"), res, val)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,449 @@
+//! Spawn and manage headless `Xvfb` or nested `Xephyr` servers
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xserver::XServer;
+//!
+//! let server = XServer::xvfb().size(1280, 720).start().unwrap();
+//! // run the clients of a test against it
+//! let display = server.display();
+//! println!("running on {}", server.name());
+//! server.stop().unwrap();
+//! # }
+//! ```
+//!
+//! A free display number is picked from [FIRST_DISPLAY] onwards unless one is given, and
+//! [XServer::start] returns once the server reports through `-displayfd` that it accepts
+//! connections. When another server started at the same time takes the number first, the next free
+//! one is tried. The server is killed when the [Server] is dropped, and its lock file and socket
+//! are removed.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use pino_xcommon::runner::{Cmd, CommandRunner, Process, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+/// Display number the search for a free one starts at, high enough to not collide with sessions
+pub const FIRST_DISPLAY: u32 = 99;
+
+/// How many display numbers are tried before giving up
+pub const DISPLAY_RANGE: u32 = 100;
+
+/// Time a server may take to accept connections, unless configured otherwise
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Directory the X servers create their lock files in
+pub const LOCK_DIR: &str = "/tmp";
+
+/// Directory the X servers create their sockets in
+pub const SOCKET_DIR: &str = "/tmp/.X11-unix";
+
+/// Errors of running an X server
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The server could not be run, failed or timed out
+    Run(RunError),
+    /// Xephyr was started in a session without an X server to show it in
+    NoXServer(Session),
+    /// The display number that was given, or every one that was tried, is taken
+    NoFreeDisplay,
+    /// Lock file or socket left behind by the stopped server could not be removed
+    Cleanup { path: PathBuf, source: io::Error },
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            Error::Cleanup { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::NoFreeDisplay => write!(f, "no free display number"),
+            Error::Cleanup { path, source } => {
+                write!(f, "could not remove {}: {}", path.display(), source)
+            },
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Server to run
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Kind {
+    /// Renders into memory, needing no display at all
+    #[default]
+    Xvfb,
+    /// Renders into a window of the running X server
+    Xephyr,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Xvfb => write!(f, "Xvfb"),
+            Kind::Xephyr => write!(f, "Xephyr"),
+        }
+    }
+}
+
+/// Configuration of a server to start
+#[derive(Debug, Clone)]
+pub struct XServer {
+    kind: Kind,
+    width: u32,
+    height: u32,
+    depth: u8,
+    number: Option<u32>,
+    args: Vec<String>,
+    ready_timeout: Duration,
+}
+
+impl XServer {
+    /// Configure a server of the given kind, with a 1024x768 screen of depth 24
+    pub fn new(kind: Kind) -> Self {
+        XServer {
+            kind,
+            width: 1024,
+            height: 768,
+            depth: 24,
+            number: None,
+            args: vec![],
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+        }
+    }
+
+    /// Configure a headless Xvfb server
+    pub fn xvfb() -> Self {
+        Self::new(Kind::Xvfb)
+    }
+
+    /// Configure an Xephyr server, shown in a window of the running X server
+    pub fn xephyr() -> Self {
+        Self::new(Kind::Xephyr)
+    }
+
+    /// Size of the screen in pixels
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Bits per pixel of the screen
+    pub fn depth(mut self, depth: u8) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Use the given display number instead of picking a free one
+    pub fn number(mut self, number: u32) -> Self {
+        self.number = Some(number);
+        self
+    }
+
+    /// Pass an extra argument to the server, such as `+extension` `RANDR`
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_owned());
+        self
+    }
+
+    /// Time the server may take to accept connections before it is killed
+    pub fn ready_timeout(mut self, timeout: Duration) -> Self {
+        self.ready_timeout = timeout;
+        self
+    }
+
+    /// Command starting the server on a display number, printing the number once it is ready
+    ///
+    /// ```rust
+    /// use pino_xserver::XServer;
+    ///
+    /// let cmd = XServer::xvfb().size(800, 600).command(99);
+    /// assert_eq!(
+    ///     cmd.to_string(),
+    ///     "Xvfb :99 -screen 0 800x600x24 -nolisten tcp -displayfd 1"
+    /// );
+    /// ```
+    pub fn command(&self, number: u32) -> Cmd {
+        let screen = format!("{}x{}x{}", self.width, self.height, self.depth);
+        let cmd = match self.kind {
+            Kind::Xvfb => Cmd::new("Xvfb").args([
+                format!(":{}", number),
+                "-screen".into(),
+                "0".into(),
+                screen,
+            ]),
+            Kind::Xephyr => {
+                Cmd::new("Xephyr").args([format!(":{}", number), "-screen".into(), screen])
+            },
+        };
+        cmd.args(["-nolisten", "tcp", "-displayfd", "1"])
+            .args(self.args.iter().cloned())
+    }
+
+    /// Start the server, returning once it accepts connections
+    #[cfg(feature = "process")]
+    pub fn start(&self) -> Result<Server, Error> {
+        if self.kind == Kind::Xephyr {
+            pino_xcommon::session::require_x(&DisplayHandle::default())
+                .map_err(Error::NoXServer)?;
+        }
+        self.start_with_runner(&runner::SystemRunner::new().timeout(None))
+    }
+
+    /// Start the server through the given runner, returning once it accepts connections
+    ///
+    /// The runner must not time out the server, as it keeps running until it is stopped.
+    pub fn start_with_runner(&self, runner: &dyn CommandRunner) -> Result<Server, Error> {
+        self.start_in(runner, Path::new(LOCK_DIR), Path::new(SOCKET_DIR))
+    }
+
+    fn start_in(
+        &self,
+        runner: &dyn CommandRunner,
+        lock_dir: &Path,
+        socket_dir: &Path,
+    ) -> Result<Server, Error> {
+        let numbers = match self.number {
+            Some(number) => number..number + 1,
+            None => FIRST_DISPLAY..FIRST_DISPLAY + DISPLAY_RANGE,
+        };
+        for number in numbers.filter(|n| is_free(lock_dir, socket_dir, *n)) {
+            let process = match self.start_on(runner, number) {
+                // another server took the number since it was checked
+                Err(Error::Run(RunError::Exited { stderr, .. }))
+                    if stderr.contains("already active") =>
+                {
+                    continue
+                },
+                result => result?,
+            };
+            let lock_file = lock_file(lock_dir, number);
+            return Ok(Server {
+                process: Some(process),
+                kind: self.kind,
+                number,
+                // the server writes its pid into the lock file
+                owner: fs::read_to_string(&lock_file).ok(),
+                lock_file,
+                socket: socket(socket_dir, number),
+            });
+        }
+        Err(Error::NoFreeDisplay)
+    }
+
+    /// Spawn the server on the display number, waiting until it reports it accepts connections
+    fn start_on(&self, runner: &dyn CommandRunner, number: u32) -> Result<Box<dyn Process>, Error> {
+        let program = self.kind.to_string();
+        let mut process = runner
+            .spawn(&self.command(number))
+            .map_err(|e| RunError::from_io(&program, e))?;
+
+        // reading the number blocks until the server is ready or exits, unless it is killed
+        let (stop, stopped) = mpsc::channel::<()>();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let watchdog = process.killer().map(|kill| {
+            let (timeout, timed_out) = (self.ready_timeout, timed_out.clone());
+            thread::spawn(move || {
+                if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    kill();
+                }
+            })
+        });
+        let mut line = String::new();
+        let read = process.stdout().read_line(&mut line);
+        drop(stop);
+        if let Some(watchdog) = watchdog {
+            let _ = watchdog.join();
+        }
+        if read.is_ok() && line.trim() == number.to_string() && !timed_out.load(Ordering::SeqCst) {
+            return Ok(process);
+        }
+
+        // a server that failed to start explains why on stderr
+        let _ = process.kill();
+        let output = process.wait().map_err(|e| RunError::from_io(&program, e))?;
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let e = match timed_out.load(Ordering::SeqCst) && stderr.trim().is_empty() {
+            true => RunError::TimedOut { program },
+            false => RunError::Exited {
+                program,
+                status: output.status,
+                stderr,
+            },
+        };
+        Err(e.into())
+    }
+}
+
+/// Running server, killed when dropped
+pub struct Server {
+    process: Option<Box<dyn Process>>,
+    kind: Kind,
+    number: u32,
+    lock_file: PathBuf,
+    socket: PathBuf,
+    /// Contents of the lock file while the server ran, to recognize it was not taken over since
+    owner: Option<String>,
+}
+
+impl Server {
+    /// Kind of the server
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Display number of the server
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Name of the display of the server, such as `:99`
+    pub fn name(&self) -> String {
+        format!(":{}", self.number)
+    }
+
+    /// Handle to the display of the server, to pass to the `*_with_runner` functions
+    pub fn display(&self) -> DisplayHandle {
+        DisplayHandle::new().display(self.name())
+    }
+
+    /// Point `$DISPLAY` of this process at the server, so everything started afterwards uses it
+    pub fn set_env(&self) {
+        std::env::set_var("DISPLAY", self.name());
+    }
+
+    /// Kill the server and remove its lock file and socket
+    pub fn stop(mut self) -> Result<(), Error> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), Error> {
+        if let Some(mut process) = self.process.take() {
+            let program = self.kind.to_string();
+            process
+                .kill()
+                .and_then(|_| process.wait())
+                .map_err(|e| RunError::from_io(&program, e))?;
+        }
+        // killed servers leave them behind, making the number look taken, but they are only
+        // removed while the lock file still names this server
+        if self.owner.is_none() || fs::read_to_string(&self.lock_file).ok() != self.owner {
+            return Ok(());
+        }
+        for path in [&self.lock_file, &self.socket] {
+            match fs::remove_file(path) {
+                Err(source) if source.kind() != io::ErrorKind::NotFound => {
+                    return Err(Error::Cleanup {
+                        path: path.clone(),
+                        source,
+                    })
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+fn lock_file(dir: &Path, number: u32) -> PathBuf {
+    dir.join(format!(".X{}-lock", number))
+}
+
+fn socket(dir: &Path, number: u32) -> PathBuf {
+    dir.join(format!("X{}", number))
+}
+
+/// Check if the display number has neither a lock file nor a socket
+fn is_free(lock_dir: &Path, socket_dir: &Path, number: u32) -> bool {
+    !lock_file(lock_dir, number).exists() && !socket(socket_dir, number).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn pick_display_and_fail_to_start() {
+        let dir = env::temp_dir().join(format!("pino_xserver_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(lock_file(&dir, FIRST_DISPLAY), "").unwrap();
+        fs::write(socket(&dir, FIRST_DISPLAY + 1), "").unwrap();
+        // the next number is taken by a server started at the same time
+        let xvfb = XServer::xvfb();
+        let runner = MockRunner::new()
+            .respond(
+                xvfb.command(FIRST_DISPLAY + 2),
+                Output::from_failure(
+                    1,
+                    "Server is already active for display 101
+",
+                ),
+            )
+            .respond(
+                xvfb.command(FIRST_DISPLAY + 3),
+                Output::from_stdout("102\n"),
+            );
+        let server = xvfb.start_in(&runner, &dir, &dir).unwrap();
+        assert_eq!(server.number(), FIRST_DISPLAY + 3);
+        server.stop().unwrap();
+        assert!(lock_file(&dir, FIRST_DISPLAY).exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let xephyr = XServer::xephyr().number(4242).arg("-resizeable");
+        assert_eq!(
+            xephyr.command(4242).to_string(),
+            "Xephyr :4242 -screen 1024x768x24 -nolisten tcp -displayfd 1 -resizeable"
+        );
+        let runner = MockRunner::new().respond(
+            xephyr.command(4242),
+            Output::from_failure(1, "Fatal server error: Cannot open host display\n"),
+        );
+        let err = xephyr
+            .ready_timeout(Duration::ZERO)
+            .start_with_runner(&runner)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, .. })
+        ));
+    }
+}
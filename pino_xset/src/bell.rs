@@ -0,0 +1,113 @@
+//! Volume, pitch and duration of the keyboard bell
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xset::bell;
+//!
+//! // a short and quiet beep instead of the default one
+//! bell::set(20, 800, 50).unwrap();
+//! # }
+//! ```
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{pairs, parse_value, Error};
+
+/// Bell settings of the X server
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Bell {
+    /// Volume in percent, `0` if the bell is off
+    pub volume: u32,
+    /// Pitch in Hz
+    pub pitch: u32,
+    /// Duration in milliseconds
+    pub duration: u32,
+}
+
+impl Bell {
+    /// Parse a line of the `Keyboard Control` section, which holds the bell settings
+    pub(crate) fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        for (key, value) in pairs(line) {
+            match key {
+                "bell percent" => self.volume = parse_value(line, value)?,
+                "bell pitch" => self.pitch = parse_value(line, value)?,
+                "bell duration" => self.duration = parse_value(line, value)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ring the bell at `volume` percent and `pitch` Hz for `duration` milliseconds
+#[cfg(feature = "process")]
+pub fn set(volume: u32, pitch: u32, duration: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        volume,
+        pitch,
+        duration,
+    )
+}
+
+/// Set the volume, pitch and duration of the bell, running `xset` through the given runner
+///
+/// ```rust
+/// use pino_xset::{bell, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xset").args(["b", "20", "800", "50"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// bell::set_with_runner(&runner, 20, 800, 50).unwrap();
+/// ```
+pub fn set_with_runner(
+    runner: &dyn CommandRunner,
+    volume: u32,
+    pitch: u32,
+    duration: u32,
+) -> Result<(), Error> {
+    let args = [volume, pitch, duration].map(|arg| arg.to_string());
+    run_checked(runner, &Cmd::new("xset").arg("b").args(args))?;
+    Ok(())
+}
+
+/// Silence the bell
+#[cfg(feature = "process")]
+pub fn disable() -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    disable_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+}
+
+/// Silence the bell, running `xset` through the given runner
+pub fn disable_with_runner(runner: &dyn CommandRunner) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xset").args(["b", "off"]))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn parse_bell() {
+        let settings = Settings::parse(concat!(
+            "Keyboard Control:\n",
+            "  auto repeat delay:  660    repeat rate:  25\n",
+            "  bell percent:  50    bell pitch:  400    bell duration:  100\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.bell,
+            Bell {
+                volume: 50,
+                pitch: 400,
+                duration: 100
+            }
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Power management of the monitors through DPMS, for lockers and presentation tools
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xset::dpms::{self, PowerLevel};
+//!
+//! // blank the monitors as soon as the screen is locked
+//! dpms::force(PowerLevel::Off).unwrap();
+//! // and after 10 minutes of inactivity otherwise
+//! dpms::set_timeouts(600, 600, 600).unwrap();
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{pairs, parse_value, Error};
+
+/// Power level of the monitors
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum PowerLevel {
+    On,
+    Standby,
+    Suspend,
+    Off,
+}
+
+impl fmt::Display for PowerLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerLevel::On => write!(f, "on"),
+            PowerLevel::Standby => write!(f, "standby"),
+            PowerLevel::Suspend => write!(f, "suspend"),
+            PowerLevel::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl FromStr for PowerLevel {
+    type Err = ();
+
+    /// From the level as taken by `xset dpms force`, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "on" => Ok(PowerLevel::On),
+            "standby" => Ok(PowerLevel::Standby),
+            "suspend" => Ok(PowerLevel::Suspend),
+            "off" => Ok(PowerLevel::Off),
+            _ => Err(()),
+        }
+    }
+}
+
+/// DPMS settings of the X server
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Dpms {
+    /// Whether the monitors are powered down after the timeouts
+    pub enabled: bool,
+    /// Seconds of inactivity before the monitors go to standby, `0` to skip it
+    pub standby: u32,
+    /// Seconds of inactivity before the monitors are suspended, `0` to skip it
+    pub suspend: u32,
+    /// Seconds of inactivity before the monitors are turned off, `0` to skip it
+    pub off: u32,
+    /// Current power level, `None` if DPMS is disabled
+    pub level: Option<PowerLevel>,
+}
+
+impl Dpms {
+    /// Parse a line of the `DPMS (Energy Star)` section
+    pub(crate) fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        let line = line.trim();
+        if let Some(state) = line.strip_prefix("DPMS is ") {
+            self.enabled = state == "Enabled";
+        } else if let Some(level) = line.strip_prefix("Monitor is ") {
+            let level = level.trim_start_matches("in ").parse();
+            self.level = Some(level.map_err(|_| Error::Malformed(line.to_owned()))?);
+        }
+        for (key, value) in pairs(line) {
+            match key {
+                "Standby" => self.standby = parse_value(line, value)?,
+                "Suspend" => self.suspend = parse_value(line, value)?,
+                "Off" => self.off = parse_value(line, value)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set the seconds of inactivity before each power level, `0` to skip a level
+///
+/// This does not enable DPMS if it is disabled, see [set_enabled].
+#[cfg(feature = "process")]
+pub fn set_timeouts(standby: u32, suspend: u32, off: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_timeouts_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        standby,
+        suspend,
+        off,
+    )
+}
+
+/// Set the timeouts of each power level, running `xset` through the given runner
+pub fn set_timeouts_with_runner(
+    runner: &dyn CommandRunner,
+    standby: u32,
+    suspend: u32,
+    off: u32,
+) -> Result<(), Error> {
+    let timeouts = [standby, suspend, off].map(|timeout| timeout.to_string());
+    run_checked(runner, &Cmd::new("xset").arg("dpms").args(timeouts))?;
+    Ok(())
+}
+
+/// Enable or disable powering down the monitors after the timeouts
+#[cfg(feature = "process")]
+pub fn set_enabled(enabled: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_enabled_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        enabled,
+    )
+}
+
+/// Enable or disable DPMS, running `xset` through the given runner
+pub fn set_enabled_with_runner(runner: &dyn CommandRunner, enabled: bool) -> Result<(), Error> {
+    let flag = if enabled { "+dpms" } else { "-dpms" };
+    run_checked(runner, &Cmd::new("xset").arg(flag))?;
+    Ok(())
+}
+
+/// Switch the monitors to a power level right away, which also enables DPMS
+///
+/// The monitors are woken up again by the next input, unless the level is [PowerLevel::On].
+#[cfg(feature = "process")]
+pub fn force(level: PowerLevel) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    force_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        level,
+    )
+}
+
+/// Switch the monitors to a power level, running `xset` through the given runner
+///
+/// ```rust
+/// use pino_xset::{dpms::{self, PowerLevel}, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xset").args(["dpms", "force", "standby"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// dpms::force_with_runner(&runner, PowerLevel::Standby).unwrap();
+/// ```
+pub fn force_with_runner(runner: &dyn CommandRunner, level: PowerLevel) -> Result<(), Error> {
+    let cmd = Cmd::new("xset").args(["dpms", "force", &level.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn parse_dpms() {
+        let settings = Settings::parse(concat!(
+            "DPMS (Energy Star):\n",
+            "  Standby: 600    Suspend: 900    Off: 1200\n",
+            "  DPMS is Enabled\n",
+            "  Monitor is in Standby\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.dpms,
+            Some(Dpms {
+                enabled: true,
+                standby: 600,
+                suspend: 900,
+                off: 1200,
+                level: Some(PowerLevel::Standby),
+            })
+        );
+
+        let settings = Settings::parse("Server does not have the DPMS Extension\n").unwrap();
+        assert_eq!(settings.dpms, None);
+        assert!(Settings::parse("DPMS (Energy Star):\n  Monitor is Asleep\n").is_err());
+    }
+}
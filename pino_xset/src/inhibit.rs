@@ -0,0 +1,226 @@
+//! Keep the screen on for as long as an [IdleInhibitor] is held
+//!
+//! The inhibitor disables DPMS and resets the screensaver from a background thread every
+//! interval, so neither kicks in while a video is playing or slides are shown. The interval is
+//! shortened to half the screensaver timeout when that is shorter. Dropping the inhibitor stops
+//! the thread and enables DPMS again if it was enabled before.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xset::inhibit::IdleInhibitor;
+//!
+//! let inhibitor = IdleInhibitor::new().unwrap();
+//! // play the video
+//! drop(inhibitor);
+//! # }
+//! ```
+
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use pino_xcommon::runner::CommandRunner;
+
+use crate::{dpms, screensaver, DisplayHandle, Error, Settings};
+
+/// Time between two resets of the screensaver, unless configured otherwise or the screensaver
+/// starts sooner
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps the screen on until it is dropped or released, see the [module](self) documentation
+pub struct IdleInhibitor {
+    runner: Arc<dyn CommandRunner>,
+    display: DisplayHandle,
+    /// Whether DPMS was enabled before, and has to be enabled again
+    dpms: bool,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IdleInhibitor {
+    /// Keep the screen of the default display on, resetting the screensaver every
+    /// [DEFAULT_INTERVAL]
+    #[cfg(feature = "process")]
+    pub fn new() -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        IdleInhibitor::start(
+            crate::runner::SystemRunner::default(),
+            display,
+            DEFAULT_INTERVAL,
+        )
+    }
+
+    /// Keep the screen of the display on, running `xset` through the given runner
+    ///
+    /// The screensaver is reset every `interval`, or every half of its timeout if that is shorter.
+    pub fn start(
+        runner: impl CommandRunner + 'static,
+        display: DisplayHandle,
+        interval: Duration,
+    ) -> Result<Self, Error> {
+        let runner: Arc<dyn CommandRunner> = Arc::new(runner);
+        let (dpms, interval) = {
+            let runner = display.runner(runner.as_ref());
+            let settings = Settings::query_with_runner(&runner)?;
+            let dpms = settings.dpms.is_some_and(|dpms| dpms.enabled);
+            if dpms {
+                dpms::set_enabled_with_runner(&runner, false)?;
+            }
+            if let Err(e) = screensaver::reset_with_runner(&runner) {
+                if dpms {
+                    let _ = dpms::set_enabled_with_runner(&runner, true);
+                }
+                return Err(e);
+            }
+            let interval = match settings.screen_saver.timeout {
+                0 => interval,
+                timeout => interval.min(Duration::from_secs(timeout.into()) / 2),
+            };
+            (dpms, interval)
+        };
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn({
+            let (runner, display) = (runner.clone(), display.clone());
+            move || loop {
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {},
+                    _ => return,
+                }
+                // a failed reset is retried on the next interval
+                let _ = screensaver::reset_with_runner(&display.runner(runner.as_ref()));
+            }
+        });
+        Ok(IdleInhibitor {
+            runner,
+            display,
+            dpms,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop keeping the screen on, reporting if DPMS could not be enabled again
+    pub fn release(mut self) -> Result<(), Error> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), Error> {
+        // dropping the sender wakes the thread up from its wait
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        if std::mem::take(&mut self.dpms) {
+            dpms::set_enabled_with_runner(&self.display.runner(self.runner.as_ref()), true)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::{
+        capture::{CaptureLog, CaptureRunner},
+        runner::{Cmd, MockRunner, Output},
+    };
+
+    use super::*;
+
+    #[test]
+    fn restore_dpms() {
+        let xset = |args: &[&str]| Cmd::new("xset").args(args.iter().copied());
+        let runner = || {
+            MockRunner::new()
+                .respond(
+                    xset(&["q"]),
+                    Output::from_stdout(concat!(
+                        "DPMS (Energy Star):\n",
+                        "  Standby: 600    Suspend: 600    Off: 600\n",
+                        "  DPMS is Enabled\n",
+                        "  Monitor is On\n",
+                    )),
+                )
+                .respond(xset(&["-dpms"]), Output::default())
+                .respond(xset(&["s", "reset"]), Output::default())
+        };
+        let display = DisplayHandle::default();
+
+        let inhibitor = IdleInhibitor::start(
+            runner().respond(xset(&["+dpms"]), Output::default()),
+            display.clone(),
+            Duration::from_millis(1),
+        )
+        .unwrap();
+        thread::sleep(Duration::from_millis(20));
+        inhibitor.release().unwrap();
+
+        // enabling DPMS again is attempted, and fails without a response
+        let inhibitor = IdleInhibitor::start(runner(), display, DEFAULT_INTERVAL).unwrap();
+        assert!(inhibitor.release().is_err());
+    }
+
+    #[test]
+    fn restore_dpms_on_failure() {
+        let xset = |args: &[&str]| Cmd::new("xset").args(args.iter().copied());
+        let mock = MockRunner::new()
+            .respond(
+                xset(&["q"]),
+                Output::from_stdout("DPMS (Energy Star):\n  DPMS is Enabled\n"),
+            )
+            .respond(xset(&["-dpms"]), Output::default())
+            .respond(
+                xset(&["s", "reset"]),
+                Output::from_failure(1, "bad request"),
+            )
+            .respond(xset(&["+dpms"]), Output::default());
+        let log = Arc::new(CaptureLog::new());
+        let runner = CaptureRunner::new(mock, log.clone());
+
+        let result = IdleInhibitor::start(runner, DisplayHandle::default(), DEFAULT_INTERVAL);
+        assert!(result.is_err());
+        let calls = log.entries().into_iter().map(|c| c.cmd).collect::<Vec<_>>();
+        assert_eq!(calls.last(), Some(&xset(&["+dpms"])));
+    }
+
+    #[test]
+    fn reset_before_timeout() {
+        let xset = |args: &[&str]| Cmd::new("xset").args(args.iter().copied());
+        let mock = MockRunner::new()
+            .respond(
+                xset(&["q"]),
+                Output::from_stdout(concat!(
+                    "Screen Saver:\n",
+                    "  prefer blanking:  yes    allow exposures:  yes\n",
+                    "  timeout:  1    cycle:  600\n",
+                )),
+            )
+            .respond(xset(&["s", "reset"]), Output::default());
+        let log = Arc::new(CaptureLog::new());
+        let runner = CaptureRunner::new(mock, log.clone());
+
+        // resets every half second instead of every 30
+        let inhibitor =
+            IdleInhibitor::start(runner, DisplayHandle::default(), DEFAULT_INTERVAL).unwrap();
+        thread::sleep(Duration::from_millis(1200));
+        inhibitor.release().unwrap();
+        let resets = log
+            .entries()
+            .iter()
+            .filter(|c| c.cmd == xset(&["s", "reset"]))
+            .count();
+        assert!(resets >= 2);
+    }
+}
@@ -0,0 +1,304 @@
+//! Keyboard autorepeat, how long a key is held before it repeats and how fast it then repeats,
+//! and the keyboard LEDs
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xset::keyboard;
+//!
+//! keyboard::set_repeat_rate(200, 40).unwrap();
+//! // keep caps lock from repeating
+//! keyboard::set_key_repeat(66, false).unwrap();
+//! keyboard::set_named_led("Scroll Lock", true).unwrap();
+//! # }
+//! ```
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::{pairs, parse_value, Error};
+
+/// Keyboard settings of the X server
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Keyboard {
+    /// Whether held keys repeat at all
+    pub auto_repeat: bool,
+    /// Milliseconds a key is held before it starts repeating
+    pub repeat_delay: u32,
+    /// Repeats per second
+    pub repeat_rate: u32,
+    /// Bitmask of the keycodes that repeat, keycode `k` being bit `k % 8` of byte `k / 8`
+    pub repeating_keys: Vec<u8>,
+    /// Bitmask of the LEDs that are lit, LED `n` being bit `n - 1`
+    pub led_mask: u32,
+    /// LEDs known to XKB, such as `Caps Lock`
+    pub indicators: Vec<Indicator>,
+}
+
+/// Keyboard LED known to XKB
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Indicator {
+    /// Number of the LED, from 1 as taken by `xset led`
+    pub led: u8,
+    pub name: String,
+    pub on: bool,
+}
+
+impl Keyboard {
+    /// Check if holding the key repeats it
+    pub fn repeats(&self, keycode: u8) -> bool {
+        let byte = self.repeating_keys.get(usize::from(keycode / 8));
+        self.auto_repeat && byte.is_some_and(|byte| byte & (1 << (keycode % 8)) != 0)
+    }
+
+    /// Check if the LED numbered `led`, from 1, is lit
+    pub fn led(&self, led: u8) -> bool {
+        (1..=32).contains(&led) && self.led_mask & (1 << (led - 1)) != 0
+    }
+
+    /// LED known to XKB by the given name
+    pub fn indicator(&self, name: &str) -> Option<&Indicator> {
+        self.indicators
+            .iter()
+            .find(|indicator| indicator.name == name)
+    }
+
+    /// Parse a line of the `Keyboard Control` section
+    pub(crate) fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        let pairs = pairs(line);
+        if pairs.is_empty() {
+            // the mask of repeating keys continues on the lines below its key
+            if let Some(bytes) = parse_hex(line.trim()) {
+                self.repeating_keys.extend(bytes);
+            }
+            return Ok(());
+        }
+        if line.trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+            let indicators =
+                parse_indicators(line).ok_or_else(|| Error::Malformed(line.to_owned()))?;
+            self.indicators.extend(indicators);
+            return Ok(());
+        }
+        for (key, value) in pairs {
+            match key {
+                "auto repeat" => self.auto_repeat = value == "on",
+                "auto repeat delay" => self.repeat_delay = parse_value(line, value)?,
+                "repeat rate" => self.repeat_rate = parse_value(line, value)?,
+                "LED mask" => {
+                    self.led_mask = u32::from_str_radix(value, 16)
+                        .map_err(|_| Error::Malformed(line.to_owned()))?;
+                },
+                "auto repeating keys" => {
+                    self.repeating_keys =
+                        parse_hex(value).ok_or_else(|| Error::Malformed(line.to_owned()))?;
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a line of XKB indicators, `00: Caps Lock:   off    01: Num Lock:    on`
+fn parse_indicators(line: &str) -> Option<Vec<Indicator>> {
+    let mut parts = line.split(':');
+    let mut index = parts.next()?.trim().parse::<u8>().ok()?;
+    let mut indicators = vec![];
+    while let Some(name) = parts.next() {
+        // the state is followed by the index of the next indicator
+        let rest = parts.next()?.trim();
+        let (state, next) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        indicators.push(Indicator {
+            led: index + 1,
+            name: name.trim().to_owned(),
+            on: state == "on",
+        });
+        if next.trim().is_empty() {
+            break;
+        }
+        index = next.trim().parse().ok()?;
+    }
+    Some(indicators)
+}
+
+/// Bytes printed in hexadecimal
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.is_empty() || !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Hold a key for `delay` milliseconds before it repeats `rate` times per second
+#[cfg(feature = "process")]
+pub fn set_repeat_rate(delay: u32, rate: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_repeat_rate_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        delay,
+        rate,
+    )
+}
+
+/// Set the repeat delay and rate, running `xset` through the given runner
+///
+/// ```rust
+/// use pino_xset::{keyboard, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xset").args(["r", "rate", "200", "40"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// keyboard::set_repeat_rate_with_runner(&runner, 200, 40).unwrap();
+/// ```
+pub fn set_repeat_rate_with_runner(
+    runner: &dyn CommandRunner,
+    delay: u32,
+    rate: u32,
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xset").args(["r", "rate", &delay.to_string(), &rate.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Turn autorepeat on or off for every key
+#[cfg(feature = "process")]
+pub fn set_auto_repeat(enabled: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_auto_repeat_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        enabled,
+    )
+}
+
+/// Turn autorepeat on or off for every key, running `xset` through the given runner
+pub fn set_auto_repeat_with_runner(runner: &dyn CommandRunner, enabled: bool) -> Result<(), Error> {
+    let cmd = Cmd::new("xset").args(["r", if enabled { "on" } else { "off" }]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Turn autorepeat on or off for a single keycode
+#[cfg(feature = "process")]
+pub fn set_key_repeat(keycode: u8, enabled: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_key_repeat_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        keycode,
+        enabled,
+    )
+}
+
+/// Turn autorepeat on or off for a single keycode, running `xset` through the given runner
+pub fn set_key_repeat_with_runner(
+    runner: &dyn CommandRunner,
+    keycode: u8,
+    enabled: bool,
+) -> Result<(), Error> {
+    let flag = if enabled { "r" } else { "-r" };
+    run_checked(runner, &Cmd::new("xset").args([flag, &keycode.to_string()]))?;
+    Ok(())
+}
+
+/// Light or turn off the LED numbered `led`, from 1
+#[cfg(feature = "process")]
+pub fn set_led(led: u8, on: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_led_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        led,
+        on,
+    )
+}
+
+/// Light or turn off an LED by number, running `xset` through the given runner
+pub fn set_led_with_runner(runner: &dyn CommandRunner, led: u8, on: bool) -> Result<(), Error> {
+    let flag = if on { "led" } else { "-led" };
+    run_checked(runner, &Cmd::new("xset").args([flag, &led.to_string()]))?;
+    Ok(())
+}
+
+/// Light or turn off the LED known to XKB by the given name, such as `Scroll Lock`
+#[cfg(feature = "process")]
+pub fn set_named_led(name: &str, on: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_named_led_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        name,
+        on,
+    )
+}
+
+/// Light or turn off an LED by name, running `xset` through the given runner
+///
+/// ```rust
+/// use pino_xset::{keyboard, runner::{Cmd, MockRunner, Output}};
+///
+/// let cmd = Cmd::new("xset").args(["-led", "named", "Scroll Lock"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// keyboard::set_named_led_with_runner(&runner, "Scroll Lock", false).unwrap();
+/// ```
+pub fn set_named_led_with_runner(
+    runner: &dyn CommandRunner,
+    name: &str,
+    on: bool,
+) -> Result<(), Error> {
+    let flag = if on { "led" } else { "-led" };
+    run_checked(runner, &Cmd::new("xset").args([flag, "named", name]))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn repeating_keys() {
+        let settings = Settings::parse(concat!(
+            "Keyboard Control:\n",
+            "  auto repeat:  on    key click percent:  0    LED mask:  00000002\n",
+            "  XKB indicators:\n",
+            "    00: Caps Lock:   off    01: Num Lock:    on     02: Scroll Lock: off\n",
+            "  auto repeat delay:  200    repeat rate:  40\n",
+            "  auto repeating keys:  00ffffffdffffbbf\n",
+            "                        fadfffefffedffff\n",
+            "                        9fffffffffffffff\n",
+            "                        fff7ffffffffffff\n",
+            "  bell percent:  50    bell pitch:  400    bell duration:  100\n",
+        ))
+        .unwrap();
+        let keyboard = settings.keyboard;
+        assert_eq!(keyboard.repeating_keys.len(), 32);
+        assert!(keyboard.repeats(38));
+        // caps lock
+        assert!(!keyboard.repeats(66));
+        assert!(!keyboard.repeats(0));
+        assert!(keyboard.led(2));
+        assert!(!keyboard.led(1));
+        assert_eq!(keyboard.indicators.len(), 3);
+        let num_lock = keyboard.indicator("Num Lock").unwrap();
+        assert!(num_lock.on && num_lock.led == 2);
+        assert!(!keyboard.indicator("Scroll Lock").unwrap().on);
+
+        let runner = MockRunner::new().respond(
+            Cmd::new("xset").args(["-r", "66"]),
+            Output::from_failure(1, "xset:  bad keycode\n"),
+        );
+        let err = set_key_repeat_with_runner(&runner, 66, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "xset exited with status 1: xset:  bad keycode"
+        );
+    }
+}
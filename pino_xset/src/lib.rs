@@ -0,0 +1,179 @@
+//! Query and change the settings of the X server through `xset`
+//!
+//! ```rust,no_run
+//! use pino_xset::{keyboard, Settings};
+//!
+//! # #[cfg(feature = "process")]
+//! fn main() {
+//!     keyboard::set_repeat_rate(200, 40).unwrap();
+//!
+//!     let keyboard = Settings::query().unwrap().keyboard;
+//!     println!("{}ms, {}/s", keyboard.repeat_delay, keyboard.repeat_rate);
+//! }
+//! # #[cfg(not(feature = "process"))]
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{display::DisplayHandle, platform, runner, session::Session};
+
+pub mod bell;
+pub mod dpms;
+pub mod inhibit;
+pub mod keyboard;
+pub mod lock;
+pub mod screensaver;
+
+use bell::Bell;
+use dpms::Dpms;
+use keyboard::Keyboard;
+use screensaver::ScreenSaver;
+
+/// Errors of running xset
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xset could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xset could not be parsed, holding the offending line
+    Malformed(String),
+    /// Request to the X server failed, with the `native` feature
+    Connection(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xset output: {}", line),
+            Error::Connection(e) => write!(f, "request to the X server failed: {}", e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Settings of the X server, as printed by `xset q`
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Settings {
+    pub keyboard: Keyboard,
+    /// DPMS settings, `None` if the server or display does not support it
+    pub dpms: Option<Dpms>,
+    pub screen_saver: ScreenSaver,
+    pub bell: Bell,
+}
+
+impl Settings {
+    /// Query the settings of the X server
+    #[cfg(feature = "process")]
+    pub fn query() -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_with_runner(&display.runner(&runner::SystemRunner::default()))
+    }
+
+    /// Query the settings of the X server, running `xset q` through the given runner
+    pub fn query_with_runner(runner: &dyn CommandRunner) -> Result<Self, Error> {
+        let output = run_checked(runner, &Cmd::new("xset").arg("q"))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `xset q`, sections that are not understood are skipped
+    ///
+    /// ```rust
+    /// use pino_xset::Settings;
+    ///
+    /// let settings = Settings::parse(concat!(
+    ///     "Keyboard Control:\n",
+    ///     "  auto repeat:  on    key click percent:  0    LED mask:  00000002\n",
+    ///     "  auto repeat delay:  660    repeat rate:  25\n",
+    ///     "Pointer Control:\n",
+    ///     "  acceleration:  2/1    threshold:  4\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert!(settings.keyboard.auto_repeat);
+    /// assert_eq!(settings.keyboard.repeat_delay, 660);
+    /// assert_eq!(settings.keyboard.repeat_rate, 25);
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut settings = Settings::default();
+        let mut section = "";
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            if !line.starts_with(char::is_whitespace) {
+                section = line.trim().trim_end_matches(':');
+                continue;
+            }
+            match section {
+                "Keyboard Control" => {
+                    settings.keyboard.parse_line(line)?;
+                    settings.bell.parse_line(line)?;
+                },
+                "Screen Saver" => settings.screen_saver.parse_line(line)?,
+                "DPMS (Energy Star)" => settings.dpms.get_or_insert_default().parse_line(line)?,
+                _ => {},
+            }
+        }
+        Ok(settings)
+    }
+}
+
+/// Pairs of `KEY:  VALUE` on a line of `xset q`, such as `timeout:  600    cycle:  600`
+fn pairs(line: &str) -> Vec<(&str, &str)> {
+    let mut pairs = vec![];
+    let mut parts = line.split(':');
+    let Some(mut key) = parts.next() else {
+        return pairs;
+    };
+    for part in parts {
+        let part = part.trim();
+        let (value, next) = part.split_once(char::is_whitespace).unwrap_or((part, ""));
+        pairs.push((key.trim(), value));
+        key = next;
+    }
+    pairs
+}
+
+/// Parse the value of a setting, failing with the line it is on
+fn parse_value<T: std::str::FromStr>(line: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::Malformed(line.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_pairs() {
+        assert_eq!(
+            pairs("  auto repeat:  on    key click percent:  0    LED mask:  00000002"),
+            [
+                ("auto repeat", "on"),
+                ("key click percent", "0"),
+                ("LED mask", "00000002")
+            ]
+        );
+        assert_eq!(pairs("  XKB indicators:"), [("XKB indicators", "")]);
+        assert!(pairs("  /usr/share/fonts/X11/misc").is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+//! Whether Num Lock and Caps Lock are locked, and locking them like `numlockx` does
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xset::lock::{self, Lock};
+//!
+//! // have the keypad type digits from the start of the session
+//! lock::set(Lock::Num, true).unwrap();
+//! println!("caps lock is {}", if lock::state(Lock::Caps).unwrap() { "on" } else { "off" });
+//! # }
+//! ```
+//!
+//! With the `native` feature the locked modifiers are read and changed through XKB. Otherwise the
+//! state is read from the indicators of `xset q`, Num Lock is set with `numlockx` and Caps Lock is
+//! toggled by pressing it with `xdotool`. Num Lock is assumed to be on `Mod2`, which is the case
+//! for nearly every setup.
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+
+use crate::{Error, Settings};
+
+/// Lock modifier of the keyboard
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Lock {
+    Num,
+    Caps,
+}
+
+impl Lock {
+    /// Name of the XKB indicator showing the lock, as printed by `xset q`
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            Lock::Num => "Num Lock",
+            Lock::Caps => "Caps Lock",
+        }
+    }
+
+    /// Name of the keysym of the key toggling the lock
+    pub fn keysym(&self) -> &'static str {
+        match self {
+            Lock::Num => "Num_Lock",
+            Lock::Caps => "Caps_Lock",
+        }
+    }
+}
+
+/// Check if the lock is on
+///
+/// The native backend is tried first when it is compiled in, see
+/// [pino_xcommon::backend].
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn state(lock: Lock) -> Result<bool, Error> {
+    use pino_xcommon::backend::{dispatch, Backend};
+
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    dispatch(|backend| match backend {
+        #[cfg(feature = "native")]
+        Backend::Native => Some(native::state(&display, lock)),
+        #[cfg(feature = "process")]
+        Backend::Binary => Some(state_with_runner(
+            &display.runner(&crate::runner::SystemRunner::default()),
+            lock,
+        )),
+        _ => None,
+    })
+    .unwrap_or_else(|| Err(Error::Connection(String::from("no backend available"))))
+}
+
+/// Check if the lock is on from its indicator, running `xset q` through the given runner
+pub fn state_with_runner(runner: &dyn CommandRunner, lock: Lock) -> Result<bool, Error> {
+    let keyboard = Settings::query_with_runner(runner)?.keyboard;
+    keyboard
+        .indicator(lock.indicator())
+        .map(|indicator| indicator.on)
+        .ok_or_else(|| {
+            Error::Run(RunError::Unsupported(format!(
+                "the keyboard has no {} indicator",
+                lock.indicator()
+            )))
+        })
+}
+
+/// Turn the lock on or off, doing nothing if it already is
+///
+/// The native backend is tried first when it is compiled in, see
+/// [pino_xcommon::backend].
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn set(lock: Lock, on: bool) -> Result<(), Error> {
+    use pino_xcommon::backend::{dispatch, Backend};
+
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    dispatch(|backend| match backend {
+        #[cfg(feature = "native")]
+        Backend::Native => Some(native::set(&display, lock, on)),
+        #[cfg(feature = "process")]
+        Backend::Binary => Some(set_with_runner(
+            &display.runner(&crate::runner::SystemRunner::default()),
+            lock,
+            on,
+        )),
+        _ => None,
+    })
+    .unwrap_or_else(|| Err(Error::Connection(String::from("no backend available"))))
+}
+
+/// Turn the lock on or off, running `numlockx`, or `xset q` and `xdotool`, through the given
+/// runner
+///
+/// ```rust
+/// use pino_xset::{
+///     lock::{self, Lock},
+///     runner::{Cmd, MockRunner, Output},
+/// };
+///
+/// let runner = MockRunner::new().respond(Cmd::new("numlockx").arg("on"), Output::default());
+/// lock::set_with_runner(&runner, Lock::Num, true).unwrap();
+/// ```
+pub fn set_with_runner(runner: &dyn CommandRunner, lock: Lock, on: bool) -> Result<(), Error> {
+    let cmd = match lock {
+        Lock::Num => Cmd::new("numlockx").arg(if on { "on" } else { "off" }),
+        Lock::Caps if state_with_runner(runner, lock)? == on => return Ok(()),
+        Lock::Caps => Cmd::new("xdotool").args(["key", lock.keysym()]),
+    };
+    let program = cmd.program.clone();
+    // not part of xset, so their absence is reported by name
+    match run_checked(runner, &cmd) {
+        Ok(_) => Ok(()),
+        Err(RunError::Spawn { source, .. }) => Err(Error::Run(RunError::Unsupported(format!(
+            "could not run {} ({})",
+            program, source
+        )))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Switch the lock to the other state, returning whether it is now on
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn toggle(lock: Lock) -> Result<bool, Error> {
+    let on = !state(lock)?;
+    set(lock, on)?;
+    Ok(on)
+}
+
+/// Locked modifiers through XKB, enabled by the `native` feature
+#[cfg(feature = "native")]
+mod native {
+    use x11rb::{
+        connection::RequestConnection,
+        protocol::{
+            xkb::{self, ConnectionExt as _, Group, ID},
+            xproto::ModMask,
+        },
+        rust_connection::RustConnection,
+    };
+
+    use super::{Lock, RunError};
+    use crate::{DisplayHandle, Error};
+
+    fn err(e: impl std::fmt::Display) -> Error {
+        Error::Connection(e.to_string())
+    }
+
+    fn mask(lock: Lock) -> ModMask {
+        match lock {
+            Lock::Num => ModMask::M2,
+            Lock::Caps => ModMask::LOCK,
+        }
+    }
+
+    /// Connect and enable XKB, which has to be done before any of its requests
+    fn connect(display: &DisplayHandle) -> Result<RustConnection, Error> {
+        let (conn, _) = pino_xcommon::native::connect_display(display).map_err(err)?;
+        if conn
+            .extension_information(xkb::X11_EXTENSION_NAME)
+            .map_err(err)?
+            .is_none()
+        {
+            return Err(Error::Run(RunError::Unsupported(String::from(
+                "the X server has no XKEYBOARD extension",
+            ))));
+        }
+        let reply = conn
+            .xkb_use_extension(1, 0)
+            .map_err(err)?
+            .reply()
+            .map_err(err)?;
+        if !reply.supported {
+            return Err(Error::Run(RunError::Unsupported(String::from(
+                "the X server does not support XKB 1.0",
+            ))));
+        }
+        Ok(conn)
+    }
+
+    pub(super) fn state(display: &DisplayHandle, lock: Lock) -> Result<bool, Error> {
+        let conn = connect(display)?;
+        let reply = conn
+            .xkb_get_state(ID::USE_CORE_KBD.into())
+            .map_err(err)?
+            .reply()
+            .map_err(err)?;
+        Ok(reply.locked_mods.contains(mask(lock)))
+    }
+
+    pub(super) fn set(display: &DisplayHandle, lock: Lock, on: bool) -> Result<(), Error> {
+        let conn = connect(display)?;
+        let locks = if on { mask(lock) } else { ModMask::from(0u16) };
+        conn.xkb_latch_lock_state(
+            ID::USE_CORE_KBD.into(),
+            mask(lock),
+            locks,
+            false,
+            Group::M1,
+            ModMask::from(0u16),
+            false,
+            0,
+        )
+        .map_err(err)?
+        .check()
+        .map_err(err)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn caps_lock_from_indicators() {
+        let xset_q = Output::from_stdout(concat!(
+            "Keyboard Control:\n",
+            "  XKB indicators:\n",
+            "    00: Caps Lock:   on     01: Num Lock:    off    02: Scroll Lock: off\n",
+        ));
+        let runner = MockRunner::new()
+            .respond(Cmd::new("xset").arg("q"), xset_q)
+            .respond(
+                Cmd::new("xdotool").args(["key", "Caps_Lock"]),
+                Output::default(),
+            );
+        assert!(state_with_runner(&runner, Lock::Caps).unwrap());
+        assert!(!state_with_runner(&runner, Lock::Num).unwrap());
+
+        // already on, so nothing is pressed
+        set_with_runner(&runner, Lock::Caps, true).unwrap();
+        assert_eq!(runner.calls().len(), 3);
+        set_with_runner(&runner, Lock::Caps, false).unwrap();
+        assert_eq!(runner.calls().last().unwrap().program, "xdotool");
+
+        let err = set_with_runner(&runner, Lock::Num, true).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Unsupported(_))));
+    }
+}
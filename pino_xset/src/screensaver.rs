@@ -0,0 +1,270 @@
+//! Timeout of the screensaver of the X server, resetting it to keep the screen awake, and how
+//! long the user has been idle
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use std::{thread, time::Duration};
+//!
+//! use pino_xset::screensaver;
+//!
+//! // keep the screen awake while a video is playing
+//! loop {
+//!     screensaver::reset().unwrap();
+//!     thread::sleep(Duration::from_secs(30));
+//! }
+//! # }
+//! ```
+//!
+//! [idle_time] asks the MIT-SCREEN-SAVER extension with the `native` feature, and runs
+//! `xprintidle` otherwise.
+
+use std::time::Duration;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+
+use crate::{pairs, parse_value, Error};
+
+/// Screensaver settings of the X server
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ScreenSaver {
+    /// Seconds of inactivity before the screensaver starts, `0` if it is disabled
+    pub timeout: u32,
+    /// Seconds between changes of the screensaver pattern
+    pub cycle: u32,
+    /// Whether the screen is blanked rather than showing a pattern
+    pub prefer_blanking: bool,
+    /// Whether patterns that need the clients to redraw the screen afterwards are allowed
+    pub allow_exposures: bool,
+}
+
+impl ScreenSaver {
+    /// Check if the screensaver starts after a timeout
+    pub fn enabled(&self) -> bool {
+        self.timeout > 0
+    }
+
+    /// Parse a line of the `Screen Saver` section
+    pub(crate) fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        for (key, value) in pairs(line) {
+            match key {
+                "prefer blanking" => self.prefer_blanking = value == "yes",
+                "allow exposures" => self.allow_exposures = value == "yes",
+                "timeout" => self.timeout = parse_value(line, value)?,
+                "cycle" => self.cycle = parse_value(line, value)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Start the screensaver after `timeout` seconds of inactivity, changing its pattern every
+/// `cycle` seconds
+#[cfg(feature = "process")]
+pub fn set_timeout(timeout: u32, cycle: u32) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_timeout_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        timeout,
+        cycle,
+    )
+}
+
+/// Set the timeout and cycle of the screensaver, running `xset` through the given runner
+pub fn set_timeout_with_runner(
+    runner: &dyn CommandRunner,
+    timeout: u32,
+    cycle: u32,
+) -> Result<(), Error> {
+    let cmd = Cmd::new("xset").args(["s", &timeout.to_string(), &cycle.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Never start the screensaver
+#[cfg(feature = "process")]
+pub fn disable() -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    disable_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+}
+
+/// Never start the screensaver, running `xset` through the given runner
+pub fn disable_with_runner(runner: &dyn CommandRunner) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xset").args(["s", "off"]))?;
+    Ok(())
+}
+
+/// Blank the screen rather than showing a pattern
+#[cfg(feature = "process")]
+pub fn set_blanking(blank: bool) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_blanking_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        blank,
+    )
+}
+
+/// Blank the screen rather than showing a pattern, running `xset` through the given runner
+pub fn set_blanking_with_runner(runner: &dyn CommandRunner, blank: bool) -> Result<(), Error> {
+    let mode = if blank { "blank" } else { "noblank" };
+    run_checked(runner, &Cmd::new("xset").args(["s", mode]))?;
+    Ok(())
+}
+
+/// Act as if the user was active, stopping the screensaver and restarting its timeout
+#[cfg(feature = "process")]
+pub fn reset() -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    reset_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+}
+
+/// Act as if the user was active, running `xset` through the given runner
+///
+/// ```rust
+/// use pino_xset::{runner::{Cmd, MockRunner, Output}, screensaver};
+///
+/// let runner = MockRunner::new().respond(Cmd::new("xset").args(["s", "reset"]), Output::default());
+/// screensaver::reset_with_runner(&runner).unwrap();
+/// ```
+pub fn reset_with_runner(runner: &dyn CommandRunner) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xset").args(["s", "reset"]))?;
+    Ok(())
+}
+
+/// Start the screensaver right away
+#[cfg(feature = "process")]
+pub fn activate() -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    activate_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+}
+
+/// Start the screensaver right away, running `xset` through the given runner
+pub fn activate_with_runner(runner: &dyn CommandRunner) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xset").args(["s", "activate"]))?;
+    Ok(())
+}
+
+/// Time since the user last used the keyboard or pointer
+///
+/// The native backend is tried first when it is compiled in, see
+/// [pino_xcommon::backend].
+#[cfg(any(feature = "process", feature = "native"))]
+pub fn idle_time() -> Result<Duration, Error> {
+    use pino_xcommon::backend::{dispatch, Backend};
+
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    dispatch(|backend| match backend {
+        #[cfg(feature = "native")]
+        Backend::Native => Some(idle_time_native(&display)),
+        #[cfg(feature = "process")]
+        Backend::Binary => Some(idle_time_with_runner(
+            &display.runner(&crate::runner::SystemRunner::default()),
+        )),
+        _ => None,
+    })
+    .unwrap_or_else(|| Err(Error::Connection(String::from("no backend available"))))
+}
+
+/// Time since the user last used the keyboard or pointer, running `xprintidle` through the
+/// given runner
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use pino_xset::{runner::{Cmd, MockRunner, Output}, screensaver};
+///
+/// let runner = MockRunner::new().respond(Cmd::new("xprintidle"), Output::from_stdout("4200\n"));
+/// let idle = screensaver::idle_time_with_runner(&runner).unwrap();
+/// assert_eq!(idle, Duration::from_millis(4200));
+/// ```
+pub fn idle_time_with_runner(runner: &dyn CommandRunner) -> Result<Duration, Error> {
+    // not part of xset, so its absence is reported by name
+    let output = run_checked(runner, &Cmd::new("xprintidle")).map_err(|e| match e {
+        RunError::Spawn { source, .. } => {
+            RunError::Unsupported(format!("could not run xprintidle ({})", source))
+        },
+        e => e,
+    })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.trim();
+    line.parse()
+        .map(Duration::from_millis)
+        .map_err(|_| Error::Malformed(line.to_owned()))
+}
+
+/// Time since the user last used the keyboard or pointer, from the MIT-SCREEN-SAVER extension
+#[cfg(feature = "native")]
+fn idle_time_native(display: &crate::DisplayHandle) -> Result<Duration, Error> {
+    use x11rb::{
+        connection::{Connection, RequestConnection},
+        protocol::screensaver::{self, ConnectionExt},
+    };
+
+    let err = |e: &dyn std::fmt::Display| Error::Connection(e.to_string());
+    let (conn, screen) = pino_xcommon::native::connect_display(display).map_err(|e| err(&e))?;
+    if conn
+        .extension_information(screensaver::X11_EXTENSION_NAME)
+        .map_err(|e| err(&e))?
+        .is_none()
+    {
+        return Err(Error::Run(RunError::Unsupported(String::from(
+            "the X server has no MIT-SCREEN-SAVER extension",
+        ))));
+    }
+    let info = conn
+        .screensaver_query_info(conn.setup().roots[screen].root)
+        .map_err(|e| err(&e))?
+        .reply()
+        .map_err(|e| err(&e))?;
+    Ok(Duration::from_millis(info.ms_since_user_input.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn parse_screensaver() {
+        let settings = Settings::parse(concat!(
+            "Screen Saver:\n",
+            "  prefer blanking:  yes    allow exposures:  no\n",
+            "  timeout:  0    cycle:  600\n",
+        ))
+        .unwrap();
+        let saver = settings.screen_saver;
+        assert!(!saver.enabled());
+        assert_eq!(saver.cycle, 600);
+        assert!(saver.prefer_blanking);
+        assert!(!saver.allow_exposures);
+
+        assert!(Settings::parse("Screen Saver:\n  timeout:  never\n").is_err());
+    }
+
+    #[test]
+    fn idle_time_from_xprintidle() {
+        let runner = MockRunner::new().respond(Cmd::new("xprintidle"), Output::from_stdout("93\n"));
+        assert_eq!(
+            idle_time_with_runner(&runner).unwrap(),
+            Duration::from_millis(93)
+        );
+
+        let runner = MockRunner::new().respond(Cmd::new("xprintidle"), Output::from_stdout("n/a"));
+        assert!(matches!(
+            idle_time_with_runner(&runner),
+            Err(Error::Malformed(_))
+        ));
+        let err = idle_time_with_runner(&MockRunner::new()).unwrap_err();
+        assert!(matches!(err, Error::Run(RunError::Unsupported(_))));
+    }
+}
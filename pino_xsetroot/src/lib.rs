@@ -0,0 +1,140 @@
+//! Set the name, background and cursor of the root window through `xsetroot`, and the wallpaper
+//! through `hsetroot`, `feh` or `xwallpaper`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xsetroot::Color;
+//!
+//! // shown in the status bar of dwm
+//! pino_xsetroot::set_name("vol 40% | 12:00").unwrap();
+//! pino_xsetroot::set_background(Color::rgb(0x28, 0x28, 0x28)).unwrap();
+//! pino_xsetroot::set_cursor("left_ptr").unwrap();
+//! # }
+//! ```
+
+use std::fmt;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    color::Color, cursor, display::DisplayHandle, platform, runner, session::Session,
+};
+
+pub mod status;
+pub mod wallpaper;
+
+/// Errors of running xsetroot
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xsetroot could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// None of the programs setting a wallpaper is installed
+    NoTool,
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::NoTool => write!(f, "none of hsetroot, feh and xwallpaper is installed"),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Set the name of the root window, which status bars such as the one of dwm display
+#[cfg(feature = "process")]
+pub fn set_name(name: &str) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_name_with_runner(&display.runner(&runner::SystemRunner::default()), name)
+}
+
+/// Set the name of the root window, running `xsetroot` through the given runner
+///
+/// ```rust
+/// use pino_xsetroot::runner::{Cmd, MockRunner, Output};
+///
+/// let cmd = Cmd::new("xsetroot").args(["-name", "12:00"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// pino_xsetroot::set_name_with_runner(&runner, "12:00").unwrap();
+/// ```
+pub fn set_name_with_runner(runner: &dyn CommandRunner, name: &str) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xsetroot").args(["-name", name]))?;
+    Ok(())
+}
+
+/// Fill the root window with a solid color, which shows wherever no window covers it
+#[cfg(feature = "process")]
+pub fn set_background(color: Color) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_background_with_runner(&display.runner(&runner::SystemRunner::default()), color)
+}
+
+/// Fill the root window with a solid color, running `xsetroot` through the given runner
+pub fn set_background_with_runner(runner: &dyn CommandRunner, color: Color) -> Result<(), Error> {
+    let cmd = Cmd::new("xsetroot").args(["-solid", &color.to_string()]);
+    run_checked(runner, &cmd)?;
+    Ok(())
+}
+
+/// Set the cursor shown over the root window to one of the cursor theme, such as `left_ptr`
+///
+/// Without it the root window keeps the `X` shaped cursor of the X server. The themes the
+/// cursor can come from are listed by [cursor::themes].
+#[cfg(feature = "process")]
+pub fn set_cursor(name: &str) -> Result<(), Error> {
+    let display = DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    set_cursor_with_runner(&display.runner(&runner::SystemRunner::default()), name)
+}
+
+/// Set the cursor of the root window, running `xsetroot` through the given runner
+pub fn set_cursor_with_runner(runner: &dyn CommandRunner, name: &str) -> Result<(), Error> {
+    run_checked(runner, &Cmd::new("xsetroot").args(["-cursor_name", name]))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn background_and_cursor() {
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xsetroot").args(["-solid", "#282828"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("xsetroot").args(["-cursor_name", "nonexistent"]),
+                Output::from_failure(1, "xsetroot: unable to load cursor nonexistent\n"),
+            );
+        set_background_with_runner(&runner, "#282828".parse().unwrap()).unwrap();
+        let err = set_cursor_with_runner(&runner, "nonexistent").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "xsetroot exited with status 1: xsetroot: unable to load cursor nonexistent"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Keep the name of the root window up to date from a status loop
+//!
+//! Status loops usually recompute their text every second or faster. A [StatusBar] only runs
+//! xsetroot when the text changed, and at most once per interval, holding back the latest text
+//! until the interval has passed.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use std::{thread, time::Duration};
+//!
+//! use pino_xsetroot::status::StatusBar;
+//!
+//! let mut bar = StatusBar::new();
+//! loop {
+//!     let time = std::time::SystemTime::now()
+//!         .duration_since(std::time::UNIX_EPOCH)
+//!         .unwrap();
+//!     bar.update(&format!("{}", time.as_secs())).unwrap();
+//!     thread::sleep(Duration::from_millis(200));
+//! }
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use pino_xcommon::runner::CommandRunner;
+
+use crate::{set_name_with_runner, DisplayHandle, Error};
+
+/// Shortest time between two updates of the name, unless configured otherwise
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Throttled setter of the root window name, see the [module](self) documentation
+pub struct StatusBar {
+    runner: Box<dyn CommandRunner>,
+    display: DisplayHandle,
+    interval: Duration,
+    current: Option<String>,
+    pending: Option<String>,
+    updated: Option<Instant>,
+}
+
+impl StatusBar {
+    /// Construct a status bar running xsetroot on the system
+    #[cfg(feature = "process")]
+    pub fn new() -> Self {
+        StatusBar::with_runner(crate::runner::SystemRunner::default())
+    }
+
+    /// Construct a status bar running xsetroot through the given runner
+    pub fn with_runner(runner: impl CommandRunner + 'static) -> Self {
+        StatusBar {
+            runner: Box::new(runner),
+            display: DisplayHandle::default(),
+            interval: DEFAULT_INTERVAL,
+            current: None,
+            pending: None,
+            updated: None,
+        }
+    }
+
+    /// Set the name on the given display instead of the one of the environment
+    pub fn display(mut self, display: DisplayHandle) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Shortest time between two updates of the name
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Text the root window was last named
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Name the root window `status`, returning whether xsetroot was run
+    ///
+    /// The text is held back if it is the current one or the previous update was less than an
+    /// interval ago, in which case a later update or [StatusBar::flush] sets it.
+    pub fn update(&mut self, status: &str) -> Result<bool, Error> {
+        if self.current.as_deref() == Some(status) {
+            self.pending = None;
+            return Ok(false);
+        }
+        self.pending = Some(status.to_owned());
+        if self
+            .updated
+            .is_some_and(|updated| updated.elapsed() < self.interval)
+        {
+            return Ok(false);
+        }
+        self.flush()
+    }
+
+    /// Set the text held back by [StatusBar::update] right away, returning whether there was one
+    ///
+    /// The text stays held back if xsetroot fails, so the next update or flush retries it.
+    pub fn flush(&mut self) -> Result<bool, Error> {
+        let Some(status) = self.pending.as_deref() else {
+            return Ok(false);
+        };
+        let runner = self.display.runner(self.runner.as_ref());
+        set_name_with_runner(&runner, status)?;
+        self.current = self.pending.take();
+        self.updated = Some(Instant::now());
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for StatusBar {
+    fn default() -> Self {
+        StatusBar::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{Cmd, MockRunner, Output, RunError};
+
+    use super::*;
+
+    #[test]
+    fn throttle_updates() {
+        let name = |status| Cmd::new("xsetroot").args(["-name", status]);
+        let runner = MockRunner::new().respond(name("12:00"), Output::default());
+        let mut bar = StatusBar::with_runner(runner).interval(Duration::from_secs(60));
+        assert!(bar.update("12:00").unwrap());
+
+        // any other run of xsetroot would fail from here on
+        bar.runner = Box::new(MockRunner::new());
+        assert!(!bar.update("12:00").unwrap());
+        assert!(!bar.update("12:01").unwrap());
+        assert_eq!(bar.current(), Some("12:00"));
+        assert!(matches!(
+            bar.flush(),
+            Err(Error::Run(RunError::Spawn { .. }))
+        ));
+
+        bar.runner = Box::new(MockRunner::new().respond(name("12:01"), Output::default()));
+        assert!(bar.flush().unwrap());
+        assert!(!bar.flush().unwrap());
+        assert_eq!(bar.current(), Some("12:01"));
+    }
+}
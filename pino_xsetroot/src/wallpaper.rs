@@ -0,0 +1,181 @@
+//! Set an image as the wallpaper through `hsetroot`, `feh` or `xwallpaper`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xsetroot::wallpaper::{self, Mode};
+//!
+//! wallpaper::set("/home/user/pictures/mountains.png".as_ref(), Mode::Fill).unwrap();
+//! # }
+//! ```
+//!
+//! Whichever of hsetroot, feh and xwallpaper is installed is used, in that order. They all keep the
+//! image on the root window and set `_XROOTPMAP_ID`, so compositors and pseudo transparent
+//! terminals pick it up.
+
+use std::path::Path;
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner};
+
+use crate::Error;
+
+/// Program used to set the wallpaper
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Tool {
+    /// `hsetroot`, from version 1.0.5 which can cover the screen
+    Hsetroot,
+    /// `feh` and its `--bg-*` options
+    Feh,
+    /// `xwallpaper`
+    Xwallpaper,
+}
+
+/// How the image is fit to the screen
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Mode {
+    /// Scaled keeping its aspect ratio until it covers the screen, cropping what is left over
+    #[default]
+    Fill,
+    /// Shown at its size in the middle of the screen
+    Center,
+    /// Repeated at its size from the top left corner
+    Tile,
+}
+
+impl Tool {
+    /// Installed tool, preferring hsetroot, detected once and remembered afterwards
+    #[cfg(feature = "process")]
+    pub fn detect() -> Option<Tool> {
+        static DETECTED: std::sync::OnceLock<Option<Tool>> = std::sync::OnceLock::new();
+        *DETECTED.get_or_init(|| Tool::detect_with_runner(&crate::runner::SystemRunner::default()))
+    }
+
+    /// Installed tool, checking through the given runner which of them can be run
+    ///
+    /// ```rust
+    /// use pino_xsetroot::{
+    ///     runner::{Cmd, MockRunner, Output},
+    ///     wallpaper::Tool,
+    /// };
+    ///
+    /// let runner = MockRunner::new().respond(Cmd::new("feh").arg("--version"), Output::default());
+    /// assert_eq!(Tool::detect_with_runner(&runner), Some(Tool::Feh));
+    /// assert_eq!(Tool::detect_with_runner(&MockRunner::new()), None);
+    /// ```
+    pub fn detect_with_runner(runner: &dyn CommandRunner) -> Option<Tool> {
+        [Tool::Hsetroot, Tool::Feh, Tool::Xwallpaper]
+            .into_iter()
+            .find(|tool| {
+                // hsetroot has no version flag and prints its usage instead
+                let flag = match tool {
+                    Tool::Hsetroot => "-help",
+                    Tool::Feh | Tool::Xwallpaper => "--version",
+                };
+                runner.output(&Cmd::new(tool.program()).arg(flag)).is_ok()
+            })
+    }
+
+    /// Name of the program
+    pub fn program(&self) -> &'static str {
+        match self {
+            Tool::Hsetroot => "hsetroot",
+            Tool::Feh => "feh",
+            Tool::Xwallpaper => "xwallpaper",
+        }
+    }
+
+    /// Command setting the image as the wallpaper
+    fn command(self, image: &Path, mode: Mode) -> Cmd {
+        let flag = match (self, mode) {
+            (Tool::Hsetroot, Mode::Fill) => "-cover",
+            (Tool::Hsetroot, Mode::Center) => "-center",
+            (Tool::Hsetroot, Mode::Tile) => "-tile",
+            (Tool::Feh, Mode::Fill) => "--bg-fill",
+            (Tool::Feh, Mode::Center) => "--bg-center",
+            (Tool::Feh, Mode::Tile) => "--bg-tile",
+            (Tool::Xwallpaper, Mode::Fill) => "--zoom",
+            (Tool::Xwallpaper, Mode::Center) => "--center",
+            (Tool::Xwallpaper, Mode::Tile) => "--tile",
+        };
+        let cmd = Cmd::new(self.program());
+        // feh would otherwise write a ~/.fehbg script on every call
+        let cmd = match self {
+            Tool::Feh => cmd.arg("--no-fehbg"),
+            Tool::Hsetroot | Tool::Xwallpaper => cmd,
+        };
+        cmd.args([flag.to_owned(), image.to_string_lossy().into_owned()])
+    }
+}
+
+/// Set the image as the wallpaper with whichever tool is installed
+#[cfg(feature = "process")]
+pub fn set(image: &Path, mode: Mode) -> Result<(), Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    let tool = Tool::detect().ok_or(Error::NoTool)?;
+    set_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        tool,
+        image,
+        mode,
+    )
+}
+
+/// Set the image as the wallpaper, running the tool through the given runner
+///
+/// ```rust
+/// use pino_xsetroot::{
+///     runner::{Cmd, MockRunner, Output},
+///     wallpaper::{self, Mode, Tool},
+/// };
+///
+/// let cmd = Cmd::new("xwallpaper").args(["--tile", "/tmp/pattern.png"]);
+/// let runner = MockRunner::new().respond(cmd, Output::default());
+/// wallpaper::set_with_runner(&runner, Tool::Xwallpaper, "/tmp/pattern.png".as_ref(), Mode::Tile)
+///     .unwrap();
+/// ```
+pub fn set_with_runner(
+    runner: &dyn CommandRunner,
+    tool: Tool,
+    image: &Path,
+    mode: Mode,
+) -> Result<(), Error> {
+    run_checked(runner, &tool.command(image, mode))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{MockRunner, Output, RunError};
+
+    use super::*;
+
+    #[test]
+    fn wallpaper_modes() {
+        let image = Path::new("/tmp/wall.jpg");
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("hsetroot").args(["-cover", "/tmp/wall.jpg"]),
+                Output::default(),
+            )
+            .respond(
+                Cmd::new("feh").args(["--no-fehbg", "--bg-center", "/tmp/wall.jpg"]),
+                Output::from_failure(1, "feh: No loadable images specified.\n"),
+            );
+        set_with_runner(&runner, Tool::Hsetroot, image, Mode::default()).unwrap();
+        let err = set_with_runner(&runner, Tool::Feh, image, Mode::Center).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Exited { status: 1, .. })
+        ));
+        // not xsetroot, so its absence is reported by name
+        let err = set_with_runner(&runner, Tool::Xwallpaper, image, Mode::Fill).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Run(RunError::Spawn { program, .. }) if program == "xwallpaper"
+        ));
+    }
+}
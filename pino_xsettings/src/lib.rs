@@ -0,0 +1,345 @@
+//! Serve XSETTINGS derived from the resource database, so running GTK programs follow it
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "native")]
+//! # {
+//! use std::time::Duration;
+//!
+//! use pino_xsettings::{Provider, Settings, Xrdb};
+//!
+//! let mut xrdb = Xrdb::new();
+//! xrdb.read().unwrap();
+//! let mut provider = Provider::start(Settings::from_xrdb(&xrdb)).unwrap();
+//! // serve changes to the resources until another settings manager takes over
+//! provider.run(Duration::from_secs(2)).unwrap();
+//! # }
+//! ```
+//!
+//! Toolkits read their font rendering, theme and cursor settings from the `_XSETTINGS_SETTINGS`
+//! property of the window owning the `_XSETTINGS_S0` selection, and reload them whenever it
+//! changes. There is no client binary for this, so the [Provider] is behind the `native` feature.
+//! Without it only [Settings] is available.
+
+#[cfg(feature = "native")]
+mod provider;
+
+use std::{collections::BTreeMap, fmt};
+
+use pino_xcommon::resource::parse_bool;
+pub use pino_xcommon::{color::Color, display::DisplayHandle, platform, session::Session};
+pub use pino_xrdb::Xrdb;
+#[cfg(feature = "native")]
+pub use provider::Provider;
+
+/// Errors of serving settings
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Request to the X server failed
+    Connection(String),
+    /// Another settings manager, such as that of a desktop environment, owns the selection
+    AlreadyRunning,
+    /// Resources could not be read
+    Xrdb(pino_xrdb::XrdbError),
+    /// Settings could not be decoded, holding what was wrong with them
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Xrdb(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Connection(e) => write!(f, "request to the X server failed: {}", e),
+            Error::AlreadyRunning => write!(f, "another settings manager is running"),
+            Error::Xrdb(e) => write!(f, "could not read the resources: {}", e),
+            Error::Malformed(e) => write!(f, "could not decode settings: {}", e),
+        }
+    }
+}
+
+impl From<pino_xrdb::XrdbError> for Error {
+    fn from(value: pino_xrdb::XrdbError) -> Self {
+        Error::Xrdb(value)
+    }
+}
+
+/// Value of a setting
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Value {
+    Int(i32),
+    String(String),
+    Color(Color),
+}
+
+/// Settings by name, such as `Net/ThemeName` or `Xft/DPI`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Settings(BTreeMap<String, Value>);
+
+impl Settings {
+    /// Construct empty settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derive the settings from the resources
+    ///
+    /// The `Xft` resources are translated to their settings, `Xft.dpi` scaled by 1024 and the
+    /// booleans turned into `0` or `1`, and `Xcursor.theme` and `Xcursor.size` to the cursor
+    /// settings of GTK. Resources named after a setting in the `Net` or `Gtk` namespace, with a
+    /// `.` in place of the `/`, are served as is, numbers as integers.
+    ///
+    /// ```rust
+    /// use pino_xsettings::{Settings, Value, Xrdb};
+    ///
+    /// let xrdb = Xrdb::parse("Xft.dpi: 144\nXft.antialias: true\nNet.ThemeName: Adwaita-dark");
+    /// let settings = Settings::from_xrdb(&xrdb);
+    ///
+    /// assert_eq!(settings.get("Xft/DPI"), Some(&Value::Int(144 * 1024)));
+    /// assert_eq!(settings.get("Xft/Antialias"), Some(&Value::Int(1)));
+    /// assert_eq!(
+    ///     settings.get("Net/ThemeName"),
+    ///     Some(&Value::String(String::from("Adwaita-dark")))
+    /// );
+    /// ```
+    pub fn from_xrdb(xrdb: &Xrdb) -> Self {
+        let mut settings = Settings::new();
+        // universal resources sort first, so resources bound to a program take precedence
+        for (program, res, val) in xrdb.entries() {
+            let name = match program {
+                Some(program) => format!("{}.{}", program, res),
+                None => res.to_owned(),
+            };
+            let int = || val.parse().ok().map(Value::Int);
+            let bool = || parse_bool(val).map(|b| Value::Int(b.into()));
+            let string = || Some(Value::String(val.to_owned()));
+            let (name, value) = match name.as_str() {
+                "Xft.dpi" => {
+                    let dpi = val.parse::<f64>().ok();
+                    (
+                        "Xft/DPI",
+                        dpi.map(|dpi| Value::Int((dpi * 1024.0).round() as i32)),
+                    )
+                },
+                "Xft.antialias" => ("Xft/Antialias", bool()),
+                "Xft.hinting" => ("Xft/Hinting", bool()),
+                "Xft.hintstyle" => ("Xft/HintStyle", string()),
+                "Xft.rgba" => ("Xft/RGBA", string()),
+                "Xcursor.theme" => ("Gtk/CursorThemeName", string()),
+                "Xcursor.size" => ("Gtk/CursorThemeSize", int()),
+                _ if name.starts_with("Net.") || name.starts_with("Gtk.") => {
+                    let value = int().or_else(string).unwrap();
+                    settings.insert(&name.replacen('.', "/", 1), value);
+                    continue;
+                },
+                _ => continue,
+            };
+            if let Some(value) = value {
+                settings.insert(name, value);
+            }
+        }
+        settings
+    }
+
+    /// Set a setting, replacing its previous value
+    pub fn insert(&mut self, name: &str, value: Value) {
+        self.0.insert(name.to_owned(), value);
+    }
+
+    /// Value of a setting
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// Every setting, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Encode the settings in the format of the `_XSETTINGS_SETTINGS` property, every setting
+    /// marked as changed in `serial`
+    pub fn encode(&self, serial: u32) -> Vec<u8> {
+        // byte order, LSBFirst, then padding
+        let mut buf = vec![0, 0, 0, 0];
+        buf.extend(serial.to_le_bytes());
+        buf.extend((self.0.len() as u32).to_le_bytes());
+        for (name, value) in &self.0 {
+            let kind = match value {
+                Value::Int(_) => 0,
+                Value::String(_) => 1,
+                Value::Color(_) => 2,
+            };
+            buf.extend([kind, 0]);
+            buf.extend((name.len() as u16).to_le_bytes());
+            push_padded(&mut buf, name.as_bytes());
+            buf.extend(serial.to_le_bytes());
+            match value {
+                Value::Int(int) => buf.extend(int.to_le_bytes()),
+                Value::String(string) => {
+                    buf.extend((string.len() as u32).to_le_bytes());
+                    push_padded(&mut buf, string.as_bytes());
+                },
+                Value::Color(color) => {
+                    let (r, g, b) = color.to_rgb16();
+                    // the channels are in this order, with an opaque alpha
+                    for channel in [r, b, g, u16::MAX] {
+                        buf.extend(channel.to_le_bytes());
+                    }
+                },
+            }
+        }
+        buf
+    }
+
+    /// Decode the `_XSETTINGS_SETTINGS` property of a settings manager, returning its serial
+    /// along with the settings
+    ///
+    /// ```rust
+    /// use pino_xsettings::{Color, Settings, Value};
+    ///
+    /// let mut settings = Settings::new();
+    /// settings.insert("Net/ThemeName", Value::String(String::from("Adwaita")));
+    /// settings.insert("Gtk/CursorBlinkTime", Value::Int(1200));
+    /// settings.insert("Gtk/Color", Value::Color(Color::rgb(0xea, 0x69, 0x62)));
+    ///
+    /// let (serial, decoded) = Settings::decode(&settings.encode(7)).unwrap();
+    /// assert_eq!((serial, decoded), (7, settings));
+    /// ```
+    pub fn decode(raw: &[u8]) -> Result<(u32, Self), Error> {
+        let mut reader = Reader {
+            raw,
+            pos: 0,
+            big_endian: raw.first() == Some(&1),
+        };
+        reader.skip(4)?;
+        let serial = reader.u32()?;
+        let len = reader.u32()?;
+
+        let mut settings = Settings::new();
+        for _ in 0..len {
+            let kind = reader.take(2)?[0];
+            let name_len = reader.u16()? as usize;
+            let name = reader.string(name_len)?;
+            reader.skip(4)?;
+            let value = match kind {
+                0 => Value::Int(reader.u32()? as i32),
+                1 => {
+                    let len = reader.u32()? as usize;
+                    Value::String(reader.string(len)?)
+                },
+                2 => {
+                    let (r, b, g, _) = (reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?);
+                    Value::Color(Color::from_rgb16(r, g, b))
+                },
+                kind => return Err(Error::Malformed(format!("unknown type {}", kind))),
+            };
+            settings.insert(&name, value);
+        }
+        Ok((serial, settings))
+    }
+}
+
+/// Append the bytes, padded to a multiple of 4
+fn push_padded(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend(bytes);
+    buf.resize(buf.len() + (4 - bytes.len() % 4) % 4, 0);
+}
+
+/// Cursor over a `_XSETTINGS_SETTINGS` property in either byte order
+struct Reader<'a> {
+    raw: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .raw
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| Error::Malformed(String::from("truncated")))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.take(2)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    /// String of `len` bytes followed by its padding
+    fn string(&mut self, len: usize) -> Result<String, Error> {
+        let bytes = self.take(len)?;
+        self.skip((4 - len % 4) % 4)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::Malformed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_from_resources() {
+        let xrdb = Xrdb::parse(concat!(
+            "*.Xft.dpi: 96\n",
+            "Xft.dpi: 120.5\n",
+            "Xft.hinting: off\n",
+            "Xft.hintstyle: hintslight\n",
+            "Xcursor.size: 32\n",
+            "Gtk.CursorBlinkTime: 1000\n",
+            "st.font: mono\n",
+        ));
+        let settings = Settings::from_xrdb(&xrdb);
+        let names = settings.iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            [
+                "Gtk/CursorBlinkTime",
+                "Gtk/CursorThemeSize",
+                "Xft/DPI",
+                "Xft/HintStyle",
+                "Xft/Hinting"
+            ]
+        );
+        assert_eq!(settings.get("Xft/DPI"), Some(&Value::Int(123392)));
+        assert_eq!(settings.get("Xft/Hinting"), Some(&Value::Int(0)));
+        assert_eq!(settings.get("Gtk/CursorBlinkTime"), Some(&Value::Int(1000)));
+
+        let raw = settings.encode(3);
+        assert_eq!(raw.len() % 4, 0);
+        assert_eq!(Settings::decode(&raw).unwrap(), (3, settings));
+        assert!(Settings::decode(&raw[..raw.len() - 4]).is_err());
+    }
+}
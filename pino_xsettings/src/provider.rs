@@ -0,0 +1,186 @@
+//! Settings manager owning the `_XSETTINGS_S<screen>` selection, enabled by the `native` feature
+
+use std::time::Duration;
+
+use pino_xcommon::native::connect_display;
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, PropMode,
+            Timestamp, Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, NONE,
+};
+
+use crate::{DisplayHandle, Error, Settings, Xrdb};
+
+fn err(e: impl std::fmt::Display) -> Error {
+    Error::Connection(e.to_string())
+}
+
+/// Serves settings for as long as it is alive, giving up the selection when dropped
+pub struct Provider {
+    conn: RustConnection,
+    display: DisplayHandle,
+    window: Window,
+    selection: Atom,
+    property: Atom,
+    serial: u32,
+    settings: Settings,
+}
+
+impl Provider {
+    /// Become the settings manager of the default display
+    pub fn start(settings: Settings) -> Result<Self, Error> {
+        Self::start_on(&DisplayHandle::default(), settings)
+    }
+
+    /// Become the settings manager of the given display, failing with [Error::AlreadyRunning]
+    /// if there is one already
+    pub fn start_on(display: &DisplayHandle, settings: Settings) -> Result<Self, Error> {
+        pino_xcommon::session::require_x(display).map_err(Error::NoXServer)?;
+        let (conn, screen) = connect_display(display).map_err(err)?;
+        let root = conn.setup().roots[screen].root;
+        let atom = |name: &str| -> Result<Atom, Error> {
+            Ok(conn
+                .intern_atom(false, name.as_bytes())
+                .map_err(err)?
+                .reply()
+                .map_err(err)?
+                .atom)
+        };
+        let selection = atom(&format!("_XSETTINGS_S{}", screen))?;
+        let property = atom("_XSETTINGS_SETTINGS")?;
+        let manager = atom("MANAGER")?;
+
+        let owner = |conn: &RustConnection| -> Result<Window, Error> {
+            Ok(conn
+                .get_selection_owner(selection)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?
+                .owner)
+        };
+        if owner(&conn)? != NONE {
+            return Err(Error::AlreadyRunning);
+        }
+
+        let window = conn.generate_id().map_err(err)?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new()
+                .override_redirect(1)
+                .event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .map_err(err)?;
+        let provider = Provider {
+            conn,
+            display: display.clone(),
+            window,
+            selection,
+            property,
+            serial: 0,
+            settings,
+        };
+
+        // the ICCCM asks for a real timestamp, taken from the notification of the first write
+        provider.write()?;
+        let time = provider.wait_for_timestamp()?;
+        provider
+            .conn
+            .set_selection_owner(window, selection, time)
+            .map_err(err)?;
+        if owner(&provider.conn)? != window {
+            return Err(Error::AlreadyRunning);
+        }
+
+        // tell the clients waiting for a settings manager
+        let event = ClientMessageEvent::new(32, root, manager, [time, selection, window, 0, 0]);
+        provider
+            .conn
+            .send_event(false, root, EventMask::STRUCTURE_NOTIFY, event)
+            .map_err(err)?;
+        provider.conn.flush().map_err(err)?;
+        Ok(provider)
+    }
+
+    /// Settings being served
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Serve new settings, doing nothing if they did not change
+    pub fn update(&mut self, settings: Settings) -> Result<(), Error> {
+        if settings == self.settings {
+            return Ok(());
+        }
+        self.settings = settings;
+        self.serial = self.serial.wrapping_add(1);
+        self.write()?;
+        self.conn.flush().map_err(err)
+    }
+
+    /// Read the resources every `interval` and serve the settings derived from them, returning
+    /// once another settings manager takes over the selection
+    pub fn run(&mut self, interval: Duration) -> Result<(), Error> {
+        loop {
+            while let Some(event) = self.conn.poll_for_event().map_err(err)? {
+                if let Event::SelectionClear(event) = event {
+                    if event.selection == self.selection {
+                        return Ok(());
+                    }
+                }
+            }
+            let mut xrdb = Xrdb::new();
+            xrdb.read_display(&self.display)?;
+            self.update(Settings::from_xrdb(&xrdb))?;
+            std::thread::sleep(interval);
+        }
+    }
+
+    fn write(&self) -> Result<(), Error> {
+        self.conn
+            .change_property8(
+                PropMode::REPLACE,
+                self.window,
+                self.property,
+                self.property,
+                &self.settings.encode(self.serial),
+            )
+            .map_err(err)?;
+        Ok(())
+    }
+
+    fn wait_for_timestamp(&self) -> Result<Timestamp, Error> {
+        self.conn.flush().map_err(err)?;
+        loop {
+            if let Event::PropertyNotify(event) = self.conn.wait_for_event().map_err(err)? {
+                if event.window == self.window {
+                    return Ok(event.time);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Provider {
+    fn drop(&mut self) {
+        // destroying the window gives up the selection
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+}
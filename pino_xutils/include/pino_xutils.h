@@ -0,0 +1,106 @@
+/* C API of pino_xutils, built with the `ffi` feature
+ *
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/pino_xutils.h` from the
+ * pino_xutils directory after changing src/ffi.rs.
+ */
+
+#ifndef PINO_XUTILS_H
+#define PINO_XUTILS_H
+
+#include <stdint.h>
+
+/* Outcome of a call, `PINO_ERROR_OK` on success */
+typedef enum PinoError {
+  PINO_ERROR_OK = 0,
+  /* A required pointer was null */
+  PINO_ERROR_NULL,
+  /* A string was not valid UTF-8 */
+  PINO_ERROR_UTF8,
+  /* The requested resource, keycode or keysym does not exist */
+  PINO_ERROR_NOT_FOUND,
+  /* Input could not be parsed */
+  PINO_ERROR_INVALID_FORMAT,
+  /* The client binary could not be run or failed */
+  PINO_ERROR_RUN_ERROR,
+  /* The client binary did not finish in time */
+  PINO_ERROR_TIMED_OUT,
+} PinoError;
+
+/* Level of a key, matching the columns of `xmodmap -pke` */
+typedef enum PinoModifier {
+  PINO_MODIFIER_KEY = 0,
+  PINO_MODIFIER_SHIFT_KEY,
+  PINO_MODIFIER_MODE_SWITCH_KEY,
+  PINO_MODIFIER_MODE_SWITCH_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL3_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL3_SHIFT_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL5_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL5_SHIFT_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL3_ISO_LEVEL5_SHIFT_KEY,
+  PINO_MODIFIER_ISO_LEVEL3_ISO_LEVEL5_SHIFT_SHIFT_KEY,
+} PinoModifier;
+
+/* Opaque handle to a keytable */
+typedef struct PinoKeyTable PinoKeyTable;
+
+/* Opaque handle to a resource database */
+typedef struct PinoXrdb PinoXrdb;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Static description of an error */
+const char *pino_error_message(PinoError err);
+
+/* Release a string returned by this library */
+void pino_string_free(char *s);
+
+/* Construct an empty resource database */
+PinoXrdb *pino_xrdb_new(void);
+
+/* Parse a resource database in the format of `xrdb -query` or an `~/.Xresources` file */
+PinoError pino_xrdb_parse(const char *raw, PinoXrdb **out);
+
+/* Read the system xrdb into the database */
+PinoError pino_xrdb_read(PinoXrdb *xrdb);
+
+/* Query a resource, writing a string to be released with pino_string_free to `out` */
+PinoError pino_xrdb_query(const PinoXrdb *xrdb,
+                          const char *program,
+                          const char *res,
+                          char **out);
+
+/* Insert a resource, `program` may be null to insert a universal resource */
+PinoError pino_xrdb_insert(PinoXrdb *xrdb, const char *program, const char *res, const char *val);
+
+/* Release a resource database */
+void pino_xrdb_free(PinoXrdb *xrdb);
+
+/* Read the keytable of the system */
+PinoError pino_keytable_new(PinoKeyTable **out);
+
+/* Parse the output of `xmodmap -pke` */
+PinoError pino_keytable_parse(const char *raw, PinoKeyTable **out);
+
+/* Name of the keysym bound to a keycode at a level, written to `out` as a string to be released
+ * with pino_string_free */
+PinoError pino_keytable_get_keysym(const PinoKeyTable *table,
+                                   PinoModifier modifier,
+                                   uint8_t keycode,
+                                   char **out);
+
+/* Keycode and level producing a keysym, given by name */
+PinoError pino_keytable_get_key(const PinoKeyTable *table,
+                                const char *keysym,
+                                PinoModifier *modifier,
+                                uint8_t *keycode);
+
+/* Release a keytable */
+void pino_keytable_free(PinoKeyTable *table);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PINO_XUTILS_H */
@@ -0,0 +1,166 @@
+//! Derive `Xft.dpi` and `Xcursor.size` from the connected monitors and merge them into the
+//! resource database
+//!
+//! The resolution of each enabled output is computed from its current mode and the physical size
+//! its monitor reports, unless it is overridden. The X server has a single `Xft.dpi` for every
+//! output, so the one of the primary output is used, or the highest when there is none.
+//!
+//! ```rust,no_run
+//! use pino_xutils::dpi::Dpi;
+//!
+//! // the projector reports a bogus size
+//! let dpi = Dpi::new().output("HDMI-1", 96).apply().unwrap();
+//! println!("Xft.dpi is now {}", dpi);
+//! ```
+
+use std::collections::HashMap;
+
+use pino_xrandr::{Output, Screen};
+use pino_xrdb::Xrdb;
+
+use crate::{runner::CommandRunner, Error};
+
+/// Resolution that toolkits render at without any scaling
+pub const BASE_DPI: u32 = 96;
+
+/// Cursor size at [BASE_DPI], scaled along with the resolution
+pub const BASE_CURSOR_SIZE: u32 = 24;
+
+const MM_PER_INCH: f64 = 25.4;
+
+/// Computes and applies the resolution of the monitors
+#[derive(Debug, Default, Clone)]
+pub struct Dpi {
+    overrides: HashMap<String, u32>,
+}
+
+impl Dpi {
+    /// Compute every output from its monitor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given resolution for an output instead of computing it, such as for monitors
+    /// reporting a wrong physical size
+    pub fn output(mut self, name: &str, dpi: u32) -> Self {
+        self.overrides.insert(name.to_owned(), dpi);
+        self
+    }
+
+    /// Resolution of an enabled output, `None` if it is disabled or the size of its monitor is
+    /// unknown
+    ///
+    /// ```rust
+    /// use pino_xutils::{dpi::Dpi, xrandr::Screen};
+    ///
+    /// let screens = Screen::parse(concat!(
+    ///     "Screen 0: minimum 320 x 200, current 2560 x 1600, maximum 16384 x 16384\n",
+    ///     "eDP-1 connected primary 2560x1600+0+0 (normal left inverted right x axis y axis) 286mm x 179mm\n",
+    ///     "   2560x1600     60.00*+\n",
+    /// ))
+    /// .unwrap();
+    /// let output = screens[0].output("eDP-1").unwrap();
+    /// assert_eq!(Dpi::new().output_dpi(output), Some(227));
+    /// ```
+    pub fn output_dpi(&self, output: &Output) -> Option<u32> {
+        if !output.enabled() {
+            return None;
+        }
+        if let Some(dpi) = self.overrides.get(&output.name) {
+            return Some(*dpi);
+        }
+        let mode = output.current_mode()?;
+        let physical = output
+            .physical_size
+            .filter(|s| s.width > 0 && s.height > 0)?;
+        // the diagonal does not depend on whether the mode and size are reported rotated
+        let pixels = (mode.size.width as f64).hypot(mode.size.height as f64);
+        let inches = (physical.width as f64).hypot(physical.height as f64) / MM_PER_INCH;
+        Some((pixels / inches).round() as u32)
+    }
+
+    /// Resolution to render at, that of the primary output or else the highest of any output,
+    /// and [BASE_DPI] when none is known
+    pub fn compute(&self, screens: &[Screen]) -> u32 {
+        let outputs = || screens.iter().flat_map(|s| s.connected());
+        outputs()
+            .find(|o| o.primary)
+            .and_then(|o| self.output_dpi(o))
+            .or_else(|| outputs().filter_map(|o| self.output_dpi(o)).max())
+            .unwrap_or(BASE_DPI)
+    }
+
+    /// Query the outputs and merge the resources for their resolution, returning it
+    #[cfg(feature = "process")]
+    pub fn apply(&self) -> Result<u32, Error> {
+        let display = crate::DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(pino_xrandr::Error::NoXServer)?;
+        self.apply_with_runner(&display.runner(&crate::runner::SystemRunner::default()))
+    }
+
+    /// Like [Dpi::apply], running `xrandr` and `xrdb` through the given runner
+    pub fn apply_with_runner(&self, runner: &dyn CommandRunner) -> Result<u32, Error> {
+        let dpi = self.compute(&Screen::query_with_runner(runner)?);
+        Xrdb::merge_with_runner(runner, &resources(dpi).to_string())?;
+        Ok(dpi)
+    }
+}
+
+/// Resources setting the resolution, with the cursor scaled to match
+///
+/// They are bound tightly as `Xft.dpi` and `Xcursor.size`, the way `~/.Xresources` usually sets
+/// them, since loosely bound ones would lose to those.
+///
+/// ```rust
+/// let xrdb = pino_xutils::dpi::resources(144);
+/// assert_eq!(xrdb.query("Xft", "dpi"), Some(String::from("144")));
+/// assert_eq!(xrdb.query("Xcursor", "size"), Some(String::from("36")));
+/// ```
+pub fn resources(dpi: u32) -> Xrdb {
+    let cursor = (BASE_CURSOR_SIZE * dpi + BASE_DPI / 2) / BASE_DPI;
+    let mut xrdb = Xrdb::new();
+    xrdb.insert("Xft", "dpi", &dpi.to_string());
+    xrdb.insert("Xcursor", "size", &cursor.to_string());
+    xrdb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{Cmd, MockRunner, Output};
+
+    #[test]
+    fn apply_primary_or_highest() {
+        let query = |primary: &str| {
+            format!(
+                concat!(
+                    "Screen 0: minimum 320 x 200, current 4480 x 1440, maximum 16384 x 16384\n",
+                    "eDP-1 connected 1920x1080+0+0 (normal left inverted right x axis y axis) 344mm x 194mm\n",
+                    "   1920x1080     60.02*+\n",
+                    "DP-1 connected {}2560x1440+1920+0 (normal left inverted right x axis y axis) 597mm x 336mm\n",
+                    "   2560x1440     59.95*+\n",
+                    "HDMI-1 connected (normal left inverted right x axis y axis) 0mm x 0mm\n",
+                    "   1920x1080     60.00 +\n",
+                ),
+                primary
+            )
+        };
+        let screens = Screen::parse(&query("")).unwrap();
+        assert_eq!(Dpi::new().compute(&screens), 142);
+        assert_eq!(Dpi::new().output("DP-1", 192).compute(&screens), 192);
+        assert_eq!(Dpi::new().compute(&[]), BASE_DPI);
+
+        let runner = MockRunner::new()
+            .respond(
+                Cmd::new("xrandr").arg("--query"),
+                Output::from_stdout(query("primary ")),
+            )
+            .respond(
+                Cmd::new("xrdb")
+                    .arg("-merge")
+                    .stdin("Xcursor.size:\t27\nXft.dpi:\t109\n"),
+                Output::default(),
+            );
+        assert_eq!(Dpi::new().apply_with_runner(&runner).unwrap(), 109);
+    }
+}
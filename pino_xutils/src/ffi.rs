@@ -0,0 +1,433 @@
+//! C-compatible API, enabled by the `ffi` feature
+//!
+//! Handles are opaque pointers created by a `*_new` or `*_parse` function and released with the
+//! matching `*_free` function. Every fallible function returns a [PinoError], with results
+//! written through out pointers. Strings returned to C are owned by the caller and must be
+//! released with [pino_string_free].
+//!
+//! The header `include/pino_xutils.h` declares every function in this module.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    str::FromStr,
+};
+
+use pino_xmodmap::{KeySym, KeyTable, Modifier, DEFAULT_COLUMNS};
+use pino_xrdb::{Xrdb, XrdbError};
+
+/// Opaque handle to a resource database
+pub struct PinoXrdb(Xrdb);
+
+/// Opaque handle to a keytable
+pub struct PinoKeyTable(KeyTable);
+
+/// Outcome of a call, `PINO_ERROR_OK` on success
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PinoError {
+    Ok = 0,
+    /// A required pointer was null
+    Null,
+    /// A string was not valid UTF-8
+    Utf8,
+    /// The requested resource, keycode or keysym does not exist
+    NotFound,
+    /// Input could not be parsed
+    InvalidFormat,
+    /// The client binary could not be run or failed
+    RunError,
+    /// The client binary did not finish in time
+    TimedOut,
+}
+
+/// Level of a key, matching the columns of `xmodmap -pke`
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PinoModifier {
+    Key = 0,
+    ShiftKey,
+    ModeSwitchKey,
+    ModeSwitchShiftKey,
+    IsoLevel3ShiftKey,
+    IsoLevel3ShiftShiftKey,
+    IsoLevel5ShiftKey,
+    IsoLevel5ShiftShiftKey,
+    IsoLevel3IsoLevel5ShiftKey,
+    IsoLevel3IsoLevel5ShiftShiftKey,
+}
+
+impl From<Modifier> for PinoModifier {
+    fn from(value: Modifier) -> Self {
+        match value {
+            Modifier::Key => PinoModifier::Key,
+            Modifier::ShiftKey => PinoModifier::ShiftKey,
+            Modifier::ModeSwitchKey => PinoModifier::ModeSwitchKey,
+            Modifier::ModeSwitchShiftKey => PinoModifier::ModeSwitchShiftKey,
+            Modifier::ISOLevel3ShiftKey => PinoModifier::IsoLevel3ShiftKey,
+            Modifier::ISOLevel3ShiftShiftKey => PinoModifier::IsoLevel3ShiftShiftKey,
+            Modifier::ISOLevel5ShiftKey => PinoModifier::IsoLevel5ShiftKey,
+            Modifier::ISOLevel5ShiftShiftKey => PinoModifier::IsoLevel5ShiftShiftKey,
+            Modifier::ISOLevel3ISOLevel5ShiftKey => PinoModifier::IsoLevel3IsoLevel5ShiftKey,
+            Modifier::ISOLevel3ISOLevel5ShiftShiftKey => {
+                PinoModifier::IsoLevel3IsoLevel5ShiftShiftKey
+            },
+        }
+    }
+}
+
+impl From<PinoModifier> for Modifier {
+    fn from(value: PinoModifier) -> Self {
+        match value {
+            PinoModifier::Key => Modifier::Key,
+            PinoModifier::ShiftKey => Modifier::ShiftKey,
+            PinoModifier::ModeSwitchKey => Modifier::ModeSwitchKey,
+            PinoModifier::ModeSwitchShiftKey => Modifier::ModeSwitchShiftKey,
+            PinoModifier::IsoLevel3ShiftKey => Modifier::ISOLevel3ShiftKey,
+            PinoModifier::IsoLevel3ShiftShiftKey => Modifier::ISOLevel3ShiftShiftKey,
+            PinoModifier::IsoLevel5ShiftKey => Modifier::ISOLevel5ShiftKey,
+            PinoModifier::IsoLevel5ShiftShiftKey => Modifier::ISOLevel5ShiftShiftKey,
+            PinoModifier::IsoLevel3IsoLevel5ShiftKey => Modifier::ISOLevel3ISOLevel5ShiftKey,
+            PinoModifier::IsoLevel3IsoLevel5ShiftShiftKey => {
+                Modifier::ISOLevel3ISOLevel5ShiftShiftKey
+            },
+        }
+    }
+}
+
+impl From<XrdbError> for PinoError {
+    fn from(value: XrdbError) -> Self {
+        match value {
+            XrdbError::Invalid | XrdbError::OutputMalformed => PinoError::InvalidFormat,
+            XrdbError::TimedOut => PinoError::TimedOut,
+            _ => PinoError::RunError,
+        }
+    }
+}
+
+impl From<pino_xmodmap::Error> for PinoError {
+    fn from(value: pino_xmodmap::Error) -> Self {
+        match value {
+            pino_xmodmap::Error::InvalidFormat { .. } => PinoError::InvalidFormat,
+            pino_xmodmap::Error::NonExistentKeyCode(_)
+            | pino_xmodmap::Error::NonExistentKeySym(_) => PinoError::NotFound,
+            pino_xmodmap::Error::TimedOut { .. } => PinoError::TimedOut,
+            _ => PinoError::RunError,
+        }
+    }
+}
+
+/// Borrow a C string as a `&str`
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, PinoError> {
+    if s.is_null() {
+        return Err(PinoError::Null);
+    }
+    CStr::from_ptr(s).to_str().or(Err(PinoError::Utf8))
+}
+
+/// Hand a string over to C
+unsafe fn write_string(out: *mut *mut c_char, s: &str) -> PinoError {
+    match CString::new(s) {
+        Ok(s) => {
+            *out = s.into_raw();
+            PinoError::Ok
+        },
+        Err(_) => PinoError::Utf8,
+    }
+}
+
+fn status(result: Result<(), PinoError>) -> PinoError {
+    result.err().unwrap_or(PinoError::Ok)
+}
+
+/// Static description of an error
+#[no_mangle]
+pub extern "C" fn pino_error_message(err: PinoError) -> *const c_char {
+    let message: &'static CStr = match err {
+        PinoError::Ok => c"ok",
+        PinoError::Null => c"null pointer",
+        PinoError::Utf8 => c"invalid utf-8",
+        PinoError::NotFound => c"not found",
+        PinoError::InvalidFormat => c"invalid format",
+        PinoError::RunError => c"could not run command",
+        PinoError::TimedOut => c"command timed out",
+    };
+    message.as_ptr()
+}
+
+/// Release a string returned by this library
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pino_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Construct an empty resource database
+#[no_mangle]
+pub extern "C" fn pino_xrdb_new() -> *mut PinoXrdb {
+    Box::into_raw(Box::new(PinoXrdb(Xrdb::new())))
+}
+
+/// Parse a resource database in the format of `xrdb -query` or an `~/.Xresources` file
+///
+/// # Safety
+///
+/// `raw` must be a valid C string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pino_xrdb_parse(raw: *const c_char, out: *mut *mut PinoXrdb) -> PinoError {
+    status((|| {
+        let raw = to_str(raw)?;
+        if out.is_null() {
+            return Err(PinoError::Null);
+        }
+        *out = Box::into_raw(Box::new(PinoXrdb(Xrdb::parse(raw))));
+        Ok(())
+    })())
+}
+
+/// Read the system xrdb into the database
+///
+/// # Safety
+///
+/// `xrdb` must be a handle returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn pino_xrdb_read(xrdb: *mut PinoXrdb) -> PinoError {
+    match xrdb.as_mut() {
+        Some(xrdb) => status(xrdb.0.read().map_err(PinoError::from)),
+        None => PinoError::Null,
+    }
+}
+
+/// Query a resource, writing a string to be released with [pino_string_free] to `out`
+///
+/// # Safety
+///
+/// `xrdb` must be a handle returned by this library, `program` and `res` valid C strings and
+/// `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pino_xrdb_query(
+    xrdb: *const PinoXrdb,
+    program: *const c_char,
+    res: *const c_char,
+    out: *mut *mut c_char,
+) -> PinoError {
+    let (Some(xrdb), false) = (xrdb.as_ref(), out.is_null()) else {
+        return PinoError::Null;
+    };
+    let (program, res) = match (to_str(program), to_str(res)) {
+        (Ok(program), Ok(res)) => (program, res),
+        (Err(e), _) | (_, Err(e)) => return e,
+    };
+    match xrdb.0.query(program, res) {
+        Some(val) => write_string(out, &val),
+        None => PinoError::NotFound,
+    }
+}
+
+/// Insert a resource, `program` may be null to insert a universal resource
+///
+/// # Safety
+///
+/// `xrdb` must be a handle returned by this library, `program` null or a valid C string and
+/// `res` and `val` valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn pino_xrdb_insert(
+    xrdb: *mut PinoXrdb,
+    program: *const c_char,
+    res: *const c_char,
+    val: *const c_char,
+) -> PinoError {
+    let Some(xrdb) = xrdb.as_mut() else {
+        return PinoError::Null;
+    };
+    status((|| {
+        let (res, val) = (to_str(res)?, to_str(val)?);
+        if program.is_null() {
+            xrdb.0.insert_universal(res, val);
+        } else {
+            xrdb.0.insert(to_str(program)?, res, val);
+        }
+        Ok(())
+    })())
+}
+
+/// Release a resource database
+///
+/// # Safety
+///
+/// `xrdb` must be null or a handle returned by this library that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pino_xrdb_free(xrdb: *mut PinoXrdb) {
+    if !xrdb.is_null() {
+        drop(Box::from_raw(xrdb));
+    }
+}
+
+/// Read the keytable of the system
+///
+/// # Safety
+///
+/// `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pino_keytable_new(out: *mut *mut PinoKeyTable) -> PinoError {
+    if out.is_null() {
+        return PinoError::Null;
+    }
+    match KeyTable::new() {
+        Ok(table) => {
+            *out = Box::into_raw(Box::new(PinoKeyTable(table)));
+            PinoError::Ok
+        },
+        Err(e) => e.into(),
+    }
+}
+
+/// Parse the output of `xmodmap -pke`
+///
+/// # Safety
+///
+/// `raw` must be a valid C string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pino_keytable_parse(
+    raw: *const c_char,
+    out: *mut *mut PinoKeyTable,
+) -> PinoError {
+    status((|| {
+        let raw = to_str(raw)?;
+        if out.is_null() {
+            return Err(PinoError::Null);
+        }
+        let table = KeyTable::parse(raw, DEFAULT_COLUMNS)?;
+        *out = Box::into_raw(Box::new(PinoKeyTable(table)));
+        Ok(())
+    })())
+}
+
+/// Name of the keysym bound to a keycode at a level, written to `out` as a string to be released
+/// with [pino_string_free]
+///
+/// # Safety
+///
+/// `table` must be a handle returned by this library and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pino_keytable_get_keysym(
+    table: *const PinoKeyTable,
+    modifier: PinoModifier,
+    keycode: u8,
+    out: *mut *mut c_char,
+) -> PinoError {
+    let (Some(table), false) = (table.as_ref(), out.is_null()) else {
+        return PinoError::Null;
+    };
+    match table.0.get_keysym(modifier.into(), keycode) {
+        Ok(keysym) => write_string(out, &keysym.to_string()),
+        Err(e) => e.into(),
+    }
+}
+
+/// Keycode and level producing a keysym, given by name
+///
+/// # Safety
+///
+/// `table` must be a handle returned by this library, `keysym` a valid C string and `modifier`
+/// and `keycode` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn pino_keytable_get_key(
+    table: *const PinoKeyTable,
+    keysym: *const c_char,
+    modifier: *mut PinoModifier,
+    keycode: *mut u8,
+) -> PinoError {
+    let Some(table) = table.as_ref() else {
+        return PinoError::Null;
+    };
+    if modifier.is_null() || keycode.is_null() {
+        return PinoError::Null;
+    }
+    status((|| {
+        let keysym = KeySym::from_str(to_str(keysym)?).or(Err(PinoError::NotFound))?;
+        let (found_modifier, found_keycode) = table.0.get_key(keysym)?;
+        *modifier = found_modifier.into();
+        *keycode = found_keycode;
+        Ok(())
+    })())
+}
+
+/// Release a keytable
+///
+/// # Safety
+///
+/// `table` must be null or a handle returned by this library that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pino_keytable_free(table: *mut PinoKeyTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn keytable_round_trip() {
+        unsafe {
+            let mut table = ptr::null_mut();
+            let raw = c"keycode  38 = a A a A";
+            assert_eq!(pino_keytable_parse(raw.as_ptr(), &mut table), PinoError::Ok);
+
+            let (mut modifier, mut keycode) = (PinoModifier::Key, 0);
+            let err = pino_keytable_get_key(table, c"A".as_ptr(), &mut modifier, &mut keycode);
+            assert_eq!(err, PinoError::Ok);
+            assert_eq!((modifier, keycode), (PinoModifier::ShiftKey, 38));
+
+            let mut name = ptr::null_mut();
+            let err = pino_keytable_get_keysym(table, PinoModifier::Key, 38, &mut name);
+            assert_eq!(err, PinoError::Ok);
+            assert_eq!(CStr::from_ptr(name).to_str(), Ok("a"));
+            pino_string_free(name);
+            pino_keytable_free(table);
+        }
+    }
+
+    #[test]
+    fn xrdb_round_trip() {
+        unsafe {
+            let xrdb = pino_xrdb_new();
+            let err = pino_xrdb_insert(xrdb, ptr::null(), c"color1".as_ptr(), c"#ea6962".as_ptr());
+            assert_eq!(err, PinoError::Ok);
+
+            let mut val = ptr::null_mut();
+            let err = pino_xrdb_query(xrdb, c"dwm".as_ptr(), c"color1".as_ptr(), &mut val);
+            assert_eq!(err, PinoError::Ok);
+            assert_eq!(CStr::from_ptr(val).to_str(), Ok("#ea6962"));
+            pino_string_free(val);
+
+            let err = pino_xrdb_query(xrdb, c"dwm".as_ptr(), c"font".as_ptr(), &mut val);
+            assert_eq!(err, PinoError::NotFound);
+            pino_xrdb_free(xrdb);
+        }
+    }
+
+    #[test]
+    fn header_declares_every_function() {
+        let header = include_str!("../include/pino_xutils.h");
+        let source = include_str!("ffi.rs");
+        for line in source.lines() {
+            let Some(name) = line
+                .split_once("extern \"C\" fn ")
+                .and_then(|(_, rest)| rest.split_once('('))
+                .map(|(name, _)| name)
+            else {
+                continue;
+            };
+            assert!(header.contains(&format!("{}(", name)), "{} missing", name);
+        }
+    }
+}
@@ -0,0 +1,783 @@
+//! Collection of utils for x11
+//!
+//! Re-exports each of the `pino_*` crates behind a feature flag of the same name (without the
+//! prefix), along with a [prelude] and an [Error] that wraps the error of every crate.
+//!
+//! Every subsystem is behind its own feature, so embedding one capability does not compile the
+//! rest of the toolkit:
+//!
+//! - `xrdb`, `xmodmap` (default): the subcrates, and `geometry` for keyboard geometry, with the
+//!   [toolkit] module writing the theme of the resources to the GTK and Qt settings
+//! - `palette`: the `palette` module generating a color scheme from a wallpaper
+//! - `xprop`: window properties
+//! - `xrandr`: outputs and modes, and with `xrdb` the [dpi] module setting `Xft.dpi` from them
+//! - `xset`: X server settings such as keyboard repeat
+//! - `xinput`: input devices
+//! - `setxkbmap`: keyboard layouts and options
+//! - `xsetroot`: the root window name, background, cursor and wallpaper
+//! - `xdpyinfo`: the dimensions, resolution and extensions of the X server
+//! - `xwininfo`: window geometry, attributes and the window tree
+//! - `wm`: windows and desktops of EWMH window managers
+//! - `xclip`: the clipboard and primary selection
+//! - `xbacklight`: the backlight brightness, with fades
+//! - `xev`: key, button, focus and property events of a window
+//! - `xgamma`: gamma correction of the whole screen
+//! - `xauth`: cookies of X authority files
+//! - `xhost`: access control list of the X server
+//! - `xkill`: disconnecting the client owning a window
+//! - `xdo`: synthetic key, pointer and button input
+//! - `xsettings`: serving XSETTINGS derived from the resources to GTK
+//! - `xserver`: headless Xvfb and nested Xephyr servers, such as for tests
+//! - `xfonts`: list fontconfig and core X fonts and check configured fonts exist
+//! - `watch` (default): the [watch] module, with `calloop` and `mio` adapters
+//! - `process` (default), `native`: running the client binaries, talking to the server directly
+//! - `tokio`, `tracing`, `ffi`, `python`: integrations
+//! - `serde`, `schemars`: serializing the data types, and the [schema] module with their schemas
+//! - `full`: everything except the `ffi` and `python` bindings
+//!
+//! ```rust,no_run
+//! use pino_xutils::prelude::*;
+//!
+//! # #[cfg(all(feature = "process", feature = "xrdb", feature = "xmodmap"))]
+//! fn main() -> Result<(), Error> {
+//!     let mut ctx = XContext::new();
+//!     let color = ctx.xrdb()?.query("dwm", "color1");
+//!     let (_, keycode) = ctx.keytable()?.get_key(KeySym::KEY_a)?;
+//!     println!("{:?} {}", color, keycode);
+//!     Ok(())
+//! }
+//! # #[cfg(not(all(feature = "process", feature = "xrdb", feature = "xmodmap")))]
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+
+#[cfg(all(feature = "xrandr", feature = "xrdb"))]
+pub mod dpi;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "palette")]
+pub mod palette;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "schemars")]
+pub mod schema;
+#[cfg(feature = "xrdb")]
+pub mod toolkit;
+#[cfg(all(feature = "watch", any(feature = "xrdb", feature = "xmodmap")))]
+pub mod watch;
+
+#[cfg(feature = "setxkbmap")]
+pub use pino_setxkbmap as setxkbmap;
+#[cfg(feature = "wm")]
+pub use pino_wm as wm;
+#[cfg(feature = "xauth")]
+pub use pino_xauth as xauth;
+#[cfg(feature = "xbacklight")]
+pub use pino_xbacklight as xbacklight;
+#[cfg(feature = "xclip")]
+pub use pino_xclip as xclip;
+use pino_xcommon::runner::CommandRunner;
+pub use pino_xcommon::{
+    backend,
+    capability::{Capabilities, Version},
+    capture,
+    color::Color,
+    cursor,
+    display::DisplayHandle,
+    platform,
+    retry::RetryPolicy,
+    runner,
+};
+#[cfg(feature = "xdo")]
+pub use pino_xdo as xdo;
+#[cfg(feature = "xdpyinfo")]
+pub use pino_xdpyinfo as xdpyinfo;
+#[cfg(feature = "xev")]
+pub use pino_xev as xev;
+#[cfg(feature = "xfonts")]
+pub use pino_xfonts as xfonts;
+#[cfg(feature = "xgamma")]
+pub use pino_xgamma as xgamma;
+#[cfg(feature = "xhost")]
+pub use pino_xhost as xhost;
+#[cfg(feature = "xinput")]
+pub use pino_xinput as xinput;
+#[cfg(feature = "xkill")]
+pub use pino_xkill as xkill;
+#[cfg(feature = "xmodmap")]
+pub use pino_xmodmap as xmodmap;
+#[cfg(feature = "xprop")]
+pub use pino_xprop as xprop;
+#[cfg(feature = "xrandr")]
+pub use pino_xrandr as xrandr;
+#[cfg(feature = "xrdb")]
+pub use pino_xrdb as xrdb;
+#[cfg(feature = "xserver")]
+pub use pino_xserver as xserver;
+#[cfg(feature = "xset")]
+pub use pino_xset as xset;
+#[cfg(feature = "xsetroot")]
+pub use pino_xsetroot as xsetroot;
+#[cfg(feature = "xsettings")]
+pub use pino_xsettings as xsettings;
+#[cfg(feature = "xwininfo")]
+pub use pino_xwininfo as xwininfo;
+
+/// Commonly used types of every enabled crate
+pub mod prelude {
+    #[cfg(feature = "xmodmap")]
+    pub use pino_xmodmap::{KeySym, KeyTable, ModIndex, ModMask, Modifier};
+    #[cfg(feature = "xrdb")]
+    pub use pino_xrdb::Xrdb;
+
+    pub use crate::{
+        runner::{CommandRunner, MockRunner},
+        Color, DisplayHandle, Error, RetryPolicy, XContext,
+    };
+}
+
+/// Error of any of the enabled crates
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "xrdb")]
+    Xrdb(pino_xrdb::XrdbError),
+    #[cfg(feature = "xmodmap")]
+    Xmodmap(pino_xmodmap::Error),
+    #[cfg(feature = "xprop")]
+    Xprop(pino_xprop::Error),
+    #[cfg(feature = "xrandr")]
+    Xrandr(pino_xrandr::Error),
+    #[cfg(feature = "xset")]
+    Xset(pino_xset::Error),
+    #[cfg(feature = "xinput")]
+    Xinput(pino_xinput::Error),
+    #[cfg(feature = "setxkbmap")]
+    Setxkbmap(pino_setxkbmap::Error),
+    #[cfg(feature = "xsetroot")]
+    Xsetroot(pino_xsetroot::Error),
+    #[cfg(feature = "xdpyinfo")]
+    Xdpyinfo(pino_xdpyinfo::Error),
+    #[cfg(feature = "xwininfo")]
+    Xwininfo(pino_xwininfo::Error),
+    #[cfg(feature = "wm")]
+    Wm(pino_wm::Error),
+    #[cfg(feature = "xclip")]
+    Xclip(pino_xclip::Error),
+    #[cfg(feature = "xbacklight")]
+    Xbacklight(pino_xbacklight::Error),
+    #[cfg(feature = "xev")]
+    Xev(pino_xev::Error),
+    #[cfg(feature = "xgamma")]
+    Xgamma(pino_xgamma::Error),
+    #[cfg(feature = "xauth")]
+    Xauth(pino_xauth::Error),
+    #[cfg(feature = "xhost")]
+    Xhost(pino_xhost::Error),
+    #[cfg(feature = "xkill")]
+    Xkill(pino_xkill::Error),
+    #[cfg(feature = "xdo")]
+    Xdo(pino_xdo::Error),
+    #[cfg(feature = "xsettings")]
+    Xsettings(pino_xsettings::Error),
+    #[cfg(feature = "xserver")]
+    Xserver(pino_xserver::Error),
+    #[cfg(feature = "xfonts")]
+    Xfonts(pino_xfonts::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            #[cfg(feature = "xrdb")]
+            Error::Xrdb(ref e) => Some(e),
+            #[cfg(feature = "xmodmap")]
+            Error::Xmodmap(ref e) => Some(e),
+            #[cfg(feature = "xprop")]
+            Error::Xprop(ref e) => Some(e),
+            #[cfg(feature = "xrandr")]
+            Error::Xrandr(ref e) => Some(e),
+            #[cfg(feature = "xset")]
+            Error::Xset(ref e) => Some(e),
+            #[cfg(feature = "xinput")]
+            Error::Xinput(ref e) => Some(e),
+            #[cfg(feature = "setxkbmap")]
+            Error::Setxkbmap(ref e) => Some(e),
+            #[cfg(feature = "xsetroot")]
+            Error::Xsetroot(ref e) => Some(e),
+            #[cfg(feature = "xdpyinfo")]
+            Error::Xdpyinfo(ref e) => Some(e),
+            #[cfg(feature = "xwininfo")]
+            Error::Xwininfo(ref e) => Some(e),
+            #[cfg(feature = "wm")]
+            Error::Wm(ref e) => Some(e),
+            #[cfg(feature = "xclip")]
+            Error::Xclip(ref e) => Some(e),
+            #[cfg(feature = "xbacklight")]
+            Error::Xbacklight(ref e) => Some(e),
+            #[cfg(feature = "xev")]
+            Error::Xev(ref e) => Some(e),
+            #[cfg(feature = "xgamma")]
+            Error::Xgamma(ref e) => Some(e),
+            #[cfg(feature = "xauth")]
+            Error::Xauth(ref e) => Some(e),
+            #[cfg(feature = "xhost")]
+            Error::Xhost(ref e) => Some(e),
+            #[cfg(feature = "xkill")]
+            Error::Xkill(ref e) => Some(e),
+            #[cfg(feature = "xdo")]
+            Error::Xdo(ref e) => Some(e),
+            #[cfg(feature = "xsettings")]
+            Error::Xsettings(ref e) => Some(e),
+            #[cfg(feature = "xserver")]
+            Error::Xserver(ref e) => Some(e),
+            #[cfg(feature = "xfonts")]
+            Error::Xfonts(ref e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    #[cfg_attr(
+        not(any(
+            feature = "xrdb",
+            feature = "xmodmap",
+            feature = "xprop",
+            feature = "xrandr",
+            feature = "xset",
+            feature = "xinput",
+            feature = "setxkbmap",
+            feature = "xsetroot",
+            feature = "xdpyinfo",
+            feature = "xwininfo",
+            feature = "wm",
+            feature = "xclip",
+            feature = "xbacklight",
+            feature = "xev",
+            feature = "xgamma",
+            feature = "xauth",
+            feature = "xhost",
+            feature = "xkill",
+            feature = "xdo",
+            feature = "xsettings",
+            feature = "xserver",
+            feature = "xfonts"
+        )),
+        allow(unused_variables)
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "xrdb")]
+            Error::Xrdb(ref e) => write!(f, "xrdb: {}", e),
+            #[cfg(feature = "xmodmap")]
+            Error::Xmodmap(ref e) => write!(f, "xmodmap: {}", e),
+            #[cfg(feature = "xprop")]
+            Error::Xprop(ref e) => write!(f, "xprop: {}", e),
+            #[cfg(feature = "xrandr")]
+            Error::Xrandr(ref e) => write!(f, "xrandr: {}", e),
+            #[cfg(feature = "xset")]
+            Error::Xset(ref e) => write!(f, "xset: {}", e),
+            #[cfg(feature = "xinput")]
+            Error::Xinput(ref e) => write!(f, "xinput: {}", e),
+            #[cfg(feature = "setxkbmap")]
+            Error::Setxkbmap(ref e) => write!(f, "setxkbmap: {}", e),
+            #[cfg(feature = "xsetroot")]
+            Error::Xsetroot(ref e) => write!(f, "xsetroot: {}", e),
+            #[cfg(feature = "xdpyinfo")]
+            Error::Xdpyinfo(ref e) => write!(f, "xdpyinfo: {}", e),
+            #[cfg(feature = "xwininfo")]
+            Error::Xwininfo(ref e) => write!(f, "xwininfo: {}", e),
+            #[cfg(feature = "wm")]
+            Error::Wm(ref e) => write!(f, "wm: {}", e),
+            #[cfg(feature = "xclip")]
+            Error::Xclip(ref e) => write!(f, "xclip: {}", e),
+            #[cfg(feature = "xbacklight")]
+            Error::Xbacklight(ref e) => write!(f, "xbacklight: {}", e),
+            #[cfg(feature = "xev")]
+            Error::Xev(ref e) => write!(f, "xev: {}", e),
+            #[cfg(feature = "xgamma")]
+            Error::Xgamma(ref e) => write!(f, "xgamma: {}", e),
+            #[cfg(feature = "xauth")]
+            Error::Xauth(ref e) => write!(f, "xauth: {}", e),
+            #[cfg(feature = "xhost")]
+            Error::Xhost(ref e) => write!(f, "xhost: {}", e),
+            #[cfg(feature = "xkill")]
+            Error::Xkill(ref e) => write!(f, "xkill: {}", e),
+            #[cfg(feature = "xdo")]
+            Error::Xdo(ref e) => write!(f, "xdo: {}", e),
+            #[cfg(feature = "xsettings")]
+            Error::Xsettings(ref e) => write!(f, "xsettings: {}", e),
+            #[cfg(feature = "xserver")]
+            Error::Xserver(ref e) => write!(f, "xserver: {}", e),
+            #[cfg(feature = "xfonts")]
+            Error::Xfonts(ref e) => write!(f, "xfonts: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "xrdb")]
+impl From<pino_xrdb::XrdbError> for Error {
+    fn from(value: pino_xrdb::XrdbError) -> Self {
+        Error::Xrdb(value)
+    }
+}
+
+#[cfg(feature = "xmodmap")]
+impl From<pino_xmodmap::Error> for Error {
+    fn from(value: pino_xmodmap::Error) -> Self {
+        Error::Xmodmap(value)
+    }
+}
+
+#[cfg(feature = "xprop")]
+impl From<pino_xprop::Error> for Error {
+    fn from(value: pino_xprop::Error) -> Self {
+        Error::Xprop(value)
+    }
+}
+
+#[cfg(feature = "xrandr")]
+impl From<pino_xrandr::Error> for Error {
+    fn from(value: pino_xrandr::Error) -> Self {
+        Error::Xrandr(value)
+    }
+}
+
+#[cfg(feature = "xset")]
+impl From<pino_xset::Error> for Error {
+    fn from(value: pino_xset::Error) -> Self {
+        Error::Xset(value)
+    }
+}
+
+#[cfg(feature = "xinput")]
+impl From<pino_xinput::Error> for Error {
+    fn from(value: pino_xinput::Error) -> Self {
+        Error::Xinput(value)
+    }
+}
+
+#[cfg(feature = "setxkbmap")]
+impl From<pino_setxkbmap::Error> for Error {
+    fn from(value: pino_setxkbmap::Error) -> Self {
+        Error::Setxkbmap(value)
+    }
+}
+
+#[cfg(feature = "xsetroot")]
+impl From<pino_xsetroot::Error> for Error {
+    fn from(value: pino_xsetroot::Error) -> Self {
+        Error::Xsetroot(value)
+    }
+}
+
+#[cfg(feature = "xdpyinfo")]
+impl From<pino_xdpyinfo::Error> for Error {
+    fn from(value: pino_xdpyinfo::Error) -> Self {
+        Error::Xdpyinfo(value)
+    }
+}
+
+#[cfg(feature = "xwininfo")]
+impl From<pino_xwininfo::Error> for Error {
+    fn from(value: pino_xwininfo::Error) -> Self {
+        Error::Xwininfo(value)
+    }
+}
+
+#[cfg(feature = "wm")]
+impl From<pino_wm::Error> for Error {
+    fn from(value: pino_wm::Error) -> Self {
+        Error::Wm(value)
+    }
+}
+
+#[cfg(feature = "xclip")]
+impl From<pino_xclip::Error> for Error {
+    fn from(value: pino_xclip::Error) -> Self {
+        Error::Xclip(value)
+    }
+}
+
+#[cfg(feature = "xbacklight")]
+impl From<pino_xbacklight::Error> for Error {
+    fn from(value: pino_xbacklight::Error) -> Self {
+        Error::Xbacklight(value)
+    }
+}
+
+#[cfg(feature = "xev")]
+impl From<pino_xev::Error> for Error {
+    fn from(value: pino_xev::Error) -> Self {
+        Error::Xev(value)
+    }
+}
+
+#[cfg(feature = "xgamma")]
+impl From<pino_xgamma::Error> for Error {
+    fn from(value: pino_xgamma::Error) -> Self {
+        Error::Xgamma(value)
+    }
+}
+
+#[cfg(feature = "xauth")]
+impl From<pino_xauth::Error> for Error {
+    fn from(value: pino_xauth::Error) -> Self {
+        Error::Xauth(value)
+    }
+}
+
+#[cfg(feature = "xhost")]
+impl From<pino_xhost::Error> for Error {
+    fn from(value: pino_xhost::Error) -> Self {
+        Error::Xhost(value)
+    }
+}
+
+#[cfg(feature = "xkill")]
+impl From<pino_xkill::Error> for Error {
+    fn from(value: pino_xkill::Error) -> Self {
+        Error::Xkill(value)
+    }
+}
+
+#[cfg(feature = "xdo")]
+impl From<pino_xdo::Error> for Error {
+    fn from(value: pino_xdo::Error) -> Self {
+        Error::Xdo(value)
+    }
+}
+
+#[cfg(feature = "xsettings")]
+impl From<pino_xsettings::Error> for Error {
+    fn from(value: pino_xsettings::Error) -> Self {
+        Error::Xsettings(value)
+    }
+}
+
+#[cfg(feature = "xserver")]
+impl From<pino_xserver::Error> for Error {
+    fn from(value: pino_xserver::Error) -> Self {
+        Error::Xserver(value)
+    }
+}
+
+#[cfg(feature = "xfonts")]
+impl From<pino_xfonts::Error> for Error {
+    fn from(value: pino_xfonts::Error) -> Self {
+        Error::Xfonts(value)
+    }
+}
+
+/// Single entry point to the state of the X server
+///
+/// Each piece of state is read from the system the first time it is accessed and cached
+/// afterwards, call [XContext::refresh] to read it again.
+pub struct XContext {
+    #[cfg_attr(not(any(feature = "xrdb", feature = "xmodmap")), allow(dead_code))]
+    runner: Box<dyn CommandRunner>,
+    #[cfg_attr(not(any(feature = "xrdb", feature = "xmodmap")), allow(dead_code))]
+    display: DisplayHandle,
+    #[cfg_attr(not(any(feature = "xrdb", feature = "xmodmap")), allow(dead_code))]
+    retry: RetryPolicy,
+    #[cfg(feature = "xrdb")]
+    xrdb: Option<pino_xrdb::Xrdb>,
+    #[cfg(feature = "xmodmap")]
+    keytable: Option<pino_xmodmap::KeyTable>,
+    #[cfg(feature = "xmodmap")]
+    modmap: Option<pino_xmodmap::modmap::ModifierMap>,
+}
+
+/// Everything read by [XContext::init_all]
+#[cfg(any(feature = "xrdb", feature = "xmodmap"))]
+pub struct Loaded<'a> {
+    #[cfg(feature = "xrdb")]
+    pub xrdb: &'a pino_xrdb::Xrdb,
+    #[cfg(feature = "xmodmap")]
+    pub keytable: &'a pino_xmodmap::KeyTable,
+    #[cfg(feature = "xmodmap")]
+    pub modmap: &'a pino_xmodmap::modmap::ModifierMap,
+}
+
+impl XContext {
+    /// Construct a context without reading anything yet
+    #[cfg(feature = "process")]
+    pub fn new() -> Self {
+        XContext::with_runner(runner::SystemRunner::default())
+    }
+
+    /// Construct a context that runs every command through the given runner
+    pub fn with_runner(runner: impl CommandRunner + 'static) -> Self {
+        XContext {
+            runner: Box::new(runner),
+            display: DisplayHandle::default(),
+            retry: RetryPolicy::default(),
+            #[cfg(feature = "xrdb")]
+            xrdb: None,
+            #[cfg(feature = "xmodmap")]
+            keytable: None,
+            #[cfg(feature = "xmodmap")]
+            modmap: None,
+        }
+    }
+
+    /// Target the given display instead of the one of the environment
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "process")] {
+    /// # use pino_xutils::prelude::*;
+    /// let mut ctx = XContext::new().display(DisplayHandle::new().display(":1"));
+    /// # }
+    /// ```
+    pub fn display(mut self, display: DisplayHandle) -> Self {
+        self.display = display;
+        self.refresh();
+        self
+    }
+
+    /// Run commands that fail again according to `policy`, they are run once by default
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "process")] {
+    /// # use pino_xutils::prelude::*;
+    /// let mut ctx = XContext::new().retry(RetryPolicy::new(3));
+    /// # }
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Resource database of the system
+    #[cfg(feature = "xrdb")]
+    pub fn xrdb(&mut self) -> Result<&pino_xrdb::Xrdb, Error> {
+        if self.xrdb.is_none() {
+            let mut xrdb = pino_xrdb::Xrdb::new();
+            let runner = self.retry.runner(self.runner.as_ref());
+            xrdb.read_with_runner(&self.display.runner(&runner))?;
+            self.xrdb = Some(xrdb);
+        }
+        Ok(self.xrdb.as_ref().unwrap())
+    }
+
+    /// Keytable of the system
+    #[cfg(feature = "xmodmap")]
+    pub fn keytable(&mut self) -> Result<&pino_xmodmap::KeyTable, Error> {
+        if self.keytable.is_none() {
+            let runner = self.retry.runner(self.runner.as_ref());
+            self.keytable = Some(pino_xmodmap::KeyTable::with_runner(
+                &self.display.runner(&runner),
+                pino_xmodmap::DEFAULT_COLUMNS,
+            )?);
+        }
+        Ok(self.keytable.as_ref().unwrap())
+    }
+
+    /// Modifier map of the system
+    #[cfg(feature = "xmodmap")]
+    pub fn modmap(&mut self) -> Result<&pino_xmodmap::modmap::ModifierMap, Error> {
+        if self.modmap.is_none() {
+            let runner = self.retry.runner(self.runner.as_ref());
+            self.modmap = Some(pino_xmodmap::modmap::ModifierMap::with_runner(
+                &self.display.runner(&runner),
+            )?);
+        }
+        Ok(self.modmap.as_ref().unwrap())
+    }
+
+    /// Read every source that is not cached yet at the same time, one thread each
+    ///
+    /// Takes as long as the slowest source instead of all of them combined. When several sources
+    /// fail, the error of the first in field order of [Loaded] is returned, and the sources that
+    /// were read are cached anyway.
+    ///
+    /// ```rust,no_run
+    /// # use pino_xutils::prelude::*;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut ctx = XContext::new();
+    /// let loaded = ctx.init_all()?;
+    /// println!("{:?}", loaded.xrdb.query("dwm", "color1"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(feature = "xrdb", feature = "xmodmap"))]
+    pub fn init_all(&mut self) -> Result<Loaded<'_>, Error> {
+        let runner = self.retry.runner(self.runner.as_ref());
+        let runner = self.display.runner(&runner);
+        let runner = &runner;
+
+        std::thread::scope(|s| {
+            #[cfg(feature = "xrdb")]
+            let xrdb = self.xrdb.is_none().then(|| {
+                s.spawn(move || {
+                    let mut xrdb = pino_xrdb::Xrdb::new();
+                    xrdb.read_with_runner(runner).map(|_| xrdb)
+                })
+            });
+            #[cfg(feature = "xmodmap")]
+            let keytable = self.keytable.is_none().then(|| {
+                s.spawn(move || {
+                    pino_xmodmap::KeyTable::with_runner(runner, pino_xmodmap::DEFAULT_COLUMNS)
+                })
+            });
+            #[cfg(feature = "xmodmap")]
+            let modmap = self
+                .modmap
+                .is_none()
+                .then(|| s.spawn(move || pino_xmodmap::modmap::ModifierMap::with_runner(runner)));
+
+            let mut result: Result<(), Error> = Ok(());
+            #[cfg(feature = "xrdb")]
+            if let Some(thread) = xrdb {
+                match thread.join().expect("reading xrdb panicked") {
+                    Ok(xrdb) => self.xrdb = Some(xrdb),
+                    Err(e) => result = result.and(Err(e.into())),
+                }
+            }
+            #[cfg(feature = "xmodmap")]
+            if let Some(thread) = keytable {
+                match thread.join().expect("reading the keytable panicked") {
+                    Ok(keytable) => self.keytable = Some(keytable),
+                    Err(e) => result = result.and(Err(e.into())),
+                }
+            }
+            #[cfg(feature = "xmodmap")]
+            if let Some(thread) = modmap {
+                match thread.join().expect("reading the modifier map panicked") {
+                    Ok(modmap) => self.modmap = Some(modmap),
+                    Err(e) => result = result.and(Err(e.into())),
+                }
+            }
+            result
+        })?;
+
+        Ok(Loaded {
+            #[cfg(feature = "xrdb")]
+            xrdb: self.xrdb.as_ref().unwrap(),
+            #[cfg(feature = "xmodmap")]
+            keytable: self.keytable.as_ref().unwrap(),
+            #[cfg(feature = "xmodmap")]
+            modmap: self.modmap.as_ref().unwrap(),
+        })
+    }
+
+    /// Async variant of [XContext::xrdb], enabled by the `tokio` feature
+    ///
+    /// Always reads from the system, bypassing the runner and display of the context.
+    #[cfg(all(feature = "xrdb", feature = "tokio"))]
+    pub async fn xrdb_async(&mut self) -> Result<&pino_xrdb::Xrdb, Error> {
+        if self.xrdb.is_none() {
+            let mut xrdb = pino_xrdb::Xrdb::new();
+            xrdb.read_async().await?;
+            self.xrdb = Some(xrdb);
+        }
+        Ok(self.xrdb.as_ref().unwrap())
+    }
+
+    /// Async variant of [XContext::keytable], enabled by the `tokio` feature
+    ///
+    /// Always reads from the system, bypassing the runner and display of the context.
+    #[cfg(all(feature = "xmodmap", feature = "tokio"))]
+    pub async fn keytable_async(&mut self) -> Result<&pino_xmodmap::KeyTable, Error> {
+        if self.keytable.is_none() {
+            self.keytable = Some(pino_xmodmap::KeyTable::new_async().await?);
+        }
+        Ok(self.keytable.as_ref().unwrap())
+    }
+
+    /// Version and supported flags of the binary behind each enabled crate
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "process")] {
+    /// # use pino_xutils::XContext;
+    /// for caps in XContext::new().capabilities() {
+    ///     println!("{}", caps);
+    /// }
+    /// # }
+    /// ```
+    pub fn capabilities(&self) -> Vec<Capabilities> {
+        // not retried, probing exits with an error on purpose
+        #[allow(unused_variables)]
+        let runner = self.display.runner(self.runner.as_ref());
+        vec![
+            #[cfg(feature = "xrdb")]
+            pino_xrdb::Xrdb::capabilities_with_runner(&runner),
+            #[cfg(feature = "xmodmap")]
+            pino_xmodmap::KeyTable::capabilities_with_runner(&runner),
+        ]
+    }
+
+    /// Forget everything read so far
+    pub fn refresh(&mut self) {
+        #[cfg(feature = "xrdb")]
+        {
+            self.xrdb = None;
+        }
+        #[cfg(feature = "xmodmap")]
+        {
+            self.keytable = None;
+            self.modmap = None;
+        }
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for XContext {
+    fn default() -> Self {
+        XContext::new()
+    }
+}
+
+#[cfg(all(test, feature = "xrdb", feature = "xmodmap"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_errors() {
+        let err = Error::from(pino_xrdb::XrdbError::Invalid);
+        assert_eq!(err.to_string(), "xrdb: failed to parse line");
+        let err = Error::from(pino_xmodmap::Error::NonExistentKeyCode(9));
+        assert_eq!(err.to_string(), "xmodmap: non-existent keycode 9");
+    }
+
+    #[test]
+    fn context_runner() {
+        let runner = runner::MockRunner::new().respond(
+            runner::Cmd::new("xrdb").arg("-query"),
+            runner::Output::from_stdout("dwm.color1: #ea6962"),
+        );
+        let mut ctx = XContext::with_runner(runner);
+        assert_eq!(
+            ctx.xrdb().unwrap().query("dwm", "color1"),
+            Some(String::from("#ea6962"))
+        );
+        assert!(matches!(ctx.keytable(), Err(Error::Xmodmap(_))));
+    }
+
+    #[test]
+    fn context_init_all() {
+        let runner = runner::MockRunner::new()
+            .respond(
+                runner::Cmd::new("xrdb").arg("-query"),
+                runner::Output::from_stdout("dwm.color1: #ea6962"),
+            )
+            .respond(
+                runner::Cmd::new("xmodmap").arg("-pke"),
+                runner::Output::from_stdout("keycode   9 = Escape"),
+            );
+        let mut ctx = XContext::with_runner(runner);
+        assert!(matches!(ctx.init_all(), Err(Error::Xmodmap(_))));
+        // the sources that could be read are kept
+        assert!(ctx.xrdb.is_some() && ctx.keytable.is_some());
+
+        ctx.modmap = Some(pino_xmodmap::modmap::ModifierMap::default());
+        let loaded = ctx.init_all().unwrap();
+        assert_eq!(
+            loaded
+                .keytable
+                .get_keysym(prelude::Modifier::Key, 9)
+                .unwrap(),
+            prelude::KeySym::KEY_ESCAPE
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Generate a terminal color scheme from a wallpaper, like pywal does, enabled by the `palette`
+//! feature
+//!
+//! The dominant colors of the image are found by median cut. The darkest becomes the
+//! background, the lightest the foreground, and the other six are given to the ANSI colors whose
+//! hue they are closest to.
+//!
+//! ```rust,no_run
+//! use pino_xutils::{palette::Palette, xrdb::Xrdb};
+//!
+//! let palette = Palette::from_image("/home/user/pictures/mountains.png".as_ref()).unwrap();
+//! Xrdb::merge(&palette.to_xrdb().to_string()).unwrap();
+//! ```
+//!
+//! PNG and JPEG images can be read.
+
+use std::{fmt, path::Path};
+
+use pino_xrdb::{Color, Xrdb};
+
+use crate::toolkit::luminance;
+
+/// Size the image is scaled down to before its colors are counted
+const SAMPLE_SIZE: u32 = 256;
+
+/// Number of dominant colors picked from the image
+const DOMINANT: usize = 8;
+
+/// Hues of the ANSI colors red, green, yellow, blue, magenta and cyan, in degrees
+const ANSI_HUES: [f64; 6] = [0.0, 120.0, 60.0, 240.0, 300.0, 180.0];
+
+/// Sixteen terminal colors along with the background and foreground
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Palette {
+    /// `color0` through `color15`
+    pub colors: [Color; 16],
+    pub background: Color,
+    pub foreground: Color,
+}
+
+/// Palette generation errors
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PaletteError {
+    /// Image could not be read or decoded
+    Image(image::ImageError),
+    /// Image has no pixels to take the colors from
+    Empty,
+}
+
+impl std::error::Error for PaletteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PaletteError::Image(e) => Some(e),
+            PaletteError::Empty => None,
+        }
+    }
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::Image(e) => write!(f, "could not read image: {}", e),
+            PaletteError::Empty => write!(f, "image has no pixels"),
+        }
+    }
+}
+
+impl From<image::ImageError> for PaletteError {
+    fn from(value: image::ImageError) -> Self {
+        PaletteError::Image(value)
+    }
+}
+
+impl Palette {
+    /// Read an image and generate the palette from its colors
+    pub fn from_image(path: &Path) -> Result<Self, PaletteError> {
+        let image = image::open(path)?.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE);
+        Palette::from_rgb(&image.to_rgb8())
+    }
+
+    fn from_rgb(image: &image::RgbImage) -> Result<Self, PaletteError> {
+        let pixels = image
+            .pixels()
+            .map(|p| Color::rgb(p[0], p[1], p[2]))
+            .collect::<Vec<_>>();
+        Palette::from_pixels(&pixels).ok_or(PaletteError::Empty)
+    }
+
+    /// Generate the palette from the pixels of an image, `None` if there are none
+    ///
+    /// ```rust
+    /// use pino_xutils::{palette::Palette, xrdb::Color};
+    ///
+    /// let pixels = [Color::rgb(0x10, 0x20, 0x30), Color::rgb(0xc0, 0x40, 0x30)].repeat(50);
+    /// let palette = Palette::from_pixels(&pixels).unwrap();
+    /// // the reddest color goes to color1
+    /// assert!(palette.colors[1].r > palette.colors[1].b);
+    /// ```
+    pub fn from_pixels(pixels: &[Color]) -> Option<Self> {
+        let mut dominant = median_cut(pixels, DOMINANT);
+        if dominant.is_empty() {
+            return None;
+        }
+        dominant.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+        let (darkest, lightest) = (dominant[0], dominant[dominant.len() - 1]);
+        // images with few colors repeat them
+        let accents = (0..6)
+            .map(|i| dominant[(i + 1) % dominant.len()])
+            .collect::<Vec<_>>();
+
+        // keep the text readable whatever the brightness of the image
+        let background = mix(darkest, Color::rgb(0, 0, 0), 0.6);
+        let foreground = mix(lightest, Color::rgb(0xff, 0xff, 0xff), 0.7);
+        let accents = assign_hues(&accents).map(|c| {
+            if luminance(c) < 0.3 {
+                mix(c, Color::rgb(0xff, 0xff, 0xff), 0.3)
+            } else {
+                c
+            }
+        });
+
+        let mut colors = [background; 16];
+        colors[1..7].copy_from_slice(&accents);
+        colors[7] = mix(foreground, background, 0.2);
+        colors[8] = mix(background, foreground, 0.3);
+        for (bright, accent) in colors[9..15].iter_mut().zip(accents) {
+            *bright = mix(accent, Color::rgb(0xff, 0xff, 0xff), 0.2);
+        }
+        colors[15] = foreground;
+        Some(Palette {
+            colors,
+            background,
+            foreground,
+        })
+    }
+
+    /// Universal resources for the palette, ready to be merged
+    ///
+    /// ```rust
+    /// use pino_xutils::{palette::Palette, xrdb::Color};
+    ///
+    /// let palette = Palette::from_pixels(&[Color::rgb(0x45, 0x85, 0x88)]).unwrap();
+    /// let xrdb = palette.to_xrdb();
+    /// assert_eq!(xrdb.query_color("st", "background"), Some(palette.background));
+    /// assert_eq!(xrdb.query_color("st", "color15"), Some(palette.foreground));
+    /// ```
+    pub fn to_xrdb(&self) -> Xrdb {
+        let mut xrdb = Xrdb::new();
+        for (i, color) in self.colors.iter().enumerate() {
+            xrdb.insert_universal(&format!("color{}", i), &color.to_string());
+        }
+        xrdb.insert_universal("background", &self.background.to_string());
+        xrdb.insert_universal("foreground", &self.foreground.to_string());
+        xrdb.insert_universal("cursorColor", &self.foreground.to_string());
+        xrdb
+    }
+}
+
+/// Up to `count` colors averaging the boxes the pixels are split into, each split cutting the box
+/// with the widest range in the middle of that range
+///
+/// Cutting at the middle rather than at the median pixel keeps a large area of a single color, such
+/// as the sky, from taking up most of the boxes.
+fn median_cut(pixels: &[Color], count: usize) -> Vec<Color> {
+    let channels = |c: &Color| [c.r, c.g, c.b];
+    let range = |pixels: &[Color], channel: usize| {
+        let values = pixels.iter().map(|c| channels(c)[channel]);
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    };
+    let mut boxes = vec![pixels.to_vec()];
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, _)| (0..3).map(move |channel| (i, channel)))
+            .max_by_key(|&(i, channel)| range(&boxes[i], channel));
+        let Some((i, channel)) = widest.filter(|&(i, channel)| range(&boxes[i], channel) > 0)
+        else {
+            break;
+        };
+        let mut pixels = boxes.swap_remove(i);
+        pixels.sort_unstable_by_key(|c| channels(c)[channel]);
+        let (min, max) = (
+            channels(&pixels[0])[channel],
+            channels(&pixels[pixels.len() - 1])[channel],
+        );
+        let middle = min + (max - min) / 2;
+        let upper = pixels.split_off(pixels.partition_point(|c| channels(c)[channel] <= middle));
+        boxes.extend([pixels, upper]);
+    }
+    boxes
+        .iter()
+        .filter(|pixels| !pixels.is_empty())
+        .map(|pixels| {
+            let sum = pixels.iter().fold([0u64; 3], |sum, c| {
+                let [r, g, b] = channels(c).map(u64::from);
+                [sum[0] + r, sum[1] + g, sum[2] + b]
+            });
+            let [r, g, b] = sum.map(|s| (s / pixels.len() as u64) as u8);
+            Color::rgb(r, g, b)
+        })
+        .collect()
+}
+
+/// Order six colors as red, green, yellow, blue, magenta and cyan, keeping the total distance
+/// from those hues lowest
+fn assign_hues(colors: &[Color]) -> [Color; 6] {
+    let distance = |color: Color, hue: f64| {
+        let d = (self::hue(color) - hue).abs() % 360.0;
+        d.min(360.0 - d)
+    };
+    // every one of the 720 orders is tried
+    let mut best = ([0, 1, 2, 3, 4, 5], f64::INFINITY);
+    let mut order = [0, 1, 2, 3, 4, 5];
+    permute(&mut order, 0, &mut |order| {
+        let total = order
+            .iter()
+            .zip(ANSI_HUES)
+            .map(|(&i, hue)| distance(colors[i], hue))
+            .sum::<f64>();
+        if total < best.1 {
+            best = (*order, total);
+        }
+    });
+    best.0.map(|i| colors[i])
+}
+
+fn permute(order: &mut [usize; 6], k: usize, f: &mut impl FnMut(&[usize; 6])) {
+    if k == order.len() {
+        return f(order);
+    }
+    for i in k..order.len() {
+        order.swap(k, i);
+        permute(order, k + 1, f);
+        order.swap(k, i);
+    }
+}
+
+/// Hue of a color in degrees
+fn hue(color: Color) -> f64 {
+    let [r, g, b] = color.to_f32().map(f64::from);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max == min {
+        return 0.0;
+    }
+    let hue = if max == r {
+        (g - b) / (max - min)
+    } else if max == g {
+        2.0 + (b - r) / (max - min)
+    } else {
+        4.0 + (r - g) / (max - min)
+    };
+    (hue * 60.0).rem_euclid(360.0)
+}
+
+/// Color `amount` of the way from `a` to `b`
+fn mix(a: Color, b: Color, amount: f64) -> Color {
+    let channel =
+        |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * amount).round() as u8;
+    Color::rgb(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_from_empty_image() {
+        let image = image::RgbImage::new(0, 0);
+        assert!(matches!(
+            Palette::from_rgb(&image),
+            Err(PaletteError::Empty)
+        ));
+    }
+
+    #[test]
+    fn palette_from_pixels() {
+        let hex = |s: &str| s.parse::<Color>().unwrap();
+        // a dusk: dark sky, a bright moon and six distinct accents
+        let mut pixels = vec![];
+        for (color, count) in [
+            ("#101820", 400),
+            ("#f0f0e0", 40),
+            ("#c03030", 30),
+            ("#30a040", 30),
+            ("#d0c030", 30),
+            ("#3050c0", 30),
+            ("#a030a0", 30),
+            ("#30b0b0", 30),
+        ] {
+            pixels.extend(std::iter::repeat_n(hex(color), count));
+        }
+        let palette = Palette::from_pixels(&pixels).unwrap();
+
+        assert!(luminance(palette.background) < luminance(hex("#101820")));
+        assert!(luminance(palette.foreground) > 0.9);
+        assert_eq!(palette.colors[0], palette.background);
+        assert_eq!(palette.colors[1], hex("#c03030"));
+        assert_eq!(palette.colors[2], hex("#30a040"));
+        assert_eq!(palette.colors[3], hex("#d0c030"));
+        assert!(hue(palette.colors[4]) > 200.0 && hue(palette.colors[4]) < 260.0);
+        assert_eq!(palette.colors[6], hex("#30b0b0"));
+
+        let xrdb = palette.to_xrdb();
+        assert_eq!(xrdb.query_color("st", "color2"), Some(hex("#30a040")));
+        assert!(Palette::from_pixels(&[]).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! Python bindings, enabled by the `python` feature
+//!
+//! Build the extension module with [maturin](https://github.com/PyO3/maturin), adding
+//! `pyo3/extension-module` to the enabled features:
+//!
+//! ```sh
+//! maturin develop --features python,pyo3/extension-module
+//! ```
+//!
+//! ```python
+//! import pino_xutils
+//!
+//! xrdb = pino_xutils.Xrdb()
+//! xrdb.read()
+//! print(xrdb.query("dwm", "color1"))
+//!
+//! table = pino_xutils.KeyTable()
+//! modifier, keycode = table.get_key("a")
+//! ```
+
+use pyo3::{
+    exceptions::{PyKeyError, PyRuntimeError, PyTimeoutError, PyValueError},
+    prelude::*,
+};
+
+use crate::{xmodmap, xrdb};
+
+/// Levels accepted and returned by [PyKeyTable], named after their [xmodmap::Modifier] variant
+static MODIFIERS: &[xmodmap::Modifier] = &[
+    xmodmap::Modifier::Key,
+    xmodmap::Modifier::ShiftKey,
+    xmodmap::Modifier::ModeSwitchKey,
+    xmodmap::Modifier::ModeSwitchShiftKey,
+    xmodmap::Modifier::ISOLevel3ShiftKey,
+    xmodmap::Modifier::ISOLevel3ShiftShiftKey,
+    xmodmap::Modifier::ISOLevel5ShiftKey,
+    xmodmap::Modifier::ISOLevel5ShiftShiftKey,
+    xmodmap::Modifier::ISOLevel3ISOLevel5ShiftKey,
+    xmodmap::Modifier::ISOLevel3ISOLevel5ShiftShiftKey,
+];
+
+fn xrdb_err(e: xrdb::XrdbError) -> PyErr {
+    match e {
+        xrdb::XrdbError::TimedOut => PyTimeoutError::new_err(e.to_string()),
+        _ => PyRuntimeError::new_err(e.to_string()),
+    }
+}
+
+fn xmodmap_err(e: xmodmap::Error) -> PyErr {
+    match e {
+        xmodmap::Error::NonExistentKeyCode(_) | xmodmap::Error::NonExistentKeySym(_) => {
+            PyKeyError::new_err(e.to_string())
+        },
+        xmodmap::Error::InvalidFormat { .. } => PyValueError::new_err(e.to_string()),
+        xmodmap::Error::TimedOut { .. } => PyTimeoutError::new_err(e.to_string()),
+        _ => PyRuntimeError::new_err(e.to_string()),
+    }
+}
+
+/// Resource database, see [xrdb::Xrdb]
+#[pyclass(name = "Xrdb")]
+pub struct PyXrdb(xrdb::Xrdb);
+
+#[pymethods]
+impl PyXrdb {
+    #[new]
+    fn new() -> Self {
+        PyXrdb(xrdb::Xrdb::new())
+    }
+
+    /// Parse a database in the format of `xrdb -query` or an `~/.Xresources` file
+    #[staticmethod]
+    fn parse(raw: &str) -> Self {
+        PyXrdb(xrdb::Xrdb::parse(raw))
+    }
+
+    /// Read the system xrdb
+    fn read(&mut self) -> PyResult<()> {
+        self.0.read().map_err(xrdb_err)
+    }
+
+    fn query(&self, program: &str, res: &str) -> Option<String> {
+        self.0.query(program, res)
+    }
+
+    fn insert(&mut self, program: &str, res: &str, val: &str) {
+        self.0.insert(program, res, val)
+    }
+
+    fn insert_universal(&mut self, res: &str, val: &str) {
+        self.0.insert_universal(res, val)
+    }
+
+    /// `color0` through `color15` of a program, `None` for colors that are not set
+    fn palette(&self, program: &str) -> Vec<Option<String>> {
+        self.0
+            .palette(program)
+            .iter()
+            .map(|color| color.map(|c| c.to_string()))
+            .collect()
+    }
+}
+
+/// Keytable of the system, see [xmodmap::KeyTable]
+#[pyclass(name = "KeyTable")]
+pub struct PyKeyTable(xmodmap::KeyTable);
+
+#[pymethods]
+impl PyKeyTable {
+    /// Read the keytable of the system
+    #[new]
+    fn new() -> PyResult<Self> {
+        xmodmap::KeyTable::new()
+            .map(PyKeyTable)
+            .map_err(xmodmap_err)
+    }
+
+    /// Parse the output of `xmodmap -pke`
+    #[staticmethod]
+    fn parse(raw: &str) -> PyResult<Self> {
+        xmodmap::KeyTable::parse(raw, xmodmap::DEFAULT_COLUMNS)
+            .map(PyKeyTable)
+            .map_err(xmodmap_err)
+    }
+
+    /// Level and keycode producing a keysym, given by name
+    fn get_key(&self, keysym: &str) -> PyResult<(String, u8)> {
+        let keysym = keysym
+            .parse::<xmodmap::KeySym>()
+            .map_err(|_| xmodmap_err(xmodmap::Error::NonExistentKeySym(keysym.to_owned())))?;
+        let (modifier, keycode) = self.0.get_key(keysym).map_err(xmodmap_err)?;
+        Ok((format!("{:?}", modifier), keycode))
+    }
+
+    /// Name of the keysym bound to a keycode at a level
+    fn get_keysym(&self, modifier: &str, keycode: u8) -> PyResult<String> {
+        let modifier = MODIFIERS
+            .iter()
+            .find(|m| format!("{:?}", m) == modifier)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown modifier {}", modifier)))?;
+        self.0
+            .get_keysym(modifier.clone(), keycode)
+            .map(|keysym| keysym.to_string())
+            .map_err(xmodmap_err)
+    }
+}
+
+#[pymodule]
+fn pino_xutils(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyXrdb>()?;
+    m.add_class::<PyKeyTable>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn python_classes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "pino_xutils").unwrap();
+            pino_xutils(&module).unwrap();
+            let locals = pyo3::types::PyDict::new(py);
+            locals.set_item("pino_xutils", module).unwrap();
+            py.run(
+                c"xrdb = pino_xutils.Xrdb.parse('*.color1: #ea6962')
+assert xrdb.query('dwm', 'color1') == '#ea6962'
+assert xrdb.palette('dwm')[1] == '#ea6962'
+table = pino_xutils.KeyTable.parse('keycode  38 = a A')
+assert table.get_key('A') == ('ShiftKey', 38)
+assert table.get_keysym('Key', 38) == 'a'",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
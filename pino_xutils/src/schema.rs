@@ -0,0 +1,255 @@
+//! JSON Schemas of the serializable types of every enabled crate, enabled by the `schemars`
+//! feature
+//!
+//! ```rust
+//! let schemas = pino_xutils::schema::schemas();
+//! let color = serde_json::to_value(&schemas["Color"]).unwrap();
+//! assert_eq!(color["type"], "string");
+//! ```
+
+use std::collections::BTreeMap;
+
+use schemars::{schema_for, Schema};
+
+/// Schema of each serializable type, by type name
+pub fn schemas() -> BTreeMap<&'static str, Schema> {
+    use pino_xcommon::{
+        backend::Backend,
+        capability::{Capabilities, Version},
+        color::Color,
+        cursor::CursorTheme,
+        display::DisplayHandle,
+        fallback::Source,
+        gamma::Gamma,
+        session::Session,
+        window::{Target, WindowId},
+    };
+
+    #[allow(unused_mut)]
+    let mut schemas = BTreeMap::from([
+        ("Backend", schema_for!(Backend)),
+        ("Capabilities", schema_for!(Capabilities)),
+        ("Color", schema_for!(Color)),
+        ("CursorTheme", schema_for!(CursorTheme)),
+        ("DisplayHandle", schema_for!(DisplayHandle)),
+        ("Gamma", schema_for!(Gamma)),
+        ("Session", schema_for!(Session)),
+        ("Source", schema_for!(Source)),
+        ("Target", schema_for!(Target)),
+        ("Version", schema_for!(Version)),
+        ("WindowId", schema_for!(WindowId)),
+    ]);
+
+    #[cfg(feature = "xrdb")]
+    schemas.extend([
+        ("Diff", schema_for!(pino_xrdb::Diff)),
+        ("ResourcePalette", schema_for!([Option<Color>; 16])),
+        ("Xrdb", schema_for!(pino_xrdb::Xrdb)),
+    ]);
+
+    #[cfg(feature = "xmodmap")]
+    {
+        use pino_xmodmap::{
+            export::Remapper,
+            expr::{Expression, Mismatch},
+            grab::Binding,
+            lint::Lint,
+            media::MediaKey,
+            modmap::ModifierMap,
+            KeySym, KeyTable, ModIndex, ModMask, Modifier,
+        };
+
+        schemas.extend([
+            ("Binding", schema_for!(Binding)),
+            ("Expression", schema_for!(Expression)),
+            ("KeySym", schema_for!(KeySym)),
+            ("KeyTable", schema_for!(KeyTable)),
+            ("Lint", schema_for!(Lint)),
+            ("MediaKey", schema_for!(MediaKey)),
+            ("Mismatch", schema_for!(Mismatch)),
+            ("ModIndex", schema_for!(ModIndex)),
+            ("ModMask", schema_for!(ModMask)),
+            ("Modifier", schema_for!(Modifier)),
+            ("ModifierMap", schema_for!(ModifierMap)),
+            ("Remapper", schema_for!(Remapper)),
+        ]);
+    }
+
+    #[cfg(feature = "xprop")]
+    {
+        use pino_xprop::{
+            focus::Focus,
+            hints::{WmClass, WmHints},
+            spy::Change,
+            Properties, Property,
+        };
+
+        schemas.extend([
+            ("Change", schema_for!(Change)),
+            ("Focus", schema_for!(Focus)),
+            ("Properties", schema_for!(Properties)),
+            ("Property", schema_for!(Property)),
+            ("WmClass", schema_for!(WmClass)),
+            ("WmHints", schema_for!(WmHints)),
+        ]);
+    }
+
+    #[cfg(feature = "xrandr")]
+    {
+        use pino_xrandr::{
+            edid::{Edid, Timing},
+            layout::{Layout, OutputLayout},
+            Geometry, Mode, Output, Rotation, Screen, Size,
+        };
+
+        schemas.extend([
+            ("Edid", schema_for!(Edid)),
+            ("Layout", schema_for!(Layout)),
+            ("OutputGeometry", schema_for!(Geometry)),
+            ("Mode", schema_for!(Mode)),
+            ("Output", schema_for!(Output)),
+            ("OutputLayout", schema_for!(OutputLayout)),
+            ("Rotation", schema_for!(Rotation)),
+            ("Screen", schema_for!(Screen)),
+            ("Size", schema_for!(Size)),
+            ("Timing", schema_for!(Timing)),
+        ]);
+    }
+
+    #[cfg(feature = "xset")]
+    {
+        use pino_xset::{
+            bell::Bell,
+            dpms::{Dpms, PowerLevel},
+            keyboard::{Indicator, Keyboard},
+            lock::Lock,
+            screensaver::ScreenSaver,
+            Settings,
+        };
+
+        schemas.extend([
+            ("Bell", schema_for!(Bell)),
+            ("Dpms", schema_for!(Dpms)),
+            ("Indicator", schema_for!(Indicator)),
+            ("KeyboardSettings", schema_for!(Keyboard)),
+            ("Lock", schema_for!(Lock)),
+            ("PowerLevel", schema_for!(PowerLevel)),
+            ("ScreenSaver", schema_for!(ScreenSaver)),
+            ("ServerSettings", schema_for!(Settings)),
+        ]);
+    }
+
+    #[cfg(feature = "xinput")]
+    {
+        use pino_xinput::{
+            props::{Properties, Property, Setting, Value},
+            Device, DeviceType, Role,
+        };
+
+        schemas.extend([
+            ("Device", schema_for!(Device)),
+            ("DeviceProperties", schema_for!(Properties)),
+            ("DeviceProperty", schema_for!(Property)),
+            ("DevicePropertyValue", schema_for!(Value)),
+            ("DeviceRole", schema_for!(Role)),
+            ("DeviceType", schema_for!(DeviceType)),
+            ("InputSetting", schema_for!(Setting)),
+        ]);
+    }
+
+    #[cfg(feature = "setxkbmap")]
+    schemas.insert("XkbConfig", schema_for!(pino_setxkbmap::XkbConfig));
+
+    #[cfg(feature = "xsetroot")]
+    {
+        schemas.insert("WallpaperMode", schema_for!(pino_xsetroot::wallpaper::Mode));
+        schemas.insert("WallpaperTool", schema_for!(pino_xsetroot::wallpaper::Tool));
+    }
+
+    #[cfg(feature = "xdpyinfo")]
+    {
+        schemas.insert("DisplayInfo", schema_for!(pino_xdpyinfo::DisplayInfo));
+        schemas.insert("ScreenInfo", schema_for!(pino_xdpyinfo::ScreenInfo));
+    }
+
+    #[cfg(feature = "xwininfo")]
+    {
+        use pino_xwininfo::{
+            tree::{Node, Tree},
+            MapState, WindowInfo,
+        };
+
+        schemas.extend([
+            ("MapState", schema_for!(MapState)),
+            ("WindowInfo", schema_for!(WindowInfo)),
+            ("WindowNode", schema_for!(Node)),
+            ("WindowTree", schema_for!(Tree)),
+        ]);
+    }
+
+    #[cfg(feature = "wm")]
+    {
+        schemas.insert("ManagedWindow", schema_for!(pino_wm::Window));
+        schemas.insert("WindowState", schema_for!(pino_wm::action::State));
+        schemas.insert("Desktops", schema_for!(pino_wm::desktop::Desktops));
+        schemas.insert("WorkArea", schema_for!(pino_wm::desktop::WorkArea));
+    }
+
+    #[cfg(feature = "xclip")]
+    {
+        schemas.insert("Selection", schema_for!(pino_xclip::Selection));
+        schemas.insert("SelectionTool", schema_for!(pino_xclip::Tool));
+    }
+
+    #[cfg(feature = "xev")]
+    {
+        schemas.insert("XEvent", schema_for!(pino_xev::Event));
+        schemas.insert("EventMask", schema_for!(pino_xev::EventMask));
+        schemas.insert("KeyEvent", schema_for!(pino_xev::KeyEvent));
+        schemas.insert("ButtonEvent", schema_for!(pino_xev::ButtonEvent));
+        schemas.insert("FocusEvent", schema_for!(pino_xev::FocusEvent));
+        schemas.insert("PropertyEvent", schema_for!(pino_xev::PropertyEvent));
+    }
+
+    #[cfg(feature = "xauth")]
+    {
+        schemas.insert("AuthorityEntry", schema_for!(pino_xauth::Entry));
+        schemas.insert("Cookie", schema_for!(pino_xauth::Cookie));
+    }
+
+    #[cfg(feature = "xhost")]
+    {
+        schemas.insert("AccessControl", schema_for!(pino_xhost::AccessControl));
+        schemas.insert("AccessEntry", schema_for!(pino_xhost::Entry));
+    }
+
+    #[cfg(feature = "xdo")]
+    {
+        schemas.insert("InputAction", schema_for!(pino_xdo::Action));
+    }
+    #[cfg(feature = "xsettings")]
+    {
+        schemas.insert("XSettings", schema_for!(pino_xsettings::Settings));
+        schemas.insert("XSettingsValue", schema_for!(pino_xsettings::Value));
+    }
+    #[cfg(feature = "xserver")]
+    {
+        schemas.insert("XServerKind", schema_for!(pino_xserver::Kind));
+    }
+    #[cfg(feature = "xfonts")]
+    {
+        schemas.insert("Font", schema_for!(pino_xfonts::Font));
+        schemas.insert("FontSpec", schema_for!(pino_xfonts::FontSpec));
+    }
+    #[cfg(feature = "geometry")]
+    schemas.insert("Geometry", schema_for!(pino_xmodmap::geometry::Geometry));
+    #[cfg(feature = "palette")]
+    schemas.insert("Palette", schema_for!(crate::palette::Palette));
+    #[cfg(feature = "xrdb")]
+    schemas.insert(
+        "ToolkitSettings",
+        schema_for!(crate::toolkit::ToolkitSettings),
+    );
+
+    schemas
+}
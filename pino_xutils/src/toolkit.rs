@@ -0,0 +1,470 @@
+//! Carry the theme of the resources over to GTK and Qt applications
+//!
+//! GTK only reads `Xft.*` and `Xcursor.*` through an XSETTINGS manager, and neither toolkit
+//! takes its theme from the resources. [ToolkitSettings] reads those settings along with the
+//! theme, icon theme, font and whether a dark theme is preferred, and writes them to the
+//! configuration files of both toolkits.
+//!
+//! ```rust,no_run
+//! use pino_xutils::{toolkit::ToolkitSettings, xrdb::Xrdb};
+//!
+//! let mut xrdb = Xrdb::new();
+//! xrdb.read().unwrap();
+//! ToolkitSettings::from_xrdb(&xrdb).apply().unwrap();
+//! ```
+//!
+//! The resources are named like those [pino_xsettings] serves, such as `Net.ThemeName`,
+//! `Net.IconThemeName`, `Gtk.FontName` and `Gtk.ApplicationPreferDarkTheme`. Without the latter,
+//! a dark theme is preferred when `*.background` is a dark color.
+//!
+//! [pino_xsettings]: https://docs.rs/pino_xsettings
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use pino_xcommon::resource::parse_bool;
+use pino_xrdb::{Color, Xrdb};
+
+use crate::runner::{Cmd, CommandRunner};
+
+/// Color scheme qt5ct switches to for a dark theme
+pub const QT5CT_DARK_SCHEME: &str = "/usr/share/qt5ct/colors/darker.conf";
+
+/// Settings shared by the toolkits, `None` for those left as they are
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ToolkitSettings {
+    /// Name of the GTK theme, from `Net.ThemeName`
+    pub theme: Option<String>,
+    /// Name of the icon theme, from `Net.IconThemeName`
+    pub icon_theme: Option<String>,
+    /// Font as a family followed by a size, such as `Sans 10`, from `Gtk.FontName`
+    pub font: Option<String>,
+    pub dpi: Option<u32>,
+    pub antialias: Option<bool>,
+    pub hinting: Option<bool>,
+    /// Hinting style, such as `hintslight`
+    pub hint_style: Option<String>,
+    /// Order of the subpixels, such as `rgb`, or `none` for grayscale antialiasing
+    pub rgba: Option<String>,
+    pub cursor_theme: Option<String>,
+    pub cursor_size: Option<u32>,
+    /// Whether applications should use the dark variant of their theme
+    pub prefer_dark: Option<bool>,
+}
+
+impl ToolkitSettings {
+    /// Take the settings from the resources
+    ///
+    /// ```rust
+    /// use pino_xutils::{toolkit::ToolkitSettings, xrdb::Xrdb};
+    ///
+    /// let xrdb = Xrdb::parse("Xft.dpi: 144\nXcursor.theme: Adwaita\n*.background: #282828");
+    /// let settings = ToolkitSettings::from_xrdb(&xrdb);
+    ///
+    /// assert_eq!(settings.dpi, Some(144));
+    /// assert_eq!(settings.cursor_theme.as_deref(), Some("Adwaita"));
+    /// assert_eq!(settings.prefer_dark, Some(true));
+    /// ```
+    pub fn from_xrdb(xrdb: &Xrdb) -> Self {
+        // `Xft.dpi` is read as the `dpi` resource of `Xft`, and `*.Xft.dpi` as a universal one
+        let get = |program: &str, res: &str| {
+            xrdb.query(program, res)
+                .or_else(|| xrdb.query("*", &format!("{}.{}", program, res)))
+        };
+        let bool = |program: &str, res: &str| get(program, res).and_then(|val| parse_bool(&val));
+        let number = |program: &str, res: &str| {
+            get(program, res).and_then(|val| val.parse::<f64>().ok().map(|n| n.round() as u32))
+        };
+        let prefer_dark = bool("Gtk", "ApplicationPreferDarkTheme").or_else(|| {
+            xrdb.query_color("*", "background")
+                .map(|background| luminance(background) < 0.5)
+        });
+        ToolkitSettings {
+            theme: get("Net", "ThemeName"),
+            icon_theme: get("Net", "IconThemeName"),
+            font: get("Gtk", "FontName"),
+            dpi: number("Xft", "dpi"),
+            antialias: bool("Xft", "antialias"),
+            hinting: bool("Xft", "hinting"),
+            hint_style: get("Xft", "hintstyle"),
+            rgba: get("Xft", "rgba"),
+            cursor_theme: get("Xcursor", "theme"),
+            cursor_size: number("Xcursor", "size"),
+            prefer_dark,
+        }
+    }
+
+    /// `settings.ini` of GTK 3 and 4 with the settings, keeping everything else in `existing`
+    ///
+    /// ```rust
+    /// use pino_xutils::toolkit::ToolkitSettings;
+    ///
+    /// let settings = ToolkitSettings {
+    ///     theme: Some(String::from("Adwaita")),
+    ///     prefer_dark: Some(true),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     settings.gtk_settings_ini("[Settings]\ngtk-theme-name=Breeze\ngtk-enable-animations=0\n"),
+    ///     concat!(
+    ///         "[Settings]\n",
+    ///         "gtk-theme-name=Adwaita\n",
+    ///         "gtk-enable-animations=0\n",
+    ///         "gtk-application-prefer-dark-theme=true\n",
+    ///     )
+    /// );
+    /// ```
+    pub fn gtk_settings_ini(&self, existing: &str) -> String {
+        let string = |value: &Option<String>| value.clone();
+        let bool = |value: Option<bool>| value.map(|b| b.to_string());
+        let int = |value: Option<bool>| value.map(|b| u8::from(b).to_string());
+        let entries = [
+            ("gtk-theme-name", string(&self.theme)),
+            ("gtk-icon-theme-name", string(&self.icon_theme)),
+            ("gtk-font-name", string(&self.font)),
+            ("gtk-cursor-theme-name", string(&self.cursor_theme)),
+            (
+                "gtk-cursor-theme-size",
+                self.cursor_size.map(|size| size.to_string()),
+            ),
+            // GTK takes the resolution in 1024ths of a dot per inch
+            ("gtk-xft-dpi", self.dpi.map(|dpi| (dpi * 1024).to_string())),
+            ("gtk-xft-antialias", int(self.antialias)),
+            ("gtk-xft-hinting", int(self.hinting)),
+            ("gtk-xft-hintstyle", string(&self.hint_style)),
+            ("gtk-xft-rgba", string(&self.rgba)),
+            ("gtk-application-prefer-dark-theme", bool(self.prefer_dark)),
+        ];
+        set_ini(existing, "Settings", &entries)
+    }
+
+    /// `qt5ct.conf` with the settings, keeping everything else in `existing`
+    ///
+    /// Qt reads the resolution, font rendering and cursor from the resources itself, so only the
+    /// icon theme, font and palette are written. A dark theme switches to the
+    /// [QT5CT_DARK_SCHEME] palette.
+    pub fn qt5ct_conf(&self, existing: &str) -> String {
+        let mut appearance = vec![("icon_theme", self.icon_theme.clone())];
+        if let Some(dark) = self.prefer_dark {
+            appearance.push(("custom_palette", Some(dark.to_string())));
+            if dark {
+                appearance.push(("color_scheme_path", Some(QT5CT_DARK_SCHEME.to_owned())));
+            }
+        }
+        let conf = set_ini(existing, "Appearance", &appearance);
+        // QFont::toString of a regular weight font
+        let font = self.font.as_deref().and_then(|font| {
+            let (family, size) = font.trim().rsplit_once(' ')?;
+            let size = size.parse::<f64>().ok()?;
+            Some(format!("\"{},{},-1,5,50,0,0,0,0,0\"", family, size))
+        });
+        set_ini(&conf, "Fonts", &[("general", font)])
+    }
+
+    /// `gsettings` commands setting the keys of `org.gnome.desktop.interface`, which GTK 4 and
+    /// GNOME applications read before `settings.ini`
+    pub fn gsettings(&self) -> Vec<Cmd> {
+        let quote = |value: &str| format!("'{}'", value.replace('\'', "\\'"));
+        let antialiasing =
+            self.antialias
+                .map(|antialias| match (antialias, self.rgba.as_deref()) {
+                    (false, _) => "none",
+                    (true, None | Some("none")) => "grayscale",
+                    (true, Some(_)) => "rgba",
+                });
+        let hinting = self
+            .hinting
+            .map(|hinting| match (hinting, self.hint_style.as_deref()) {
+                (false, _) | (true, Some("hintnone")) => "none",
+                (true, Some("hintmedium")) => "medium",
+                (true, Some("hintfull")) => "full",
+                (true, _) => "slight",
+            });
+        let color_scheme = self
+            .prefer_dark
+            .map(|dark| if dark { "prefer-dark" } else { "default" });
+        let keys = [
+            ("gtk-theme", self.theme.as_deref().map(quote)),
+            ("icon-theme", self.icon_theme.as_deref().map(quote)),
+            ("font-name", self.font.as_deref().map(quote)),
+            ("cursor-theme", self.cursor_theme.as_deref().map(quote)),
+            ("cursor-size", self.cursor_size.map(|size| size.to_string())),
+            // relative to the 96 dots per inch toolkits render at without scaling
+            (
+                "text-scaling-factor",
+                self.dpi.map(|dpi| (f64::from(dpi) / 96.0).to_string()),
+            ),
+            ("font-antialiasing", antialiasing.map(quote)),
+            ("font-hinting", hinting.map(quote)),
+            ("color-scheme", color_scheme.map(quote)),
+        ];
+        keys.into_iter()
+            .filter_map(|(key, value)| {
+                Some(Cmd::new("gsettings").args([
+                    String::from("set"),
+                    String::from("org.gnome.desktop.interface"),
+                    key.to_owned(),
+                    value?,
+                ]))
+            })
+            .collect()
+    }
+
+    /// Write the settings for GTK and Qt to the configuration directory of the user, and set them
+    /// with `gsettings` when it is installed
+    #[cfg(feature = "process")]
+    pub fn apply(&self) -> io::Result<()> {
+        let dir = config_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no configuration directory"))?;
+        self.apply_with_runner(&crate::runner::SystemRunner::default(), &dir)
+    }
+
+    /// Write the settings to `gtk-3.0/settings.ini`, `gtk-4.0/settings.ini` and
+    /// `qt5ct/qt5ct.conf` of a configuration directory, running `gsettings` through the given
+    /// runner
+    ///
+    /// Each file is replaced at once, so a toolkit never reads one that is half written. Every
+    /// key is set even if `gsettings` fails for some of them, those are then reported together as
+    /// a [GsettingsError] inside the returned error.
+    pub fn apply_with_runner(&self, runner: &dyn CommandRunner, dir: &Path) -> io::Result<()> {
+        let update = |file: &str, f: &dyn Fn(&str) -> String| -> io::Result<()> {
+            let path = dir.join(file);
+            let existing = match fs::read_to_string(&path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                existing => existing?,
+            };
+            fs::create_dir_all(path.parent().unwrap_or(dir))?;
+            write_atomic(&path, f(&existing).as_bytes())
+        };
+        update("gtk-3.0/settings.ini", &|s| self.gtk_settings_ini(s))?;
+        update("gtk-4.0/settings.ini", &|s| self.gtk_settings_ini(s))?;
+        update("qt5ct/qt5ct.conf", &|s| self.qt5ct_conf(s))?;
+
+        let mut failed = vec![];
+        for cmd in self.gsettings() {
+            let key = cmd.args[2].clone();
+            match runner.output(&cmd) {
+                // gsettings is only there along with GNOME libraries
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => failed.push((key, e.to_string())),
+                Ok(output) if !output.success() => failed.push((
+                    key,
+                    format!(
+                        "exited with status {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
+                )),
+                Ok(_) => {},
+            }
+        }
+        if !failed.is_empty() {
+            return Err(io::Error::other(GsettingsError { failed }));
+        }
+        Ok(())
+    }
+}
+
+/// Keys `gsettings` could not set, along with the reason for each
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GsettingsError {
+    pub failed: Vec<(String, String)>,
+}
+
+impl std::error::Error for GsettingsError {}
+
+impl fmt::Display for GsettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self
+            .failed
+            .iter()
+            .map(|(key, reason)| format!("{} ({})", key, reason))
+            .collect::<Vec<_>>();
+        write!(f, "gsettings could not set {}", failed.join(", "))
+    }
+}
+
+/// Replace a file by writing a temporary file next to it and renaming it over the original
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = path.with_file_name(name);
+    fs::write(&tmp, contents)
+        .and_then(|_| fs::rename(&tmp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+}
+
+/// Configuration directory of the user, `$XDG_CONFIG_HOME` or else `~/.config`
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Set the keys of a section of an INI file, adding the section if it is missing
+///
+/// Keys whose value is `None` are left as they are.
+fn set_ini(existing: &str, section: &str, entries: &[(&str, Option<String>)]) -> String {
+    if entries.iter().all(|(_, value)| value.is_none()) {
+        return existing.to_owned();
+    }
+    let header = format!("[{}]", section);
+    let mut lines = existing.lines().map(str::to_owned).collect::<Vec<_>>();
+    let start = match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => start + 1,
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.len()
+        },
+    };
+    let mut end = lines[start..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| start + i);
+    // new keys go after the last line of the section that is not blank
+    while end > start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    for (key, value) in entries {
+        let Some(value) = value else {
+            continue;
+        };
+        let line = format!("{}={}", key, value);
+        let existing = lines[start..end].iter().position(|line| {
+            line.split_once('=')
+                .is_some_and(|(name, _)| name.trim() == *key)
+        });
+        match existing {
+            Some(i) => lines[start + i] = line,
+            None => {
+                lines.insert(end, line);
+                end += 1;
+            },
+        }
+    }
+    let mut ini = lines.join("\n");
+    ini.push('\n');
+    ini
+}
+
+/// Luminance of a color, from 0 for black to 1 for white
+///
+/// The sRGB components are weighted without linearizing them first, which is close enough to
+/// tell light colors from dark ones and keeps mid grey near 0.5.
+pub(crate) fn luminance(color: Color) -> f64 {
+    let [r, g, b] = color.to_f32().map(f64::from);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{MockRunner, Output};
+
+    #[test]
+    fn write_toolkit_settings() {
+        let xrdb = Xrdb::parse(concat!(
+            "Xft.dpi: 120\n",
+            "Xft.antialias: true\n",
+            "Xft.rgba: rgb\n",
+            "Xcursor.size: 30\n",
+            "Net.IconThemeName: Papirus\n",
+            "Gtk.FontName: Noto Sans 11\n",
+            "*.background: #fbf1c7\n",
+        ));
+        let settings = ToolkitSettings::from_xrdb(&xrdb);
+        assert_eq!(settings.prefer_dark, Some(false));
+
+        let dir = env::temp_dir().join(format!("pino_xutils_toolkit_{}", std::process::id()));
+        fs::create_dir_all(dir.join("qt5ct")).unwrap();
+        fs::write(
+            dir.join("qt5ct/qt5ct.conf"),
+            "[Appearance]\nstyle=Fusion\n\n[Interface]\ndouble_click_interval=400\n",
+        )
+        .unwrap();
+        let gsettings = |key: &str, value: &str| {
+            Cmd::new("gsettings").args(["set", "org.gnome.desktop.interface", key, value])
+        };
+        let runner = [
+            ("icon-theme", "'Papirus'"),
+            ("font-name", "'Noto Sans 11'"),
+            ("cursor-size", "30"),
+            ("text-scaling-factor", "1.25"),
+            ("font-antialiasing", "'rgba'"),
+            ("color-scheme", "'default'"),
+        ]
+        .into_iter()
+        .fold(MockRunner::new(), |runner, (key, value)| {
+            runner.respond(gsettings(key, value), Output::default())
+        });
+        settings.apply_with_runner(&runner, &dir).unwrap();
+        assert_eq!(runner.calls().len(), 6);
+
+        let gtk = fs::read_to_string(dir.join("gtk-4.0/settings.ini")).unwrap();
+        assert!(gtk.starts_with("[Settings]\ngtk-icon-theme-name=Papirus\n"));
+        assert!(gtk.contains("gtk-xft-dpi=122880\n"));
+        assert!(gtk.contains("gtk-xft-antialias=1\n"));
+        assert_eq!(
+            fs::read_to_string(dir.join("qt5ct/qt5ct.conf")).unwrap(),
+            concat!(
+                "[Appearance]\n",
+                "style=Fusion\n",
+                "icon_theme=Papirus\n",
+                "custom_palette=false\n",
+                "\n",
+                "[Interface]\n",
+                "double_click_interval=400\n",
+                "\n",
+                "[Fonts]\n",
+                "general=\"Noto Sans,11,-1,5,50,0,0,0,0,0\"\n",
+            )
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn report_gsettings_keys() {
+        let settings = ToolkitSettings {
+            theme: Some(String::from("Adwaita")),
+            cursor_size: Some(24),
+            ..Default::default()
+        };
+        let dir = env::temp_dir().join(format!("pino_xutils_gsettings_{}", std::process::id()));
+        let gsettings = |key: &str, value: &str| {
+            Cmd::new("gsettings").args(["set", "org.gnome.desktop.interface", key, value])
+        };
+        let runner = MockRunner::new()
+            .respond(
+                gsettings("gtk-theme", "'Adwaita'"),
+                Output::from_failure(1, "No such schema"),
+            )
+            .respond(gsettings("cursor-size", "24"), Output::default());
+        let err = settings.apply_with_runner(&runner, &dir).unwrap_err();
+        // the failing key does not keep the next one from being set
+        assert_eq!(runner.calls().len(), 2);
+        let err = err
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<GsettingsError>()
+            .unwrap();
+        assert_eq!(err.failed.len(), 1);
+        assert_eq!(err.failed[0].0, "gtk-theme");
+
+        assert!(fs::read_to_string(dir.join("gtk-3.0/settings.ini"))
+            .unwrap()
+            .contains("gtk-theme-name=Adwaita\n"));
+        let leftovers = fs::read_dir(dir.join("gtk-3.0")).unwrap().count();
+        assert_eq!(leftovers, 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+//! Notify about changes to the resources, keymap and keyboard layout of the X server
+//!
+//! A [Watcher] polls the enabled sources from a background thread and sends typed [Event]s over a
+//! single channel, so a window manager or bar only has to register once. On Linux it can instead
+//! be driven by an existing event loop through [Watcher::into_source].
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use pino_xutils::watch::{Event, Watcher};
+//!
+//! let handle = Watcher::new().interval(Duration::from_secs(2)).spawn();
+//! for event in handle.events() {
+//!     match event {
+//!         Event::ResourceChanged(diffs) => println!("{} resources changed", diffs.len()),
+//!         Event::LayoutChanged(layout) => println!("layout is now {}", layout),
+//!         _ => {},
+//!     }
+//! }
+//! ```
+
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(feature = "xmodmap")]
+use crate::runner::{Cmd, CommandRunner};
+use crate::{Error, XContext};
+
+mod source;
+pub use source::WatchSource;
+#[cfg(feature = "tokio")]
+mod stream;
+#[cfg(feature = "tokio")]
+pub use stream::WatchStream;
+
+/// Time between two polls, unless configured otherwise
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Change observed by a [Watcher]
+#[non_exhaustive]
+pub enum Event {
+    /// Resources were added, removed or changed
+    #[cfg(feature = "xrdb")]
+    ResourceChanged(Vec<pino_xrdb::Diff>),
+    /// Keysyms bound to a keycode changed, holds the new keytable
+    #[cfg(feature = "xmodmap")]
+    KeymapChanged(pino_xmodmap::KeyTable),
+    /// XKB layout changed, holds the new layout such as `us,de`
+    #[cfg(feature = "xmodmap")]
+    LayoutChanged(String),
+    /// A source could not be read, reported once until it can be read again
+    Error(Error),
+    /// Changes may be missed in this session, reported by the first poll
+    Warning(String),
+}
+
+/// Last state read from a source, and whether reading it failed since
+struct Tracked<T> {
+    last: Option<T>,
+    failing: bool,
+}
+
+impl<T> Default for Tracked<T> {
+    fn default() -> Self {
+        Tracked {
+            last: None,
+            failing: false,
+        }
+    }
+}
+
+impl<T> Tracked<T> {
+    /// Record a new reading, returning the previous state if it changed
+    fn update(
+        &mut self,
+        result: Result<T, Error>,
+        changed: impl Fn(&T, &T) -> bool,
+        events: &mut Vec<Event>,
+    ) -> Option<(T, &T)> {
+        let new = match result {
+            Ok(new) => new,
+            Err(e) => {
+                if !self.failing {
+                    self.failing = true;
+                    events.push(Event::Error(e));
+                }
+                return None;
+            },
+        };
+        self.failing = false;
+        match self.last.replace(new) {
+            Some(old) if changed(&old, self.last.as_ref().unwrap()) => {
+                Some((old, self.last.as_ref().unwrap()))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Polls the X server for changes, see the [module](self) documentation
+pub struct Watcher {
+    ctx: XContext,
+    interval: Duration,
+    #[cfg(feature = "xrdb")]
+    xrdb: Tracked<pino_xrdb::Xrdb>,
+    #[cfg(feature = "xmodmap")]
+    keytable: Tracked<pino_xmodmap::KeyTable>,
+    #[cfg(feature = "xmodmap")]
+    layout: Tracked<String>,
+    warning: Option<String>,
+}
+
+impl Watcher {
+    /// Construct a watcher reading from the system
+    #[cfg(feature = "process")]
+    pub fn new() -> Self {
+        Watcher::with_context(XContext::new())
+    }
+
+    /// Construct a watcher reading through a context, using its runner, display and retry policy
+    pub fn with_context(ctx: XContext) -> Self {
+        Watcher {
+            ctx,
+            interval: DEFAULT_INTERVAL,
+            #[cfg(feature = "xrdb")]
+            xrdb: Tracked::default(),
+            #[cfg(feature = "xmodmap")]
+            keytable: Tracked::default(),
+            #[cfg(feature = "xmodmap")]
+            layout: Tracked::default(),
+            warning: Watcher::session_warning(pino_xcommon::session::Session::detect()),
+        }
+    }
+
+    /// Warning about what can not be watched in `session`
+    #[cfg_attr(not(feature = "xmodmap"), allow(unused_variables))]
+    fn session_warning(session: pino_xcommon::session::Session) -> Option<String> {
+        #[cfg(feature = "xmodmap")]
+        if session == pino_xcommon::session::Session::XWayland {
+            return Some(String::from(
+                "the Wayland compositor owns the keymap under XWayland, keymap and layout \
+                 changes are only reported once XWayland picks them up",
+            ));
+        }
+        None
+    }
+
+    /// Time between two polls
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Read every source once, returning what changed since the previous poll
+    ///
+    /// The first poll only records the current state, so it reports nothing but errors.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        self.ctx.refresh();
+        if let Some(warning) = self.warning.take() {
+            events.push(Event::Warning(warning));
+        }
+
+        #[cfg(feature = "xrdb")]
+        {
+            let xrdb = self.ctx.xrdb().cloned();
+            let changed = |old: &pino_xrdb::Xrdb, new: &pino_xrdb::Xrdb| old != new;
+            if let Some((old, new)) = self.xrdb.update(xrdb, changed, &mut events) {
+                events.push(Event::ResourceChanged(old.diff(new)));
+            }
+        }
+
+        #[cfg(feature = "xmodmap")]
+        {
+            let keytable = self.ctx.keytable().cloned();
+            let changed = |old: &pino_xmodmap::KeyTable, new: &pino_xmodmap::KeyTable| {
+                !old.iter_keys().eq(new.iter_keys())
+            };
+            if let Some((_, new)) = self.keytable.update(keytable, changed, &mut events) {
+                events.push(Event::KeymapChanged(new.clone()));
+            }
+
+            let layout = self.read_layout();
+            if let Some((_, new)) = self.layout.update(layout, String::ne, &mut events) {
+                events.push(Event::LayoutChanged(new.clone()));
+            }
+        }
+
+        events
+    }
+
+    /// Poll from a background thread, sending every event to the channel of the returned handle
+    ///
+    /// The thread runs until the handle is stopped or dropped.
+    pub fn spawn(self) -> WatchHandle {
+        let (tx, rx) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || {
+            self.run(stopped, |events| {
+                events.into_iter().all(|e| tx.send(e).is_ok())
+            });
+        });
+        WatchHandle {
+            events: rx,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Poll every interval until `stopped` disconnects or `send` fails, returning the watcher
+    ///
+    /// The events of a poll are sent together, polls without any are skipped.
+    fn run(mut self, stopped: Receiver<()>, mut send: impl FnMut(Vec<Event>) -> bool) -> Self {
+        loop {
+            let events = self.poll();
+            if !events.is_empty() && !send(events) {
+                return self;
+            }
+            match stopped.recv_timeout(self.interval) {
+                Err(RecvTimeoutError::Timeout) => {},
+                _ => return self,
+            }
+        }
+    }
+
+    /// Layout reported by `setxkbmap -query`
+    #[cfg(feature = "xmodmap")]
+    fn read_layout(&self) -> Result<String, Error> {
+        let runner = self.ctx.retry.runner(self.ctx.runner.as_ref());
+        let runner = self.ctx.display.runner(&runner);
+        let output = runner
+            .output(&Cmd::new("setxkbmap").arg("-query"))
+            .map_err(|source| pino_xmodmap::Error::Spawn {
+                program: "setxkbmap",
+                source,
+            })?;
+        if !output.success() {
+            return Err(pino_xmodmap::Error::Exited {
+                program: "setxkbmap",
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }
+            .into());
+        }
+        let layout = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|l| l.strip_prefix("layout:"))
+            .map(|layout| layout.trim().to_owned());
+        layout.ok_or_else(|| {
+            pino_xmodmap::Error::InvalidFormat {
+                line: None,
+                content: String::from("setxkbmap -query did not print a layout"),
+            }
+            .into()
+        })
+    }
+}
+
+/// Running [Watcher], stopped when dropped
+pub struct WatchHandle {
+    events: Receiver<Event>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// Events sent by the watcher, the channel disconnects once it stops
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+
+    /// Stop the background thread and wait for it to exit
+    ///
+    /// A poll that is in progress is finished first, which takes at most the timeout of the
+    /// commands it runs.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    /// Check if the background thread is still running
+    pub fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    fn shutdown(&mut self) {
+        // dropping the sender wakes the thread up from its wait
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for Watcher {
+    fn default() -> Self {
+        Watcher::new()
+    }
+}
+
+#[cfg(all(test, feature = "xrdb", feature = "xmodmap"))]
+mod tests {
+    use super::*;
+    use crate::runner::{MockRunner, Output};
+
+    #[test]
+    fn poll_changes() {
+        let mut watcher = Watcher::with_context(XContext::with_runner(
+            MockRunner::new()
+                .respond(
+                    Cmd::new("xrdb").arg("-query"),
+                    Output::from_stdout("*.color1: #ea6962"),
+                )
+                .respond(
+                    Cmd::new("xmodmap").arg("-pke"),
+                    Output::from_stdout("keycode   9 = Escape"),
+                )
+                .respond(
+                    Cmd::new("setxkbmap").arg("-query"),
+                    Output::from_stdout("rules:      evdev\nlayout:     us,de\n"),
+                ),
+        ));
+
+        assert!(watcher.poll().is_empty());
+        assert_eq!(watcher.layout.last.as_deref(), Some("us,de"));
+        // nothing changed in between
+        assert!(watcher.poll().is_empty());
+    }
+
+    #[test]
+    fn track_changes() {
+        let mut events = vec![];
+        let mut layout = Tracked::default();
+        assert!(layout
+            .update(Ok(String::from("us")), String::ne, &mut events)
+            .is_none());
+        let (old, new) = layout
+            .update(Ok(String::from("de")), String::ne, &mut events)
+            .unwrap();
+        assert_eq!((old.as_str(), new.as_str()), ("us", "de"));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn poll_errors_once() {
+        let mut watcher = Watcher::with_context(XContext::with_runner(MockRunner::new()));
+        let events = watcher.poll();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| matches!(e, Event::Error(_))));
+        assert!(watcher.poll().is_empty());
+    }
+
+    #[test]
+    fn warn_xwayland() {
+        let mut watcher = Watcher::with_context(XContext::with_runner(MockRunner::new()));
+        watcher.warning = Watcher::session_warning(pino_xcommon::session::Session::XWayland);
+        assert!(matches!(watcher.poll()[0], Event::Warning(_)));
+        assert!(watcher.poll().is_empty());
+    }
+
+    #[test]
+    fn stop_handle() {
+        let handle = Watcher::with_context(XContext::with_runner(MockRunner::new()))
+            .interval(Duration::from_secs(3600))
+            .spawn();
+        assert!(matches!(handle.events().recv(), Ok(Event::Error(_))));
+        assert!(handle.is_running());
+        // returns right away instead of waiting out the interval
+        handle.stop();
+    }
+}
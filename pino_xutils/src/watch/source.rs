@@ -0,0 +1,293 @@
+//! Watcher driven by an event loop instead of a channel
+//!
+//! The commands of a poll can take up to their timeout, so they still run on a worker thread,
+//! which signals a Linux eventfd once events are ready. The event loop only collects them and is
+//! never blocked. Elsewhere [Watcher::into_source] fails with
+//! [Unsupported](pino_xcommon::platform::Unsupported).
+
+use std::io;
+#[cfg(target_os = "linux")]
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Write},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+#[cfg(target_os = "linux")]
+use super::Event;
+use super::Watcher;
+
+/// [Watcher] polling on a worker thread, whose events are collected once a file descriptor becomes
+/// readable
+///
+/// Register the descriptor with an event loop and call [WatchSource::dispatch] once it is
+/// readable. With the `calloop` or `mio` feature it can be registered with those directly.
+/// Watching stops once the source is removed from the loop and dropped, the worker exits after
+/// the poll it is running.
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "process")] {
+/// use std::os::fd::AsRawFd;
+///
+/// use pino_xutils::watch::Watcher;
+///
+/// let mut source = Watcher::new().into_source().unwrap();
+/// let fd = source.as_raw_fd();
+/// // once `fd` is readable
+/// for event in source.dispatch().unwrap() {
+///     // ...
+/// }
+/// # }
+/// ```
+pub struct WatchSource {
+    #[cfg(target_os = "linux")]
+    ready: Arc<File>,
+    #[cfg(target_os = "linux")]
+    events: Receiver<Vec<Event>>,
+    #[cfg(target_os = "linux")]
+    stop: Sender<()>,
+    #[cfg(target_os = "linux")]
+    worker: JoinHandle<Watcher>,
+    #[cfg(not(target_os = "linux"))]
+    #[allow(dead_code)]
+    watcher: Watcher,
+    #[cfg(all(target_os = "linux", feature = "calloop"))]
+    token: Option<calloop::Token>,
+}
+
+impl Watcher {
+    /// Turn the watcher into a source for an event loop, polling every interval
+    ///
+    /// The first poll starts right away.
+    #[cfg(target_os = "linux")]
+    pub fn into_source(self) -> io::Result<WatchSource> {
+        // SAFETY: eventfd has no preconditions and the returned descriptor is owned here
+        let ready = unsafe {
+            let fd = libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Arc::new(File::from(OwnedFd::from_raw_fd(fd)))
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let worker = thread::spawn({
+            let ready = ready.clone();
+            move || {
+                self.run(stopped, |events| {
+                    let sent = tx.send(events).is_ok();
+                    // adds to the counter of the eventfd, making it readable
+                    let _ = (&*ready).write(&1u64.to_ne_bytes());
+                    sent
+                })
+            }
+        });
+
+        Ok(WatchSource {
+            ready,
+            events: rx,
+            stop,
+            worker,
+            #[cfg(feature = "calloop")]
+            token: None,
+        })
+    }
+
+    /// Turn the watcher into a source for an event loop, Linux only
+    #[cfg(not(target_os = "linux"))]
+    pub fn into_source(self) -> io::Result<WatchSource> {
+        use pino_xcommon::platform::{Facility, Unsupported};
+
+        Err(Unsupported(Facility::EventSource).into())
+    }
+}
+
+impl WatchSource {
+    /// Collect the events of the polls that finished since the last dispatch
+    ///
+    /// Returns no events without blocking if there are none.
+    #[cfg(target_os = "linux")]
+    pub fn dispatch(&mut self) -> io::Result<Vec<Event>> {
+        // reading resets the counter, so the descriptor is no longer readable
+        let mut counter = [0; 8];
+        match (&*self.ready).read(&mut counter) {
+            Ok(_) => {},
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(vec![]),
+            Err(e) => return Err(e),
+        }
+        Ok(self.events.try_iter().flatten().collect())
+    }
+
+    /// Stop watching and return the watcher, once the poll in progress finished
+    #[cfg(target_os = "linux")]
+    pub fn into_inner(self) -> Watcher {
+        // dropping the sender wakes the worker up from its wait
+        drop(self.stop);
+        self.worker
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    }
+
+    /// Stop watching and return the watcher
+    #[cfg(not(target_os = "linux"))]
+    pub fn into_inner(self) -> Watcher {
+        self.watcher
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl AsFd for WatchSource {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.ready.as_fd()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl AsRawFd for WatchSource {
+    fn as_raw_fd(&self) -> RawFd {
+        self.ready.as_raw_fd()
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "calloop"))]
+impl calloop::EventSource for WatchSource {
+    type Event = Event;
+    type Metadata = ();
+    type Ret = ();
+    type Error = io::Error;
+
+    fn process_events<F>(
+        &mut self,
+        _: calloop::Readiness,
+        token: calloop::Token,
+        mut callback: F,
+    ) -> Result<calloop::PostAction, Self::Error>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        if self.token == Some(token) {
+            for event in self.dispatch()? {
+                callback(event, &mut ());
+            }
+        }
+        Ok(calloop::PostAction::Continue)
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut calloop::Poll,
+        token_factory: &mut calloop::TokenFactory,
+    ) -> calloop::Result<()> {
+        let token = token_factory.token();
+        // SAFETY: the eventfd is unregistered before it is dropped, see `unregister`
+        unsafe {
+            poll.register(
+                &*self.ready,
+                calloop::Interest::READ,
+                calloop::Mode::Level,
+                token,
+            )?;
+        }
+        self.token = Some(token);
+        Ok(())
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut calloop::Poll,
+        token_factory: &mut calloop::TokenFactory,
+    ) -> calloop::Result<()> {
+        let token = token_factory.token();
+        poll.reregister(
+            &*self.ready,
+            calloop::Interest::READ,
+            calloop::Mode::Level,
+            token,
+        )?;
+        self.token = Some(token);
+        Ok(())
+    }
+
+    fn unregister(&mut self, poll: &mut calloop::Poll) -> calloop::Result<()> {
+        self.token = None;
+        poll.unregister(&*self.ready)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "mio"))]
+impl mio::event::Source for WatchSource {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
+
+#[cfg(all(test, target_os = "linux", feature = "xrdb", feature = "xmodmap"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{runner::MockRunner, XContext};
+
+    #[test]
+    fn dispatch_worker() {
+        let mut source = Watcher::with_context(XContext::with_runner(MockRunner::new()))
+            .interval(Duration::from_secs(3600))
+            .into_source()
+            .unwrap();
+        // the first poll runs on the worker, the descriptor is readable once it finished
+        let mut fd = libc::pollfd {
+            fd: source.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single valid pollfd that outlives the call
+        assert_eq!(unsafe { libc::poll(&mut fd, 1, 1000) }, 1);
+        assert_eq!(source.dispatch().unwrap().len(), 3);
+        // the worker does not poll again for an hour
+        assert!(source.dispatch().unwrap().is_empty());
+        source.into_inner();
+    }
+
+    #[cfg(feature = "calloop")]
+    #[test]
+    fn calloop_source() {
+        let source = Watcher::with_context(XContext::with_runner(MockRunner::new()))
+            .into_source()
+            .unwrap();
+        let mut event_loop = calloop::EventLoop::<usize>::try_new().unwrap();
+        event_loop
+            .handle()
+            .insert_source(source, |_, _, count| *count += 1)
+            .unwrap();
+
+        let mut count = 0;
+        event_loop
+            .dispatch(Duration::from_secs(1), &mut count)
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+}
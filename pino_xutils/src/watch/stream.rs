@@ -0,0 +1,88 @@
+//! Watcher yielding its events as a [Stream], enabled by the `tokio` feature
+//!
+//! The commands of a poll block, so they run on a background thread like [Watcher::spawn] and
+//! only the events are handed to the executor.
+
+use std::{
+    pin::Pin,
+    sync::mpsc::{self, Sender},
+    task::{Context, Poll},
+    thread,
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use super::{Event, Watcher};
+
+/// [Watcher] polling on a background thread, whose events are yielded as a [Stream]
+///
+/// The stream ends if the thread exits. Dropping the stream stops the thread once the poll it is
+/// running finished, without waiting for it.
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "process")]
+/// # async fn watch() {
+/// use std::{future::poll_fn, pin::Pin};
+///
+/// use futures_core::Stream;
+/// use pino_xutils::watch::{Event, Watcher};
+///
+/// let mut stream = Watcher::new().into_stream();
+/// while let Some(event) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+///     if let Event::LayoutChanged(layout) = event {
+///         println!("layout is now {}", layout);
+///     }
+/// }
+/// # }
+/// ```
+pub struct WatchStream {
+    events: UnboundedReceiver<Event>,
+    // dropped with the stream, which stops the thread
+    _stop: Sender<()>,
+}
+
+impl Watcher {
+    /// Poll from a background thread, yielding every event from the returned stream
+    pub fn into_stream(self) -> WatchStream {
+        let (tx, rx) = unbounded_channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            self.run(stopped, |events| {
+                events.into_iter().all(|e| tx.send(e).is_ok())
+            });
+        });
+        WatchStream {
+            events: rx,
+            _stop: stop,
+        }
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+#[cfg(all(test, feature = "xrdb", feature = "xmodmap"))]
+mod tests {
+    use std::{future::poll_fn, time::Duration};
+
+    use super::*;
+    use crate::{runner::MockRunner, XContext};
+
+    #[tokio::test]
+    async fn stream_events() {
+        let mut stream = Watcher::with_context(XContext::with_runner(MockRunner::new()))
+            .interval(Duration::from_secs(3600))
+            .into_stream();
+        // every source fails once on the first poll
+        for _ in 0..3 {
+            let event = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+            assert!(matches!(event, Some(Event::Error(_))));
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Query the geometry, attributes and tree of windows through `xwininfo`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xwininfo::{Target, WindowInfo};
+//!
+//! let info = WindowInfo::query(&Target::Select).unwrap();
+//! println!("{}x{}+{}+{}", info.width, info.height, info.x, info.y);
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use pino_xcommon::runner::{run_checked, Cmd, CommandRunner, RunError};
+pub use pino_xcommon::{
+    display::DisplayHandle,
+    platform, runner,
+    session::Session,
+    window::{Target, WindowId},
+};
+
+pub mod tree;
+
+/// Errors of running xwininfo
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// xwininfo could not be run, failed or timed out
+    Run(RunError),
+    /// The session has no X server, such as Wayland without XWayland
+    NoXServer(Session),
+    /// Output of xwininfo could not be parsed, holding the offending line
+    Malformed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Run(e) => write!(f, "{}", e),
+            Error::NoXServer(session) => write!(f, "no X server in a {} session", session),
+            Error::Malformed(line) => write!(f, "could not parse xwininfo output: {}", line),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(value: RunError) -> Self {
+        Error::Run(value)
+    }
+}
+
+/// Whether a window is shown
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum MapState {
+    /// Window is not mapped, such as a minimized one
+    #[default]
+    Unmapped,
+    /// Window is mapped but one of its ancestors is not
+    Unviewable,
+    /// Window is mapped along with all of its ancestors
+    Viewable,
+}
+
+impl FromStr for MapState {
+    type Err = ();
+
+    /// From the names xwininfo prints, such as `IsViewable`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "IsUnMapped" => Ok(MapState::Unmapped),
+            "IsUnviewable" => Ok(MapState::Unviewable),
+            "IsViewable" => Ok(MapState::Viewable),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Geometry and attributes of a window, as printed by `xwininfo`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WindowInfo {
+    /// Id of the window
+    pub id: WindowId,
+    /// Name of the window, `None` if it has none
+    pub name: Option<String>,
+    /// Horizontal position of the window on the screen, inside its border
+    pub x: i32,
+    /// Vertical position of the window on the screen, inside its border
+    pub y: i32,
+    /// Horizontal position relative to the parent, which is the frame of reparenting window
+    /// managers
+    pub relative_x: i32,
+    /// Vertical position relative to the parent
+    pub relative_y: i32,
+    /// Width without the border
+    pub width: u32,
+    /// Height without the border
+    pub height: u32,
+    /// Width of the border the X server draws around the window
+    pub border_width: u32,
+    /// Bits per pixel
+    pub depth: u8,
+    /// Whether the window is shown
+    pub map_state: MapState,
+    /// Whether the window manager was told to leave the window alone, as menus and tooltips do
+    pub override_redirect: bool,
+}
+
+impl WindowInfo {
+    /// Query the geometry and attributes of the target window
+    ///
+    /// [Target::Select] waits for the user to click a window.
+    #[cfg(feature = "process")]
+    pub fn query(target: &Target) -> Result<Self, Error> {
+        let display = DisplayHandle::default();
+        pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+        Self::query_with_runner(&display.runner(&runner::SystemRunner::default()), target)
+    }
+
+    /// Query the target window, running `xwininfo` through the given runner
+    ///
+    /// ```rust
+    /// use pino_xwininfo::{runner::{Cmd, MockRunner, Output}, Target, WindowInfo};
+    ///
+    /// let runner = MockRunner::new().respond(
+    ///     Cmd::new("xwininfo").arg("-root"),
+    ///     Output::from_stdout(concat!(
+    ///         "xwininfo: Window id: 0x3b2 (the root window) (has no name)\n",
+    ///         "  Width: 1920\n",
+    ///         "  Height: 1080\n",
+    ///     )),
+    /// );
+    /// let info = WindowInfo::query_with_runner(&runner, &Target::Root).unwrap();
+    /// assert_eq!((info.width, info.height), (1920, 1080));
+    /// ```
+    pub fn query_with_runner(runner: &dyn CommandRunner, target: &Target) -> Result<Self, Error> {
+        let output = run_checked(runner, &xwininfo(target))?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse the output of `xwininfo`
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut info = WindowInfo::default();
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            if let Some(window) = line.strip_prefix("xwininfo: Window id:") {
+                (info.id, info.name) = parse_window(line, window)?;
+                continue;
+            }
+            let Some((key, value)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key {
+                "Absolute upper-left X" => info.x = parse_value(line, value)?,
+                "Absolute upper-left Y" => info.y = parse_value(line, value)?,
+                "Relative upper-left X" => info.relative_x = parse_value(line, value)?,
+                "Relative upper-left Y" => info.relative_y = parse_value(line, value)?,
+                "Width" => info.width = parse_value(line, value)?,
+                "Height" => info.height = parse_value(line, value)?,
+                "Border width" => info.border_width = parse_value(line, value)?,
+                "Depth" => info.depth = parse_value(line, value)?,
+                "Map State" => info.map_state = parse_value(line, value)?,
+                "Override Redirect State" => info.override_redirect = value == "yes",
+                _ => {},
+            }
+        }
+        Ok(info)
+    }
+
+    /// Width including the border on both sides
+    pub fn outer_width(&self) -> u32 {
+        self.width + 2 * self.border_width
+    }
+
+    /// Height including the border on both sides
+    pub fn outer_height(&self) -> u32 {
+        self.height + 2 * self.border_width
+    }
+}
+
+/// Parse the id and name of a window, such as `0x2a00006 "~ - st"` or
+/// `0x3b2 (the root window) (has no name)`
+pub(crate) fn parse_window(line: &str, window: &str) -> Result<(WindowId, Option<String>), Error> {
+    let window = window.trim_start();
+    let (id, name) = window.split_once(' ').unwrap_or((window, ""));
+    let id = parse_value(line, id)?;
+    let name = name.trim().trim_start_matches("(the root window)").trim();
+    let name = name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .map(str::to_owned);
+    Ok((id, name))
+}
+
+/// Parse a value, failing with the line it is part of
+pub(crate) fn parse_value<T: FromStr>(line: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::Malformed(line.to_owned()))
+}
+
+/// Command running xwininfo on the target window
+pub(crate) fn xwininfo(target: &Target) -> Cmd {
+    let cmd = Cmd::new("xwininfo").args(target.args());
+    match target.timeout() {
+        Some(timeout) => cmd.timeout(timeout),
+        None => cmd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_xwininfo() {
+        let info = WindowInfo::parse(concat!(
+            "\n",
+            "xwininfo: Window id: 0x2a00006 \"~ - st\"\n",
+            "\n",
+            "  Absolute upper-left X:  100\n",
+            "  Absolute upper-left Y:  50\n",
+            "  Relative upper-left X:  -2\n",
+            "  Relative upper-left Y:  0\n",
+            "  Width: 800\n",
+            "  Height: 600\n",
+            "  Depth: 24\n",
+            "  Visual: 0x21\n",
+            "  Visual Class: TrueColor\n",
+            "  Border width: 2\n",
+            "  Class: InputOutput\n",
+            "  Colormap: 0x20 (installed)\n",
+            "  Map State: IsViewable\n",
+            "  Override Redirect State: no\n",
+            "  Corners:  +100+50  -1020+50  -1020-430  +100-430\n",
+            "  -geometry 800x600+98+50\n",
+        ))
+        .unwrap();
+
+        assert_eq!(info.id, WindowId(0x2a00006));
+        assert_eq!(info.name.as_deref(), Some("~ - st"));
+        assert_eq!((info.x, info.y, info.relative_x), (100, 50, -2));
+        assert_eq!((info.outer_width(), info.outer_height()), (804, 604));
+        assert_eq!(info.map_state, MapState::Viewable);
+        assert!(!info.override_redirect);
+
+        assert!(WindowInfo::parse("  Map State: IsIconic\n").is_err());
+    }
+}
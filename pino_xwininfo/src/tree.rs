@@ -0,0 +1,259 @@
+//! Walk the tree of windows through `xwininfo -tree` and `xwininfo -children`
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "process")] {
+//! use pino_xwininfo::{tree, Target};
+//!
+//! // every window with a class, which are the ones of applications
+//! for node in tree::tree(&Target::Root).unwrap().walk() {
+//!     if let Some(class) = &node.class {
+//!         println!("{} {} {}x{}", node.id, class, node.width, node.height);
+//!     }
+//! }
+//! # }
+//! ```
+
+use pino_xcommon::runner::{run_checked, CommandRunner};
+
+use crate::{parse_value, parse_window, xwininfo, Error, Target, WindowId};
+
+/// Window and its descendants, as listed by `xwininfo -tree`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Tree {
+    /// Id of the window the tree was listed for
+    pub id: WindowId,
+    /// Name of the window, `None` if it has none
+    pub name: Option<String>,
+    /// Root window of the screen
+    pub root: WindowId,
+    /// Parent of the window, `None` for the root window
+    pub parent: Option<WindowId>,
+    /// Children of the window, from the bottom of the stack to the top
+    pub children: Vec<Node>,
+}
+
+/// Window in a [Tree]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Node {
+    /// Id of the window
+    pub id: WindowId,
+    /// Name of the window, `None` if it has none
+    pub name: Option<String>,
+    /// Instance part of `WM_CLASS`, such as `st-256color`
+    pub instance: Option<String>,
+    /// Class part of `WM_CLASS`
+    pub class: Option<String>,
+    /// Width without the border
+    pub width: u32,
+    /// Height without the border
+    pub height: u32,
+    /// Horizontal position relative to the parent
+    pub x: i32,
+    /// Vertical position relative to the parent
+    pub y: i32,
+    /// Horizontal position on the screen
+    pub absolute_x: i32,
+    /// Vertical position on the screen
+    pub absolute_y: i32,
+    /// Children of the window, only listed by `xwininfo -tree`
+    pub children: Vec<Node>,
+}
+
+impl Tree {
+    /// Parse the output of `xwininfo -tree` or `xwininfo -children`
+    ///
+    /// ```rust
+    /// use pino_xwininfo::{tree::Tree, WindowId};
+    ///
+    /// let tree = Tree::parse(concat!(
+    ///     "xwininfo: Window id: 0x3b2 (the root window) (has no name)\n",
+    ///     "  Root window id: 0x3b2 (the root window) (has no name)\n",
+    ///     "  Parent window id: 0x0 (none)\n",
+    ///     "     1 child:\n",
+    ///     "     0x2a00006 \"~ - st\": (\"st-256color\" \"st-256color\")  800x600+100+50  +100+50\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert_eq!(tree.parent, None);
+    /// assert_eq!(tree.children[0].id, WindowId(0x2a00006));
+    /// assert_eq!(tree.children[0].class.as_deref(), Some("st-256color"));
+    /// ```
+    pub fn parse(raw: &str) -> Result<Self, Error> {
+        let mut tree = Tree::default();
+        // nodes whose children are still being listed, along with their indentation
+        let mut stack: Vec<(usize, Node)> = vec![];
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            let trimmed = line.trim_start();
+            if let Some(window) = line.strip_prefix("xwininfo: Window id:") {
+                (tree.id, tree.name) = parse_window(line, window)?;
+            } else if let Some(window) = trimmed.strip_prefix("Root window id:") {
+                tree.root = parse_window(line, window)?.0;
+            } else if let Some(window) = trimmed.strip_prefix("Parent window id:") {
+                let (parent, _) = parse_window(line, window)?;
+                tree.parent = Some(parent).filter(|parent| parent.0 != 0);
+            } else if trimmed.starts_with("0x") {
+                let indent = line.len() - trimmed.len();
+                let node = Node::parse(line)?;
+                close(&mut stack, &mut tree, indent);
+                stack.push((indent, node));
+            }
+        }
+        close(&mut stack, &mut tree, 0);
+        Ok(tree)
+    }
+
+    /// Every window in the tree, parents before their children
+    pub fn walk(&self) -> impl Iterator<Item = &Node> {
+        let mut stack = self.children.iter().rev().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Window in the tree with the given id
+    pub fn find(&self, id: WindowId) -> Option<&Node> {
+        self.walk().find(|node| node.id == id)
+    }
+}
+
+/// Attach the nodes indented at least `indent` to their parents
+fn close(stack: &mut Vec<(usize, Node)>, tree: &mut Tree, indent: usize) {
+    while stack.last().is_some_and(|(top, _)| *top >= indent) {
+        let (_, node) = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(node),
+            None => tree.children.push(node),
+        }
+    }
+}
+
+impl Node {
+    /// Parse a line such as `0x2a00006 "~ - st": ("st-256color" "st-256color")  800x600+100+50
+    /// +100+50`
+    fn parse(line: &str) -> Result<Self, Error> {
+        let malformed = || Error::Malformed(line.to_owned());
+        let mut words = line.split_whitespace().rev();
+        let (absolute, geometry) = (words.next().ok_or_else(malformed)?, words.next());
+        let geometry = geometry.ok_or_else(malformed)?;
+
+        // the name may hold anything, so the class is searched for from the end
+        let window = line[..line.rfind(geometry).ok_or_else(malformed)?].trim();
+        let (window, class) = window.rsplit_once(": (").ok_or_else(malformed)?;
+        let (id, name) = parse_window(line, window)?;
+        let mut class = class
+            .trim_end_matches(')')
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(str::to_owned);
+
+        // offsets are always preceded by a plus, as in 1x1+-1+-1
+        let (width, rest) = geometry.split_once('x').ok_or_else(malformed)?;
+        let (height, offset) = rest.split_once('+').ok_or_else(malformed)?;
+        let (x, y) = offset.split_once('+').ok_or_else(malformed)?;
+        let (absolute_x, absolute_y) = absolute
+            .strip_prefix('+')
+            .and_then(|absolute| absolute.split_once('+'))
+            .ok_or_else(malformed)?;
+        Ok(Node {
+            id,
+            name,
+            instance: class.next(),
+            class: class.next(),
+            width: parse_value(line, width)?,
+            height: parse_value(line, height)?,
+            x: parse_value(line, x)?,
+            y: parse_value(line, y)?,
+            absolute_x: parse_value(line, absolute_x)?,
+            absolute_y: parse_value(line, absolute_y)?,
+            children: vec![],
+        })
+    }
+}
+
+/// List the descendants of the target window
+///
+/// [Target::Select] waits for the user to click a window.
+#[cfg(feature = "process")]
+pub fn tree(target: &Target) -> Result<Tree, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    tree_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        target,
+    )
+}
+
+/// List the descendants of the target window, running `xwininfo -tree` through the given runner
+pub fn tree_with_runner(runner: &dyn CommandRunner, target: &Target) -> Result<Tree, Error> {
+    let output = run_checked(runner, &xwininfo(target).arg("-tree"))?;
+    Tree::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// List the children of the target window, without their own children
+#[cfg(feature = "process")]
+pub fn children(target: &Target) -> Result<Tree, Error> {
+    let display = crate::DisplayHandle::default();
+    pino_xcommon::session::require_x(&display).map_err(Error::NoXServer)?;
+    children_with_runner(
+        &display.runner(&crate::runner::SystemRunner::default()),
+        target,
+    )
+}
+
+/// List the children of the target window, running `xwininfo -children` through the given runner
+pub fn children_with_runner(runner: &dyn CommandRunner, target: &Target) -> Result<Tree, Error> {
+    let output = run_checked(runner, &xwininfo(target).arg("-children"))?;
+    Tree::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use pino_xcommon::runner::{Cmd, MockRunner, Output};
+
+    use super::*;
+
+    #[test]
+    fn nested_tree() {
+        let runner = MockRunner::new().respond(
+            Cmd::new("xwininfo").args(["-root", "-tree"]),
+            Output::from_stdout(concat!(
+                "\n",
+                "xwininfo: Window id: 0x3b2 (the root window) (has no name)\n",
+                "\n",
+                "  Root window id: 0x3b2 (the root window) (has no name)\n",
+                "  Parent window id: 0x0 (none)\n",
+                "     3 children:\n",
+                "     0x1e00001 \"dwm\": (\"dwm\" \"dwm\")  10x10+-1+-1  +-1+-1\n",
+                "     0x800003 (has no name): ()  1924x1084+0+0  +0+0\n",
+                "        1 child:\n",
+                "        0x2a00006 \"vim: (a) b\": (\"st-256color\" \"st-256color\")  800x600+2+2  +2+2\n",
+                "           1 child:\n",
+                "           0x2a00007 (has no name): ()  1x1+-1+-1  +1+1\n",
+                "     0x1a00002 \"firefox\": ()  1x1+0+0  +0+0\n",
+            )),
+        );
+        let tree = tree_with_runner(&runner, &Target::Root).unwrap();
+
+        assert_eq!(tree.root, WindowId(0x3b2));
+        assert_eq!(tree.children.len(), 3);
+        assert_eq!(tree.children[0].x, -1);
+        assert_eq!(tree.children[1].name, None);
+        let st = &tree.children[1].children[0];
+        assert_eq!(st.name.as_deref(), Some("vim: (a) b"));
+        assert_eq!(st.instance.as_deref(), Some("st-256color"));
+        assert_eq!((st.width, st.height, st.absolute_x), (800, 600, 2));
+        assert_eq!(st.children[0].id, WindowId(0x2a00007));
+        assert_eq!(tree.children[2].class, None);
+
+        let ids = tree.walk().map(|node| node.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [0x1e00001, 0x800003, 0x2a00006, 0x2a00007, 0x1a00002]);
+        assert!(tree.find(WindowId(0x2a00007)).is_some());
+    }
+}